@@ -0,0 +1,136 @@
+use {
+    clap::Parser,
+    colored::*,
+    rakurai_cli::{
+        crank::{run_once, CrankConfig, CrankState},
+        normalize_to_url_if_moniker, parse_keypair, parse_pubkey,
+    },
+    solana_rpc_client::rpc_client::RpcClient,
+    solana_sdk::{commitment_config::CommitmentConfig, packet::PACKET_DATA_SIZE, pubkey::Pubkey},
+    std::{path::PathBuf, thread::sleep, time::Duration},
+};
+
+#[derive(Parser)]
+#[command(
+    author,
+    version,
+    about = "Claims staker rewards from every Reward Collection Account with an uploaded merkle root",
+    arg_required_else_help = true,
+    color = clap::ColorChoice::Always
+)]
+pub struct Cli {
+    /// Path to the keypair file paying for the claims
+    #[arg(
+        short,
+        long,
+        default_value = "~/.config/solana/id.json",
+        help = "Path to the Solana keypair paying transaction fees and ClaimStatus rent"
+    )]
+    pub keypair: String,
+
+    /// RPC URL for sending transactions
+    #[arg(short, long, default_value = "t", value_parser = normalize_to_url_if_moniker, help = "Solana RPC endpoint to send transactions through")]
+    pub url: String,
+
+    /// Reward Distribution Program ID (Pubkey)
+    #[arg(
+            short,
+            long,
+            required = true,
+            value_parser = parse_pubkey,
+            help = "Reward distribution Program ID [testnet: A37zgM34Q43gKAxBWQ9zSbQRRhjPqGK8jM49H7aWqNVB, mainnet-beta: RAkd1EJg45QQHeuXy7JEWBhdNvsd64Z5PbZJWQT96iB]"
+        )]
+    pub program_id: Pubkey,
+
+    /// Directory holding the distribution files
+    #[arg(
+        short = 'd',
+        long = "distributions",
+        required = true,
+        help = "Directory holding the distribution files written by generate-distribution"
+    )]
+    pub distributions: PathBuf,
+
+    /// File the crank's progress is persisted to
+    #[arg(
+        short = 's',
+        long = "state",
+        default_value = "claim-crank-state.json",
+        help = "File the claimed leaves are persisted to, so the crank resumes after a restart"
+    )]
+    pub state: PathBuf,
+
+    /// Priority fee in micro-lamports per compute unit
+    #[arg(
+        long = "priority_fee",
+        default_value_t = 0,
+        help = "Priority fee in micro-lamports per compute unit"
+    )]
+    pub priority_fee: u64,
+
+    /// Compute unit limit requested for every transaction
+    #[arg(
+        long = "compute_unit_limit",
+        help = "Compute unit limit requested for every transaction"
+    )]
+    pub compute_unit_limit: Option<u32>,
+
+    /// Attempts per transaction
+    #[arg(
+        long = "max_retries",
+        default_value_t = 5,
+        help = "Attempts per transaction before it is left for the next pass"
+    )]
+    pub max_retries: u32,
+
+    /// Seconds between passes
+    #[arg(
+        long = "interval",
+        default_value_t = 60,
+        help = "Seconds to wait between passes"
+    )]
+    pub interval: u64,
+
+    /// Run a single pass and exit
+    #[arg(long = "once", help = "Run a single pass and exit")]
+    pub once: bool,
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let cli = Cli::parse();
+    let keypair = parse_keypair(&cli.keypair)?;
+    let rpc_client = RpcClient::new_with_commitment(cli.url.clone(), CommitmentConfig::confirmed());
+
+    let config = CrankConfig {
+        program_id: cli.program_id,
+        distributions_dir: cli.distributions,
+        state_path: cli.state,
+        priority_fee: cli.priority_fee,
+        compute_unit_limit: cli.compute_unit_limit,
+        max_transaction_size: PACKET_DATA_SIZE,
+        max_retries: cli.max_retries,
+        retry_delay: Duration::from_secs(1),
+    };
+    let mut state = CrankState::load(&config.state_path)?;
+
+    loop {
+        match run_once(&rpc_client, &keypair, &config, &mut state) {
+            Ok(summary) => println!(
+                "{} {} accounts, {} claims, {} lamports, {} failed, {} unclaimable",
+                "📊 Pass complete:".green(),
+                summary.reward_collection_accounts,
+                summary.claims,
+                summary.lamports,
+                summary.failed_claims,
+                summary.unclaimable_claims
+            ),
+            Err(err) if cli.once => return Err(err),
+            Err(err) => println!("{} {}", "❌ Pass failed:".red(), err),
+        }
+
+        if cli.once {
+            return Ok(());
+        }
+        sleep(Duration::from_secs(cli.interval));
+    }
+}
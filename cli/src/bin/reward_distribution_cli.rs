@@ -0,0 +1,3006 @@
+use {
+    clap::{Args, Parser, Subcommand},
+    colored::*,
+    rakurai_activation::sdk::{
+        derive_activation_account_address,
+        derive_config_account_address as derive_activation_config_account_address,
+    },
+    rakurai_cli::{
+        display_claim_status_account, display_cumulative_claim_status_account,
+        display_cumulative_distributor_account, display_reward_collection_account,
+        display_reward_distribution_config_account, display_root_attestation_account,
+        get_activation_account, get_address_lookup_table, get_claim_bitmap_account,
+        get_claim_status_account, get_cumulative_claim_status_account,
+        get_cumulative_distributor_account, get_program_accounts_of, get_reward_collection_account,
+        get_reward_distribution_config_account, get_root_attestation_account, get_vote_account,
+        normalize_to_url_if_moniker, parse_deposit_source, parse_hash, parse_keypair, parse_pubkey,
+        send_instructions, send_versioned_instructions, sign_and_send_transaction,
+        validate_commission,
+    },
+    rakurai_distribution::{
+        cumulative::CumulativeDistributionFile,
+        distribution::{stake_weights, DistributionFile},
+        policy::{AuditReport, DistributionPolicy},
+        stake_accounts::{load_stake_accounts, StakeAccountsFormat},
+    },
+    reward_distribution::{
+        commission::{CategorizedRewards, CategoryCommissions},
+        sdk::{
+            derive_claim_bitmap_account_address, derive_claim_status_account_address,
+            derive_config_account_address, derive_cumulative_claim_status_address,
+            derive_cumulative_distributor_address, derive_proof_buffer_address,
+            derive_reward_collection_account_address, derive_root_attestation_address,
+            derive_stake_pool_deposit_address,
+            instruction::{
+                attest_merkle_root_ix, claim_cumulative_ix, claim_indexed_ix,
+                claim_to_stake_account_ix, claim_to_stake_pool_ix, close_claim_bitmap_ix,
+                close_claim_status_ix, close_proof_buffer_ix, close_reward_collection_account_ix,
+                close_root_attestation_ix, deposit_rewards_ix, initialize_claim_bitmap_ix,
+                initialize_cumulative_distributor_ix, initialize_ix,
+                initialize_reward_collection_account_ix, initialize_root_attestation_ix,
+                migrate_reward_collection_account_ix, rollover_unclaimed_rewards_ix,
+                sweep_to_cumulative_distributor_ix, transfer_staker_rewards_ix, update_config_ix,
+                update_merkle_root_upload_authority_ix, update_validator_commission_ix,
+                upload_cumulative_merkle_root_ix, upload_merkle_root_ix, withdraw_surplus_ix,
+                AttestMerkleRootAccounts, AttestMerkleRootArgs, ClaimCumulativeAccounts,
+                ClaimCumulativeArgs, ClaimIndexedAccounts, ClaimIndexedArgs,
+                ClaimToStakeAccountAccounts, ClaimToStakeAccountArgs, ClaimToStakePoolAccounts,
+                ClaimToStakePoolArgs, CloseClaimBitmapAccounts, CloseClaimBitmapArgs,
+                CloseClaimStatusAccounts, CloseClaimStatusArgs, CloseProofBufferAccounts,
+                CloseProofBufferArgs, CloseRewardCollectionAccountArgs,
+                CloseRewardCollectionAccounts, CloseRootAttestationAccounts,
+                CloseRootAttestationArgs, DepositRewardsAccounts, DepositRewardsArgs,
+                InitializeAccounts, InitializeArgs, InitializeClaimBitmapAccounts,
+                InitializeClaimBitmapArgs, InitializeCumulativeDistributorAccounts,
+                InitializeCumulativeDistributorArgs, InitializeRewardCollectionAccountAccounts,
+                InitializeRewardCollectionAccountArgs, InitializeRootAttestationAccounts,
+                InitializeRootAttestationArgs, MigrateRewardCollectionAccountAccounts,
+                MigrateRewardCollectionAccountArgs, RolloverUnclaimedRewardsAccounts,
+                RolloverUnclaimedRewardsArgs, SweepToCumulativeDistributorAccounts,
+                SweepToCumulativeDistributorArgs, TransferStakerRewardsAccounts,
+                TransferStakerRewardsArgs, UpdateConfigAccounts, UpdateConfigArgs,
+                UpdateMerkleRootUploadAuthorityAccounts, UpdateMerkleRootUploadAuthorityArgs,
+                UpdateValidatorCommissionAccounts, UpdateValidatorCommissionArgs,
+                UploadCumulativeMerkleRootAccounts, UploadCumulativeMerkleRootArgs,
+                UploadMerkleRootAccounts, UploadMerkleRootArgs, WithdrawSurplusAccounts,
+                WithdrawSurplusArgs,
+            },
+            packing::{
+                pack_aggregated_claims, plan_claim, AggregatedClaimEntry, ClaimPlanAccounts,
+                PackAccounts,
+            },
+        },
+        state::{
+            ClaimBitmap, ClaimScale, ClaimStatus, DepositSource, MerkleRoot,
+            RewardCollectionAccount, RootAttestation,
+        },
+    },
+    solana_rpc_client::rpc_client::RpcClient,
+    solana_sdk::{
+        borsh1::try_from_slice_unchecked,
+        commitment_config::CommitmentConfig,
+        instruction::Instruction,
+        message::Message,
+        pubkey::Pubkey,
+        signature::Signature,
+        signature::{Keypair, Signer},
+        stake, system_program, sysvar,
+        transaction::Transaction,
+    },
+    spl_stake_pool::{find_withdraw_authority_program_address, state::StakePool},
+    std::{collections::HashSet, path::PathBuf, sync::Arc},
+};
+
+#[derive(Parser)]
+#[command(
+    author,
+    version,
+    about = "A comprehensive CLI tool for managing Rakurai Reward Distribution accounts",
+    arg_required_else_help = true,
+    color = clap::ColorChoice::Always
+)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Commands,
+
+    /// Path to the keypair file (must be a valid Solana keypair)
+    #[arg(
+        short,
+        long,
+        global = true,
+        default_value = "~/.config/solana/id.json",
+        help = "Path to the Solana keypair"
+    )]
+    pub keypair: String,
+
+    /// RPC URL for sending transactions
+    #[arg(short, long, global = true, default_value = "t", value_parser = normalize_to_url_if_moniker, help = "Solana RPC endpoint to send transactions through")]
+    pub url: String,
+
+    /// Reward Distribution Program ID (Pubkey)
+    #[arg(
+            short,
+            long,
+            required = true,
+            value_parser = parse_pubkey,
+            help = "Reward distribution Program ID [testnet: A37zgM34Q43gKAxBWQ9zSbQRRhjPqGK8jM49H7aWqNVB, mainnet-beta: RAkd1EJg45QQHeuXy7JEWBhdNvsd64Z5PbZJWQT96iB]"
+        )]
+    pub program_id: Pubkey,
+}
+
+#[derive(Subcommand)]
+pub enum Commands {
+    /// Initialize Reward Distribution config account
+    #[command(hide = true)]
+    InitConfig(InitConfigArgs),
+
+    /// Update Reward Distribution config account
+    #[command(hide = true)]
+    UpdateConfig(UpdateConfigCmdArgs),
+
+    /// Display Reward Distribution config account info
+    ShowConfig,
+
+    /// Initialize the Reward Collection Account for the current epoch
+    Init(InitArgs),
+
+    /// Transfer block rewards to the Reward Collection Account of the current epoch
+    TransferRewards(TransferRewardsArgs),
+
+    /// Deposit commission-free staker rewards, e.g. tips or incentives, to a Reward Collection Account
+    DepositRewards(DepositRewardsCmdArgs),
+
+    /// Upload a merkle root to a Reward Collection Account
+    UploadMerkleRoot(UploadMerkleRootCmdArgs),
+
+    /// Change the merkle root upload authority of a Reward Collection Account before its root is uploaded
+    UpdateUploadAuthority(UpdateUploadAuthorityArgs),
+
+    /// Hand the merkle root upload of a Reward Collection Account to several attesters, uploaded once a threshold of them agree
+    InitAttestation(InitAttestationArgs),
+
+    /// Submit the signer's merkle root as an attester of a Reward Collection Account
+    Attest(UploadMerkleRootCmdArgs),
+
+    /// Close an expired Root Attestation account
+    CloseAttestation(CollectionAccountArgs),
+
+    /// Change the validator commission of a Reward Collection Account before its root is uploaded
+    UpdateCommission(UpdateCommissionArgs),
+
+    /// Migrate a Reward Collection Account created by an older program version to the current account version
+    Migrate(CollectionAccountArgs),
+
+    /// Claim staker rewards from a Reward Collection Account
+    Claim(ClaimCmdArgs),
+
+    /// Claim the signer's staker rewards into a new stake account delegated to the validator, or into a stake pool
+    ClaimToStake(ClaimToStakeCmdArgs),
+
+    /// Claim a staker's rewards from several Reward Collection Accounts in as few transactions as possible
+    ClaimAggregated(ClaimAggregatedCmdArgs),
+
+    /// Close an expired Claim Status account
+    CloseClaimStatus(ClaimStatusArgs),
+
+    /// Create the Claim Bitmap of a Reward Collection Account with an indexed merkle tree
+    InitClaimBitmap(CollectionAccountArgs),
+
+    /// Close an expired Claim Bitmap account
+    CloseClaimBitmap(CollectionAccountArgs),
+
+    /// Close a Reward Collection Account that has expired or whose every node has claimed
+    Close(CollectionAccountArgs),
+
+    /// Close an expired Reward Collection Account, moving its unclaimed funds into the validator's Reward Collection Account of the current epoch
+    Rollover(CollectionAccountArgs),
+
+    /// Return the funds of a Reward Collection Account beyond its outstanding claims to the validator identity
+    WithdrawSurplus(CollectionAccountArgs),
+
+    /// Display Reward Collection Account info
+    Show(CollectionAccountArgs),
+
+    /// Display Claim Status account info
+    ShowClaimStatus(ClaimStatusArgs),
+
+    /// Split a Reward Collection Account's balance across its stakers and write a distribution file
+    GenerateDistribution(GenerateDistributionArgs),
+
+    /// Close every expired Claim Status, Claim Bitmap, Root Attestation and Reward Collection Account
+    SweepExpired(SweepExpiredArgs),
+
+    /// Initialize the Cumulative Distributor of a vote account
+    InitDistributor(InitDistributorArgs),
+
+    /// Move a past epoch's staker rewards from its Reward Collection Account into the Cumulative Distributor
+    SweepToDistributor(CollectionAccountArgs),
+
+    /// Add epoch distribution files to the stakers' lifetime totals in a cumulative distribution file
+    GenerateCumulativeDistribution(GenerateCumulativeDistributionArgs),
+
+    /// Upload a cumulative merkle root to the Cumulative Distributor
+    UploadCumulativeRoot(UploadCumulativeRootArgs),
+
+    /// Claim the unclaimed part of a staker's lifetime total from the Cumulative Distributor
+    ClaimCumulative(ClaimCumulativeCmdArgs),
+
+    /// Display Cumulative Distributor info, and a claimant's Cumulative Claim Status
+    ShowDistributor(ShowDistributorArgs),
+}
+
+#[derive(Args, Clone)]
+#[command(arg_required_else_help = false, color = clap::ColorChoice::Always)]
+pub struct InitConfigArgs {
+    /// Config authority pubkey
+    #[arg(short = 'x', long = "config_authority", value_parser = parse_pubkey, help = "Config account authority pubkey (default: signer)")]
+    pub config_authority: Option<Pubkey>,
+
+    /// Number of epochs a Reward Collection Account stays claimable
+    #[arg(
+        short = 'n',
+        long = "num_epochs_valid",
+        default_value_t = 10,
+        help = "Number of epochs a Reward Collection Account stays claimable"
+    )]
+    pub num_epochs_valid: u64,
+
+    /// Maximum combined commission in base points (0 to 10,000)
+    #[arg(short = 'c', long = "max_commission_bps", default_value_t = 10_000, value_parser = validate_commission, help = "Maximum combined commission in base points")]
+    pub max_commission_bps: u16,
+}
+
+#[derive(Args, Clone)]
+#[command(arg_required_else_help = true, color = clap::ColorChoice::Always)]
+pub struct UpdateConfigCmdArgs {
+    /// New config authority pubkey. If omitted, no change is made.
+    #[arg(short = 'x', long = "config_authority", value_parser = parse_pubkey, help = "New config account authority pubkey")]
+    pub config_authority: Option<Pubkey>,
+
+    /// New number of epochs a Reward Collection Account stays claimable. If omitted, no change is made.
+    #[arg(
+        short = 'n',
+        long = "num_epochs_valid",
+        help = "New number of epochs a Reward Collection Account stays claimable"
+    )]
+    pub num_epochs_valid: Option<u64>,
+
+    /// New maximum combined commission in base points. If omitted, no change is made.
+    #[arg(short = 'c', long = "max_commission_bps", value_parser = validate_commission, help = "New maximum combined commission in base points")]
+    pub max_commission_bps: Option<u16>,
+}
+
+#[derive(Args, Clone)]
+#[command(arg_required_else_help = true, color = clap::ColorChoice::Always)]
+pub struct InitArgs {
+    /// Validator vote account pubkey
+    #[arg(short = 'v', long = "vote_pubkey", required = true, value_parser = parse_pubkey, help = "Validator vote account pubkey")]
+    pub vote_pubkey: Pubkey,
+
+    /// Merkle root upload authority pubkey
+    #[arg(short = 'm', long = "merkle_root_upload_authority", required = true, value_parser = parse_pubkey, help = "Authority allowed to upload the merkle root")]
+    pub merkle_root_upload_authority: Pubkey,
+
+    /// Rakurai Activation Program ID, owner of the validator's activation account
+    #[arg(short = 'a', long = "activation_program_id", required = true, value_parser = parse_pubkey, help = "Rakurai activation Program ID [testnet: pmQHMpnpA534JmxEdwY3ADfwDBFmy5my3CeutHM2QTt, mainnet-beta: rAKACC6Qw8HYa87ntGPRbfYEMnK2D9JVLsmZaKPpMmi]")]
+    pub activation_program_id: Pubkey,
+
+    /// Roll unclaimed funds over to a later epoch's stakers on expiry
+    #[arg(
+        long = "rollover_unclaimed",
+        help = "On expiry, move unclaimed funds into the validator's Reward Collection Account of the current epoch instead of back to the validator identity"
+    )]
+    pub rollover_unclaimed: bool,
+}
+
+#[derive(Args, Clone)]
+#[command(arg_required_else_help = true, color = clap::ColorChoice::Always)]
+pub struct TransferRewardsArgs {
+    /// Validator vote account pubkey
+    #[arg(short = 'v', long = "vote_pubkey", required = true, value_parser = parse_pubkey, help = "Validator vote account pubkey")]
+    pub vote_pubkey: Pubkey,
+
+    /// Base transaction fees in lamports, before commissions
+    #[arg(
+        short = 'b',
+        long = "block_fees",
+        default_value_t = 0,
+        help = "Base transaction fees in lamports"
+    )]
+    pub block_fees: u64,
+
+    /// Priority fees in lamports, before commissions
+    #[arg(
+        short = 'f',
+        long = "priority_fees",
+        default_value_t = 0,
+        help = "Priority fees in lamports"
+    )]
+    pub priority_fees: u64,
+
+    /// Tips in lamports, before commissions
+    #[arg(
+        short = 't',
+        long = "tips",
+        default_value_t = 0,
+        help = "Tips in lamports"
+    )]
+    pub tips: u64,
+
+    /// First slot of the leader turn the rewards were earned in
+    #[arg(
+        long = "start_slot",
+        required = true,
+        help = "First slot of the leader turn"
+    )]
+    pub start_slot: u64,
+
+    /// Last slot of the leader turn the rewards were earned in
+    #[arg(
+        long = "end_slot",
+        required = true,
+        help = "Last slot of the leader turn"
+    )]
+    pub end_slot: u64,
+}
+
+impl TransferRewardsArgs {
+    fn rewards(&self) -> CategorizedRewards {
+        CategorizedRewards {
+            block_fees: self.block_fees,
+            priority_fees: self.priority_fees,
+            tips: self.tips,
+        }
+    }
+}
+
+#[derive(Args, Clone)]
+#[command(arg_required_else_help = true, color = clap::ColorChoice::Always)]
+pub struct DepositRewardsCmdArgs {
+    #[command(flatten)]
+    pub collection_account: CollectionAccountArgs,
+
+    /// Lamports to deposit, all of them for the stakers
+    #[arg(
+        short = 'a',
+        long = "amount",
+        required = true,
+        help = "Lamports to deposit, all of them for the stakers"
+    )]
+    pub amount: u64,
+
+    /// Where the deposited lamports came from
+    #[arg(
+        short = 's',
+        long = "source",
+        required = true,
+        value_parser = parse_deposit_source,
+        help = "Where the deposited lamports came from: tips, partner-incentives, validator-top-up or other"
+    )]
+    pub source: DepositSource,
+}
+
+#[derive(Args, Clone)]
+#[command(arg_required_else_help = true, color = clap::ColorChoice::Always)]
+pub struct CollectionAccountArgs {
+    /// Validator vote account pubkey
+    #[arg(short = 'v', long = "vote_pubkey", required = true, value_parser = parse_pubkey, help = "Validator vote account pubkey")]
+    pub vote_pubkey: Pubkey,
+
+    /// Epoch the Reward Collection Account was created in
+    #[arg(
+        short = 'e',
+        long = "epoch",
+        required = true,
+        help = "Epoch the Reward Collection Account was created in"
+    )]
+    pub epoch: u64,
+}
+
+#[derive(Args, Clone)]
+#[command(arg_required_else_help = true, color = clap::ColorChoice::Always)]
+pub struct UpdateUploadAuthorityArgs {
+    #[command(flatten)]
+    pub collection_account: CollectionAccountArgs,
+
+    /// New merkle root upload authority pubkey
+    #[arg(short = 'm', long = "merkle_root_upload_authority", required = true, value_parser = parse_pubkey, help = "New authority allowed to upload the merkle root")]
+    pub merkle_root_upload_authority: Pubkey,
+}
+
+#[derive(Args, Clone)]
+#[command(arg_required_else_help = true, color = clap::ColorChoice::Always)]
+pub struct UpdateCommissionArgs {
+    #[command(flatten)]
+    pub collection_account: CollectionAccountArgs,
+
+    /// New validator commission in base points (0 to 10,000)
+    #[arg(short = 'c', long = "commission_bps", required = true, value_parser = validate_commission, help = "New validator commission in base points")]
+    pub validator_commission_bps: u16,
+
+    /// Validator commission of the base transaction fees. If omitted, the account's commission applies.
+    #[arg(long = "block_fees_commission_bps", value_parser = validate_commission, help = "Validator commission of the base transaction fees in base points")]
+    pub block_fees_commission_bps: Option<u16>,
+
+    /// Validator commission of the priority fees. If omitted, the account's commission applies.
+    #[arg(long = "priority_fees_commission_bps", value_parser = validate_commission, help = "Validator commission of the priority fees in base points")]
+    pub priority_fees_commission_bps: Option<u16>,
+
+    /// Validator commission of the tips. If omitted, the account's commission applies.
+    #[arg(long = "tips_commission_bps", value_parser = validate_commission, help = "Validator commission of the tips in base points")]
+    pub tips_commission_bps: Option<u16>,
+}
+
+impl UpdateCommissionArgs {
+    fn category_commissions(&self) -> CategoryCommissions {
+        CategoryCommissions {
+            block_fees: self.block_fees_commission_bps,
+            priority_fees: self.priority_fees_commission_bps,
+            tips: self.tips_commission_bps,
+        }
+    }
+}
+
+#[derive(Args, Clone)]
+#[command(arg_required_else_help = true, color = clap::ColorChoice::Always)]
+pub struct UploadMerkleRootCmdArgs {
+    #[command(flatten)]
+    pub collection_account: CollectionAccountArgs,
+
+    /// Merkle root (base58)
+    #[arg(short = 'r', long = "root", required = true, value_parser = parse_hash, help = "Merkle root (base58)")]
+    pub root: [u8; 32],
+
+    /// Sum of all claims in the tree
+    #[arg(
+        short = 't',
+        long = "max_total_claim",
+        required = true,
+        help = "Sum of all claims in the tree (lamports)"
+    )]
+    pub max_total_claim: u64,
+
+    /// Number of leaves in the tree
+    #[arg(
+        short = 'n',
+        long = "max_num_nodes",
+        required = true,
+        help = "Number of leaves in the tree"
+    )]
+    pub max_num_nodes: u64,
+
+    /// Leaf format of the tree
+    #[arg(
+        short = 'l',
+        long = "leaf_version",
+        default_value_t = MerkleRoot::LEAF_VERSION_V1,
+        value_parser = clap::value_parser!(u8).range(0..=1),
+        help = "Leaf format of the tree: 0 for legacy leaves, 1 for leaves bound to the Reward Collection Account and epoch"
+    )]
+    pub leaf_version: u8,
+
+    /// Scale every claim down if the account holds less than the max total claim
+    #[arg(
+        long = "pro_rata",
+        help = "If the Reward Collection Account holds less than the max total claim, scale every claim down by the same factor instead of failing the last claims"
+    )]
+    pub pro_rata: bool,
+}
+
+#[derive(Args, Clone)]
+#[command(arg_required_else_help = true, color = clap::ColorChoice::Always)]
+pub struct InitAttestationArgs {
+    #[command(flatten)]
+    pub collection_account: CollectionAccountArgs,
+
+    /// Attester pubkeys
+    #[arg(short = 'a', long = "attester", required = true, value_parser = parse_pubkey, help = "Attester pubkey; repeat for every attester")]
+    pub attesters: Vec<Pubkey>,
+
+    /// Number of attesters that must submit the same root
+    #[arg(
+        short = 't',
+        long = "threshold",
+        required = true,
+        help = "Number of attesters that must submit the same root before it is uploaded"
+    )]
+    pub threshold: u8,
+}
+
+#[derive(Args, Clone)]
+#[command(arg_required_else_help = true, color = clap::ColorChoice::Always)]
+pub struct ClaimCmdArgs {
+    #[command(flatten)]
+    pub claim_status: ClaimStatusArgs,
+
+    /// Amount to claim in lamports
+    #[arg(
+        short = 'a',
+        long = "amount",
+        required = true,
+        help = "Amount to claim in lamports"
+    )]
+    pub amount: u64,
+
+    /// Merkle proof, comma separated base58 hashes
+    #[arg(short = 'f', long = "proof", value_delimiter = ',', value_parser = parse_hash, help = "Merkle proof, comma separated base58 hashes")]
+    pub proof: Vec<[u8; 32]>,
+
+    /// Leaf index, for trees with indexed leaves
+    #[arg(
+        short = 'i',
+        long = "index",
+        help = "Leaf index; claims through the Claim Bitmap of an indexed merkle tree"
+    )]
+    pub index: Option<u64>,
+}
+
+#[derive(Args, Clone)]
+#[command(arg_required_else_help = true, color = clap::ColorChoice::Always)]
+pub struct ClaimToStakeCmdArgs {
+    #[command(flatten)]
+    pub collection_account: CollectionAccountArgs,
+
+    /// Amount to claim in lamports
+    #[arg(
+        short = 'a',
+        long = "amount",
+        required = true,
+        help = "Amount to claim in lamports"
+    )]
+    pub amount: u64,
+
+    /// Merkle proof, comma separated base58 hashes
+    #[arg(short = 'f', long = "proof", value_delimiter = ',', value_parser = parse_hash, help = "Merkle proof, comma separated base58 hashes")]
+    pub proof: Vec<[u8; 32]>,
+
+    /// Stake pool to deposit into instead of a new stake account
+    #[arg(long = "stake_pool", value_parser = parse_pubkey, requires = "pool_token_account", help = "SPL stake pool to deposit the claim into instead of a new stake account")]
+    pub stake_pool: Option<Pubkey>,
+
+    /// Signer's token account receiving the pool tokens
+    #[arg(long = "pool_token_account", value_parser = parse_pubkey, requires = "stake_pool", help = "Signer's token account for the stake pool's mint, receiving the pool tokens")]
+    pub pool_token_account: Option<Pubkey>,
+}
+
+#[derive(Args, Clone)]
+#[command(arg_required_else_help = true, color = clap::ColorChoice::Always)]
+pub struct ClaimAggregatedCmdArgs {
+    /// Claimant pubkey
+    #[arg(short = 'c', long = "claimant", required = true, value_parser = parse_pubkey, help = "Claimant pubkey")]
+    pub claimant: Pubkey,
+
+    /// Distribution files to claim from
+    #[arg(
+        short = 'd',
+        long = "distribution",
+        required = true,
+        help = "Distribution file to claim from; repeat for every Reward Collection Account"
+    )]
+    pub distributions: Vec<PathBuf>,
+
+    /// Address lookup table holding the claimed accounts
+    #[arg(short = 'l', long = "lookup_table", value_parser = parse_pubkey, help = "Address lookup table holding the claimed accounts; sends v0 transactions")]
+    pub lookup_table: Option<Pubkey>,
+}
+
+#[derive(Args, Clone)]
+#[command(arg_required_else_help = true, color = clap::ColorChoice::Always)]
+pub struct ClaimStatusArgs {
+    #[command(flatten)]
+    pub collection_account: CollectionAccountArgs,
+
+    /// Claimant pubkey
+    #[arg(short = 'c', long = "claimant", required = true, value_parser = parse_pubkey, help = "Claimant pubkey")]
+    pub claimant: Pubkey,
+}
+
+#[derive(Args, Clone)]
+#[command(arg_required_else_help = true, color = clap::ColorChoice::Always)]
+pub struct GenerateDistributionArgs {
+    #[command(flatten)]
+    pub collection_account: CollectionAccountArgs,
+
+    /// Export of the stake accounts at the end of the epoch
+    #[arg(
+        short = 's',
+        long = "stake_accounts",
+        required = true,
+        help = "Path to the exported stake accounts"
+    )]
+    pub stake_accounts: PathBuf,
+
+    /// Format of the stake accounts export
+    #[arg(
+        short = 'f',
+        long = "format",
+        default_value = "program-accounts",
+        help = "Stake accounts format: program-accounts (stake program getProgramAccounts result, base64), json (solana stakes --output json) or csv"
+    )]
+    pub format: StakeAccountsFormat,
+
+    /// Lamports to distribute. Defaults to the Reward Collection Account balance minus rent.
+    #[arg(
+        short = 't',
+        long = "total_rewards",
+        help = "Lamports to distribute (default: Reward Collection Account balance minus rent)"
+    )]
+    pub total_rewards: Option<u64>,
+
+    /// Where to write the distribution file
+    #[arg(
+        short = 'o',
+        long = "output",
+        required = true,
+        help = "Path to write the distribution file to"
+    )]
+    pub output: PathBuf,
+
+    /// Policy applied to the distribution: exclusions, weight multipliers, minimum payout and dust
+    #[arg(
+        short = 'P',
+        long = "policy",
+        help = "Path to a distribution policy file (exclusions, weight multipliers, minimum payout, dust handling)"
+    )]
+    pub policy: Option<PathBuf>,
+
+    /// Where to write the audit report
+    #[arg(
+        short = 'a',
+        long = "audit_report",
+        help = "Path to write an audit report of every policy adjustment to"
+    )]
+    pub audit_report: Option<PathBuf>,
+
+    /// Build an indexed merkle tree, claimed through a Claim Bitmap
+    #[arg(
+        long = "indexed",
+        help = "Use indexed leaves, claimed through a Claim Bitmap instead of Claim Status accounts"
+    )]
+    pub indexed: bool,
+}
+
+#[derive(Args, Clone)]
+#[command(arg_required_else_help = true, color = clap::ColorChoice::Always)]
+pub struct DistributorArgs {
+    /// Validator vote account pubkey
+    #[arg(short = 'v', long = "vote_pubkey", required = true, value_parser = parse_pubkey, help = "Validator vote account pubkey")]
+    pub vote_pubkey: Pubkey,
+}
+
+#[derive(Args, Clone)]
+#[command(arg_required_else_help = true, color = clap::ColorChoice::Always)]
+pub struct InitDistributorArgs {
+    #[command(flatten)]
+    pub distributor: DistributorArgs,
+
+    /// Merkle root upload authority pubkey
+    #[arg(short = 'm', long = "merkle_root_upload_authority", required = true, value_parser = parse_pubkey, help = "Authority allowed to upload the cumulative merkle root")]
+    pub merkle_root_upload_authority: Pubkey,
+}
+
+#[derive(Args, Clone)]
+#[command(arg_required_else_help = true, color = clap::ColorChoice::Always)]
+pub struct GenerateCumulativeDistributionArgs {
+    #[command(flatten)]
+    pub distributor: DistributorArgs,
+
+    /// Epoch distribution files to add
+    #[arg(
+        short = 'd',
+        long = "distribution",
+        required = true,
+        help = "Distribution file of an epoch swept into the Cumulative Distributor; may be repeated"
+    )]
+    pub distributions: Vec<PathBuf>,
+
+    /// Cumulative distribution file to add to
+    #[arg(
+        short = 'p',
+        long = "previous",
+        help = "Cumulative distribution file of the previous root to add the epochs to"
+    )]
+    pub previous: Option<PathBuf>,
+
+    /// Where to write the cumulative distribution file
+    #[arg(
+        short = 'o',
+        long = "output",
+        required = true,
+        help = "Path to write the cumulative distribution file to"
+    )]
+    pub output: PathBuf,
+}
+
+#[derive(Args, Clone)]
+#[command(arg_required_else_help = true, color = clap::ColorChoice::Always)]
+pub struct UploadCumulativeRootArgs {
+    #[command(flatten)]
+    pub distributor: DistributorArgs,
+
+    /// Merkle root (base58)
+    #[arg(short = 'r', long = "root", required = true, value_parser = parse_hash, help = "Merkle root (base58)")]
+    pub root: [u8; 32],
+
+    /// Sum of all lifetime totals in the tree
+    #[arg(
+        short = 't',
+        long = "max_total_claim",
+        required = true,
+        help = "Sum of all lifetime totals in the tree (lamports)"
+    )]
+    pub max_total_claim: u64,
+}
+
+#[derive(Args, Clone)]
+#[command(arg_required_else_help = true, color = clap::ColorChoice::Always)]
+pub struct ClaimCumulativeCmdArgs {
+    #[command(flatten)]
+    pub distributor: DistributorArgs,
+
+    /// Claimant pubkey
+    #[arg(short = 'c', long = "claimant", required = true, value_parser = parse_pubkey, help = "Claimant pubkey")]
+    pub claimant: Pubkey,
+
+    /// Lifetime total of the claimant in lamports
+    #[arg(
+        short = 'a',
+        long = "total_amount",
+        required = true,
+        help = "Lifetime total of the claimant in lamports"
+    )]
+    pub total_amount: u64,
+
+    /// Merkle proof, comma separated base58 hashes
+    #[arg(short = 'f', long = "proof", value_delimiter = ',', value_parser = parse_hash, help = "Merkle proof, comma separated base58 hashes")]
+    pub proof: Vec<[u8; 32]>,
+}
+
+#[derive(Args, Clone)]
+#[command(arg_required_else_help = true, color = clap::ColorChoice::Always)]
+pub struct ShowDistributorArgs {
+    #[command(flatten)]
+    pub distributor: DistributorArgs,
+
+    /// Claimant whose Cumulative Claim Status to display
+    #[arg(short = 'c', long = "claimant", value_parser = parse_pubkey, help = "Also display the Cumulative Claim Status of this claimant")]
+    pub claimant: Option<Pubkey>,
+}
+
+#[derive(Args, Clone)]
+#[command(arg_required_else_help = false, color = clap::ColorChoice::Always)]
+pub struct SweepExpiredArgs {
+    /// Close instructions per transaction
+    #[arg(
+        short = 'b',
+        long = "batch_size",
+        default_value_t = 8,
+        help = "Close instructions per transaction"
+    )]
+    pub batch_size: usize,
+
+    /// Only list the expired accounts
+    #[arg(
+        long = "dry_run",
+        help = "List the expired accounts without closing them"
+    )]
+    pub dry_run: bool,
+}
+
+fn process_init_config(
+    rpc_client: Arc<RpcClient>,
+    kp: Arc<Keypair>,
+    program_id: Pubkey,
+    args: InitConfigArgs,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let signer_pubkey = kp.pubkey();
+    let authority = args.config_authority.unwrap_or(signer_pubkey);
+
+    let (config_pubkey, bump) = derive_config_account_address(&program_id);
+    println!(
+        "📌 Derived Config Account: {} (Bump: {})",
+        config_pubkey, bump
+    );
+    println!(
+        "{} {}\n{} {}\n{} {}\n{} {}",
+        "📅 Epochs Valid:".green(),
+        args.num_epochs_valid,
+        "💰 Max Commission:".blue(),
+        args.max_commission_bps,
+        "🔑 Authority:".purple(),
+        authority,
+        "🔗 Signer:".cyan(),
+        signer_pubkey
+    );
+
+    let initialize_instruction = initialize_ix(
+        program_id,
+        InitializeArgs {
+            authority,
+            num_epochs_valid: args.num_epochs_valid,
+            max_commission_bps: args.max_commission_bps,
+            bump,
+        },
+        InitializeAccounts {
+            config: config_pubkey,
+            system_program: system_program::id(),
+            initializer: signer_pubkey,
+        },
+    );
+
+    sign_and_send_transaction(rpc_client, initialize_instruction, &kp)
+}
+
+fn process_update_config(
+    rpc_client: Arc<RpcClient>,
+    kp: Arc<Keypair>,
+    program_id: Pubkey,
+    args: UpdateConfigCmdArgs,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let signer_pubkey = kp.pubkey();
+
+    let (config_pubkey, _) = derive_config_account_address(&program_id);
+    let mut config = get_reward_distribution_config_account(rpc_client.clone(), config_pubkey)?;
+    if config.authority != signer_pubkey {
+        return Err(format!(
+            "❌ Unauthorized Signer! Expected: {}, Found: {}",
+            config.authority, signer_pubkey
+        )
+        .into());
+    }
+
+    if let Some(authority) = args.config_authority {
+        config.authority = authority;
+    }
+    if let Some(num_epochs_valid) = args.num_epochs_valid {
+        config.num_epochs_valid = num_epochs_valid;
+    }
+    if let Some(max_commission_bps) = args.max_commission_bps {
+        config.max_commission_bps = max_commission_bps;
+    }
+
+    println!("📌 Config Account: {}", config_pubkey);
+    display_reward_distribution_config_account(config.clone());
+
+    let update_config_instruction = update_config_ix(
+        program_id,
+        UpdateConfigArgs { new_config: config },
+        UpdateConfigAccounts {
+            config: config_pubkey,
+            authority: signer_pubkey,
+        },
+    );
+    sign_and_send_transaction(rpc_client, update_config_instruction, &kp)
+}
+
+fn process_show_config(
+    rpc_client: Arc<RpcClient>,
+    program_id: Pubkey,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let (config_pubkey, _) = derive_config_account_address(&program_id);
+
+    let config = get_reward_distribution_config_account(rpc_client.clone(), config_pubkey)?;
+    println!("📌 Config Account: {}", config_pubkey);
+    display_reward_distribution_config_account(config);
+    Ok(())
+}
+
+fn process_init(
+    rpc_client: Arc<RpcClient>,
+    kp: Arc<Keypair>,
+    program_id: Pubkey,
+    args: InitArgs,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let signer_pubkey = kp.pubkey();
+    let vote_pubkey = args.vote_pubkey;
+
+    let vote_state = get_vote_account(rpc_client.clone(), vote_pubkey)?;
+    if vote_state.node_pubkey != signer_pubkey {
+        return Err(format!(
+            "❌ Unauthorized signer! Expected: {:?}, Found: {:?}",
+            vote_state.node_pubkey, signer_pubkey
+        )
+        .into());
+    }
+
+    let (activation_pubkey, _) =
+        derive_activation_account_address(&args.activation_program_id, &signer_pubkey);
+    let (activation_config_pubkey, _) =
+        derive_activation_config_account_address(&args.activation_program_id);
+    let activation_account = get_activation_account(rpc_client.clone(), activation_pubkey)?;
+    if !activation_account.is_enabled {
+        return Err(format!("❌ Rakurai is not enabled for identity {}", signer_pubkey).into());
+    }
+
+    let epoch = rpc_client.get_epoch_info()?.epoch;
+    let (config_pubkey, _) = derive_config_account_address(&program_id);
+    let (reward_collection_pubkey, bump) =
+        derive_reward_collection_account_address(&program_id, &vote_pubkey, epoch);
+
+    println!(
+        "📌 {}",
+        "Reward Collection Account".bold().underline().blue()
+    );
+    println!(
+        "   🔗 Pubkey: {}",
+        reward_collection_pubkey.to_string().bold().green()
+    );
+    println!(
+        "{} {}\n{} {}\n{} {}\n{} {}\n{} {}\n{} {}",
+        "📅 Epoch:".green(),
+        epoch,
+        "🚀 Validator commission:".green(),
+        activation_account.validator_commission_bps,
+        "🚀 Rakurai commission:".green(),
+        activation_account.block_builder_commission_bps,
+        "🔁 Rollover Unclaimed:".green(),
+        args.rollover_unclaimed,
+        "🏦 Vote Pubkey:".blue(),
+        vote_pubkey,
+        "🔗 Signer:".cyan(),
+        signer_pubkey
+    );
+
+    let initialize_instruction = initialize_reward_collection_account_ix(
+        program_id,
+        InitializeRewardCollectionAccountArgs {
+            merkle_root_upload_authority: args.merkle_root_upload_authority,
+            bump,
+            rollover_unclaimed: args.rollover_unclaimed,
+        },
+        InitializeRewardCollectionAccountAccounts {
+            config: config_pubkey,
+            signer: signer_pubkey,
+            system_program: system_program::id(),
+            reward_collection_account: reward_collection_pubkey,
+            validator_vote_account: vote_pubkey,
+            activation_account: activation_pubkey,
+            activation_config: activation_config_pubkey,
+        },
+    );
+
+    sign_and_send_transaction(rpc_client, initialize_instruction, &kp)
+}
+
+fn process_transfer_rewards(
+    rpc_client: Arc<RpcClient>,
+    kp: Arc<Keypair>,
+    program_id: Pubkey,
+    args: TransferRewardsArgs,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let signer_pubkey = kp.pubkey();
+
+    let epoch = rpc_client.get_epoch_info()?.epoch;
+    let (reward_collection_pubkey, _) =
+        derive_reward_collection_account_address(&program_id, &args.vote_pubkey, epoch);
+    let reward_collection_account =
+        get_reward_collection_account(rpc_client.clone(), reward_collection_pubkey)?;
+    if reward_collection_account.initializer != signer_pubkey {
+        return Err(format!(
+            "❌ Unauthorized Signer! Expected: {}, Found: {}",
+            reward_collection_account.initializer, signer_pubkey
+        )
+        .into());
+    }
+    if let Some(last_settled_slot) = reward_collection_account.last_settled_slot {
+        if args.start_slot <= last_settled_slot {
+            return Err(format!(
+                "❌ Slots up to {} are already settled, the turn starts at {}",
+                last_settled_slot, args.start_slot
+            )
+            .into());
+        }
+    }
+
+    println!(
+        "📌 {}",
+        "Reward Collection Account".bold().underline().blue()
+    );
+    println!(
+        "   🔗 Pubkey: {}",
+        reward_collection_pubkey.to_string().bold().green()
+    );
+    println!(
+        "{} {}..={}",
+        "🎰 Slots:".cyan(),
+        args.start_slot,
+        args.end_slot
+    );
+    let rewards = args.rewards();
+    println!(
+        "{} {}\n{} {}\n{} {}\n{} {}",
+        "💰 Block Fees:".green(),
+        rewards.block_fees.amount,
+        "💰 Priority Fees:".green(),
+        rewards.priority_fees.amount,
+        "💰 Tips:".green(),
+        rewards.tips.amount,
+        "🔗 Signer:".cyan(),
+        signer_pubkey
+    );
+
+    let transfer_instruction = transfer_staker_rewards_ix(
+        program_id,
+        TransferStakerRewardsArgs {
+            rewards,
+            start_slot: args.start_slot,
+            end_slot: args.end_slot,
+        },
+        TransferStakerRewardsAccounts {
+            rakurai_commission_account: reward_collection_account.rakurai_commission_account,
+            reward_collection_account: reward_collection_pubkey,
+            system_program: system_program::id(),
+            signer: signer_pubkey,
+        },
+    );
+    sign_and_send_transaction(rpc_client, transfer_instruction, &kp)
+}
+
+fn process_deposit_rewards(
+    rpc_client: Arc<RpcClient>,
+    kp: Arc<Keypair>,
+    program_id: Pubkey,
+    args: DepositRewardsCmdArgs,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let signer_pubkey = kp.pubkey();
+    let CollectionAccountArgs { vote_pubkey, epoch } = args.collection_account;
+
+    let (reward_collection_pubkey, _) =
+        derive_reward_collection_account_address(&program_id, &vote_pubkey, epoch);
+    let reward_collection_account =
+        get_reward_collection_account(rpc_client.clone(), reward_collection_pubkey)?;
+    if reward_collection_account.merkle_root.is_some() {
+        return Err(format!(
+            "❌ Merkle root already uploaded to {}",
+            reward_collection_pubkey
+        )
+        .into());
+    }
+
+    println!(
+        "📌 {}",
+        "Reward Collection Account".bold().underline().blue()
+    );
+    println!(
+        "   🔗 Pubkey: {}",
+        reward_collection_pubkey.to_string().bold().green()
+    );
+    println!(
+        "{} {}\n{} {:?}\n{} {}",
+        "💰 Deposit:".green(),
+        args.amount,
+        "🏷️  Source:".cyan(),
+        args.source,
+        "🔗 Depositor:".cyan(),
+        signer_pubkey
+    );
+
+    let deposit_instruction = deposit_rewards_ix(
+        program_id,
+        DepositRewardsArgs {
+            amount: args.amount,
+            source: args.source,
+        },
+        DepositRewardsAccounts {
+            reward_collection_account: reward_collection_pubkey,
+            depositor: signer_pubkey,
+            system_program: system_program::id(),
+        },
+    );
+    sign_and_send_transaction(rpc_client, deposit_instruction, &kp)
+}
+
+fn process_upload_merkle_root(
+    rpc_client: Arc<RpcClient>,
+    kp: Arc<Keypair>,
+    program_id: Pubkey,
+    args: UploadMerkleRootCmdArgs,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let signer_pubkey = kp.pubkey();
+    let CollectionAccountArgs { vote_pubkey, epoch } = args.collection_account;
+
+    let (config_pubkey, _) = derive_config_account_address(&program_id);
+    let (reward_collection_pubkey, _) =
+        derive_reward_collection_account_address(&program_id, &vote_pubkey, epoch);
+    let reward_collection_account =
+        get_reward_collection_account(rpc_client.clone(), reward_collection_pubkey)?;
+    if reward_collection_account.merkle_root_upload_authority != signer_pubkey {
+        return Err(format!(
+            "❌ Unauthorized Signer! Expected: {}, Found: {}",
+            reward_collection_account.merkle_root_upload_authority, signer_pubkey
+        )
+        .into());
+    }
+
+    println!(
+        "📌 {}",
+        "Reward Collection Account".bold().underline().blue()
+    );
+    println!(
+        "   🔗 Pubkey: {}",
+        reward_collection_pubkey.to_string().bold().green()
+    );
+    println!(
+        "{} {}\n{} {}\n{} {}\n{} {}\n{} {}",
+        "🌳 Root:".green(),
+        bs58::encode(args.root).into_string(),
+        "💰 Max Total Claim:".blue(),
+        args.max_total_claim,
+        "👥 Max Num Nodes:".blue(),
+        args.max_num_nodes,
+        "🍃 Leaf Version:".blue(),
+        args.leaf_version,
+        "🔗 Signer:".cyan(),
+        signer_pubkey
+    );
+    if args.pro_rata {
+        let account = rpc_client.get_account(&reward_collection_pubkey)?;
+        let rent = rpc_client.get_minimum_balance_for_rent_exemption(account.data.len())?;
+        let available = account.lamports.saturating_sub(rent);
+        match ClaimScale::new(available, args.max_total_claim) {
+            Some(claim_scale) => println!(
+                "{} {} / {}",
+                "⚖️ Pro-rata Scale:".yellow(),
+                claim_scale.available,
+                claim_scale.outstanding
+            ),
+            None => println!(
+                "{} {}",
+                "⚖️ Pro-rata Scale:".yellow(),
+                "claims paid in full"
+            ),
+        }
+    }
+
+    let upload_instruction = upload_merkle_root_ix(
+        program_id,
+        UploadMerkleRootArgs {
+            root: args.root,
+            max_total_claim: args.max_total_claim,
+            max_num_nodes: args.max_num_nodes,
+            version: args.leaf_version,
+            pro_rata: args.pro_rata,
+        },
+        UploadMerkleRootAccounts {
+            config: config_pubkey,
+            merkle_root_upload_authority: signer_pubkey,
+            reward_collection_account: reward_collection_pubkey,
+        },
+    );
+    sign_and_send_transaction(rpc_client, upload_instruction, &kp)
+}
+
+/// Fetches the Reward Collection Account the signer can still change: it must have initialized
+/// it, and no merkle root may be uploaded yet.
+fn get_updatable_reward_collection_account(
+    rpc_client: Arc<RpcClient>,
+    signer_pubkey: Pubkey,
+    reward_collection_pubkey: Pubkey,
+) -> Result<RewardCollectionAccount, Box<dyn std::error::Error>> {
+    let reward_collection_account =
+        get_reward_collection_account(rpc_client, reward_collection_pubkey)?;
+    if reward_collection_account.initializer != signer_pubkey {
+        return Err(format!(
+            "❌ Unauthorized Signer! Expected: {}, Found: {}",
+            reward_collection_account.initializer, signer_pubkey
+        )
+        .into());
+    }
+    if reward_collection_account.merkle_root.is_some() {
+        return Err(
+            "❌ A merkle root is already uploaded to this Reward Collection Account".into(),
+        );
+    }
+    Ok(reward_collection_account)
+}
+
+fn process_update_upload_authority(
+    rpc_client: Arc<RpcClient>,
+    kp: Arc<Keypair>,
+    program_id: Pubkey,
+    args: UpdateUploadAuthorityArgs,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let signer_pubkey = kp.pubkey();
+    let CollectionAccountArgs { vote_pubkey, epoch } = args.collection_account;
+
+    let (reward_collection_pubkey, _) =
+        derive_reward_collection_account_address(&program_id, &vote_pubkey, epoch);
+    let reward_collection_account = get_updatable_reward_collection_account(
+        rpc_client.clone(),
+        signer_pubkey,
+        reward_collection_pubkey,
+    )?;
+    let (root_attestation_pubkey, _) =
+        derive_root_attestation_address(&program_id, &reward_collection_pubkey);
+    if reward_collection_account.merkle_root_upload_authority == root_attestation_pubkey {
+        return Err(format!(
+            "❌ The upload of {} is handed to its Root Attestation {}, the root can only be attested",
+            reward_collection_pubkey, root_attestation_pubkey
+        )
+        .into());
+    }
+
+    println!(
+        "📌 {}",
+        "Reward Collection Account".bold().underline().blue()
+    );
+    println!(
+        "   🔗 Pubkey: {}",
+        reward_collection_pubkey.to_string().bold().green()
+    );
+    println!(
+        "{} {}\n{} {}\n{} {}",
+        "🔑 Old Upload Authority:".blue(),
+        reward_collection_account.merkle_root_upload_authority,
+        "🔑 New Upload Authority:".green(),
+        args.merkle_root_upload_authority,
+        "🔗 Signer:".cyan(),
+        signer_pubkey
+    );
+
+    let update_instruction = update_merkle_root_upload_authority_ix(
+        program_id,
+        UpdateMerkleRootUploadAuthorityArgs {
+            new_merkle_root_upload_authority: args.merkle_root_upload_authority,
+        },
+        UpdateMerkleRootUploadAuthorityAccounts {
+            reward_collection_account: reward_collection_pubkey,
+            signer: signer_pubkey,
+        },
+    );
+    sign_and_send_transaction(rpc_client, update_instruction, &kp)
+}
+
+fn process_init_attestation(
+    rpc_client: Arc<RpcClient>,
+    kp: Arc<Keypair>,
+    program_id: Pubkey,
+    args: InitAttestationArgs,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let signer_pubkey = kp.pubkey();
+    let CollectionAccountArgs { vote_pubkey, epoch } = args.collection_account;
+
+    let (reward_collection_pubkey, _) =
+        derive_reward_collection_account_address(&program_id, &vote_pubkey, epoch);
+    get_updatable_reward_collection_account(
+        rpc_client.clone(),
+        signer_pubkey,
+        reward_collection_pubkey,
+    )?;
+    let (root_attestation_pubkey, _) =
+        derive_root_attestation_address(&program_id, &reward_collection_pubkey);
+
+    println!("📌 {}", "Root Attestation".bold().underline().blue());
+    println!(
+        "   🔗 Pubkey: {}",
+        root_attestation_pubkey.to_string().bold().green()
+    );
+    println!(
+        "{} {}\n{} {} of {}",
+        "🏦 Reward Collection Account:".blue(),
+        reward_collection_pubkey,
+        "🤝 Threshold:".green(),
+        args.threshold,
+        args.attesters.len()
+    );
+    for attester in &args.attesters {
+        println!("{} {}", "🔏 Attester:".cyan(), attester);
+    }
+
+    let init_instruction = initialize_root_attestation_ix(
+        program_id,
+        InitializeRootAttestationArgs {
+            attesters: args.attesters,
+            threshold: args.threshold,
+        },
+        InitializeRootAttestationAccounts {
+            reward_collection_account: reward_collection_pubkey,
+            root_attestation: root_attestation_pubkey,
+            signer: signer_pubkey,
+            system_program: system_program::id(),
+        },
+    );
+    sign_and_send_transaction(rpc_client, init_instruction, &kp)
+}
+
+fn process_attest(
+    rpc_client: Arc<RpcClient>,
+    kp: Arc<Keypair>,
+    program_id: Pubkey,
+    args: UploadMerkleRootCmdArgs,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let signer_pubkey = kp.pubkey();
+    let CollectionAccountArgs { vote_pubkey, epoch } = args.collection_account;
+
+    let (config_pubkey, _) = derive_config_account_address(&program_id);
+    let (reward_collection_pubkey, _) =
+        derive_reward_collection_account_address(&program_id, &vote_pubkey, epoch);
+    let (root_attestation_pubkey, _) =
+        derive_root_attestation_address(&program_id, &reward_collection_pubkey);
+    let root_attestation =
+        get_root_attestation_account(rpc_client.clone(), root_attestation_pubkey)?;
+    if !root_attestation.attesters.contains(&signer_pubkey) {
+        return Err(format!("❌ {} is not an attester", signer_pubkey).into());
+    }
+
+    println!("📌 {}", "Root Attestation".bold().underline().blue());
+    println!(
+        "   🔗 Pubkey: {}",
+        root_attestation_pubkey.to_string().bold().green()
+    );
+    println!(
+        "{} {}\n{} {}\n{} {}\n{} {}\n{} {}\n{} {}",
+        "🏦 Reward Collection Account:".blue(),
+        reward_collection_pubkey,
+        "🌳 Root:".green(),
+        bs58::encode(args.root).into_string(),
+        "💰 Max Total Claim:".blue(),
+        args.max_total_claim,
+        "👥 Max Num Nodes:".blue(),
+        args.max_num_nodes,
+        "🍃 Leaf Version:".blue(),
+        args.leaf_version,
+        "🔏 Attester:".cyan(),
+        signer_pubkey
+    );
+
+    let attest_instruction = attest_merkle_root_ix(
+        program_id,
+        AttestMerkleRootArgs {
+            root: args.root,
+            max_total_claim: args.max_total_claim,
+            max_num_nodes: args.max_num_nodes,
+            version: args.leaf_version,
+            pro_rata: args.pro_rata,
+        },
+        AttestMerkleRootAccounts {
+            config: config_pubkey,
+            reward_collection_account: reward_collection_pubkey,
+            root_attestation: root_attestation_pubkey,
+            attester: signer_pubkey,
+        },
+    );
+    sign_and_send_transaction(rpc_client, attest_instruction, &kp)
+}
+
+fn process_close_attestation(
+    rpc_client: Arc<RpcClient>,
+    kp: Arc<Keypair>,
+    program_id: Pubkey,
+    args: CollectionAccountArgs,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let CollectionAccountArgs { vote_pubkey, epoch } = args;
+
+    let (reward_collection_pubkey, _) =
+        derive_reward_collection_account_address(&program_id, &vote_pubkey, epoch);
+    let (root_attestation_pubkey, _) =
+        derive_root_attestation_address(&program_id, &reward_collection_pubkey);
+    let root_attestation =
+        get_root_attestation_account(rpc_client.clone(), root_attestation_pubkey)?;
+
+    let current_epoch = rpc_client.get_epoch_info()?.epoch;
+    if current_epoch <= root_attestation.expires_at {
+        return Err(format!(
+            "❌ Root Attestation expires at epoch {}, current epoch: {}",
+            root_attestation.expires_at, current_epoch
+        )
+        .into());
+    }
+
+    println!("📌 {}", "Root Attestation".bold().underline().blue());
+    println!(
+        "   🔗 Pubkey: {}",
+        root_attestation_pubkey.to_string().bold().green()
+    );
+    println!("{} {}", "🔑 Rent Receiver:".blue(), root_attestation.payer);
+
+    let close_instruction = close_root_attestation_ix(
+        program_id,
+        CloseRootAttestationArgs,
+        CloseRootAttestationAccounts {
+            root_attestation: root_attestation_pubkey,
+            payer: root_attestation.payer,
+        },
+    );
+    sign_and_send_transaction(rpc_client, close_instruction, &kp)
+}
+
+fn process_update_commission(
+    rpc_client: Arc<RpcClient>,
+    kp: Arc<Keypair>,
+    program_id: Pubkey,
+    args: UpdateCommissionArgs,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let signer_pubkey = kp.pubkey();
+    let CollectionAccountArgs { vote_pubkey, epoch } = args.collection_account;
+
+    let (config_pubkey, _) = derive_config_account_address(&program_id);
+    let (reward_collection_pubkey, _) =
+        derive_reward_collection_account_address(&program_id, &vote_pubkey, epoch);
+    let reward_collection_account = get_updatable_reward_collection_account(
+        rpc_client.clone(),
+        signer_pubkey,
+        reward_collection_pubkey,
+    )?;
+
+    println!(
+        "📌 {}",
+        "Reward Collection Account".bold().underline().blue()
+    );
+    println!(
+        "   🔗 Pubkey: {}",
+        reward_collection_pubkey.to_string().bold().green()
+    );
+    let category_commissions = args.category_commissions();
+    println!(
+        "{} {} {:?}\n{} {} {:?}\n{} {}",
+        "🚀 Old Validator commission:".blue(),
+        reward_collection_account.validator_commission_bps,
+        reward_collection_account.category_commissions,
+        "🚀 New Validator commission:".green(),
+        args.validator_commission_bps,
+        category_commissions,
+        "🔗 Signer:".cyan(),
+        signer_pubkey
+    );
+
+    let update_instruction = update_validator_commission_ix(
+        program_id,
+        UpdateValidatorCommissionArgs {
+            new_validator_commission_bps: args.validator_commission_bps,
+            new_category_commissions: category_commissions,
+        },
+        UpdateValidatorCommissionAccounts {
+            config: config_pubkey,
+            reward_collection_account: reward_collection_pubkey,
+            signer: signer_pubkey,
+        },
+    );
+    sign_and_send_transaction(rpc_client, update_instruction, &kp)
+}
+
+fn process_claim(
+    rpc_client: Arc<RpcClient>,
+    kp: Arc<Keypair>,
+    program_id: Pubkey,
+    args: ClaimCmdArgs,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let signer_pubkey = kp.pubkey();
+    let ClaimStatusArgs {
+        collection_account: CollectionAccountArgs { vote_pubkey, epoch },
+        claimant,
+    } = args.claim_status;
+
+    let (config_pubkey, _) = derive_config_account_address(&program_id);
+    let (reward_collection_pubkey, _) =
+        derive_reward_collection_account_address(&program_id, &vote_pubkey, epoch);
+
+    if let Some(index) = args.index {
+        let (claim_bitmap_pubkey, _) =
+            derive_claim_bitmap_account_address(&program_id, &reward_collection_pubkey);
+        let claim_bitmap = get_claim_bitmap_account(rpc_client.clone(), claim_bitmap_pubkey)?;
+        if claim_bitmap.is_claimed(index).unwrap_or(false) {
+            return Err(format!("❌ Rewards already claimed for leaf {}", index).into());
+        }
+
+        println!("📌 {}", "Indexed Claim".bold().underline().blue());
+        println!(
+            "   🔗 Claim Bitmap: {}",
+            claim_bitmap_pubkey.to_string().bold().green()
+        );
+        println!(
+            "{} {}\n{} {}\n{} {}\n{} {}\n{} {}",
+            "🏦 Reward Collection Account:".blue(),
+            reward_collection_pubkey,
+            "🔢 Index:".green(),
+            index,
+            "👤 Claimant:".green(),
+            claimant,
+            "💰 Amount:".green(),
+            args.amount,
+            "🔗 Payer:".cyan(),
+            signer_pubkey
+        );
+
+        let claim_instruction = claim_indexed_ix(
+            program_id,
+            ClaimIndexedArgs {
+                index,
+                proof: args.proof,
+                amount: args.amount,
+            },
+            ClaimIndexedAccounts {
+                config: config_pubkey,
+                reward_collection_account: reward_collection_pubkey,
+                claim_bitmap: claim_bitmap_pubkey,
+                claimant,
+                payer: signer_pubkey,
+                system_program: system_program::id(),
+            },
+        );
+        return sign_and_send_transaction(rpc_client, claim_instruction, &kp);
+    }
+
+    let (claim_status_pubkey, _) =
+        derive_claim_status_account_address(&program_id, &claimant, &reward_collection_pubkey);
+
+    if rpc_client.get_account(&claim_status_pubkey).is_ok() {
+        return Err(format!("❌ Rewards already claimed for {}", claimant).into());
+    }
+
+    println!("📌 {}", "Claim Status Account".bold().underline().blue());
+    println!(
+        "   🔗 Pubkey: {}",
+        claim_status_pubkey.to_string().bold().green()
+    );
+    println!(
+        "{} {}\n{} {}\n{} {}\n{} {}",
+        "🏦 Reward Collection Account:".blue(),
+        reward_collection_pubkey,
+        "👤 Claimant:".green(),
+        claimant,
+        "💰 Amount:".green(),
+        args.amount,
+        "🔗 Payer:".cyan(),
+        signer_pubkey
+    );
+
+    let mut transactions = plan_claim(
+        program_id,
+        ClaimPlanAccounts {
+            config: config_pubkey,
+            reward_collection_account: reward_collection_pubkey,
+            claimant,
+            payer: signer_pubkey,
+            system_program: system_program::id(),
+        },
+        args.amount,
+        args.proof,
+        &[],
+    );
+    if transactions.len() > 1 {
+        let (proof_buffer_pubkey, _) = derive_proof_buffer_address(
+            &program_id,
+            &reward_collection_pubkey,
+            &claimant,
+            &signer_pubkey,
+        );
+        println!(
+            "   🧾 Proof Buffer: {} ({} transactions)",
+            proof_buffer_pubkey.to_string().bold().green(),
+            transactions.len()
+        );
+        // A buffer left behind by an interrupted claim is closed and written again.
+        if rpc_client.get_account(&proof_buffer_pubkey).is_ok() {
+            transactions.insert(
+                0,
+                vec![close_proof_buffer_ix(
+                    program_id,
+                    CloseProofBufferArgs,
+                    CloseProofBufferAccounts {
+                        proof_buffer: proof_buffer_pubkey,
+                        payer: signer_pubkey,
+                    },
+                )],
+            );
+        }
+    }
+
+    for instructions in transactions {
+        let signature = send_instructions(rpc_client.clone(), &instructions, &kp)?;
+        println!(
+            "✅ Transaction Confirmed \n🔗 Txn Signature: {:?}",
+            signature
+        );
+    }
+    Ok(())
+}
+
+fn process_claim_to_stake(
+    rpc_client: Arc<RpcClient>,
+    kp: Arc<Keypair>,
+    program_id: Pubkey,
+    args: ClaimToStakeCmdArgs,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let claimant = kp.pubkey();
+    let CollectionAccountArgs { vote_pubkey, epoch } = args.collection_account;
+
+    let (config_pubkey, _) = derive_config_account_address(&program_id);
+    let (reward_collection_pubkey, _) =
+        derive_reward_collection_account_address(&program_id, &vote_pubkey, epoch);
+    let (claim_status_pubkey, _) =
+        derive_claim_status_account_address(&program_id, &claimant, &reward_collection_pubkey);
+
+    if rpc_client.get_account(&claim_status_pubkey).is_ok() {
+        return Err(format!("❌ Rewards already claimed for {}", claimant).into());
+    }
+
+    println!("📌 {}", "Claim Status Account".bold().underline().blue());
+    println!(
+        "   🔗 Pubkey: {}",
+        claim_status_pubkey.to_string().bold().green()
+    );
+    println!(
+        "{} {}\n{} {}\n{} {}",
+        "🏦 Reward Collection Account:".blue(),
+        reward_collection_pubkey,
+        "👤 Claimant:".green(),
+        claimant,
+        "💰 Amount:".green(),
+        args.amount,
+    );
+
+    let (instruction, stake_account) = if let (Some(stake_pool_pubkey), Some(pool_token_account)) =
+        (args.stake_pool, args.pool_token_account)
+    {
+        let stake_pool_account = rpc_client.get_account(&stake_pool_pubkey)?;
+        let stake_pool = try_from_slice_unchecked::<StakePool>(&stake_pool_account.data)?;
+        let (deposit_account, _) =
+            derive_stake_pool_deposit_address(&program_id, &reward_collection_pubkey);
+        println!(
+            "   🏊 Stake Pool: {}",
+            stake_pool_pubkey.to_string().bold().green()
+        );
+
+        let instruction = claim_to_stake_pool_ix(
+            program_id,
+            ClaimToStakePoolArgs {
+                proof: args.proof,
+                amount: args.amount,
+            },
+            ClaimToStakePoolAccounts {
+                config: config_pubkey,
+                reward_collection_account: reward_collection_pubkey,
+                claim_status: claim_status_pubkey,
+                claimant,
+                deposit_account,
+                stake_pool: stake_pool_pubkey,
+                stake_pool_withdraw_authority: find_withdraw_authority_program_address(
+                    &spl_stake_pool::ID,
+                    &stake_pool_pubkey,
+                )
+                .0,
+                reserve_stake: stake_pool.reserve_stake,
+                pool_tokens_to: pool_token_account,
+                manager_fee_account: stake_pool.manager_fee_account,
+                pool_mint: stake_pool.pool_mint,
+                token_program: stake_pool.token_program_id,
+                stake_pool_program: spl_stake_pool::ID,
+                payer: claimant,
+                system_program: system_program::id(),
+            },
+        );
+        (instruction, None)
+    } else {
+        let stake_account = Keypair::new();
+        println!(
+            "   🥩 Stake Account: {}",
+            stake_account.pubkey().to_string().bold().green()
+        );
+
+        let instruction = claim_to_stake_account_ix(
+            program_id,
+            ClaimToStakeAccountArgs {
+                proof: args.proof,
+                amount: args.amount,
+            },
+            ClaimToStakeAccountAccounts {
+                config: config_pubkey,
+                reward_collection_account: reward_collection_pubkey,
+                claim_status: claim_status_pubkey,
+                claimant,
+                stake_account: stake_account.pubkey(),
+                validator_vote_account: vote_pubkey,
+                #[allow(deprecated)]
+                stake_config: stake::config::ID,
+                stake_history: sysvar::stake_history::ID,
+                clock: sysvar::clock::ID,
+                rent: sysvar::rent::ID,
+                stake_program: stake::program::ID,
+                payer: claimant,
+                system_program: system_program::id(),
+            },
+        );
+        (instruction, Some(stake_account))
+    };
+
+    let mut signers = vec![kp.as_ref()];
+    signers.extend(stake_account.as_ref());
+    let transaction = Transaction::new(
+        &signers,
+        Message::new(&[instruction], Some(&claimant)),
+        rpc_client.get_latest_blockhash()?,
+    );
+    let signature = rpc_client.send_and_confirm_transaction(&transaction)?;
+    println!(
+        "✅ Transaction Confirmed \n🔗 Txn Signature: {:?}",
+        signature
+    );
+    Ok(())
+}
+
+fn process_close_claim_status(
+    rpc_client: Arc<RpcClient>,
+    kp: Arc<Keypair>,
+    program_id: Pubkey,
+    args: ClaimStatusArgs,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let CollectionAccountArgs { vote_pubkey, epoch } = args.collection_account;
+
+    let (config_pubkey, _) = derive_config_account_address(&program_id);
+    let (reward_collection_pubkey, _) =
+        derive_reward_collection_account_address(&program_id, &vote_pubkey, epoch);
+    let (claim_status_pubkey, _) =
+        derive_claim_status_account_address(&program_id, &args.claimant, &reward_collection_pubkey);
+    let claim_status = get_claim_status_account(rpc_client.clone(), claim_status_pubkey)?;
+
+    let current_epoch = rpc_client.get_epoch_info()?.epoch;
+    if current_epoch <= claim_status.expires_at {
+        return Err(format!(
+            "❌ Claim Status account expires at epoch {}, current epoch: {}",
+            claim_status.expires_at, current_epoch
+        )
+        .into());
+    }
+
+    println!("📌 {}", "Claim Status Account".bold().underline().blue());
+    println!(
+        "   🔗 Pubkey: {}",
+        claim_status_pubkey.to_string().bold().green()
+    );
+    println!(
+        "{} {}",
+        "🔑 Rent Receiver:".blue(),
+        claim_status.claim_status_payer
+    );
+
+    let close_instruction = close_claim_status_ix(
+        program_id,
+        CloseClaimStatusArgs,
+        CloseClaimStatusAccounts {
+            config: config_pubkey,
+            claim_status: claim_status_pubkey,
+            claim_status_payer: claim_status.claim_status_payer,
+        },
+    );
+    sign_and_send_transaction(rpc_client, close_instruction, &kp)
+}
+
+fn process_migrate(
+    rpc_client: Arc<RpcClient>,
+    kp: Arc<Keypair>,
+    program_id: Pubkey,
+    args: CollectionAccountArgs,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let signer_pubkey = kp.pubkey();
+    let CollectionAccountArgs { vote_pubkey, epoch } = args;
+
+    let (reward_collection_pubkey, _) =
+        derive_reward_collection_account_address(&program_id, &vote_pubkey, epoch);
+
+    // A version 0 account does not deserialize, so any account that does is already current.
+    if let Ok(reward_collection_account) =
+        get_reward_collection_account(rpc_client.clone(), reward_collection_pubkey)
+    {
+        return Err(format!(
+            "❌ Reward Collection Account {} is already at version {}",
+            reward_collection_pubkey, reward_collection_account.account_version
+        )
+        .into());
+    }
+
+    println!(
+        "📌 {}",
+        "Reward Collection Account".bold().underline().blue()
+    );
+    println!(
+        "   🔗 Pubkey: {}",
+        reward_collection_pubkey.to_string().bold().green()
+    );
+    println!(
+        "{} {}\n{} {}",
+        "🆕 Account Version:".green(),
+        RewardCollectionAccount::CURRENT_VERSION,
+        "🔗 Payer:".cyan(),
+        signer_pubkey
+    );
+
+    let migrate_instruction = migrate_reward_collection_account_ix(
+        program_id,
+        MigrateRewardCollectionAccountArgs,
+        MigrateRewardCollectionAccountAccounts {
+            reward_collection_account: reward_collection_pubkey,
+            payer: signer_pubkey,
+            system_program: system_program::id(),
+        },
+    );
+    sign_and_send_transaction(rpc_client, migrate_instruction, &kp)
+}
+
+fn process_init_claim_bitmap(
+    rpc_client: Arc<RpcClient>,
+    kp: Arc<Keypair>,
+    program_id: Pubkey,
+    args: CollectionAccountArgs,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let signer_pubkey = kp.pubkey();
+    let CollectionAccountArgs { vote_pubkey, epoch } = args;
+
+    let (config_pubkey, _) = derive_config_account_address(&program_id);
+    let (reward_collection_pubkey, _) =
+        derive_reward_collection_account_address(&program_id, &vote_pubkey, epoch);
+    let (claim_bitmap_pubkey, _) =
+        derive_claim_bitmap_account_address(&program_id, &reward_collection_pubkey);
+
+    if rpc_client.get_account(&claim_bitmap_pubkey).is_ok() {
+        return Err(format!("❌ Claim Bitmap {} already exists", claim_bitmap_pubkey).into());
+    }
+
+    println!("📌 {}", "Claim Bitmap".bold().underline().blue());
+    println!(
+        "   🔗 Pubkey: {}",
+        claim_bitmap_pubkey.to_string().bold().green()
+    );
+    println!(
+        "{} {}\n{} {}",
+        "🏦 Reward Collection Account:".blue(),
+        reward_collection_pubkey,
+        "🔗 Payer:".cyan(),
+        signer_pubkey
+    );
+
+    let init_instruction = initialize_claim_bitmap_ix(
+        program_id,
+        InitializeClaimBitmapArgs,
+        InitializeClaimBitmapAccounts {
+            config: config_pubkey,
+            reward_collection_account: reward_collection_pubkey,
+            claim_bitmap: claim_bitmap_pubkey,
+            payer: signer_pubkey,
+            system_program: system_program::id(),
+        },
+    );
+    sign_and_send_transaction(rpc_client, init_instruction, &kp)
+}
+
+fn process_close_claim_bitmap(
+    rpc_client: Arc<RpcClient>,
+    kp: Arc<Keypair>,
+    program_id: Pubkey,
+    args: CollectionAccountArgs,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let CollectionAccountArgs { vote_pubkey, epoch } = args;
+
+    let (reward_collection_pubkey, _) =
+        derive_reward_collection_account_address(&program_id, &vote_pubkey, epoch);
+    let (claim_bitmap_pubkey, _) =
+        derive_claim_bitmap_account_address(&program_id, &reward_collection_pubkey);
+    let claim_bitmap = get_claim_bitmap_account(rpc_client.clone(), claim_bitmap_pubkey)?;
+
+    let current_epoch = rpc_client.get_epoch_info()?.epoch;
+    if current_epoch <= claim_bitmap.expires_at {
+        return Err(format!(
+            "❌ Claim Bitmap expires at epoch {}, current epoch: {}",
+            claim_bitmap.expires_at, current_epoch
+        )
+        .into());
+    }
+
+    println!("📌 {}", "Claim Bitmap".bold().underline().blue());
+    println!(
+        "   🔗 Pubkey: {}",
+        claim_bitmap_pubkey.to_string().bold().green()
+    );
+    println!("{} {}", "🔑 Rent Receiver:".blue(), claim_bitmap.payer);
+
+    let close_instruction = close_claim_bitmap_ix(
+        program_id,
+        CloseClaimBitmapArgs,
+        CloseClaimBitmapAccounts {
+            claim_bitmap: claim_bitmap_pubkey,
+            payer: claim_bitmap.payer,
+        },
+    );
+    sign_and_send_transaction(rpc_client, close_instruction, &kp)
+}
+
+fn process_close(
+    rpc_client: Arc<RpcClient>,
+    kp: Arc<Keypair>,
+    program_id: Pubkey,
+    args: CollectionAccountArgs,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let signer_pubkey = kp.pubkey();
+    let CollectionAccountArgs { vote_pubkey, epoch } = args;
+
+    let (config_pubkey, _) = derive_config_account_address(&program_id);
+    let (reward_collection_pubkey, _) =
+        derive_reward_collection_account_address(&program_id, &vote_pubkey, epoch);
+    let reward_collection_account =
+        get_reward_collection_account(rpc_client.clone(), reward_collection_pubkey)?;
+
+    let current_epoch = rpc_client.get_epoch_info()?.epoch;
+    let fully_claimed = reward_collection_account
+        .merkle_root
+        .as_ref()
+        .is_some_and(MerkleRoot::is_fully_claimed);
+    if current_epoch <= reward_collection_account.expires_at && !fully_claimed {
+        return Err(format!(
+            "❌ Reward Collection Account expires at epoch {}, current epoch: {}, and not every node has claimed",
+            reward_collection_account.expires_at, current_epoch
+        )
+        .into());
+    }
+    if reward_collection_account.rollover_unclaimed && !fully_claimed {
+        return Err(format!(
+            "❌ Unclaimed funds of {} roll over, use the rollover command",
+            reward_collection_pubkey
+        )
+        .into());
+    }
+
+    println!(
+        "📌 {}",
+        "Reward Collection Account".bold().underline().blue()
+    );
+    println!(
+        "   🔗 Pubkey: {}",
+        reward_collection_pubkey.to_string().bold().green()
+    );
+    println!(
+        "{} {}\n{} {}\n{} {}",
+        "🔑 Unclaimed Funds Receiver:".blue(),
+        reward_collection_account.initializer,
+        "🏦 Rent Receiver:".blue(),
+        vote_pubkey,
+        "🔗 Signer:".cyan(),
+        signer_pubkey
+    );
+
+    let close_instruction = close_reward_collection_account_ix(
+        program_id,
+        CloseRewardCollectionAccountArgs { _epoch: epoch },
+        CloseRewardCollectionAccounts {
+            config: config_pubkey,
+            initializer: reward_collection_account.initializer,
+            reward_collection_account: reward_collection_pubkey,
+            validator_vote_account: vote_pubkey,
+            signer: signer_pubkey,
+        },
+    );
+    sign_and_send_transaction(rpc_client, close_instruction, &kp)
+}
+
+fn process_rollover(
+    rpc_client: Arc<RpcClient>,
+    kp: Arc<Keypair>,
+    program_id: Pubkey,
+    args: CollectionAccountArgs,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let CollectionAccountArgs { vote_pubkey, epoch } = args;
+
+    let (reward_collection_pubkey, _) =
+        derive_reward_collection_account_address(&program_id, &vote_pubkey, epoch);
+    let reward_collection_account =
+        get_reward_collection_account(rpc_client.clone(), reward_collection_pubkey)?;
+    if !reward_collection_account.rollover_unclaimed {
+        return Err(format!(
+            "❌ {} was not created to roll its unclaimed funds over, use the close command",
+            reward_collection_pubkey
+        )
+        .into());
+    }
+
+    let current_epoch = rpc_client.get_epoch_info()?.epoch;
+    if current_epoch <= reward_collection_account.expires_at {
+        return Err(format!(
+            "❌ Reward Collection Account expires at epoch {}, current epoch: {}",
+            reward_collection_account.expires_at, current_epoch
+        )
+        .into());
+    }
+
+    let (current_reward_collection_pubkey, _) =
+        derive_reward_collection_account_address(&program_id, &vote_pubkey, current_epoch);
+    match get_reward_collection_account(rpc_client.clone(), current_reward_collection_pubkey) {
+        Ok(current_reward_collection_account)
+            if current_reward_collection_account.account_version
+                == RewardCollectionAccount::CURRENT_VERSION
+                && current_reward_collection_account.merkle_root.is_none() => {}
+        _ => {
+            return Err(format!(
+                "❌ {} can not take the unclaimed funds yet, it must exist at the current version without a merkle root",
+                current_reward_collection_pubkey
+            )
+            .into());
+        }
+    }
+
+    println!(
+        "📌 {}",
+        "Reward Collection Account".bold().underline().blue()
+    );
+    println!(
+        "   🔗 Pubkey: {}",
+        reward_collection_pubkey.to_string().bold().green()
+    );
+    println!(
+        "{} {}\n{} {}",
+        "🔁 Unclaimed Funds Receiver:".blue(),
+        current_reward_collection_pubkey,
+        "🏦 Rent Receiver:".blue(),
+        vote_pubkey,
+    );
+
+    let rollover_instruction = rollover_unclaimed_rewards_ix(
+        program_id,
+        RolloverUnclaimedRewardsArgs { epoch },
+        RolloverUnclaimedRewardsAccounts {
+            reward_collection_account: reward_collection_pubkey,
+            current_reward_collection_account: current_reward_collection_pubkey,
+            validator_vote_account: vote_pubkey,
+        },
+    );
+    sign_and_send_transaction(rpc_client, rollover_instruction, &kp)
+}
+
+fn process_withdraw_surplus(
+    rpc_client: Arc<RpcClient>,
+    kp: Arc<Keypair>,
+    program_id: Pubkey,
+    args: CollectionAccountArgs,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let signer_pubkey = kp.pubkey();
+    let CollectionAccountArgs { vote_pubkey, epoch } = args;
+
+    let (reward_collection_pubkey, _) =
+        derive_reward_collection_account_address(&program_id, &vote_pubkey, epoch);
+    let reward_collection_account =
+        get_reward_collection_account(rpc_client.clone(), reward_collection_pubkey)?;
+    if reward_collection_account.initializer != signer_pubkey {
+        return Err(format!(
+            "❌ Unauthorized Signer! Expected: {}, Found: {}",
+            reward_collection_account.initializer, signer_pubkey
+        )
+        .into());
+    }
+    let merkle_root = reward_collection_account
+        .merkle_root
+        .as_ref()
+        .ok_or(format!(
+            "❌ No merkle root uploaded to {}",
+            reward_collection_pubkey
+        ))?;
+
+    let account = rpc_client.get_account(&reward_collection_pubkey)?;
+    let rent = rpc_client.get_minimum_balance_for_rent_exemption(account.data.len())?;
+    let outstanding_payout = reward_collection_account.payout(
+        merkle_root
+            .max_total_claim
+            .saturating_sub(merkle_root.total_funds_claimed),
+    )?;
+    let surplus = account
+        .lamports
+        .saturating_sub(rent)
+        .saturating_sub(outstanding_payout);
+    if surplus == 0 {
+        return Err(format!(
+            "❌ {} holds no funds beyond its rent and outstanding claims",
+            reward_collection_pubkey
+        )
+        .into());
+    }
+
+    println!(
+        "📌 {}",
+        "Reward Collection Account".bold().underline().blue()
+    );
+    println!(
+        "   🔗 Pubkey: {}",
+        reward_collection_pubkey.to_string().bold().green()
+    );
+    println!(
+        "{} {}\n{} {}",
+        "💰 Surplus:".green(),
+        surplus,
+        "🔑 Receiver:".blue(),
+        signer_pubkey
+    );
+
+    let withdraw_instruction = withdraw_surplus_ix(
+        program_id,
+        WithdrawSurplusArgs,
+        WithdrawSurplusAccounts {
+            reward_collection_account: reward_collection_pubkey,
+            initializer: signer_pubkey,
+        },
+    );
+    sign_and_send_transaction(rpc_client, withdraw_instruction, &kp)
+}
+
+fn process_show(
+    rpc_client: Arc<RpcClient>,
+    program_id: Pubkey,
+    args: CollectionAccountArgs,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let (reward_collection_pubkey, _) =
+        derive_reward_collection_account_address(&program_id, &args.vote_pubkey, args.epoch);
+
+    let reward_collection_account =
+        get_reward_collection_account(rpc_client.clone(), reward_collection_pubkey)?;
+    let balance = rpc_client.get_balance(&reward_collection_pubkey)?;
+    println!(
+        "📌 {}",
+        "Reward Collection Account".bold().underline().blue()
+    );
+    println!(
+        "   🔗 Pubkey: {}",
+        reward_collection_pubkey.to_string().bold().green()
+    );
+    println!("   💰 Balance: {}", balance.to_string().magenta());
+    display_reward_collection_account(reward_collection_account);
+
+    let (root_attestation_pubkey, _) =
+        derive_root_attestation_address(&program_id, &reward_collection_pubkey);
+    if let Ok(root_attestation) = get_root_attestation_account(rpc_client, root_attestation_pubkey)
+    {
+        display_root_attestation_account(root_attestation);
+    }
+    Ok(())
+}
+
+fn process_show_claim_status(
+    rpc_client: Arc<RpcClient>,
+    program_id: Pubkey,
+    args: ClaimStatusArgs,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let CollectionAccountArgs { vote_pubkey, epoch } = args.collection_account;
+
+    let (reward_collection_pubkey, _) =
+        derive_reward_collection_account_address(&program_id, &vote_pubkey, epoch);
+    let (claim_status_pubkey, _) =
+        derive_claim_status_account_address(&program_id, &args.claimant, &reward_collection_pubkey);
+
+    let claim_status = get_claim_status_account(rpc_client.clone(), claim_status_pubkey)?;
+    println!("📌 {}", "Claim Status Account".bold().underline().blue());
+    println!(
+        "   🔗 Pubkey: {}",
+        claim_status_pubkey.to_string().bold().green()
+    );
+    display_claim_status_account(claim_status);
+    Ok(())
+}
+
+fn process_generate_distribution(
+    rpc_client: Arc<RpcClient>,
+    program_id: Pubkey,
+    args: GenerateDistributionArgs,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let CollectionAccountArgs { vote_pubkey, epoch } = args.collection_account;
+
+    let (reward_collection_pubkey, _) =
+        derive_reward_collection_account_address(&program_id, &vote_pubkey, epoch);
+    let total_rewards = match args.total_rewards {
+        Some(total_rewards) => total_rewards,
+        None => {
+            let account = rpc_client.get_account(&reward_collection_pubkey)?;
+            let rent = rpc_client.get_minimum_balance_for_rent_exemption(account.data.len())?;
+            account.lamports.saturating_sub(rent)
+        }
+    };
+
+    let policy = match &args.policy {
+        Some(path) => DistributionPolicy::read(path)?,
+        None => DistributionPolicy::default(),
+    };
+    let mut audit_report = AuditReport::new(vote_pubkey, epoch, &policy);
+
+    let stake_accounts = load_stake_accounts(&args.stake_accounts, args.format)?;
+    let stake_accounts =
+        policy.apply_to_stake_accounts(&vote_pubkey, epoch, stake_accounts, &mut audit_report)?;
+    let weights = stake_weights(&vote_pubkey, epoch, &stake_accounts)?;
+    let mut distribution = DistributionFile::new(
+        vote_pubkey,
+        epoch,
+        reward_collection_pubkey,
+        total_rewards,
+        weights,
+    )?;
+    policy.apply_to_distribution(&mut distribution, &mut audit_report)?;
+    distribution.indexed = args.indexed;
+    distribution.write(&args.output)?;
+    if let Some(path) = &args.audit_report {
+        audit_report.write(path)?;
+    }
+
+    println!("📌 {}", "Distribution".bold().underline().blue());
+    println!(
+        "   🔗 Reward Collection Account: {}",
+        reward_collection_pubkey.to_string().bold().green()
+    );
+    println!(
+        "{} {}\n{} {}\n{} {}\n{} {}\n{} {}",
+        "💰 Total Rewards:".green(),
+        distribution.total_rewards,
+        "🥩 Total Stake:".green(),
+        distribution.total_stake,
+        "💰 Max Total Claim:".blue(),
+        distribution.max_total_claim,
+        "👥 Max Num Nodes:".blue(),
+        distribution.max_num_nodes,
+        "📝 Output:".cyan(),
+        args.output.display()
+    );
+    if !audit_report.adjustments.is_empty() || args.audit_report.is_some() {
+        println!(
+            "{} {}\n{} {}",
+            "⚖️ Policy Adjustments:".yellow(),
+            audit_report.adjustments.len(),
+            "🪙 Unallocated:".yellow(),
+            audit_report.unallocated
+        );
+    }
+    if let Some(path) = &args.audit_report {
+        println!("{} {}", "🧾 Audit Report:".cyan(), path.display());
+    }
+    Ok(())
+}
+
+fn process_init_distributor(
+    rpc_client: Arc<RpcClient>,
+    kp: Arc<Keypair>,
+    program_id: Pubkey,
+    args: InitDistributorArgs,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let signer_pubkey = kp.pubkey();
+    let vote_pubkey = args.distributor.vote_pubkey;
+
+    let vote_state = get_vote_account(rpc_client.clone(), vote_pubkey)?;
+    if vote_state.node_pubkey != signer_pubkey {
+        return Err(format!(
+            "❌ Unauthorized signer! Expected: {:?}, Found: {:?}",
+            vote_state.node_pubkey, signer_pubkey
+        )
+        .into());
+    }
+
+    let (config_pubkey, _) = derive_config_account_address(&program_id);
+    let (distributor_pubkey, _) = derive_cumulative_distributor_address(&program_id, &vote_pubkey);
+
+    println!("📌 {}", "Cumulative Distributor".bold().underline().blue());
+    println!(
+        "   🔗 Pubkey: {}",
+        distributor_pubkey.to_string().bold().green()
+    );
+    println!(
+        "{} {}\n{} {}\n{} {}",
+        "🏦 Vote Pubkey:".blue(),
+        vote_pubkey,
+        "🔑 Upload Authority:".green(),
+        args.merkle_root_upload_authority,
+        "🔗 Signer:".cyan(),
+        signer_pubkey
+    );
+
+    let initialize_instruction = initialize_cumulative_distributor_ix(
+        program_id,
+        InitializeCumulativeDistributorArgs {
+            merkle_root_upload_authority: args.merkle_root_upload_authority,
+        },
+        InitializeCumulativeDistributorAccounts {
+            config: config_pubkey,
+            distributor: distributor_pubkey,
+            validator_vote_account: vote_pubkey,
+            signer: signer_pubkey,
+            system_program: system_program::id(),
+        },
+    );
+    sign_and_send_transaction(rpc_client, initialize_instruction, &kp)
+}
+
+fn process_sweep_to_distributor(
+    rpc_client: Arc<RpcClient>,
+    kp: Arc<Keypair>,
+    program_id: Pubkey,
+    args: CollectionAccountArgs,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let signer_pubkey = kp.pubkey();
+    let CollectionAccountArgs { vote_pubkey, epoch } = args;
+
+    let (distributor_pubkey, _) = derive_cumulative_distributor_address(&program_id, &vote_pubkey);
+    let (reward_collection_pubkey, _) =
+        derive_reward_collection_account_address(&program_id, &vote_pubkey, epoch);
+    let reward_collection_account = get_updatable_reward_collection_account(
+        rpc_client.clone(),
+        signer_pubkey,
+        reward_collection_pubkey,
+    )?;
+
+    let account = rpc_client.get_account(&reward_collection_pubkey)?;
+    let rent = rpc_client.get_minimum_balance_for_rent_exemption(account.data.len())?;
+
+    println!(
+        "📌 {}",
+        "Sweep to Cumulative Distributor".bold().underline().blue()
+    );
+    println!(
+        "   🔗 Cumulative Distributor: {}",
+        distributor_pubkey.to_string().bold().green()
+    );
+    println!(
+        "{} {}\n{} {}\n{} {}\n{} {}",
+        "🏦 Reward Collection Account:".blue(),
+        reward_collection_pubkey,
+        "📅 Epoch:".green(),
+        reward_collection_account.creation_epoch,
+        "💰 Amount:".green(),
+        account.lamports.saturating_sub(rent),
+        "🔗 Signer:".cyan(),
+        signer_pubkey
+    );
+
+    let sweep_instruction = sweep_to_cumulative_distributor_ix(
+        program_id,
+        SweepToCumulativeDistributorArgs { epoch },
+        SweepToCumulativeDistributorAccounts {
+            reward_collection_account: reward_collection_pubkey,
+            distributor: distributor_pubkey,
+            signer: signer_pubkey,
+        },
+    );
+    sign_and_send_transaction(rpc_client, sweep_instruction, &kp)
+}
+
+fn process_generate_cumulative_distribution(
+    program_id: Pubkey,
+    args: GenerateCumulativeDistributionArgs,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let vote_pubkey = args.distributor.vote_pubkey;
+    let (distributor_pubkey, _) = derive_cumulative_distributor_address(&program_id, &vote_pubkey);
+
+    let mut cumulative = match &args.previous {
+        Some(path) => CumulativeDistributionFile::read(path)?,
+        None => CumulativeDistributionFile::new(vote_pubkey, distributor_pubkey),
+    };
+    if cumulative.distributor != distributor_pubkey {
+        return Err(format!(
+            "❌ Cumulative distribution is for distributor {}, expected {}",
+            cumulative.distributor, distributor_pubkey
+        )
+        .into());
+    }
+    for path in &args.distributions {
+        cumulative.add_distribution(&DistributionFile::read(path)?)?;
+    }
+    let tree = cumulative.merkle_tree()?;
+    cumulative.write(&args.output)?;
+
+    println!("📌 {}", "Cumulative Distribution".bold().underline().blue());
+    println!(
+        "   🔗 Cumulative Distributor: {}",
+        distributor_pubkey.to_string().bold().green()
+    );
+    println!(
+        "{} {:?}\n{} {}\n{} {}\n{} {}\n{} {}",
+        "📅 Epochs:".green(),
+        cumulative.epochs,
+        "🌳 Root:".green(),
+        bs58::encode(tree.root).into_string(),
+        "💰 Max Total Claim:".blue(),
+        cumulative.max_total_claim,
+        "👥 Claimants:".blue(),
+        cumulative.claims.len(),
+        "📝 Output:".cyan(),
+        args.output.display()
+    );
+    Ok(())
+}
+
+fn process_upload_cumulative_root(
+    rpc_client: Arc<RpcClient>,
+    kp: Arc<Keypair>,
+    program_id: Pubkey,
+    args: UploadCumulativeRootArgs,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let signer_pubkey = kp.pubkey();
+
+    let (distributor_pubkey, _) =
+        derive_cumulative_distributor_address(&program_id, &args.distributor.vote_pubkey);
+    let distributor = get_cumulative_distributor_account(rpc_client.clone(), distributor_pubkey)?;
+    if distributor.merkle_root_upload_authority != signer_pubkey {
+        return Err(format!(
+            "❌ Unauthorized Signer! Expected: {}, Found: {}",
+            distributor.merkle_root_upload_authority, signer_pubkey
+        )
+        .into());
+    }
+    if args.max_total_claim > distributor.total_funds_deposited {
+        return Err(format!(
+            "❌ Max total claim {} exceeds the {} lamports swept into the distributor",
+            args.max_total_claim, distributor.total_funds_deposited
+        )
+        .into());
+    }
+
+    println!("📌 {}", "Cumulative Distributor".bold().underline().blue());
+    println!(
+        "   🔗 Pubkey: {}",
+        distributor_pubkey.to_string().bold().green()
+    );
+    println!(
+        "{} {}\n{} {}\n{} {}",
+        "🌳 Root:".green(),
+        bs58::encode(args.root).into_string(),
+        "💰 Max Total Claim:".blue(),
+        args.max_total_claim,
+        "🔗 Signer:".cyan(),
+        signer_pubkey
+    );
+
+    let upload_instruction = upload_cumulative_merkle_root_ix(
+        program_id,
+        UploadCumulativeMerkleRootArgs {
+            root: args.root,
+            max_total_claim: args.max_total_claim,
+        },
+        UploadCumulativeMerkleRootAccounts {
+            distributor: distributor_pubkey,
+            merkle_root_upload_authority: signer_pubkey,
+        },
+    );
+    sign_and_send_transaction(rpc_client, upload_instruction, &kp)
+}
+
+fn process_claim_aggregated(
+    rpc_client: Arc<RpcClient>,
+    kp: Arc<Keypair>,
+    program_id: Pubkey,
+    args: ClaimAggregatedCmdArgs,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let signer_pubkey = kp.pubkey();
+    let claimant = args.claimant;
+
+    let mut entries = Vec::new();
+    for path in &args.distributions {
+        let distribution = DistributionFile::read(path)?;
+        let reward_collection_pubkey = distribution.reward_collection_account;
+        if distribution.indexed {
+            println!(
+                "⚠️ Skipping {}: indexed merkle trees are claimed through the Claim Bitmap",
+                reward_collection_pubkey
+            );
+            continue;
+        }
+        let merkle_tree = distribution.merkle_tree()?;
+        let Some(node) = merkle_tree.find_node(&claimant) else {
+            println!(
+                "⚠️ Skipping {}: {} is not in the distribution",
+                reward_collection_pubkey, claimant
+            );
+            continue;
+        };
+        let (claim_status_pubkey, _) =
+            derive_claim_status_account_address(&program_id, &claimant, &reward_collection_pubkey);
+        if rpc_client.get_account(&claim_status_pubkey).is_ok() {
+            println!(
+                "⚠️ Skipping {}: rewards already claimed",
+                reward_collection_pubkey
+            );
+            continue;
+        }
+
+        println!(
+            "{} {} {} {}",
+            "🏦 Reward Collection Account:".blue(),
+            reward_collection_pubkey,
+            "💰 Amount:".green(),
+            node.amount
+        );
+        entries.push(AggregatedClaimEntry {
+            reward_collection_account: reward_collection_pubkey,
+            claim_status: claim_status_pubkey,
+            amount: node.amount,
+            proof: node.proof.clone(),
+        });
+    }
+    if entries.is_empty() {
+        return Err(format!("❌ Nothing to claim for {}", claimant).into());
+    }
+
+    let lookup_tables = match args.lookup_table {
+        Some(lookup_table) => vec![get_address_lookup_table(rpc_client.clone(), lookup_table)?],
+        None => vec![],
+    };
+    let (config_pubkey, _) = derive_config_account_address(&program_id);
+    let num_claims = entries.len();
+    let instructions = pack_aggregated_claims(
+        program_id,
+        PackAccounts {
+            config: config_pubkey,
+            claimant,
+            payer: signer_pubkey,
+            system_program: system_program::id(),
+        },
+        entries,
+        &[],
+        &lookup_tables,
+    );
+
+    println!("📌 {}", "Aggregated Claim".bold().underline().blue());
+    println!(
+        "{} {}\n{} {}\n{} {}\n{} {}",
+        "👤 Claimant:".green(),
+        claimant,
+        "🧾 Claims:".green(),
+        num_claims,
+        "📦 Transactions:".green(),
+        instructions.len(),
+        "🔗 Payer:".cyan(),
+        signer_pubkey
+    );
+
+    for instruction in instructions {
+        let signature = if lookup_tables.is_empty() {
+            send_instructions(rpc_client.clone(), &[instruction], &kp)?
+        } else {
+            send_versioned_instructions(rpc_client.clone(), &[instruction], &kp, &lookup_tables)?
+        };
+        println!(
+            "✅ Transaction Confirmed \n🔗 Txn Signature: {:?}",
+            signature
+        );
+    }
+    Ok(())
+}
+
+fn process_claim_cumulative(
+    rpc_client: Arc<RpcClient>,
+    kp: Arc<Keypair>,
+    program_id: Pubkey,
+    args: ClaimCumulativeCmdArgs,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let signer_pubkey = kp.pubkey();
+    let claimant = args.claimant;
+
+    let (distributor_pubkey, _) =
+        derive_cumulative_distributor_address(&program_id, &args.distributor.vote_pubkey);
+    let (claim_status_pubkey, _) =
+        derive_cumulative_claim_status_address(&program_id, &claimant, &distributor_pubkey);
+
+    let claimed_amount =
+        get_cumulative_claim_status_account(rpc_client.clone(), claim_status_pubkey)
+            .map_or(0, |claim_status| claim_status.claimed_amount);
+    if args.total_amount <= claimed_amount {
+        return Err(format!(
+            "❌ {} already claimed {} of a lifetime total of {}",
+            claimant, claimed_amount, args.total_amount
+        )
+        .into());
+    }
+
+    println!("📌 {}", "Cumulative Claim Status".bold().underline().blue());
+    println!(
+        "   🔗 Pubkey: {}",
+        claim_status_pubkey.to_string().bold().green()
+    );
+    println!(
+        "{} {}\n{} {}\n{} {}\n{} {}\n{} {}",
+        "🏛️ Cumulative Distributor:".blue(),
+        distributor_pubkey,
+        "👤 Claimant:".green(),
+        claimant,
+        "💰 Lifetime Total:".green(),
+        args.total_amount,
+        "💸 Amount:".green(),
+        args.total_amount - claimed_amount,
+        "🔗 Payer:".cyan(),
+        signer_pubkey
+    );
+
+    let claim_instruction = claim_cumulative_ix(
+        program_id,
+        ClaimCumulativeArgs {
+            total_amount: args.total_amount,
+            proof: args.proof,
+        },
+        ClaimCumulativeAccounts {
+            distributor: distributor_pubkey,
+            claim_status: claim_status_pubkey,
+            claimant,
+            payer: signer_pubkey,
+            system_program: system_program::id(),
+        },
+    );
+    sign_and_send_transaction(rpc_client, claim_instruction, &kp)
+}
+
+fn process_show_distributor(
+    rpc_client: Arc<RpcClient>,
+    program_id: Pubkey,
+    args: ShowDistributorArgs,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let (distributor_pubkey, _) =
+        derive_cumulative_distributor_address(&program_id, &args.distributor.vote_pubkey);
+
+    let distributor = get_cumulative_distributor_account(rpc_client.clone(), distributor_pubkey)?;
+    let balance = rpc_client.get_balance(&distributor_pubkey)?;
+    println!("📌 {}", "Cumulative Distributor".bold().underline().blue());
+    println!(
+        "   🔗 Pubkey: {}",
+        distributor_pubkey.to_string().bold().green()
+    );
+    println!("   💰 Balance: {}", balance.to_string().magenta());
+    display_cumulative_distributor_account(distributor);
+
+    if let Some(claimant) = args.claimant {
+        let (claim_status_pubkey, _) =
+            derive_cumulative_claim_status_address(&program_id, &claimant, &distributor_pubkey);
+        let claim_status =
+            get_cumulative_claim_status_account(rpc_client.clone(), claim_status_pubkey)?;
+        println!(
+            "   🔗 Claim Status: {}",
+            claim_status_pubkey.to_string().bold().green()
+        );
+        display_cumulative_claim_status_account(claim_status);
+    }
+    Ok(())
+}
+
+/// An expired account and the lamports its close instruction recovers.
+struct SweepItem {
+    instruction: Instruction,
+    /// Rent returned to the payer, or to the vote account for Reward Collection Accounts.
+    rent: u64,
+    /// Unclaimed rewards returned to the initializer.
+    unclaimed: u64,
+}
+
+/// Totals of the accounts closed by `sweep-expired`.
+#[derive(Default)]
+struct SweepSummary {
+    closed: usize,
+    failed: usize,
+    rent: u64,
+    unclaimed: u64,
+}
+
+fn process_sweep_expired(
+    rpc_client: Arc<RpcClient>,
+    kp: Arc<Keypair>,
+    program_id: Pubkey,
+    args: SweepExpiredArgs,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let signer_pubkey = kp.pubkey();
+    let current_epoch = rpc_client.get_epoch_info()?.epoch;
+    let (config_pubkey, _) = derive_config_account_address(&program_id);
+
+    let claim_statuses: Vec<SweepItem> =
+        get_program_accounts_of::<ClaimStatus>(rpc_client.clone(), &program_id)?
+            .into_iter()
+            .filter(|(_, _, claim_status)| current_epoch > claim_status.expires_at)
+            .map(|(pubkey, lamports, claim_status)| SweepItem {
+                instruction: close_claim_status_ix(
+                    program_id,
+                    CloseClaimStatusArgs,
+                    CloseClaimStatusAccounts {
+                        config: config_pubkey,
+                        claim_status: pubkey,
+                        claim_status_payer: claim_status.claim_status_payer,
+                    },
+                ),
+                rent: lamports,
+                unclaimed: 0,
+            })
+            .collect();
+
+    let claim_bitmaps: Vec<SweepItem> =
+        get_program_accounts_of::<ClaimBitmap>(rpc_client.clone(), &program_id)?
+            .into_iter()
+            .filter(|(_, _, claim_bitmap)| current_epoch > claim_bitmap.expires_at)
+            .map(|(pubkey, lamports, claim_bitmap)| SweepItem {
+                instruction: close_claim_bitmap_ix(
+                    program_id,
+                    CloseClaimBitmapArgs,
+                    CloseClaimBitmapAccounts {
+                        claim_bitmap: pubkey,
+                        payer: claim_bitmap.payer,
+                    },
+                ),
+                rent: lamports,
+                unclaimed: 0,
+            })
+            .collect();
+
+    let root_attestations: Vec<SweepItem> =
+        get_program_accounts_of::<RootAttestation>(rpc_client.clone(), &program_id)?
+            .into_iter()
+            .filter(|(_, _, root_attestation)| current_epoch > root_attestation.expires_at)
+            .map(|(pubkey, lamports, root_attestation)| SweepItem {
+                instruction: close_root_attestation_ix(
+                    program_id,
+                    CloseRootAttestationArgs,
+                    CloseRootAttestationAccounts {
+                        root_attestation: pubkey,
+                        payer: root_attestation.payer,
+                    },
+                ),
+                rent: lamports,
+                unclaimed: 0,
+            })
+            .collect();
+
+    let reward_collection_rent =
+        rpc_client.get_minimum_balance_for_rent_exemption(RewardCollectionAccount::SIZE)?;
+    let all_reward_collection_accounts =
+        get_program_accounts_of::<RewardCollectionAccount>(rpc_client.clone(), &program_id)?;
+    // Validators whose account of the current epoch can take rolled over funds; rollovers of the
+    // others wait until it exists.
+    let rollover_targets: HashSet<Pubkey> = all_reward_collection_accounts
+        .iter()
+        .filter(|(_, _, reward_collection_account)| {
+            reward_collection_account.creation_epoch == current_epoch
+                && reward_collection_account.account_version
+                    == RewardCollectionAccount::CURRENT_VERSION
+                && reward_collection_account.merkle_root.is_none()
+        })
+        .map(|(_, _, reward_collection_account)| reward_collection_account.validator_vote_account)
+        .collect();
+    let mut awaiting_rollover_target = 0;
+    let reward_collection_accounts: Vec<SweepItem> = all_reward_collection_accounts
+        .into_iter()
+        .filter(|(_, _, reward_collection_account)| {
+            current_epoch > reward_collection_account.expires_at
+        })
+        .filter_map(|(pubkey, lamports, reward_collection_account)| {
+            let fully_claimed = reward_collection_account
+                .merkle_root
+                .as_ref()
+                .is_some_and(MerkleRoot::is_fully_claimed);
+            let rollover = reward_collection_account.rollover_unclaimed && !fully_claimed;
+            if rollover
+                && !rollover_targets.contains(&reward_collection_account.validator_vote_account)
+            {
+                awaiting_rollover_target += 1;
+                return None;
+            }
+            let instruction = if rollover {
+                rollover_unclaimed_rewards_ix(
+                    program_id,
+                    RolloverUnclaimedRewardsArgs {
+                        epoch: reward_collection_account.creation_epoch,
+                    },
+                    RolloverUnclaimedRewardsAccounts {
+                        reward_collection_account: pubkey,
+                        current_reward_collection_account:
+                            derive_reward_collection_account_address(
+                                &program_id,
+                                &reward_collection_account.validator_vote_account,
+                                current_epoch,
+                            )
+                            .0,
+                        validator_vote_account: reward_collection_account.validator_vote_account,
+                    },
+                )
+            } else {
+                close_reward_collection_account_ix(
+                    program_id,
+                    CloseRewardCollectionAccountArgs {
+                        _epoch: reward_collection_account.creation_epoch,
+                    },
+                    CloseRewardCollectionAccounts {
+                        config: config_pubkey,
+                        initializer: reward_collection_account.initializer,
+                        reward_collection_account: pubkey,
+                        validator_vote_account: reward_collection_account.validator_vote_account,
+                        signer: signer_pubkey,
+                    },
+                )
+            };
+            Some(SweepItem {
+                instruction,
+                rent: lamports.min(reward_collection_rent),
+                unclaimed: lamports.saturating_sub(reward_collection_rent),
+            })
+        })
+        .collect();
+
+    println!("📌 {}", "Expired Accounts".bold().underline().blue());
+    println!(
+        "{} {}\n{} {}\n{} {}\n{} {}\n{} {}\n{} {}",
+        "📅 Current Epoch:".blue(),
+        current_epoch,
+        "🧾 Claim Status:".green(),
+        claim_statuses.len(),
+        "🗺️ Claim Bitmap:".green(),
+        claim_bitmaps.len(),
+        "🛡️ Root Attestation:".green(),
+        root_attestations.len(),
+        "🏦 Reward Collection Account:".green(),
+        reward_collection_accounts.len(),
+        "⏳ Awaiting Rollover Target:".yellow(),
+        awaiting_rollover_target
+    );
+    if args.dry_run {
+        return Ok(());
+    }
+
+    let batch_size = args.batch_size.max(1);
+    let mut summaries = Vec::new();
+    for (name, items) in [
+        ("Claim Status", claim_statuses),
+        ("Claim Bitmap", claim_bitmaps),
+        ("Root Attestation", root_attestations),
+        ("Reward Collection Account", reward_collection_accounts),
+    ] {
+        let mut summary = SweepSummary::default();
+        for batch in items.chunks(batch_size) {
+            let instructions: Vec<Instruction> =
+                batch.iter().map(|item| item.instruction.clone()).collect();
+            match send_instructions(rpc_client.clone(), &instructions, &kp) {
+                Ok(signature) => {
+                    print_sweep_batch(name, batch.len(), Some(signature));
+                    summary.closed += batch.len();
+                    summary.rent += batch.iter().map(|item| item.rent).sum::<u64>();
+                    summary.unclaimed += batch.iter().map(|item| item.unclaimed).sum::<u64>();
+                }
+                Err(err) => {
+                    print_sweep_batch(name, batch.len(), None);
+                    println!("   {}", err);
+                    summary.failed += batch.len();
+                }
+            }
+        }
+        summaries.push((name, summary));
+    }
+
+    println!("📊 {}", "Sweep Summary".bold().underline().blue());
+    for (name, summary) in &summaries {
+        println!(
+            "   {} {} closed, {} failed, {} lamports rent, {} lamports unclaimed",
+            format!("{}:", name).green(),
+            summary.closed,
+            summary.failed,
+            summary.rent,
+            summary.unclaimed
+        );
+    }
+    println!(
+        "{} {}\n{} {}",
+        "💰 Rent Recovered:".blue(),
+        summaries
+            .iter()
+            .map(|(_, summary)| summary.rent)
+            .sum::<u64>(),
+        "💰 Unclaimed Funds Returned:".blue(),
+        summaries
+            .iter()
+            .map(|(_, summary)| summary.unclaimed)
+            .sum::<u64>()
+    );
+    Ok(())
+}
+
+fn print_sweep_batch(name: &str, count: usize, signature: Option<Signature>) {
+    match signature {
+        Some(signature) => println!(
+            "   {} Closed {} {}: {}",
+            "✅".green(),
+            count,
+            name,
+            signature
+        ),
+        None => println!("   {} Failed to close {} {}", "❌".red(), count, name),
+    }
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let cli = Cli::parse();
+    let keypair = parse_keypair(&cli.keypair)?;
+    let rpc_client = Arc::new(RpcClient::new_with_commitment(
+        cli.url.clone(),
+        CommitmentConfig::confirmed(),
+    ));
+
+    match &cli.command {
+        Commands::InitConfig(args) => {
+            process_init_config(rpc_client.clone(), keypair, cli.program_id, args.clone())?
+        }
+        Commands::UpdateConfig(args) => {
+            process_update_config(rpc_client.clone(), keypair, cli.program_id, args.clone())?
+        }
+        Commands::ShowConfig => process_show_config(rpc_client.clone(), cli.program_id)?,
+        Commands::Init(args) => {
+            process_init(rpc_client.clone(), keypair, cli.program_id, args.clone())?
+        }
+        Commands::TransferRewards(args) => {
+            process_transfer_rewards(rpc_client.clone(), keypair, cli.program_id, args.clone())?
+        }
+        Commands::DepositRewards(args) => {
+            process_deposit_rewards(rpc_client.clone(), keypair, cli.program_id, args.clone())?
+        }
+        Commands::UploadMerkleRoot(args) => {
+            process_upload_merkle_root(rpc_client.clone(), keypair, cli.program_id, args.clone())?
+        }
+        Commands::UpdateUploadAuthority(args) => process_update_upload_authority(
+            rpc_client.clone(),
+            keypair,
+            cli.program_id,
+            args.clone(),
+        )?,
+        Commands::InitAttestation(args) => {
+            process_init_attestation(rpc_client.clone(), keypair, cli.program_id, args.clone())?
+        }
+        Commands::Attest(args) => {
+            process_attest(rpc_client.clone(), keypair, cli.program_id, args.clone())?
+        }
+        Commands::CloseAttestation(args) => {
+            process_close_attestation(rpc_client.clone(), keypair, cli.program_id, args.clone())?
+        }
+        Commands::UpdateCommission(args) => {
+            process_update_commission(rpc_client.clone(), keypair, cli.program_id, args.clone())?
+        }
+        Commands::Claim(args) => {
+            process_claim(rpc_client.clone(), keypair, cli.program_id, args.clone())?
+        }
+        Commands::ClaimToStake(args) => {
+            process_claim_to_stake(rpc_client.clone(), keypair, cli.program_id, args.clone())?
+        }
+        Commands::ClaimAggregated(args) => {
+            process_claim_aggregated(rpc_client.clone(), keypair, cli.program_id, args.clone())?
+        }
+        Commands::CloseClaimStatus(args) => {
+            process_close_claim_status(rpc_client.clone(), keypair, cli.program_id, args.clone())?
+        }
+        Commands::Migrate(args) => {
+            process_migrate(rpc_client.clone(), keypair, cli.program_id, args.clone())?
+        }
+        Commands::InitClaimBitmap(args) => {
+            process_init_claim_bitmap(rpc_client.clone(), keypair, cli.program_id, args.clone())?
+        }
+        Commands::CloseClaimBitmap(args) => {
+            process_close_claim_bitmap(rpc_client.clone(), keypair, cli.program_id, args.clone())?
+        }
+        Commands::Close(args) => {
+            process_close(rpc_client.clone(), keypair, cli.program_id, args.clone())?
+        }
+        Commands::Rollover(args) => {
+            process_rollover(rpc_client.clone(), keypair, cli.program_id, args.clone())?
+        }
+        Commands::WithdrawSurplus(args) => {
+            process_withdraw_surplus(rpc_client.clone(), keypair, cli.program_id, args.clone())?
+        }
+        Commands::Show(args) => process_show(rpc_client.clone(), cli.program_id, args.clone())?,
+        Commands::ShowClaimStatus(args) => {
+            process_show_claim_status(rpc_client.clone(), cli.program_id, args.clone())?
+        }
+        Commands::SweepExpired(args) => {
+            process_sweep_expired(rpc_client.clone(), keypair, cli.program_id, args.clone())?
+        }
+        Commands::GenerateDistribution(args) => {
+            process_generate_distribution(rpc_client.clone(), cli.program_id, args.clone())?
+        }
+        Commands::InitDistributor(args) => {
+            process_init_distributor(rpc_client.clone(), keypair, cli.program_id, args.clone())?
+        }
+        Commands::SweepToDistributor(args) => {
+            process_sweep_to_distributor(rpc_client.clone(), keypair, cli.program_id, args.clone())?
+        }
+        Commands::GenerateCumulativeDistribution(args) => {
+            process_generate_cumulative_distribution(cli.program_id, args.clone())?
+        }
+        Commands::UploadCumulativeRoot(args) => process_upload_cumulative_root(
+            rpc_client.clone(),
+            keypair,
+            cli.program_id,
+            args.clone(),
+        )?,
+        Commands::ClaimCumulative(args) => {
+            process_claim_cumulative(rpc_client.clone(), keypair, cli.program_id, args.clone())?
+        }
+        Commands::ShowDistributor(args) => {
+            process_show_distributor(rpc_client.clone(), cli.program_id, args.clone())?
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,704 @@
+//! Claim crank: submits the claims of every `RewardCollectionAccount` with an uploaded merkle
+//! root on behalf of its stakers.
+//!
+//! Each pass the crank:
+//! 1. Loads every distribution file in a directory and indexes them by `RewardCollectionAccount`.
+//! 2. Fetches the program's `RewardCollectionAccount`s that have a merkle root and have not
+//!    expired, and skips those without a matching distribution file or whose root differs.
+//! 3. Drops leaves that are already claimed on-chain, packs the rest into as few transactions as
+//!    fit (`claim_batch` for plain trees, `claim_indexed` for indexed trees) and sends them with
+//!    an optional priority fee, retrying failed transactions. Leaves of plain trees whose proof
+//!    does not fit a transaction are claimed through a proof buffer; those of indexed trees, which
+//!    have no proof buffer claim, are reported and recorded as unclaimable.
+//! 4. Persists the claimed leaves to a state file so a restart resumes where it stopped.
+//!
+//! Chain access goes through [CrankClient], so the crank can run against an RPC node, a
+//! `solana-test-validator` or an in-process bank.
+
+use {
+    anchor_lang::{AccountDeserialize, Discriminator},
+    colored::*,
+    rakurai_distribution::distribution::DistributionFile,
+    rakurai_merkle_tree::{MerkleTree, TreeNode},
+    reward_distribution::{
+        sdk::{
+            derive_claim_bitmap_account_address, derive_claim_status_account_address,
+            derive_config_account_address, derive_proof_buffer_address,
+            instruction::{
+                claim_batch_ix, claim_indexed_ix, close_proof_buffer_ix,
+                initialize_claim_bitmap_ix, ClaimBatchAccounts, ClaimBatchArgs,
+                ClaimIndexedAccounts, ClaimIndexedArgs, CloseProofBufferAccounts,
+                CloseProofBufferArgs, InitializeClaimBitmapAccounts, InitializeClaimBitmapArgs,
+            },
+            packing::{plan_claim_with_max_size, ClaimPlanAccounts},
+        },
+        state::{ClaimBitmap, RewardCollectionAccount},
+        BatchClaim,
+    },
+    serde_derive::{Deserialize, Serialize},
+    solana_rpc_client::rpc_client::RpcClient,
+    solana_rpc_client_api::{
+        config::RpcProgramAccountsConfig,
+        filter::{Memcmp, RpcFilterType},
+    },
+    solana_sdk::{
+        account::Account,
+        clock::Epoch,
+        compute_budget::ComputeBudgetInstruction,
+        hash::Hash,
+        instruction::Instruction,
+        pubkey::Pubkey,
+        signature::{Keypair, Signature},
+        signer::Signer,
+        system_program,
+        transaction::Transaction,
+    },
+    std::{
+        collections::{BTreeMap, BTreeSet, HashMap},
+        fs,
+        path::{Path, PathBuf},
+        thread::sleep,
+        time::Duration,
+    },
+};
+
+/// Offset of the `merkle_root` option tag in a `RewardCollectionAccount`: discriminator,
+/// `validator_vote_account` and `merkle_root_upload_authority`.
+const MERKLE_ROOT_OFFSET: usize = 8 + 32 + 32;
+
+/// Accounts fetched per `getMultipleAccounts` request.
+const MAX_MULTIPLE_ACCOUNTS: usize = 100;
+
+/// Chain access needed by the crank.
+pub trait CrankClient {
+    /// Every `RewardCollectionAccount` of `program_id` with an uploaded merkle root.
+    fn get_reward_collection_accounts(
+        &self,
+        program_id: &Pubkey,
+    ) -> Result<Vec<(Pubkey, RewardCollectionAccount)>, Box<dyn std::error::Error>>;
+
+    fn get_multiple_accounts(
+        &self,
+        pubkeys: &[Pubkey],
+    ) -> Result<Vec<Option<Account>>, Box<dyn std::error::Error>>;
+
+    fn get_epoch(&self) -> Result<Epoch, Box<dyn std::error::Error>>;
+
+    /// Signs `instructions` with `payer` and waits for confirmation.
+    fn send_transaction(
+        &self,
+        instructions: &[Instruction],
+        payer: &Keypair,
+    ) -> Result<Signature, Box<dyn std::error::Error>>;
+}
+
+impl CrankClient for RpcClient {
+    fn get_reward_collection_accounts(
+        &self,
+        program_id: &Pubkey,
+    ) -> Result<Vec<(Pubkey, RewardCollectionAccount)>, Box<dyn std::error::Error>> {
+        let config = RpcProgramAccountsConfig {
+            filters: Some(vec![
+                RpcFilterType::Memcmp(Memcmp::new_base58_encoded(
+                    0,
+                    RewardCollectionAccount::DISCRIMINATOR,
+                )),
+                RpcFilterType::Memcmp(Memcmp::new_base58_encoded(MERKLE_ROOT_OFFSET, &[1])),
+            ]),
+            ..RpcProgramAccountsConfig::default()
+        };
+
+        let mut reward_collection_accounts = Vec::new();
+        for (pubkey, account) in self.get_program_accounts_with_config(program_id, config)? {
+            let mut data = account.data.as_slice();
+            match RewardCollectionAccount::try_deserialize(&mut data) {
+                Ok(reward_collection_account) => {
+                    reward_collection_accounts.push((pubkey, reward_collection_account))
+                }
+                Err(err) => println!("{} {}: {}", "⚠️ Skipping".yellow(), pubkey, err),
+            }
+        }
+        Ok(reward_collection_accounts)
+    }
+
+    fn get_multiple_accounts(
+        &self,
+        pubkeys: &[Pubkey],
+    ) -> Result<Vec<Option<Account>>, Box<dyn std::error::Error>> {
+        let mut accounts = Vec::with_capacity(pubkeys.len());
+        for chunk in pubkeys.chunks(MAX_MULTIPLE_ACCOUNTS) {
+            accounts.extend(RpcClient::get_multiple_accounts(self, chunk)?);
+        }
+        Ok(accounts)
+    }
+
+    fn get_epoch(&self) -> Result<Epoch, Box<dyn std::error::Error>> {
+        Ok(self.get_epoch_info()?.epoch)
+    }
+
+    fn send_transaction(
+        &self,
+        instructions: &[Instruction],
+        payer: &Keypair,
+    ) -> Result<Signature, Box<dyn std::error::Error>> {
+        let transaction = Transaction::new_signed_with_payer(
+            instructions,
+            Some(&payer.pubkey()),
+            &[payer],
+            self.get_latest_blockhash()?,
+        );
+        Ok(self.send_and_confirm_transaction(&transaction)?)
+    }
+}
+
+/// Settings of a crank run.
+#[derive(Clone, Debug)]
+pub struct CrankConfig {
+    pub program_id: Pubkey,
+    /// Directory holding the distribution files.
+    pub distributions_dir: PathBuf,
+    /// File the claimed leaves are persisted to.
+    pub state_path: PathBuf,
+    /// Priority fee in micro-lamports per compute unit; `0` sends no compute budget instruction.
+    pub priority_fee: u64,
+    /// Compute unit limit requested for every transaction.
+    pub compute_unit_limit: Option<u32>,
+    /// Largest serialized transaction the crank builds, at most a packet.
+    pub max_transaction_size: usize,
+    /// Attempts per transaction before giving up until the next pass.
+    pub max_retries: u32,
+    /// Delay before the first retry, doubled after every failed attempt.
+    pub retry_delay: Duration,
+}
+
+/// Claims confirmed so far, keyed by `RewardCollectionAccount`.
+#[derive(Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CrankState {
+    /// `RewardCollectionAccount`s with no leaf left for the crank to claim.
+    pub completed: BTreeSet<String>,
+    /// Claimed claimants of every `RewardCollectionAccount` that is still in progress.
+    pub claimed: BTreeMap<String, BTreeSet<String>>,
+    /// Claimants of indexed trees whose claim does not fit a transaction. They are reported once
+    /// and left out of later passes.
+    #[serde(default)]
+    pub unclaimable: BTreeMap<String, BTreeSet<String>>,
+}
+
+impl CrankState {
+    /// Reads the state file, starting from scratch if it does not exist yet.
+    pub fn load(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        Ok(serde_json::from_slice(&fs::read(path)?)?)
+    }
+
+    /// Writes the state to a temporary file first, so a crash never leaves a truncated file.
+    pub fn save(&self, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        let tmp_path = path.with_extension("tmp");
+        fs::write(&tmp_path, serde_json::to_vec_pretty(self)?)?;
+        fs::rename(tmp_path, path)?;
+        Ok(())
+    }
+
+    fn is_claimed(&self, reward_collection_account: &Pubkey, claimant: &Pubkey) -> bool {
+        [&self.claimed, &self.unclaimable]
+            .into_iter()
+            .any(|claimants| {
+                claimants
+                    .get(&reward_collection_account.to_string())
+                    .is_some_and(|claimants| claimants.contains(&claimant.to_string()))
+            })
+    }
+
+    fn mark_claimed(&mut self, reward_collection_account: &Pubkey, claimants: &[Pubkey]) {
+        let claimed = self
+            .claimed
+            .entry(reward_collection_account.to_string())
+            .or_default();
+        claimed.extend(claimants.iter().map(Pubkey::to_string));
+    }
+
+    fn mark_unclaimable(&mut self, reward_collection_account: &Pubkey, claimant: &Pubkey) {
+        self.unclaimable
+            .entry(reward_collection_account.to_string())
+            .or_default()
+            .insert(claimant.to_string());
+    }
+
+    fn mark_completed(&mut self, reward_collection_account: &Pubkey) {
+        self.claimed.remove(&reward_collection_account.to_string());
+        self.completed.insert(reward_collection_account.to_string());
+    }
+}
+
+/// Totals of a crank pass.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CrankSummary {
+    /// Reward collection accounts that had pending claims.
+    pub reward_collection_accounts: usize,
+    /// Claims landed in this pass.
+    pub claims: usize,
+    /// Lamports paid out by the claims landed in this pass, after the `claim_scale` haircut.
+    pub lamports: u64,
+    /// Claims whose transactions failed after every retry.
+    pub failed_claims: usize,
+    /// Claims of indexed trees found not to fit a transaction in this pass.
+    pub unclaimable_claims: usize,
+}
+
+/// Loads every distribution file in `dir`, keyed by `RewardCollectionAccount`. Files that are
+/// not distribution files are skipped.
+pub fn load_distributions(
+    dir: &Path,
+) -> Result<HashMap<Pubkey, DistributionFile>, Box<dyn std::error::Error>> {
+    let mut distributions = HashMap::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().and_then(|extension| extension.to_str()) != Some("json") {
+            continue;
+        }
+        match DistributionFile::read(&path) {
+            Ok(distribution) => {
+                distributions.insert(distribution.reward_collection_account, distribution);
+            }
+            Err(err) => println!("{} {}: {}", "⚠️ Skipping".yellow(), path.display(), err),
+        }
+    }
+    Ok(distributions)
+}
+
+/// Runs a single pass over every claimable `RewardCollectionAccount`.
+pub fn run_once(
+    client: &impl CrankClient,
+    payer: &Keypair,
+    config: &CrankConfig,
+    state: &mut CrankState,
+) -> Result<CrankSummary, Box<dyn std::error::Error>> {
+    let distributions = load_distributions(&config.distributions_dir)?;
+    let current_epoch = client.get_epoch()?;
+
+    let mut summary = CrankSummary::default();
+    for (reward_collection_pubkey, reward_collection_account) in
+        client.get_reward_collection_accounts(&config.program_id)?
+    {
+        if state
+            .completed
+            .contains(&reward_collection_pubkey.to_string())
+            || current_epoch > reward_collection_account.expires_at
+        {
+            continue;
+        }
+        let Some(merkle_root) = &reward_collection_account.merkle_root else {
+            continue;
+        };
+        let Some(distribution) = distributions.get(&reward_collection_pubkey) else {
+            continue;
+        };
+
+        let tree = distribution.merkle_tree()?;
+        if tree.root != merkle_root.root {
+            println!(
+                "{} {}: distribution root does not match the uploaded root",
+                "⚠️ Skipping".yellow(),
+                reward_collection_pubkey
+            );
+            continue;
+        }
+
+        let pending = pending_nodes(client, config, state, &reward_collection_pubkey, &tree)?;
+        if pending.is_empty() {
+            state.mark_completed(&reward_collection_pubkey);
+            state.save(&config.state_path)?;
+            continue;
+        }
+
+        println!(
+            "{} {} ({} pending claims)",
+            "🏦 Reward Collection Account:".blue(),
+            reward_collection_pubkey,
+            pending.len()
+        );
+        summary.reward_collection_accounts += 1;
+        let (mut transactions, oversized) = if tree.indexed {
+            ensure_claim_bitmap(client, payer, config, &reward_collection_pubkey)?;
+            pack_claims(config, payer, &pending, |nodes| {
+                claim_indexed_instructions(config, payer, &reward_collection_pubkey, nodes)
+            })
+        } else {
+            pack_claims(config, payer, &pending, |nodes| {
+                vec![claim_batch_instruction(
+                    config,
+                    payer,
+                    &reward_collection_pubkey,
+                    nodes,
+                )]
+            })
+        };
+
+        for node in oversized {
+            if tree.indexed {
+                println!(
+                    "   {} claim of {} does not fit in a transaction and is left unclaimed",
+                    "❌".red(),
+                    node.claimant
+                );
+                state.mark_unclaimable(&reward_collection_pubkey, &node.claimant);
+                state.save(&config.state_path)?;
+                summary.unclaimable_claims += 1;
+                continue;
+            }
+            let instructions =
+                proof_buffer_instructions(client, payer, config, &reward_collection_pubkey, node)?;
+            transactions.push((instructions, vec![node]));
+        }
+
+        for (instructions, nodes) in transactions {
+            let claimants: Vec<Pubkey> = nodes.iter().map(|node| node.claimant).collect();
+            match send_all_with_retries(client, payer, config, &instructions) {
+                Ok(signature) => {
+                    let mut lamports = 0;
+                    for node in &nodes {
+                        lamports += reward_collection_account.payout(node.amount)?;
+                    }
+                    println!(
+                        "   {} {} claims, {} lamports: {}",
+                        "✅".green(),
+                        nodes.len(),
+                        lamports,
+                        signature
+                    );
+                    state.mark_claimed(&reward_collection_pubkey, &claimants);
+                    state.save(&config.state_path)?;
+                    summary.claims += nodes.len();
+                    summary.lamports += lamports;
+                }
+                Err(err) => {
+                    println!("   {} {} claims: {}", "❌".red(), nodes.len(), err);
+                    summary.failed_claims += nodes.len();
+                }
+            }
+        }
+    }
+
+    Ok(summary)
+}
+
+/// Leaves that are neither recorded in the state file nor claimed on-chain.
+fn pending_nodes<'a>(
+    client: &impl CrankClient,
+    config: &CrankConfig,
+    state: &mut CrankState,
+    reward_collection_pubkey: &Pubkey,
+    tree: &'a MerkleTree,
+) -> Result<Vec<&'a TreeNode>, Box<dyn std::error::Error>> {
+    let unrecorded: Vec<&TreeNode> = tree
+        .tree_nodes
+        .iter()
+        .filter(|node| !state.is_claimed(reward_collection_pubkey, &node.claimant))
+        .collect();
+
+    let mut pending = Vec::with_capacity(unrecorded.len());
+    let mut claimed_on_chain = Vec::new();
+    if tree.indexed {
+        let (claim_bitmap_pubkey, _) =
+            derive_claim_bitmap_account_address(&config.program_id, reward_collection_pubkey);
+        let claim_bitmap = match &client.get_multiple_accounts(&[claim_bitmap_pubkey])?[0] {
+            Some(account) => Some(ClaimBitmap::try_deserialize(&mut account.data.as_slice())?),
+            None => None,
+        };
+        for node in unrecorded {
+            let is_claimed = match &claim_bitmap {
+                Some(claim_bitmap) => claim_bitmap.is_claimed(node.index)?,
+                None => false,
+            };
+            if is_claimed {
+                claimed_on_chain.push(node.claimant);
+            } else {
+                pending.push(node);
+            }
+        }
+    } else {
+        let claim_status_pubkeys: Vec<Pubkey> = unrecorded
+            .iter()
+            .map(|node| {
+                derive_claim_status_account_address(
+                    &config.program_id,
+                    &node.claimant,
+                    reward_collection_pubkey,
+                )
+                .0
+            })
+            .collect();
+        let claim_status_accounts = client.get_multiple_accounts(&claim_status_pubkeys)?;
+        for (node, claim_status) in unrecorded.into_iter().zip(claim_status_accounts) {
+            if claim_status.is_some() {
+                claimed_on_chain.push(node.claimant);
+            } else {
+                pending.push(node);
+            }
+        }
+    }
+
+    if !claimed_on_chain.is_empty() {
+        state.mark_claimed(reward_collection_pubkey, &claimed_on_chain);
+        state.save(&config.state_path)?;
+    }
+    Ok(pending)
+}
+
+/// Creates the `ClaimBitmap` of an indexed tree if nobody has done so yet.
+fn ensure_claim_bitmap(
+    client: &impl CrankClient,
+    payer: &Keypair,
+    config: &CrankConfig,
+    reward_collection_pubkey: &Pubkey,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let (claim_bitmap_pubkey, _) =
+        derive_claim_bitmap_account_address(&config.program_id, reward_collection_pubkey);
+    if client.get_multiple_accounts(&[claim_bitmap_pubkey])?[0].is_some() {
+        return Ok(());
+    }
+
+    let instruction = initialize_claim_bitmap_ix(
+        config.program_id,
+        InitializeClaimBitmapArgs,
+        InitializeClaimBitmapAccounts {
+            config: derive_config_account_address(&config.program_id).0,
+            reward_collection_account: *reward_collection_pubkey,
+            claim_bitmap: claim_bitmap_pubkey,
+            payer: payer.pubkey(),
+            system_program: system_program::id(),
+        },
+    );
+    let signature = send_with_retries(client, payer, config, &[instruction])?;
+    println!("   {} Claim Bitmap created: {}", "✅".green(), signature);
+    Ok(())
+}
+
+fn claim_batch_instruction(
+    config: &CrankConfig,
+    payer: &Keypair,
+    reward_collection_pubkey: &Pubkey,
+    nodes: &[&TreeNode],
+) -> Instruction {
+    claim_batch_ix(
+        config.program_id,
+        ClaimBatchArgs {
+            claims: nodes
+                .iter()
+                .map(|node| BatchClaim {
+                    amount: node.amount,
+                    proof: node.proof.clone(),
+                })
+                .collect(),
+        },
+        ClaimBatchAccounts {
+            config: derive_config_account_address(&config.program_id).0,
+            reward_collection_account: *reward_collection_pubkey,
+            payer: payer.pubkey(),
+            system_program: system_program::id(),
+            claimants: nodes
+                .iter()
+                .map(|node| {
+                    let (claim_status, _) = derive_claim_status_account_address(
+                        &config.program_id,
+                        &node.claimant,
+                        reward_collection_pubkey,
+                    );
+                    (node.claimant, claim_status)
+                })
+                .collect(),
+        },
+    )
+}
+
+fn claim_indexed_instructions(
+    config: &CrankConfig,
+    payer: &Keypair,
+    reward_collection_pubkey: &Pubkey,
+    nodes: &[&TreeNode],
+) -> Vec<Instruction> {
+    let config_pubkey = derive_config_account_address(&config.program_id).0;
+    let (claim_bitmap, _) =
+        derive_claim_bitmap_account_address(&config.program_id, reward_collection_pubkey);
+    nodes
+        .iter()
+        .map(|node| {
+            claim_indexed_ix(
+                config.program_id,
+                ClaimIndexedArgs {
+                    index: node.index,
+                    proof: node.proof.clone(),
+                    amount: node.amount,
+                },
+                ClaimIndexedAccounts {
+                    config: config_pubkey,
+                    reward_collection_account: *reward_collection_pubkey,
+                    claim_bitmap,
+                    claimant: node.claimant,
+                    payer: payer.pubkey(),
+                    system_program: system_program::id(),
+                },
+            )
+        })
+        .collect()
+}
+
+/// Compute budget instructions prepended to every transaction.
+fn compute_budget_instructions(config: &CrankConfig) -> Vec<Instruction> {
+    let mut instructions = Vec::new();
+    if let Some(compute_unit_limit) = config.compute_unit_limit {
+        instructions.push(ComputeBudgetInstruction::set_compute_unit_limit(
+            compute_unit_limit,
+        ));
+    }
+    if config.priority_fee > 0 {
+        instructions.push(ComputeBudgetInstruction::set_compute_unit_price(
+            config.priority_fee,
+        ));
+    }
+    instructions
+}
+
+/// Transactions claiming a node through the payer's proof buffer, closing a buffer left behind by
+/// an interrupted run first.
+fn proof_buffer_instructions(
+    client: &impl CrankClient,
+    payer: &Keypair,
+    config: &CrankConfig,
+    reward_collection_pubkey: &Pubkey,
+    node: &TreeNode,
+) -> Result<Vec<Vec<Instruction>>, Box<dyn std::error::Error>> {
+    let mut transactions = plan_claim_with_max_size(
+        config.program_id,
+        ClaimPlanAccounts {
+            config: derive_config_account_address(&config.program_id).0,
+            reward_collection_account: *reward_collection_pubkey,
+            claimant: node.claimant,
+            payer: payer.pubkey(),
+            system_program: system_program::id(),
+        },
+        node.amount,
+        node.proof.clone(),
+        &compute_budget_instructions(config),
+        config.max_transaction_size,
+    );
+
+    let (proof_buffer, _) = derive_proof_buffer_address(
+        &config.program_id,
+        reward_collection_pubkey,
+        &node.claimant,
+        &payer.pubkey(),
+    );
+    if client.get_multiple_accounts(&[proof_buffer])?[0].is_some() {
+        transactions.insert(
+            0,
+            vec![close_proof_buffer_ix(
+                config.program_id,
+                CloseProofBufferArgs,
+                CloseProofBufferAccounts {
+                    proof_buffer,
+                    payer: payer.pubkey(),
+                },
+            )],
+        );
+    }
+    Ok(transactions)
+}
+
+/// Transactions to send in order, and the nodes they claim.
+type ClaimTransactions<'a> = (Vec<Vec<Instruction>>, Vec<&'a TreeNode>);
+
+/// Greedily packs `nodes` into transactions no larger than `config.max_transaction_size`. Nodes
+/// whose claim does not fit a transaction on its own are returned separately.
+fn pack_claims<'a>(
+    config: &CrankConfig,
+    payer: &Keypair,
+    nodes: &[&'a TreeNode],
+    build: impl Fn(&[&'a TreeNode]) -> Vec<Instruction>,
+) -> (Vec<ClaimTransactions<'a>>, Vec<&'a TreeNode>) {
+    let instructions_for = |batch: &[&'a TreeNode]| {
+        let mut instructions = compute_budget_instructions(config);
+        instructions.extend(build(batch));
+        instructions
+    };
+
+    let mut transactions = Vec::new();
+    let mut oversized = Vec::new();
+    let mut batch: Vec<&TreeNode> = Vec::new();
+    for node in nodes {
+        batch.push(node);
+        if fits(config, &instructions_for(&batch), payer) {
+            continue;
+        }
+
+        batch.pop();
+        if !batch.is_empty() {
+            transactions.push((vec![instructions_for(&batch)], std::mem::take(&mut batch)));
+        }
+        if fits(config, &instructions_for(&[node]), payer) {
+            batch.push(node);
+        } else {
+            oversized.push(*node);
+        }
+    }
+    if !batch.is_empty() {
+        transactions.push((vec![instructions_for(&batch)], batch));
+    }
+    (transactions, oversized)
+}
+
+fn fits(config: &CrankConfig, instructions: &[Instruction], payer: &Keypair) -> bool {
+    let transaction = Transaction::new_signed_with_payer(
+        instructions,
+        Some(&payer.pubkey()),
+        &[payer],
+        Hash::default(),
+    );
+    bincode::serialized_size(&transaction)
+        .is_ok_and(|size| size as usize <= config.max_transaction_size)
+}
+
+/// Sends `transactions` in order with [send_with_retries], stopping at the first failure.
+/// Returns the signature of the last one.
+fn send_all_with_retries(
+    client: &impl CrankClient,
+    payer: &Keypair,
+    config: &CrankConfig,
+    transactions: &[Vec<Instruction>],
+) -> Result<Signature, Box<dyn std::error::Error>> {
+    let mut signature = Signature::default();
+    for instructions in transactions {
+        signature = send_with_retries(client, payer, config, instructions)?;
+    }
+    Ok(signature)
+}
+
+fn send_with_retries(
+    client: &impl CrankClient,
+    payer: &Keypair,
+    config: &CrankConfig,
+    instructions: &[Instruction],
+) -> Result<Signature, Box<dyn std::error::Error>> {
+    let mut delay = config.retry_delay;
+    let mut attempt = 1;
+    loop {
+        match client.send_transaction(instructions, payer) {
+            Ok(signature) => return Ok(signature),
+            Err(err) if attempt >= config.max_retries.max(1) => return Err(err),
+            Err(err) => {
+                println!(
+                    "   {} attempt {}/{} failed: {}",
+                    "🔁".yellow(),
+                    attempt,
+                    config.max_retries,
+                    err
+                );
+                sleep(delay);
+                delay *= 2;
+                attempt += 1;
+            }
+        }
+    }
+}
@@ -1,20 +1,31 @@
 use {
-    anchor_lang::AccountDeserialize,
+    anchor_lang::{AccountDeserialize, Discriminator},
     colored::*,
     rakurai_activation::state::{RakuraiActivationAccount, RakuraiActivationConfigAccount},
+    reward_distribution::state::{
+        ClaimBitmap, ClaimStatus, CumulativeClaimStatus, CumulativeDistributor, DepositSource,
+        RewardCollectionAccount, RewardDistributionConfigAccount, RootAttestation,
+    },
     solana_rpc_client::rpc_client::RpcClient,
+    solana_rpc_client_api::{
+        config::RpcProgramAccountsConfig,
+        filter::{Memcmp, RpcFilterType},
+    },
     solana_sdk::{
+        address_lookup_table::{state::AddressLookupTable, AddressLookupTableAccount},
         instruction::Instruction,
-        message::Message,
+        message::{v0, Message, VersionedMessage},
         pubkey::Pubkey,
-        signature::Keypair,
+        signature::{Keypair, Signature},
         signer::{EncodableKey, Signer},
-        transaction::Transaction,
+        transaction::{Transaction, VersionedTransaction},
         vote::state::{VoteState, VoteStateVersions},
     },
     std::{path::Path, str::FromStr, sync::Arc},
 };
 
+pub mod crank;
+
 /// Parses and validates a Solana `Pubkey` from a string
 pub fn parse_pubkey(s: &str) -> Result<Pubkey, String> {
     Pubkey::from_str(s).map_err(|_| format!("Invalid Solana public key: {}", s))
@@ -45,6 +56,29 @@ pub fn validate_commission(val: &str) -> Result<u16, String> {
         })
 }
 
+/// Parses a base58 encoded 32 byte hash, e.g. a merkle root or proof element
+pub fn parse_hash(s: &str) -> Result<[u8; 32], String> {
+    bs58::decode(s)
+        .into_vec()
+        .map_err(|_| format!("Invalid base58 hash: {}", s))?
+        .try_into()
+        .map_err(|_| format!("Hash must be 32 bytes: {}", s))
+}
+
+/// Parses the source of deposited rewards: tips, partner-incentives, validator-top-up or other
+pub fn parse_deposit_source(s: &str) -> Result<DepositSource, String> {
+    match s {
+        "tips" => Ok(DepositSource::Tips),
+        "partner-incentives" => Ok(DepositSource::PartnerIncentives),
+        "validator-top-up" => Ok(DepositSource::ValidatorTopUp),
+        "other" => Ok(DepositSource::Other),
+        _ => Err(format!(
+            "Unknown deposit source: {} (expected tips, partner-incentives, validator-top-up or other)",
+            s
+        )),
+    }
+}
+
 /// Parses a Solana keypair from a file
 pub fn parse_keypair(path: &str) -> Result<Arc<Keypair>, Box<dyn std::error::Error>> {
     let expanded_path = shellexpand::tilde(path).into_owned();
@@ -158,6 +192,491 @@ pub fn display_activation_config_account(
     );
 }
 
+pub fn get_reward_collection_account(
+    rpc_client: Arc<RpcClient>,
+    reward_collection_pda: Pubkey,
+) -> Result<RewardCollectionAccount, Box<dyn std::error::Error>> {
+    let account_data = rpc_client.get_account_data(&reward_collection_pda)?;
+    let mut account_slice = account_data.as_slice();
+    RewardCollectionAccount::try_deserialize(&mut account_slice).map_err(Into::into)
+}
+
+pub fn get_claim_status_account(
+    rpc_client: Arc<RpcClient>,
+    claim_status_pda: Pubkey,
+) -> Result<ClaimStatus, Box<dyn std::error::Error>> {
+    let account_data = rpc_client.get_account_data(&claim_status_pda)?;
+    let mut account_slice = account_data.as_slice();
+    ClaimStatus::try_deserialize(&mut account_slice).map_err(Into::into)
+}
+
+pub fn get_claim_bitmap_account(
+    rpc_client: Arc<RpcClient>,
+    claim_bitmap_pda: Pubkey,
+) -> Result<ClaimBitmap, Box<dyn std::error::Error>> {
+    let account_data = rpc_client.get_account_data(&claim_bitmap_pda)?;
+    let mut account_slice = account_data.as_slice();
+    ClaimBitmap::try_deserialize(&mut account_slice).map_err(Into::into)
+}
+
+pub fn get_root_attestation_account(
+    rpc_client: Arc<RpcClient>,
+    root_attestation_pda: Pubkey,
+) -> Result<RootAttestation, Box<dyn std::error::Error>> {
+    let account_data = rpc_client.get_account_data(&root_attestation_pda)?;
+    let mut account_slice = account_data.as_slice();
+    RootAttestation::try_deserialize(&mut account_slice).map_err(Into::into)
+}
+
+pub fn get_cumulative_distributor_account(
+    rpc_client: Arc<RpcClient>,
+    distributor_pda: Pubkey,
+) -> Result<CumulativeDistributor, Box<dyn std::error::Error>> {
+    let account_data = rpc_client.get_account_data(&distributor_pda)?;
+    let mut account_slice = account_data.as_slice();
+    CumulativeDistributor::try_deserialize(&mut account_slice).map_err(Into::into)
+}
+
+pub fn get_cumulative_claim_status_account(
+    rpc_client: Arc<RpcClient>,
+    claim_status_pda: Pubkey,
+) -> Result<CumulativeClaimStatus, Box<dyn std::error::Error>> {
+    let account_data = rpc_client.get_account_data(&claim_status_pda)?;
+    let mut account_slice = account_data.as_slice();
+    CumulativeClaimStatus::try_deserialize(&mut account_slice).map_err(Into::into)
+}
+
+/// Fetches every account of type `T` owned by `program_id`, selected by its Anchor discriminator.
+/// Returns each account's address, lamports and deserialized data. Accounts that do not
+/// deserialize, e.g. [RewardCollectionAccount]s of an older account version, are skipped with a
+/// warning.
+pub fn get_program_accounts_of<T: AccountDeserialize + Discriminator>(
+    rpc_client: Arc<RpcClient>,
+    program_id: &Pubkey,
+) -> Result<Vec<(Pubkey, u64, T)>, Box<dyn std::error::Error>> {
+    let config = RpcProgramAccountsConfig {
+        filters: Some(vec![RpcFilterType::Memcmp(Memcmp::new_base58_encoded(
+            0,
+            T::DISCRIMINATOR,
+        ))]),
+        ..RpcProgramAccountsConfig::default()
+    };
+
+    let mut accounts = Vec::new();
+    for (pubkey, account) in rpc_client.get_program_accounts_with_config(program_id, config)? {
+        let mut account_slice = account.data.as_slice();
+        match T::try_deserialize(&mut account_slice) {
+            Ok(data) => accounts.push((pubkey, account.lamports, data)),
+            Err(err) => println!("{} {}: {}", "⚠️ Skipping".yellow(), pubkey, err),
+        }
+    }
+    Ok(accounts)
+}
+
+pub fn get_reward_distribution_config_account(
+    rpc_client: Arc<RpcClient>,
+    reward_distribution_config_account: Pubkey,
+) -> Result<RewardDistributionConfigAccount, Box<dyn std::error::Error>> {
+    let account_data = rpc_client.get_account_data(&reward_distribution_config_account)?;
+    let mut account_slice = account_data.as_slice();
+    RewardDistributionConfigAccount::try_deserialize(&mut account_slice).map_err(Into::into)
+}
+
+pub fn display_reward_collection_account(reward_collection_account: RewardCollectionAccount) {
+    println!("{}", "🗳️ Validator".bold().underline().blue());
+    println!(
+        "   {} {:<10} {}",
+        "🏦".cyan(),
+        "Vote Account:",
+        reward_collection_account.validator_vote_account
+    );
+    println!(
+        "   {} {:<10} {}",
+        "🔑".red(),
+        "Initializer:",
+        reward_collection_account.initializer
+    );
+    println!(
+        "   {} {:<10} {}",
+        "💰".green(),
+        "Commission:",
+        reward_collection_account
+            .validator_commission_bps
+            .to_string()
+            .magenta()
+    );
+    let category_commissions = reward_collection_account.category_commissions;
+    for (category, commission_bps) in ["Block Fees", "Priority Fees", "Tips"]
+        .iter()
+        .zip(category_commissions.categories())
+    {
+        if let Some(commission_bps) = commission_bps {
+            println!(
+                "   {} {:<10} {}",
+                "💰".green(),
+                format!("{} Commission:", category),
+                commission_bps.to_string().magenta()
+            );
+        }
+    }
+
+    println!("{}", "🚀 Rakurai".bold().underline().blue());
+    println!(
+        "   {} {:<10} {}",
+        "💰".green(),
+        "Commission:",
+        reward_collection_account
+            .rakurai_commission_bps
+            .to_string()
+            .magenta()
+    );
+    println!(
+        "   {} {:<10} {}",
+        "🏦".cyan(),
+        "Commission Account:",
+        reward_collection_account.rakurai_commission_account
+    );
+
+    println!("{}", "⏳ Lifecycle".bold().underline().blue());
+    println!(
+        "   {} {:<10} {}",
+        "📅".cyan(),
+        "Creation Epoch:",
+        reward_collection_account.creation_epoch.to_string().blue()
+    );
+    println!(
+        "   {} {:<10} {}",
+        "📅".cyan(),
+        "Expires At:",
+        reward_collection_account.expires_at.to_string().blue()
+    );
+    println!(
+        "   {} {:<10} {}",
+        "🔢".cyan(),
+        "Account Version:",
+        reward_collection_account.account_version
+    );
+
+    println!(
+        "{}",
+        "📊 Rewards (total / stakers)".bold().underline().blue()
+    );
+    println!(
+        "   {} {:<10} {} / {}",
+        "💰".green(),
+        "Total:",
+        reward_collection_account
+            .total_rewards
+            .to_string()
+            .magenta(),
+        reward_collection_account.staker_rewards
+    );
+    println!(
+        "   {} {:<10} {}",
+        "🚀".cyan(),
+        "Rakurai Fees:",
+        reward_collection_account.block_builder_fees
+    );
+    println!(
+        "   {} {:<10} {}",
+        "🗳️".cyan(),
+        "Validator Fees:",
+        reward_collection_account.validator_fees
+    );
+    println!(
+        "   {} {:<10} {}",
+        "📥".cyan(),
+        "Deposited:",
+        reward_collection_account.deposited_rewards
+    );
+    println!(
+        "   {} {:<10} {}",
+        "🔁".cyan(),
+        "Transfers:",
+        reward_collection_account.num_transfers
+    );
+    println!(
+        "   {} {:<10} {}",
+        "🔁".cyan(),
+        "Rollover Unclaimed:",
+        reward_collection_account.rollover_unclaimed
+    );
+    if let Some(last_settled_slot) = reward_collection_account.last_settled_slot {
+        println!(
+            "   {} {:<10} {}",
+            "🎰".cyan(),
+            "Last Settled Slot:",
+            last_settled_slot
+        );
+    }
+    let buckets = reward_collection_account.reward_buckets;
+    for (name, bucket) in [
+        ("Block Fees:", buckets.block_fees),
+        ("Priority Fees:", buckets.priority_fees),
+        ("Tips:", buckets.tips),
+    ] {
+        println!(
+            "   {} {:<10} {} / {}",
+            "💰".green(),
+            name,
+            bucket.total_rewards.to_string().magenta(),
+            bucket.staker_rewards
+        );
+    }
+
+    println!("{}", "🌳 Merkle Root".bold().underline().blue());
+    println!(
+        "   {} {:<10} {}",
+        "🔑".red(),
+        "Upload Authority:",
+        reward_collection_account.merkle_root_upload_authority
+    );
+    match reward_collection_account.merkle_root {
+        Some(merkle_root) => {
+            println!(
+                "   {} {:<10} {}",
+                "📝".cyan(),
+                "Root:",
+                bs58::encode(merkle_root.root).into_string()
+            );
+            println!(
+                "   {} {:<10} {} / {}",
+                "💰".green(),
+                "Funds Claimed:",
+                merkle_root.total_funds_claimed.to_string().magenta(),
+                merkle_root.max_total_claim
+            );
+            println!(
+                "   {} {:<10} {} / {}",
+                "👥".green(),
+                "Nodes Claimed:",
+                merkle_root.num_nodes_claimed.to_string().magenta(),
+                merkle_root.max_num_nodes
+            );
+            println!(
+                "   {} {:<10} {}",
+                "🍃".green(),
+                "Leaf Version:",
+                reward_collection_account.leaf_version
+            );
+            if let Some(claim_scale) = reward_collection_account.claim_scale {
+                println!(
+                    "   {} {:<10} {} / {}",
+                    "⚖️".yellow(),
+                    "Pro-rata Scale:",
+                    claim_scale.available,
+                    claim_scale.outstanding
+                );
+            }
+        }
+        None => println!("   {} {:<10}", "📝".cyan(), "Not uploaded yet".yellow()),
+    }
+}
+
+pub fn display_claim_status_account(claim_status: ClaimStatus) {
+    println!("{}", "🧾 Claim Status".bold().underline().blue());
+    println!(
+        "   {} {:<10} {}",
+        "✅".green(),
+        "Claimed:",
+        claim_status.is_claimed.to_string().blue()
+    );
+    println!(
+        "   {} {:<10} {}",
+        "👤".cyan(),
+        "Claimant:",
+        claim_status.claimant
+    );
+    println!(
+        "   {} {:<10} {}",
+        "💰".green(),
+        "Amount:",
+        claim_status.amount.to_string().magenta()
+    );
+    println!(
+        "   {} {:<10} {}",
+        "🕒".cyan(),
+        "Slot Claimed At:",
+        claim_status.slot_claimed_at.to_string().blue()
+    );
+    println!(
+        "   {} {:<10} {}",
+        "📅".cyan(),
+        "Expires At:",
+        claim_status.expires_at.to_string().blue()
+    );
+    println!(
+        "   {} {:<10} {}",
+        "🔑".red(),
+        "Payer:",
+        claim_status.claim_status_payer
+    );
+}
+
+pub fn display_root_attestation_account(root_attestation: RootAttestation) {
+    println!("{}", "🛡️ Root Attestation".bold().underline().blue());
+    println!(
+        "   {} {:<10} {} of {}",
+        "🤝".green(),
+        "Threshold:",
+        root_attestation.threshold.to_string().magenta(),
+        root_attestation.attesters.len()
+    );
+    for (attester, submission) in root_attestation
+        .attesters
+        .iter()
+        .zip(&root_attestation.submissions)
+    {
+        match submission {
+            Some(submission) => println!(
+                "   {} {} {} {} / {} nodes / leaf version {}{}",
+                "🔏".cyan(),
+                attester,
+                bs58::encode(submission.root).into_string().green(),
+                submission.max_total_claim.to_string().magenta(),
+                submission.max_num_nodes,
+                submission.version,
+                if submission.pro_rata {
+                    " / pro-rata"
+                } else {
+                    ""
+                }
+            ),
+            None => println!(
+                "   {} {} {}",
+                "🔏".cyan(),
+                attester,
+                "no submission".yellow()
+            ),
+        }
+    }
+    println!(
+        "   {} {:<10} {}",
+        "📅".cyan(),
+        "Expires At:",
+        root_attestation.expires_at.to_string().blue()
+    );
+    println!(
+        "   {} {:<10} {}",
+        "🔑".red(),
+        "Payer:",
+        root_attestation.payer
+    );
+}
+
+pub fn display_cumulative_distributor_account(distributor: CumulativeDistributor) {
+    println!("{}", "🏛️ Cumulative Distributor".bold().underline().blue());
+    println!(
+        "   {} {:<10} {}",
+        "🏦".cyan(),
+        "Vote Account:",
+        distributor.validator_vote_account
+    );
+    println!(
+        "   {} {:<10} {}",
+        "🌳".green(),
+        "Root:",
+        bs58::encode(distributor.root).into_string()
+    );
+    println!(
+        "   {} {:<10} {}",
+        "📅".cyan(),
+        "Root Epoch:",
+        distributor.root_epoch.to_string().blue()
+    );
+    println!(
+        "   {} {:<10} {}",
+        "💰".green(),
+        "Max Total Claim:",
+        distributor.max_total_claim.to_string().magenta()
+    );
+    println!(
+        "   {} {:<10} {}",
+        "📥".green(),
+        "Total Deposited:",
+        distributor.total_funds_deposited.to_string().magenta()
+    );
+    println!(
+        "   {} {:<10} {}",
+        "📤".green(),
+        "Total Claimed:",
+        distributor.total_funds_claimed.to_string().magenta()
+    );
+    println!(
+        "   {} {:<10} {}",
+        "🔑".red(),
+        "Upload Authority:",
+        distributor.merkle_root_upload_authority
+    );
+    println!(
+        "   {} {:<10} {}",
+        "👤".cyan(),
+        "Initializer:",
+        distributor.initializer
+    );
+}
+
+pub fn display_cumulative_claim_status_account(claim_status: CumulativeClaimStatus) {
+    println!("{}", "🧾 Cumulative Claim Status".bold().underline().blue());
+    println!(
+        "   {} {:<10} {}",
+        "👤".cyan(),
+        "Claimant:",
+        claim_status.claimant
+    );
+    println!(
+        "   {} {:<10} {}",
+        "💰".green(),
+        "Claimed Amount:",
+        claim_status.claimed_amount.to_string().magenta()
+    );
+    println!(
+        "   {} {:<10} {}",
+        "🕒".cyan(),
+        "Slot Claimed At:",
+        claim_status.slot_claimed_at.to_string().blue()
+    );
+}
+
+pub fn display_reward_distribution_config_account(
+    reward_distribution_config_account: RewardDistributionConfigAccount,
+) {
+    println!(
+        "{}",
+        "📜 Reward Distribution Config Account"
+            .bold()
+            .underline()
+            .blue()
+    );
+    println!(
+        "   {} {:<10} {}",
+        "📅".cyan(),
+        "Epochs Valid:",
+        reward_distribution_config_account
+            .num_epochs_valid
+            .to_string()
+            .blue()
+    );
+    println!(
+        "   {} {:<10} {}",
+        "💰".green(),
+        "Max Commission:",
+        reward_distribution_config_account
+            .max_commission_bps
+            .to_string()
+            .magenta()
+    );
+    println!(
+        "   {} {:<10} {}",
+        "🔑".red(),
+        "Authority:",
+        reward_distribution_config_account
+            .authority
+            .to_string()
+            .magenta()
+    );
+}
+
 pub fn get_vote_account(
     rpc_client: Arc<RpcClient>,
     vote_pubkey: Pubkey,
@@ -192,3 +711,48 @@ pub fn sign_and_send_transaction(
         }
     }
 }
+
+/// Sends several instructions in one transaction signed by `signer`, without printing.
+pub fn send_instructions(
+    rpc_client: Arc<RpcClient>,
+    instructions: &[Instruction],
+    signer: &Keypair,
+) -> Result<Signature, Box<dyn std::error::Error>> {
+    let transaction = Transaction::new(
+        &[&signer],
+        Message::new(instructions, Some(&signer.pubkey())),
+        rpc_client.get_latest_blockhash()?,
+    );
+    Ok(rpc_client.send_and_confirm_transaction(&transaction)?)
+}
+
+/// Sends `instructions` in a single v0 transaction that resolves accounts through
+/// `lookup_tables`, signed and paid by `signer`.
+pub fn send_versioned_instructions(
+    rpc_client: Arc<RpcClient>,
+    instructions: &[Instruction],
+    signer: &Keypair,
+    lookup_tables: &[AddressLookupTableAccount],
+) -> Result<Signature, Box<dyn std::error::Error>> {
+    let message = v0::Message::try_compile(
+        &signer.pubkey(),
+        instructions,
+        lookup_tables,
+        rpc_client.get_latest_blockhash()?,
+    )?;
+    let transaction = VersionedTransaction::try_new(VersionedMessage::V0(message), &[signer])?;
+    Ok(rpc_client.send_and_confirm_transaction(&transaction)?)
+}
+
+/// Fetches an address lookup table
+pub fn get_address_lookup_table(
+    rpc_client: Arc<RpcClient>,
+    pubkey: Pubkey,
+) -> Result<AddressLookupTableAccount, Box<dyn std::error::Error>> {
+    let account = rpc_client.get_account(&pubkey)?;
+    let lookup_table = AddressLookupTable::deserialize(&account.data)?;
+    Ok(AddressLookupTableAccount {
+        key: pubkey,
+        addresses: lookup_table.addresses.to_vec(),
+    })
+}
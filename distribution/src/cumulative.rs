@@ -0,0 +1,122 @@
+//! Lifetime totals of a validator's stakers, claimed from a `CumulativeDistributor` with a single
+//! proof instead of one claim per epoch.
+
+use {
+    crate::{distribution::DistributionFile, pubkey_string, DistributionError},
+    rakurai_merkle_tree::MerkleTree,
+    serde_derive::{Deserialize, Serialize},
+    solana_program::{clock::Epoch, pubkey::Pubkey},
+    std::{collections::BTreeMap, fs::File, io::BufWriter, path::Path},
+};
+
+/// Version written to, and expected from, every [CumulativeDistributionFile].
+pub const CUMULATIVE_DISTRIBUTION_FILE_VERSION: u8 = 1;
+
+/// A single leaf of the cumulative distribution.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CumulativeClaim {
+    #[serde(with = "pubkey_string")]
+    pub claimant: Pubkey,
+    /// Lamports the claimant earned over all included epochs.
+    pub total_amount: u64,
+}
+
+/// Everything needed to build, upload and claim a validator's cumulative merkle tree. Every
+/// epoch whose `RewardCollectionAccount` was swept into the distributor is added once, so the
+/// totals only ever grow.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CumulativeDistributionFile {
+    /// File format version, see [CUMULATIVE_DISTRIBUTION_FILE_VERSION].
+    pub version: u8,
+    #[serde(with = "pubkey_string")]
+    pub vote_account: Pubkey,
+    #[serde(with = "pubkey_string")]
+    pub distributor: Pubkey,
+    /// Epochs included in the totals, in the order they were added.
+    pub epochs: Vec<Epoch>,
+    /// Sum of all lifetime totals; passed to `upload_cumulative_merkle_root`.
+    pub max_total_claim: u64,
+    /// Claims sorted by claimant.
+    pub claims: Vec<CumulativeClaim>,
+}
+
+impl CumulativeDistributionFile {
+    /// An empty distribution for the `CumulativeDistributor` at `distributor`.
+    pub fn new(vote_account: Pubkey, distributor: Pubkey) -> Self {
+        Self {
+            version: CUMULATIVE_DISTRIBUTION_FILE_VERSION,
+            vote_account,
+            distributor,
+            epochs: Vec::new(),
+            max_total_claim: 0,
+            claims: Vec::new(),
+        }
+    }
+
+    /// Adds the claims of one epoch's distribution to the lifetime totals. The distribution must
+    /// be for the same vote account and its epoch must not have been added before.
+    pub fn add_distribution(
+        &mut self,
+        distribution: &DistributionFile,
+    ) -> Result<(), DistributionError> {
+        if distribution.vote_account != self.vote_account {
+            return Err(DistributionError::VoteAccountMismatch(
+                distribution.vote_account,
+            ));
+        }
+        if self.epochs.contains(&distribution.epoch) {
+            return Err(DistributionError::DuplicateEpoch(distribution.epoch));
+        }
+
+        let mut totals: BTreeMap<Pubkey, u64> = self
+            .claims
+            .iter()
+            .map(|claim| (claim.claimant, claim.total_amount))
+            .collect();
+        for claim in &distribution.claims {
+            let total = totals.entry(claim.claimant).or_default();
+            *total = total
+                .checked_add(claim.amount)
+                .ok_or(DistributionError::ArithmeticError)?;
+        }
+
+        self.max_total_claim = self
+            .max_total_claim
+            .checked_add(distribution.sum_claims()?)
+            .ok_or(DistributionError::ArithmeticError)?;
+        self.claims = totals
+            .into_iter()
+            .map(|(claimant, total_amount)| CumulativeClaim {
+                claimant,
+                total_amount,
+            })
+            .collect();
+        self.epochs.push(distribution.epoch);
+        Ok(())
+    }
+
+    /// Reads a cumulative distribution file, rejecting files written by another format version.
+    pub fn read(path: &Path) -> Result<Self, DistributionError> {
+        let distribution: Self = serde_json::from_reader(File::open(path)?)?;
+        if distribution.version != CUMULATIVE_DISTRIBUTION_FILE_VERSION {
+            return Err(DistributionError::UnsupportedVersion(distribution.version));
+        }
+        Ok(distribution)
+    }
+
+    /// Writes the distribution as pretty printed JSON.
+    pub fn write(&self, path: &Path) -> Result<(), DistributionError> {
+        serde_json::to_writer_pretty(BufWriter::new(File::create(path)?), self)?;
+        Ok(())
+    }
+
+    /// Builds the cumulative merkle tree for the claims, in file order.
+    pub fn merkle_tree(&self) -> Result<MerkleTree, DistributionError> {
+        let entries: Vec<(Pubkey, u64)> = self
+            .claims
+            .iter()
+            .map(|claim| (claim.claimant, claim.total_amount))
+            .collect();
+        Ok(MerkleTree::new_cumulative(&entries, self.distributor)?)
+    }
+}
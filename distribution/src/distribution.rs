@@ -0,0 +1,375 @@
+//! Pro rata split of a `RewardCollectionAccount` balance across the validator's stakers.
+
+use {
+    crate::{
+        pubkey_string, pubkey_vec_string, stake_accounts::StakeAccountEntry, DistributionError,
+    },
+    rakurai_merkle_tree::{LeafDomain, MerkleTree, LEAF_VERSION_V0, LEAF_VERSION_V1},
+    serde_derive::{Deserialize, Serialize},
+    solana_program::{clock::Epoch, pubkey::Pubkey},
+    std::{collections::BTreeMap, fs::File, io::BufWriter, path::Path},
+};
+
+/// Version written to, and expected from, every [DistributionFile].
+pub const DISTRIBUTION_FILE_VERSION: u8 = 1;
+
+/// Stake delegated by a single claimant, summed over all of their stake accounts.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StakeWeight {
+    /// Withdraw authority of the stake accounts; receives the claim.
+    pub claimant: Pubkey,
+    /// Delegated lamports used as the claimant's weight.
+    pub stake: u64,
+    /// Stake accounts contributing to `stake`.
+    pub stake_accounts: Vec<Pubkey>,
+}
+
+/// Groups the stake that was active for `epoch` on `vote_account` by withdraw authority.
+/// Only one claim can be made per claimant, so every stake account of the same withdrawer
+/// contributes to a single leaf. The result is sorted by claimant.
+pub fn stake_weights(
+    vote_account: &Pubkey,
+    epoch: Epoch,
+    stake_accounts: &[StakeAccountEntry],
+) -> Result<Vec<StakeWeight>, DistributionError> {
+    let mut weights: BTreeMap<Pubkey, StakeWeight> = BTreeMap::new();
+    for stake_account in stake_accounts {
+        if &stake_account.vote_pubkey != vote_account || !stake_account.is_active_at(epoch) {
+            continue;
+        }
+
+        let weight = weights
+            .entry(stake_account.withdrawer)
+            .or_insert_with(|| StakeWeight {
+                claimant: stake_account.withdrawer,
+                stake: 0,
+                stake_accounts: Vec::new(),
+            });
+        weight.stake = weight
+            .stake
+            .checked_add(stake_account.delegated_stake)
+            .ok_or(DistributionError::ArithmeticError)?;
+        weight.stake_accounts.push(stake_account.stake_pubkey);
+    }
+
+    Ok(weights.into_values().collect())
+}
+
+/// A single leaf of the distribution.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StakerClaim {
+    #[serde(with = "pubkey_string")]
+    pub claimant: Pubkey,
+    /// Lamports the claimant can claim.
+    pub amount: u64,
+    /// Stake weight the amount was derived from.
+    pub stake: u64,
+    #[serde(with = "pubkey_vec_string")]
+    pub stake_accounts: Vec<Pubkey>,
+}
+
+/// Everything needed to build, upload and claim a validator's merkle tree for one epoch.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DistributionFile {
+    /// File format version, see [DISTRIBUTION_FILE_VERSION].
+    pub version: u8,
+    #[serde(with = "pubkey_string")]
+    pub vote_account: Pubkey,
+    /// Epoch the rewards were earned in, i.e. the `RewardCollectionAccount`'s creation epoch.
+    pub epoch: Epoch,
+    #[serde(with = "pubkey_string")]
+    pub reward_collection_account: Pubkey,
+    /// Lamports available for distribution.
+    pub total_rewards: u64,
+    /// Sum of all claimants' stake weights.
+    pub total_stake: u64,
+    /// Sum of all claims; passed to `upload_merkle_root`.
+    pub max_total_claim: u64,
+    /// Number of claims; passed to `upload_merkle_root`.
+    pub max_num_nodes: u64,
+    /// Whether the tree uses indexed leaves, claimed through a `ClaimBitmap` instead of
+    /// `ClaimStatus` accounts. The index of a claim is its position in `claims`.
+    #[serde(default)]
+    pub indexed: bool,
+    /// Leaf format of the tree, passed to `upload_merkle_root`. Files written before leaves were
+    /// versioned use [LEAF_VERSION_V0]; new files bind their leaves to `reward_collection_account`
+    /// and `epoch` with [LEAF_VERSION_V1].
+    #[serde(default)]
+    pub leaf_version: u8,
+    pub claims: Vec<StakerClaim>,
+}
+
+impl DistributionFile {
+    /// Splits `total_rewards` across `weights` pro rata. Amounts are rounded down, so up to one
+    /// lamport per claimant stays unallocated in the `RewardCollectionAccount`. Claimants whose
+    /// share rounds down to zero are left out of the tree.
+    pub fn new(
+        vote_account: Pubkey,
+        epoch: Epoch,
+        reward_collection_account: Pubkey,
+        total_rewards: u64,
+        weights: Vec<StakeWeight>,
+    ) -> Result<Self, DistributionError> {
+        let total_stake = weights.iter().try_fold(0u64, |total, weight| {
+            total
+                .checked_add(weight.stake)
+                .ok_or(DistributionError::ArithmeticError)
+        })?;
+        if total_stake == 0 {
+            return Err(DistributionError::NoActiveStake(vote_account));
+        }
+
+        let mut claims = Vec::with_capacity(weights.len());
+        for weight in weights {
+            let amount = pro_rata_share(total_rewards, weight.stake, total_stake)?;
+            if amount == 0 {
+                continue;
+            }
+            claims.push(StakerClaim {
+                claimant: weight.claimant,
+                amount,
+                stake: weight.stake,
+                stake_accounts: weight.stake_accounts,
+            });
+        }
+
+        let mut distribution = Self {
+            version: DISTRIBUTION_FILE_VERSION,
+            vote_account,
+            epoch,
+            reward_collection_account,
+            total_rewards,
+            total_stake,
+            max_total_claim: 0,
+            max_num_nodes: claims.len() as u64,
+            indexed: false,
+            leaf_version: LEAF_VERSION_V1,
+            claims,
+        };
+        distribution.max_total_claim = distribution.sum_claims()?;
+        Ok(distribution)
+    }
+
+    /// Sum of all claim amounts.
+    pub fn sum_claims(&self) -> Result<u64, DistributionError> {
+        self.claims.iter().try_fold(0u64, |total, claim| {
+            total
+                .checked_add(claim.amount)
+                .ok_or(DistributionError::ArithmeticError)
+        })
+    }
+
+    /// Reads a distribution file, rejecting files written by another format version.
+    pub fn read(path: &Path) -> Result<Self, DistributionError> {
+        let distribution: Self = serde_json::from_reader(File::open(path)?)?;
+        if distribution.version != DISTRIBUTION_FILE_VERSION {
+            return Err(DistributionError::UnsupportedVersion(distribution.version));
+        }
+        Ok(distribution)
+    }
+
+    /// Writes the distribution as pretty printed JSON.
+    pub fn write(&self, path: &Path) -> Result<(), DistributionError> {
+        serde_json::to_writer_pretty(BufWriter::new(File::create(path)?), self)?;
+        Ok(())
+    }
+
+    /// Builds the merkle tree for the claims, in file order.
+    pub fn merkle_tree(&self) -> Result<MerkleTree, DistributionError> {
+        let entries: Vec<(Pubkey, u64)> = self
+            .claims
+            .iter()
+            .map(|claim| (claim.claimant, claim.amount))
+            .collect();
+        let domain = LeafDomain {
+            reward_collection_account: self.reward_collection_account,
+            creation_epoch: self.epoch,
+        };
+        let tree = match (self.leaf_version, self.indexed) {
+            (LEAF_VERSION_V0, false) => MerkleTree::new(&entries)?,
+            (LEAF_VERSION_V0, true) => MerkleTree::new_indexed(&entries)?,
+            (LEAF_VERSION_V1, false) => MerkleTree::new_bound(&entries, domain)?,
+            (LEAF_VERSION_V1, true) => MerkleTree::new_indexed_bound(&entries, domain)?,
+            (version, _) => return Err(DistributionError::UnsupportedLeafVersion(version)),
+        };
+        Ok(tree)
+    }
+}
+
+/// `total_rewards * stake / total_stake`, rounded down.
+pub fn pro_rata_share(
+    total_rewards: u64,
+    stake: u64,
+    total_stake: u64,
+) -> Result<u64, DistributionError> {
+    (total_rewards as u128)
+        .checked_mul(stake as u128)
+        .and_then(|product| product.checked_div(total_stake as u128))
+        .and_then(|share| u64::try_from(share).ok())
+        .ok_or(DistributionError::ArithmeticError)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pubkey(byte: u8) -> Pubkey {
+        Pubkey::new_from_array([byte; 32])
+    }
+
+    fn stake_account(
+        stake_pubkey: u8,
+        vote_pubkey: u8,
+        withdrawer: u8,
+        delegated_stake: u64,
+    ) -> StakeAccountEntry {
+        StakeAccountEntry {
+            stake_pubkey: pubkey(stake_pubkey),
+            vote_pubkey: pubkey(vote_pubkey),
+            staker: pubkey(withdrawer),
+            withdrawer: pubkey(withdrawer),
+            delegated_stake,
+            activation_epoch: 1,
+            deactivation_epoch: Epoch::MAX,
+        }
+    }
+
+    fn weight(claimant: u8, stake: u64) -> StakeWeight {
+        StakeWeight {
+            claimant: pubkey(claimant),
+            stake,
+            stake_accounts: vec![],
+        }
+    }
+
+    #[test]
+    fn stake_weights_group_active_stake_by_withdrawer() {
+        let inactive = StakeAccountEntry {
+            activation_epoch: 5,
+            ..stake_account(13, 100, 1, 1_000)
+        };
+        let stake_accounts = vec![
+            stake_account(10, 100, 2, 300),
+            stake_account(11, 100, 1, 100),
+            stake_account(12, 100, 2, 200),
+            stake_account(14, 101, 1, 1_000),
+            inactive,
+        ];
+        assert_eq!(
+            stake_weights(&pubkey(100), 5, &stake_accounts).unwrap(),
+            vec![
+                StakeWeight {
+                    claimant: pubkey(1),
+                    stake: 100,
+                    stake_accounts: vec![pubkey(11)],
+                },
+                StakeWeight {
+                    claimant: pubkey(2),
+                    stake: 500,
+                    stake_accounts: vec![pubkey(10), pubkey(12)],
+                },
+            ]
+        );
+
+        let overflowing = vec![
+            stake_account(10, 100, 1, u64::MAX),
+            stake_account(11, 100, 1, 1),
+        ];
+        assert!(matches!(
+            stake_weights(&pubkey(100), 5, &overflowing),
+            Err(DistributionError::ArithmeticError)
+        ));
+    }
+
+    #[test]
+    fn pro_rata_split_rounds_down() {
+        let distribution = DistributionFile::new(
+            pubkey(100),
+            5,
+            pubkey(101),
+            100,
+            vec![weight(1, 3), weight(2, 3), weight(3, 1), weight(4, 0)],
+        )
+        .unwrap();
+
+        let amounts: Vec<_> = distribution
+            .claims
+            .iter()
+            .map(|claim| (claim.claimant, claim.amount))
+            .collect();
+        // 100 * 3 / 7 = 42.86, 100 * 1 / 7 = 14.29; the zero share is left out of the tree.
+        assert_eq!(
+            amounts,
+            vec![(pubkey(1), 42), (pubkey(2), 42), (pubkey(3), 14)]
+        );
+        assert_eq!(distribution.total_stake, 7);
+        assert_eq!(distribution.max_total_claim, 98);
+        assert_eq!(distribution.max_num_nodes, 3);
+        assert_eq!(distribution.leaf_version, LEAF_VERSION_V1);
+        assert!(!distribution.indexed);
+    }
+
+    #[test]
+    fn pro_rata_split_errors() {
+        assert!(matches!(
+            DistributionFile::new(pubkey(100), 5, pubkey(101), 100, vec![weight(1, 0)]),
+            Err(DistributionError::NoActiveStake(vote_account)) if vote_account == pubkey(100)
+        ));
+        assert!(matches!(
+            DistributionFile::new(
+                pubkey(100),
+                5,
+                pubkey(101),
+                100,
+                vec![weight(1, u64::MAX), weight(2, 1)]
+            ),
+            Err(DistributionError::ArithmeticError)
+        ));
+
+        assert_eq!(
+            pro_rata_share(u64::MAX, u64::MAX, u64::MAX).unwrap(),
+            u64::MAX
+        );
+        assert_eq!(pro_rata_share(u64::MAX, 1, 2).unwrap(), u64::MAX / 2);
+        assert!(pro_rata_share(1, 1, 0).is_err());
+    }
+
+    #[test]
+    fn merkle_tree_per_leaf_version() {
+        let mut distribution = DistributionFile::new(
+            pubkey(100),
+            5,
+            pubkey(101),
+            100,
+            vec![weight(1, 1), weight(2, 3)],
+        )
+        .unwrap();
+
+        let tree = distribution.merkle_tree().unwrap();
+        assert_eq!(tree.version(), LEAF_VERSION_V1);
+        assert_eq!(
+            tree.domain,
+            Some(LeafDomain {
+                reward_collection_account: pubkey(101),
+                creation_epoch: 5,
+            })
+        );
+        assert_eq!(tree.max_total_claim, distribution.max_total_claim);
+        assert!(tree.verify_proofs());
+
+        distribution.indexed = true;
+        assert!(distribution.merkle_tree().unwrap().indexed);
+
+        distribution.leaf_version = LEAF_VERSION_V0;
+        assert_eq!(
+            distribution.merkle_tree().unwrap().version(),
+            LEAF_VERSION_V0
+        );
+
+        distribution.leaf_version = 2;
+        assert!(matches!(
+            distribution.merkle_tree(),
+            Err(DistributionError::UnsupportedLeafVersion(2))
+        ));
+    }
+}
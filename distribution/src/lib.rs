@@ -0,0 +1,172 @@
+//! Builds the off-chain inputs of the `reward_distribution` program for a single validator and epoch:
+//! 1. [stake_accounts] loads the stake accounts delegated to the validator from an export.
+//! 2. [distribution] groups them into per-claimant stake weights and splits the
+//!    `RewardCollectionAccount` balance pro rata into a versioned [DistributionFile].
+//! 3. [policy] optionally applies exclusions, weight multipliers, a minimum payout and dust
+//!    handling, and records each adjustment in an [AuditReport].
+//! 4. [DistributionFile::merkle_tree] builds the merkle tree that is uploaded on-chain.
+//!
+//! For validators using a `CumulativeDistributor`, [cumulative] adds each epoch's distribution to
+//! the stakers' lifetime totals in a [CumulativeDistributionFile] instead.
+//!
+//! [DistributionFile]: distribution::DistributionFile
+//! [AuditReport]: policy::AuditReport
+//! [DistributionFile::merkle_tree]: distribution::DistributionFile::merkle_tree
+//! [CumulativeDistributionFile]: cumulative::CumulativeDistributionFile
+
+use {
+    rakurai_merkle_tree::MerkleTreeError,
+    solana_program::pubkey::Pubkey,
+    std::{fmt, io},
+};
+
+pub mod cumulative;
+pub mod distribution;
+pub mod policy;
+pub mod stake_accounts;
+
+/// Errors returned while building a distribution.
+#[derive(Debug)]
+pub enum DistributionError {
+    /// Reading or writing a file failed.
+    Io(io::Error),
+    /// A JSON document could not be parsed or written.
+    Json(serde_json::Error),
+    /// A CSV document could not be parsed.
+    Csv(csv::Error),
+    /// A pubkey string is not valid base58.
+    InvalidPubkey(String),
+    /// A stake account could not be decoded.
+    InvalidStakeAccount(String),
+    /// No active stake is delegated to the vote account.
+    NoActiveStake(Pubkey),
+    /// The distribution file was written by an incompatible version.
+    UnsupportedVersion(u8),
+    /// The distribution file asks for a merkle leaf format this crate can not build.
+    UnsupportedLeafVersion(u8),
+    /// Encountered an arithmetic under/overflow.
+    ArithmeticError,
+    /// A distribution of another vote account was added to a cumulative distribution.
+    VoteAccountMismatch(Pubkey),
+    /// The epoch is already part of the cumulative distribution.
+    DuplicateEpoch(u64),
+    /// The merkle tree could not be built.
+    MerkleTree(MerkleTreeError),
+}
+
+impl fmt::Display for DistributionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DistributionError::Io(err) => write!(f, "I/O error: {}", err),
+            DistributionError::Json(err) => write!(f, "JSON error: {}", err),
+            DistributionError::Csv(err) => write!(f, "CSV error: {}", err),
+            DistributionError::InvalidPubkey(pubkey) => {
+                write!(f, "Invalid Solana public key: {}", pubkey)
+            }
+            DistributionError::InvalidStakeAccount(reason) => {
+                write!(f, "Invalid stake account: {}", reason)
+            }
+            DistributionError::NoActiveStake(vote_account) => {
+                write!(f, "No active stake is delegated to {}", vote_account)
+            }
+            DistributionError::UnsupportedVersion(version) => {
+                write!(f, "Unsupported distribution file version: {}", version)
+            }
+            DistributionError::UnsupportedLeafVersion(version) => {
+                write!(f, "Unsupported merkle leaf version: {}", version)
+            }
+            DistributionError::ArithmeticError => {
+                write!(f, "Encountered an arithmetic under/overflow error")
+            }
+            DistributionError::VoteAccountMismatch(vote_account) => {
+                write!(
+                    f,
+                    "Distribution is for another vote account: {}",
+                    vote_account
+                )
+            }
+            DistributionError::DuplicateEpoch(epoch) => {
+                write!(
+                    f,
+                    "Epoch {} is already part of the cumulative distribution",
+                    epoch
+                )
+            }
+            DistributionError::MerkleTree(err) => write!(f, "Merkle tree error: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for DistributionError {}
+
+impl From<io::Error> for DistributionError {
+    fn from(err: io::Error) -> Self {
+        DistributionError::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for DistributionError {
+    fn from(err: serde_json::Error) -> Self {
+        DistributionError::Json(err)
+    }
+}
+
+impl From<csv::Error> for DistributionError {
+    fn from(err: csv::Error) -> Self {
+        DistributionError::Csv(err)
+    }
+}
+
+impl From<MerkleTreeError> for DistributionError {
+    fn from(err: MerkleTreeError) -> Self {
+        DistributionError::MerkleTree(err)
+    }
+}
+
+/// Parses a base58 encoded pubkey.
+pub(crate) fn parse_pubkey(s: &str) -> Result<Pubkey, DistributionError> {
+    s.parse()
+        .map_err(|_| DistributionError::InvalidPubkey(s.to_string()))
+}
+
+/// Serializes pubkeys as base58 strings instead of byte arrays, so files stay human readable.
+pub(crate) mod pubkey_string {
+    use {
+        serde::{de::Error, Deserialize, Deserializer, Serializer},
+        solana_program::pubkey::Pubkey,
+    };
+
+    pub fn serialize<S: Serializer>(pubkey: &Pubkey, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&pubkey.to_string())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Pubkey, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(D::Error::custom)
+    }
+}
+
+/// Same as [pubkey_string] for a list of pubkeys.
+pub(crate) mod pubkey_vec_string {
+    use {
+        serde::{de::Error, ser::SerializeSeq, Deserialize, Deserializer, Serializer},
+        solana_program::pubkey::Pubkey,
+    };
+
+    pub fn serialize<S: Serializer>(pubkeys: &[Pubkey], serializer: S) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(Some(pubkeys.len()))?;
+        for pubkey in pubkeys {
+            seq.serialize_element(&pubkey.to_string())?;
+        }
+        seq.end()
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<Pubkey>, D::Error> {
+        Vec::<String>::deserialize(deserializer)?
+            .iter()
+            .map(|s| s.parse().map_err(D::Error::custom))
+            .collect()
+    }
+}
@@ -0,0 +1,636 @@
+//! Declarative adjustments applied while generating a distribution, and the audit report that
+//! records every one of them.
+//!
+//! A policy file is JSON; every field is optional:
+//! ```json
+//! {
+//!   "excluded_stake_accounts": ["<stake account>"],
+//!   "excluded_withdrawers": ["<withdraw authority>"],
+//!   "weight_multipliers": [{ "account": "<stake account or withdraw authority>", "multiplier_bps": 5000 }],
+//!   "min_payout_lamports": 10000,
+//!   "dust": "unallocated" | "largest_claim" | "pro_rata"
+//! }
+//! ```
+//!
+//! Exclusions and multipliers change stake weights before the pro rata split. The minimum payout
+//! and dust handling run afterwards, on the claim amounts.
+
+use {
+    crate::{
+        distribution::{pro_rata_share, DistributionFile},
+        pubkey_string, pubkey_vec_string,
+        stake_accounts::StakeAccountEntry,
+        DistributionError,
+    },
+    serde_derive::{Deserialize, Serialize},
+    solana_program::{clock::Epoch, pubkey::Pubkey},
+    std::{
+        collections::{HashMap, HashSet},
+        fs::File,
+        io::BufWriter,
+        path::Path,
+    },
+};
+
+/// Multiplier that leaves a stake weight unchanged.
+pub const BASE_MULTIPLIER_BPS: u64 = 10_000;
+
+/// What happens to lamports that are not allocated to any claim, either because of rounding or
+/// because a claim fell below `min_payout_lamports`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DustHandling {
+    /// Leave them in the `RewardCollectionAccount`; they go back to the validator on expiry.
+    #[default]
+    Unallocated,
+    /// Add them to the largest claim.
+    LargestClaim,
+    /// Split them across the remaining claims by stake weight, handing out the rounding
+    /// remainder one lamport at a time to the largest fractional shares.
+    ProRata,
+}
+
+/// Scales the stake weight of a stake account, or of every stake account of a withdrawer.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WeightMultiplier {
+    /// Stake account or withdraw authority the multiplier applies to.
+    #[serde(with = "pubkey_string")]
+    pub account: Pubkey,
+    /// New weight in basis points of the delegated stake, e.g. `5_000` halves it.
+    pub multiplier_bps: u64,
+}
+
+/// Adjustments applied to a distribution before its merkle tree is built.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DistributionPolicy {
+    /// Stake accounts that receive nothing.
+    #[serde(with = "pubkey_vec_string")]
+    pub excluded_stake_accounts: Vec<Pubkey>,
+    /// Withdraw authorities whose stake accounts receive nothing.
+    #[serde(with = "pubkey_vec_string")]
+    pub excluded_withdrawers: Vec<Pubkey>,
+    /// Stake weight multipliers. A stake account multiplier takes precedence over one set for
+    /// its withdrawer.
+    pub weight_multipliers: Vec<WeightMultiplier>,
+    /// Claims below this amount are dropped and their lamports treated as dust.
+    pub min_payout_lamports: u64,
+    pub dust: DustHandling,
+}
+
+/// Why a stake account was left out of the distribution.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExclusionReason {
+    StakeAccount,
+    Withdrawer,
+}
+
+/// A single change made by the policy.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Adjustment {
+    Excluded {
+        #[serde(with = "pubkey_string")]
+        stake_account: Pubkey,
+        #[serde(with = "pubkey_string")]
+        withdrawer: Pubkey,
+        delegated_stake: u64,
+        reason: ExclusionReason,
+    },
+    WeightMultiplied {
+        #[serde(with = "pubkey_string")]
+        stake_account: Pubkey,
+        #[serde(with = "pubkey_string")]
+        withdrawer: Pubkey,
+        multiplier_bps: u64,
+        stake_before: u64,
+        stake_after: u64,
+    },
+    BelowMinimumPayout {
+        #[serde(with = "pubkey_string")]
+        claimant: Pubkey,
+        amount: u64,
+    },
+    DustAllocated {
+        #[serde(with = "pubkey_string")]
+        claimant: Pubkey,
+        amount: u64,
+    },
+}
+
+/// Shows how `max_total_claim` was derived from `total_rewards`:
+/// `max_total_claim = pro_rata_total - below_minimum_total + dust_allocated`.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct AuditReport {
+    #[serde(with = "pubkey_string")]
+    pub vote_account: Pubkey,
+    pub epoch: Epoch,
+    pub policy: DistributionPolicy,
+    /// Lamports available for distribution.
+    pub total_rewards: u64,
+    /// Sum of the claims straight after the pro rata split.
+    pub pro_rata_total: u64,
+    /// Sum of the claims dropped for being below `min_payout_lamports`.
+    pub below_minimum_total: u64,
+    /// Lamports added to claims by the dust handling.
+    pub dust_allocated: u64,
+    /// Lamports left in the `RewardCollectionAccount`.
+    pub unallocated: u64,
+    pub max_total_claim: u64,
+    pub max_num_nodes: u64,
+    pub adjustments: Vec<Adjustment>,
+}
+
+impl AuditReport {
+    pub fn new(vote_account: Pubkey, epoch: Epoch, policy: &DistributionPolicy) -> Self {
+        Self {
+            vote_account,
+            epoch,
+            policy: policy.clone(),
+            ..Self::default()
+        }
+    }
+
+    /// Writes the report as pretty printed JSON.
+    pub fn write(&self, path: &Path) -> Result<(), DistributionError> {
+        serde_json::to_writer_pretty(BufWriter::new(File::create(path)?), self)?;
+        Ok(())
+    }
+}
+
+impl DistributionPolicy {
+    /// Reads a policy file.
+    pub fn read(path: &Path) -> Result<Self, DistributionError> {
+        Ok(serde_json::from_reader(File::open(path)?)?)
+    }
+
+    /// Drops excluded stake accounts and applies weight multipliers. Only stake that was active
+    /// for `epoch` on `vote_account` is returned and recorded in the report.
+    pub fn apply_to_stake_accounts(
+        &self,
+        vote_account: &Pubkey,
+        epoch: Epoch,
+        stake_accounts: Vec<StakeAccountEntry>,
+        report: &mut AuditReport,
+    ) -> Result<Vec<StakeAccountEntry>, DistributionError> {
+        let excluded_stake_accounts: HashSet<&Pubkey> =
+            self.excluded_stake_accounts.iter().collect();
+        let excluded_withdrawers: HashSet<&Pubkey> = self.excluded_withdrawers.iter().collect();
+        let multipliers: HashMap<&Pubkey, u64> = self
+            .weight_multipliers
+            .iter()
+            .map(|multiplier| (&multiplier.account, multiplier.multiplier_bps))
+            .collect();
+
+        let mut adjusted = Vec::with_capacity(stake_accounts.len());
+        for mut stake_account in stake_accounts {
+            if &stake_account.vote_pubkey != vote_account || !stake_account.is_active_at(epoch) {
+                continue;
+            }
+
+            let reason = if excluded_stake_accounts.contains(&stake_account.stake_pubkey) {
+                Some(ExclusionReason::StakeAccount)
+            } else if excluded_withdrawers.contains(&stake_account.withdrawer) {
+                Some(ExclusionReason::Withdrawer)
+            } else {
+                None
+            };
+            if let Some(reason) = reason {
+                report.adjustments.push(Adjustment::Excluded {
+                    stake_account: stake_account.stake_pubkey,
+                    withdrawer: stake_account.withdrawer,
+                    delegated_stake: stake_account.delegated_stake,
+                    reason,
+                });
+                continue;
+            }
+
+            let multiplier_bps = multipliers
+                .get(&stake_account.stake_pubkey)
+                .or_else(|| multipliers.get(&stake_account.withdrawer));
+            if let Some(&multiplier_bps) = multiplier_bps {
+                let stake_before = stake_account.delegated_stake;
+                let stake_after = (stake_before as u128)
+                    .checked_mul(multiplier_bps as u128)
+                    .map(|stake| stake / BASE_MULTIPLIER_BPS as u128)
+                    .and_then(|stake| u64::try_from(stake).ok())
+                    .ok_or(DistributionError::ArithmeticError)?;
+                report.adjustments.push(Adjustment::WeightMultiplied {
+                    stake_account: stake_account.stake_pubkey,
+                    withdrawer: stake_account.withdrawer,
+                    multiplier_bps,
+                    stake_before,
+                    stake_after,
+                });
+                stake_account.delegated_stake = stake_after;
+            }
+
+            adjusted.push(stake_account);
+        }
+
+        Ok(adjusted)
+    }
+
+    /// Drops claims below `min_payout_lamports`, allocates dust and refreshes the distribution's
+    /// `max_total_claim` and `max_num_nodes`.
+    pub fn apply_to_distribution(
+        &self,
+        distribution: &mut DistributionFile,
+        report: &mut AuditReport,
+    ) -> Result<(), DistributionError> {
+        report.total_rewards = distribution.total_rewards;
+        report.pro_rata_total = distribution.max_total_claim;
+
+        let mut below_minimum_total = 0u64;
+        for claim in std::mem::take(&mut distribution.claims) {
+            if claim.amount >= self.min_payout_lamports {
+                distribution.claims.push(claim);
+                continue;
+            }
+            below_minimum_total = below_minimum_total
+                .checked_add(claim.amount)
+                .ok_or(DistributionError::ArithmeticError)?;
+            report.adjustments.push(Adjustment::BelowMinimumPayout {
+                claimant: claim.claimant,
+                amount: claim.amount,
+            });
+        }
+        report.below_minimum_total = below_minimum_total;
+
+        let allocated = distribution.sum_claims()?;
+        let dust = distribution
+            .total_rewards
+            .checked_sub(allocated)
+            .ok_or(DistributionError::ArithmeticError)?;
+        if dust > 0 && !distribution.claims.is_empty() {
+            match self.dust {
+                DustHandling::Unallocated => {}
+                DustHandling::LargestClaim => {
+                    // `max_by_key` returns the last maximum, prefer the first one instead.
+                    let largest_claim = distribution
+                        .claims
+                        .iter_mut()
+                        .rev()
+                        .max_by_key(|claim| claim.amount)
+                        .ok_or(DistributionError::ArithmeticError)?;
+                    largest_claim.amount = largest_claim
+                        .amount
+                        .checked_add(dust)
+                        .ok_or(DistributionError::ArithmeticError)?;
+                    report.adjustments.push(Adjustment::DustAllocated {
+                        claimant: largest_claim.claimant,
+                        amount: dust,
+                    });
+                }
+                DustHandling::ProRata => redistribute(distribution, report)?,
+            }
+        }
+
+        distribution.max_total_claim = distribution.sum_claims()?;
+        distribution.max_num_nodes = distribution.claims.len() as u64;
+
+        report.dust_allocated = distribution
+            .max_total_claim
+            .checked_sub(allocated)
+            .ok_or(DistributionError::ArithmeticError)?;
+        report.unallocated = distribution
+            .total_rewards
+            .checked_sub(distribution.max_total_claim)
+            .ok_or(DistributionError::ArithmeticError)?;
+        report.max_total_claim = distribution.max_total_claim;
+        report.max_num_nodes = distribution.max_num_nodes;
+
+        Ok(())
+    }
+}
+
+/// Re-splits `total_rewards` across the remaining claims using the largest remainder method, so
+/// every lamport is allocated.
+fn redistribute(
+    distribution: &mut DistributionFile,
+    report: &mut AuditReport,
+) -> Result<(), DistributionError> {
+    let total_stake = distribution.claims.iter().try_fold(0u64, |total, claim| {
+        total
+            .checked_add(claim.stake)
+            .ok_or(DistributionError::ArithmeticError)
+    })?;
+    if total_stake == 0 {
+        return Ok(());
+    }
+
+    let mut amounts = Vec::with_capacity(distribution.claims.len());
+    let mut remainders = Vec::with_capacity(distribution.claims.len());
+    for (index, claim) in distribution.claims.iter().enumerate() {
+        amounts.push(pro_rata_share(
+            distribution.total_rewards,
+            claim.stake,
+            total_stake,
+        )?);
+        let remainder =
+            (distribution.total_rewards as u128 * claim.stake as u128) % total_stake as u128;
+        remainders.push((remainder, index));
+    }
+
+    let allocated = amounts.iter().try_fold(0u64, |total, amount| {
+        total
+            .checked_add(*amount)
+            .ok_or(DistributionError::ArithmeticError)
+    })?;
+    let leftover = distribution
+        .total_rewards
+        .checked_sub(allocated)
+        .ok_or(DistributionError::ArithmeticError)?;
+    // Largest remainder first, ties broken by claim order.
+    remainders.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+    for &(_, index) in remainders.iter().take(leftover as usize) {
+        amounts[index] = amounts[index]
+            .checked_add(1)
+            .ok_or(DistributionError::ArithmeticError)?;
+    }
+
+    for (claim, amount) in distribution.claims.iter_mut().zip(amounts) {
+        if amount > claim.amount {
+            report.adjustments.push(Adjustment::DustAllocated {
+                claimant: claim.claimant,
+                amount: amount - claim.amount,
+            });
+        }
+        claim.amount = amount;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::distribution::StakeWeight};
+
+    fn pubkey(byte: u8) -> Pubkey {
+        Pubkey::new_from_array([byte; 32])
+    }
+
+    fn stake_account(stake_pubkey: u8, withdrawer: u8, delegated_stake: u64) -> StakeAccountEntry {
+        StakeAccountEntry {
+            stake_pubkey: pubkey(stake_pubkey),
+            vote_pubkey: pubkey(100),
+            staker: pubkey(withdrawer),
+            withdrawer: pubkey(withdrawer),
+            delegated_stake,
+            activation_epoch: 1,
+            deactivation_epoch: Epoch::MAX,
+        }
+    }
+
+    /// A distribution of `total_rewards` over claimants `1..` with the given stakes.
+    fn pro_rata_distribution(total_rewards: u64, stakes: &[u64]) -> DistributionFile {
+        let weights = stakes
+            .iter()
+            .enumerate()
+            .map(|(i, stake)| StakeWeight {
+                claimant: pubkey(i as u8 + 1),
+                stake: *stake,
+                stake_accounts: vec![],
+            })
+            .collect();
+        DistributionFile::new(pubkey(100), 5, pubkey(101), total_rewards, weights).unwrap()
+    }
+
+    fn amounts(distribution: &DistributionFile) -> Vec<(Pubkey, u64)> {
+        distribution
+            .claims
+            .iter()
+            .map(|claim| (claim.claimant, claim.amount))
+            .collect()
+    }
+
+    /// Applies `policy` to `distribution` and checks the totals of the report add up.
+    fn apply(policy: &DistributionPolicy, distribution: &mut DistributionFile) -> AuditReport {
+        let mut report = AuditReport::new(pubkey(100), 5, policy);
+        policy
+            .apply_to_distribution(distribution, &mut report)
+            .unwrap();
+        assert_eq!(
+            report.max_total_claim,
+            report.pro_rata_total - report.below_minimum_total + report.dust_allocated
+        );
+        assert_eq!(
+            report.max_total_claim + report.unallocated,
+            report.total_rewards
+        );
+        assert_eq!(report.max_total_claim, distribution.max_total_claim);
+        assert_eq!(report.max_num_nodes, distribution.claims.len() as u64);
+        report
+    }
+
+    #[test]
+    fn exclusions_and_weight_multipliers() {
+        let policy = DistributionPolicy {
+            excluded_stake_accounts: vec![pubkey(10)],
+            excluded_withdrawers: vec![pubkey(2)],
+            weight_multipliers: vec![
+                WeightMultiplier {
+                    account: pubkey(3),
+                    multiplier_bps: 5_000,
+                },
+                WeightMultiplier {
+                    account: pubkey(13),
+                    multiplier_bps: 20_000,
+                },
+            ],
+            ..DistributionPolicy::default()
+        };
+        let inactive = StakeAccountEntry {
+            activation_epoch: 5,
+            ..stake_account(15, 1, 100)
+        };
+        let stake_accounts = vec![
+            stake_account(10, 1, 100),
+            stake_account(11, 2, 100),
+            stake_account(12, 3, 100),
+            stake_account(13, 3, 100),
+            stake_account(14, 4, 100),
+            inactive,
+        ];
+
+        let mut report = AuditReport::new(pubkey(100), 5, &policy);
+        let adjusted = policy
+            .apply_to_stake_accounts(&pubkey(100), 5, stake_accounts, &mut report)
+            .unwrap();
+        let stakes: Vec<_> = adjusted
+            .iter()
+            .map(|stake_account| (stake_account.stake_pubkey, stake_account.delegated_stake))
+            .collect();
+        // The stake account multiplier of 13 takes precedence over the withdrawer's.
+        assert_eq!(
+            stakes,
+            vec![(pubkey(12), 50), (pubkey(13), 200), (pubkey(14), 100)]
+        );
+        assert_eq!(
+            report.adjustments,
+            vec![
+                Adjustment::Excluded {
+                    stake_account: pubkey(10),
+                    withdrawer: pubkey(1),
+                    delegated_stake: 100,
+                    reason: ExclusionReason::StakeAccount,
+                },
+                Adjustment::Excluded {
+                    stake_account: pubkey(11),
+                    withdrawer: pubkey(2),
+                    delegated_stake: 100,
+                    reason: ExclusionReason::Withdrawer,
+                },
+                Adjustment::WeightMultiplied {
+                    stake_account: pubkey(12),
+                    withdrawer: pubkey(3),
+                    multiplier_bps: 5_000,
+                    stake_before: 100,
+                    stake_after: 50,
+                },
+                Adjustment::WeightMultiplied {
+                    stake_account: pubkey(13),
+                    withdrawer: pubkey(3),
+                    multiplier_bps: 20_000,
+                    stake_before: 100,
+                    stake_after: 200,
+                },
+            ]
+        );
+
+        let overflowing = DistributionPolicy {
+            weight_multipliers: vec![WeightMultiplier {
+                account: pubkey(1),
+                multiplier_bps: 20_000,
+            }],
+            ..DistributionPolicy::default()
+        };
+        assert!(matches!(
+            overflowing.apply_to_stake_accounts(
+                &pubkey(100),
+                5,
+                vec![stake_account(10, 1, u64::MAX)],
+                &mut report
+            ),
+            Err(DistributionError::ArithmeticError)
+        ));
+    }
+
+    #[test]
+    fn minimum_payout_leaves_dust_unallocated() {
+        let policy = DistributionPolicy {
+            min_payout_lamports: 20,
+            ..DistributionPolicy::default()
+        };
+        let mut distribution = pro_rata_distribution(100, &[3, 3, 1]);
+        let report = apply(&policy, &mut distribution);
+
+        assert_eq!(
+            amounts(&distribution),
+            vec![(pubkey(1), 42), (pubkey(2), 42)]
+        );
+        assert_eq!(report.pro_rata_total, 98);
+        assert_eq!(report.below_minimum_total, 14);
+        assert_eq!(report.dust_allocated, 0);
+        assert_eq!(report.unallocated, 16);
+        assert_eq!(
+            report.adjustments,
+            vec![Adjustment::BelowMinimumPayout {
+                claimant: pubkey(3),
+                amount: 14,
+            }]
+        );
+    }
+
+    #[test]
+    fn largest_claim_takes_the_dust() {
+        let policy = DistributionPolicy {
+            dust: DustHandling::LargestClaim,
+            ..DistributionPolicy::default()
+        };
+        let mut distribution = pro_rata_distribution(100, &[3, 3, 1]);
+        let report = apply(&policy, &mut distribution);
+
+        // Ties go to the first largest claim.
+        assert_eq!(
+            amounts(&distribution),
+            vec![(pubkey(1), 44), (pubkey(2), 42), (pubkey(3), 14)]
+        );
+        assert_eq!(report.dust_allocated, 2);
+        assert_eq!(report.unallocated, 0);
+        assert_eq!(
+            report.adjustments,
+            vec![Adjustment::DustAllocated {
+                claimant: pubkey(1),
+                amount: 2,
+            }]
+        );
+    }
+
+    #[test]
+    fn pro_rata_dust_uses_largest_remainders() {
+        let policy = DistributionPolicy {
+            dust: DustHandling::ProRata,
+            ..DistributionPolicy::default()
+        };
+        // 100 * [3, 2, 2] / 7 = [42.86, 28.57, 28.57]: the two leftover lamports go to the
+        // largest remainder, then to the first of the tied ones.
+        let mut distribution = pro_rata_distribution(100, &[3, 2, 2]);
+        let report = apply(&policy, &mut distribution);
+        assert_eq!(
+            amounts(&distribution),
+            vec![(pubkey(1), 43), (pubkey(2), 29), (pubkey(3), 28)]
+        );
+        assert_eq!(report.pro_rata_total, 98);
+        assert_eq!(report.dust_allocated, 2);
+        assert_eq!(report.unallocated, 0);
+
+        // The lamports of the claims below the minimum are re-split as well.
+        let policy = DistributionPolicy {
+            min_payout_lamports: 20,
+            dust: DustHandling::ProRata,
+            ..DistributionPolicy::default()
+        };
+        let mut distribution = pro_rata_distribution(100, &[3, 3, 1]);
+        let report = apply(&policy, &mut distribution);
+        assert_eq!(
+            amounts(&distribution),
+            vec![(pubkey(1), 50), (pubkey(2), 50)]
+        );
+        assert_eq!(report.below_minimum_total, 14);
+        assert_eq!(report.dust_allocated, 16);
+        assert_eq!(report.unallocated, 0);
+    }
+
+    #[test]
+    fn every_claim_below_minimum() {
+        let policy = DistributionPolicy {
+            min_payout_lamports: 1_000,
+            dust: DustHandling::ProRata,
+            ..DistributionPolicy::default()
+        };
+        let mut distribution = pro_rata_distribution(100, &[1, 1]);
+        let report = apply(&policy, &mut distribution);
+        assert!(distribution.claims.is_empty());
+        assert_eq!(report.max_total_claim, 0);
+        assert_eq!(report.unallocated, 100);
+    }
+
+    #[test]
+    fn below_minimum_total_overflow() {
+        let policy = DistributionPolicy {
+            min_payout_lamports: u64::MAX,
+            ..DistributionPolicy::default()
+        };
+        let mut distribution = pro_rata_distribution(100, &[1, 1]);
+        for claim in &mut distribution.claims {
+            claim.amount = u64::MAX - 1;
+        }
+        let mut report = AuditReport::new(pubkey(100), 5, &policy);
+        assert!(matches!(
+            policy.apply_to_distribution(&mut distribution, &mut report),
+            Err(DistributionError::ArithmeticError)
+        ));
+    }
+}
@@ -0,0 +1,414 @@
+//! Loads the stake accounts delegated to a validator from an export of the epoch's final state.
+//! Snapshots are not read directly: the stake accounts are exported through RPC first, e.g. from
+//! a node started from the snapshot of the epoch's last slot.
+//!
+//! Three formats are supported:
+//! - [StakeAccountsFormat::ProgramAccounts]: the `result` of a stake program `getProgramAccounts`
+//!   request with `base64` encoding. Account data is decoded as `StakeStateV2`.
+//! - [StakeAccountsFormat::Json]: the output of `solana stakes <VOTE_ACCOUNT> --output json`.
+//! - [StakeAccountsFormat::Csv]: a CSV file with the header
+//!   `stake_pubkey,vote_pubkey,staker,withdrawer,delegated_stake,activation_epoch,deactivation_epoch`.
+//!   An empty `deactivation_epoch` means the stake is not deactivating.
+
+use {
+    crate::{parse_pubkey, DistributionError},
+    base64::{engine::general_purpose::STANDARD, Engine},
+    serde_derive::Deserialize,
+    solana_program::{clock::Epoch, pubkey::Pubkey},
+    std::{fs::File, io::Read, path::Path, str::FromStr},
+};
+// The stake types moved to solana-stake-interface, which this crate does not depend on yet.
+#[allow(deprecated)]
+use solana_program::stake::{self, state::StakeStateV2};
+
+/// A delegated stake account.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StakeAccountEntry {
+    pub stake_pubkey: Pubkey,
+    /// Vote account the stake is delegated to.
+    pub vote_pubkey: Pubkey,
+    pub staker: Pubkey,
+    /// Withdraw authority, which receives the stake account's rewards.
+    pub withdrawer: Pubkey,
+    /// Delegated lamports.
+    pub delegated_stake: u64,
+    pub activation_epoch: Epoch,
+    /// `Epoch::MAX` when the stake is not deactivating.
+    pub deactivation_epoch: Epoch,
+}
+
+impl StakeAccountEntry {
+    /// Whether the stake was effective for the whole of `epoch`. Stake activated in `epoch` only
+    /// becomes effective in the next one, while stake deactivated in `epoch` stays effective until
+    /// the epoch ends. Warmup and cooldown rate limits are not taken into account.
+    pub fn is_active_at(&self, epoch: Epoch) -> bool {
+        self.activation_epoch < epoch && epoch <= self.deactivation_epoch
+    }
+}
+
+/// Supported stake account export formats.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StakeAccountsFormat {
+    ProgramAccounts,
+    Json,
+    Csv,
+}
+
+impl FromStr for StakeAccountsFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "program-accounts" => Ok(StakeAccountsFormat::ProgramAccounts),
+            "json" => Ok(StakeAccountsFormat::Json),
+            "csv" => Ok(StakeAccountsFormat::Csv),
+            _ => Err(format!(
+                "Unknown stake accounts format: {} (expected program-accounts, json or csv)",
+                s
+            )),
+        }
+    }
+}
+
+/// Reads every delegated stake account from `path`. Undelegated accounts are skipped.
+pub fn load_stake_accounts(
+    path: &Path,
+    format: StakeAccountsFormat,
+) -> Result<Vec<StakeAccountEntry>, DistributionError> {
+    let file = File::open(path)?;
+    match format {
+        StakeAccountsFormat::ProgramAccounts => parse_keyed_accounts(file),
+        StakeAccountsFormat::Json => parse_cli_stakes(file),
+        StakeAccountsFormat::Csv => parse_csv(file),
+    }
+}
+
+#[derive(Deserialize)]
+struct KeyedAccount {
+    pubkey: String,
+    account: RawAccount,
+}
+
+#[derive(Deserialize)]
+struct RawAccount {
+    /// `[data, encoding]`
+    data: (String, String),
+    owner: String,
+}
+
+fn parse_keyed_accounts(reader: impl Read) -> Result<Vec<StakeAccountEntry>, DistributionError> {
+    let keyed_accounts: Vec<KeyedAccount> = serde_json::from_reader(reader)?;
+
+    let mut entries = Vec::with_capacity(keyed_accounts.len());
+    for keyed_account in keyed_accounts {
+        let stake_pubkey = parse_pubkey(&keyed_account.pubkey)?;
+        if parse_pubkey(&keyed_account.account.owner)? != stake::program::id() {
+            return Err(DistributionError::InvalidStakeAccount(format!(
+                "{} is not owned by the stake program",
+                stake_pubkey
+            )));
+        }
+
+        let (data, encoding) = &keyed_account.account.data;
+        if encoding != "base64" {
+            return Err(DistributionError::InvalidStakeAccount(format!(
+                "{} uses unsupported encoding {}",
+                stake_pubkey, encoding
+            )));
+        }
+        let data = STANDARD.decode(data).map_err(|err| {
+            DistributionError::InvalidStakeAccount(format!("{}: {}", stake_pubkey, err))
+        })?;
+        let stake_state: StakeStateV2 = bincode::deserialize(&data).map_err(|err| {
+            DistributionError::InvalidStakeAccount(format!("{}: {}", stake_pubkey, err))
+        })?;
+
+        if let StakeStateV2::Stake(meta, stake, _) = stake_state {
+            entries.push(StakeAccountEntry {
+                stake_pubkey,
+                vote_pubkey: stake.delegation.voter_pubkey,
+                staker: meta.authorized.staker,
+                withdrawer: meta.authorized.withdrawer,
+                delegated_stake: stake.delegation.stake,
+                activation_epoch: stake.delegation.activation_epoch,
+                deactivation_epoch: stake.delegation.deactivation_epoch,
+            });
+        }
+    }
+
+    Ok(entries)
+}
+
+/// Subset of the fields printed by `solana stakes --output json`.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CliStakeAccount {
+    stake_pubkey: String,
+    delegated_stake: Option<u64>,
+    delegated_vote_account_address: Option<String>,
+    activation_epoch: Option<Epoch>,
+    deactivation_epoch: Option<Epoch>,
+    staker: Option<String>,
+    withdrawer: Option<String>,
+}
+
+fn parse_cli_stakes(reader: impl Read) -> Result<Vec<StakeAccountEntry>, DistributionError> {
+    let cli_stake_accounts: Vec<CliStakeAccount> = serde_json::from_reader(reader)?;
+
+    let mut entries = Vec::with_capacity(cli_stake_accounts.len());
+    for cli_stake_account in cli_stake_accounts {
+        let (
+            Some(delegated_stake),
+            Some(vote_pubkey),
+            Some(activation_epoch),
+            Some(staker),
+            Some(withdrawer),
+        ) = (
+            cli_stake_account.delegated_stake,
+            cli_stake_account.delegated_vote_account_address,
+            cli_stake_account.activation_epoch,
+            cli_stake_account.staker,
+            cli_stake_account.withdrawer,
+        )
+        else {
+            continue;
+        };
+
+        entries.push(StakeAccountEntry {
+            stake_pubkey: parse_pubkey(&cli_stake_account.stake_pubkey)?,
+            vote_pubkey: parse_pubkey(&vote_pubkey)?,
+            staker: parse_pubkey(&staker)?,
+            withdrawer: parse_pubkey(&withdrawer)?,
+            delegated_stake,
+            activation_epoch,
+            deactivation_epoch: cli_stake_account.deactivation_epoch.unwrap_or(Epoch::MAX),
+        });
+    }
+
+    Ok(entries)
+}
+
+#[derive(Deserialize)]
+struct CsvStakeAccount {
+    stake_pubkey: String,
+    vote_pubkey: String,
+    staker: String,
+    withdrawer: String,
+    delegated_stake: u64,
+    activation_epoch: Epoch,
+    deactivation_epoch: Option<Epoch>,
+}
+
+fn parse_csv(reader: impl Read) -> Result<Vec<StakeAccountEntry>, DistributionError> {
+    let mut csv_reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .from_reader(reader);
+
+    let mut entries = Vec::new();
+    for record in csv_reader.deserialize() {
+        let record: CsvStakeAccount = record?;
+        entries.push(StakeAccountEntry {
+            stake_pubkey: parse_pubkey(&record.stake_pubkey)?,
+            vote_pubkey: parse_pubkey(&record.vote_pubkey)?,
+            staker: parse_pubkey(&record.staker)?,
+            withdrawer: parse_pubkey(&record.withdrawer)?,
+            delegated_stake: record.delegated_stake,
+            activation_epoch: record.activation_epoch,
+            deactivation_epoch: record.deactivation_epoch.unwrap_or(Epoch::MAX),
+        });
+    }
+
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    #[allow(deprecated)]
+    use {
+        super::*,
+        solana_program::stake::state::{Authorized, Delegation, Lockup, Meta, Stake},
+    };
+
+    fn pubkey(byte: u8) -> Pubkey {
+        Pubkey::new_from_array([byte; 32])
+    }
+
+    fn keyed_account(stake_pubkey: Pubkey, owner: Pubkey, stake_state: &StakeStateV2) -> String {
+        let data = STANDARD.encode(bincode::serialize(stake_state).unwrap());
+        format!(
+            r#"{{"pubkey":"{}","account":{{"data":["{}","base64"],"executable":false,"lamports":1,"owner":"{}","rentEpoch":0}}}}"#,
+            stake_pubkey, data, owner
+        )
+    }
+
+    fn delegated(vote_pubkey: Pubkey, withdrawer: Pubkey, stake: u64) -> StakeStateV2 {
+        let meta = Meta {
+            rent_exempt_reserve: 2_282_880,
+            authorized: Authorized {
+                staker: pubkey(9),
+                withdrawer,
+            },
+            lockup: Lockup::default(),
+        };
+        let stake = Stake {
+            delegation: Delegation {
+                voter_pubkey: vote_pubkey,
+                stake,
+                activation_epoch: 3,
+                deactivation_epoch: 8,
+                ..Delegation::default()
+            },
+            credits_observed: 0,
+        };
+        StakeStateV2::Stake(meta, stake, Default::default())
+    }
+
+    #[test]
+    fn parses_program_accounts() {
+        let json = format!(
+            "[{},{}]",
+            keyed_account(
+                pubkey(1),
+                stake::program::id(),
+                &delegated(pubkey(2), pubkey(3), 5_000)
+            ),
+            keyed_account(
+                pubkey(4),
+                stake::program::id(),
+                &StakeStateV2::Uninitialized
+            ),
+        );
+        assert_eq!(
+            parse_keyed_accounts(json.as_bytes()).unwrap(),
+            vec![StakeAccountEntry {
+                stake_pubkey: pubkey(1),
+                vote_pubkey: pubkey(2),
+                staker: pubkey(9),
+                withdrawer: pubkey(3),
+                delegated_stake: 5_000,
+                activation_epoch: 3,
+                deactivation_epoch: 8,
+            }]
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_program_accounts() {
+        let stake_state = delegated(pubkey(2), pubkey(3), 5_000);
+
+        let json = format!("[{}]", keyed_account(pubkey(1), pubkey(7), &stake_state));
+        assert!(matches!(
+            parse_keyed_accounts(json.as_bytes()),
+            Err(DistributionError::InvalidStakeAccount(_))
+        ));
+
+        let json = format!(
+            "[{}]",
+            keyed_account(pubkey(1), stake::program::id(), &stake_state)
+        )
+        .replace("\"base64\"", "\"base58\"");
+        assert!(matches!(
+            parse_keyed_accounts(json.as_bytes()),
+            Err(DistributionError::InvalidStakeAccount(_))
+        ));
+
+        let json = format!(
+            r#"[{{"pubkey":"{}","account":{{"data":["AAEC","base64"],"owner":"{}"}}}}]"#,
+            pubkey(1),
+            stake::program::id()
+        );
+        assert!(matches!(
+            parse_keyed_accounts(json.as_bytes()),
+            Err(DistributionError::InvalidStakeAccount(_))
+        ));
+    }
+
+    #[test]
+    fn parses_cli_stakes() {
+        let json = format!(
+            r#"[
+                {{"stakePubkey":"{}","stakeType":"Stake","accountBalance":5002282880,"delegatedStake":5000000000,"delegatedVoteAccountAddress":"{}","activationEpoch":3,"staker":"{}","withdrawer":"{}"}},
+                {{"stakePubkey":"{}","stakeType":"Stake","delegatedStake":7,"delegatedVoteAccountAddress":"{}","activationEpoch":4,"deactivationEpoch":6,"staker":"{}","withdrawer":"{}"}},
+                {{"stakePubkey":"{}","stakeType":"Initialized","accountBalance":2282880,"staker":"{}","withdrawer":"{}"}}
+            ]"#,
+            pubkey(1),
+            pubkey(2),
+            pubkey(9),
+            pubkey(3),
+            pubkey(4),
+            pubkey(2),
+            pubkey(9),
+            pubkey(5),
+            pubkey(6),
+            pubkey(9),
+            pubkey(3),
+        );
+        let entries = parse_cli_stakes(json.as_bytes()).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].stake_pubkey, pubkey(1));
+        assert_eq!(entries[0].vote_pubkey, pubkey(2));
+        assert_eq!(entries[0].withdrawer, pubkey(3));
+        assert_eq!(entries[0].delegated_stake, 5_000_000_000);
+        assert_eq!(entries[0].deactivation_epoch, Epoch::MAX);
+        assert_eq!(entries[1].stake_pubkey, pubkey(4));
+        assert_eq!(entries[1].activation_epoch, 4);
+        assert_eq!(entries[1].deactivation_epoch, 6);
+    }
+
+    #[test]
+    fn parses_csv() {
+        let csv = format!(
+            "stake_pubkey,vote_pubkey,staker,withdrawer,delegated_stake,activation_epoch,deactivation_epoch\n\
+             {}, {}, {}, {}, 100, 3,\n\
+             {}, {}, {}, {}, 200, 4, 9\n",
+            pubkey(1),
+            pubkey(2),
+            pubkey(9),
+            pubkey(3),
+            pubkey(4),
+            pubkey(2),
+            pubkey(9),
+            pubkey(5),
+        );
+        let entries = parse_csv(csv.as_bytes()).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].delegated_stake, 100);
+        assert_eq!(entries[0].deactivation_epoch, Epoch::MAX);
+        assert_eq!(entries[1].withdrawer, pubkey(5));
+        assert_eq!(entries[1].deactivation_epoch, 9);
+
+        let invalid = csv.replace(&pubkey(3).to_string(), "not-a-pubkey");
+        assert!(matches!(
+            parse_csv(invalid.as_bytes()),
+            Err(DistributionError::InvalidPubkey(_))
+        ));
+    }
+
+    #[test]
+    fn activity() {
+        let entry = StakeAccountEntry {
+            stake_pubkey: pubkey(1),
+            vote_pubkey: pubkey(2),
+            staker: pubkey(9),
+            withdrawer: pubkey(3),
+            delegated_stake: 100,
+            activation_epoch: 3,
+            deactivation_epoch: 5,
+        };
+        assert!(!entry.is_active_at(3));
+        assert!(entry.is_active_at(4));
+        assert!(entry.is_active_at(5));
+        assert!(!entry.is_active_at(6));
+    }
+
+    #[test]
+    fn formats() {
+        assert_eq!(
+            "program-accounts".parse(),
+            Ok(StakeAccountsFormat::ProgramAccounts)
+        );
+        assert_eq!("json".parse(), Ok(StakeAccountsFormat::Json));
+        assert_eq!("csv".parse(), Ok(StakeAccountsFormat::Csv));
+        assert!("snapshot".parse::<StakeAccountsFormat>().is_err());
+    }
+}
@@ -0,0 +1,338 @@
+//! Test harness running the `reward_distribution` and `rakurai_activation` programs natively under
+//! `solana-program-test`.
+//!
+//! [program_test] registers both programs, along with the SPL stake pool program claims can be
+//! deposited into; validators and funded keypairs are added to the
+//! genesis with [add_validator] and [add_funded_keypair] before [TestContext::start]. The
+//! [TestContext] wraps the running bank: it sends transactions with the bank's payer as fee
+//! payer, so every other account's balance only moves by what the programs transfer, and it
+//! warps between epochs.
+//!
+//! The tests live in `tests/`, one file per program.
+
+use {
+    anchor_lang::{
+        solana_program::account_info::AccountInfo, AccountDeserialize, AccountSerialize,
+    },
+    solana_program_test::{processor, BanksClientError, ProgramTest, ProgramTestContext},
+    solana_sdk::{
+        account::Account,
+        clock::{Clock, Epoch},
+        entrypoint::ProgramResult,
+        instruction::{Instruction, InstructionError},
+        pubkey::Pubkey,
+        rent::Rent,
+        signature::Keypair,
+        signer::Signer,
+        system_program, system_transaction,
+        transaction::{Transaction, TransactionError},
+        vote::{
+            self,
+            state::{VoteInit, VoteState, VoteStateVersions},
+        },
+    },
+};
+
+/// Lamports given to every keypair added with [add_funded_keypair] or [add_validator].
+pub const DEFAULT_BALANCE: u64 = 100_000_000_000;
+
+/// A validator whose vote account names `identity` as its node.
+pub struct Validator {
+    /// Validator identity; signs and pays for the validator's accounts.
+    pub identity: Keypair,
+    /// Vote account of the validator.
+    pub vote_account: Pubkey,
+}
+
+/// Running bank with both programs loaded.
+pub struct TestContext {
+    pub context: ProgramTestContext,
+}
+
+/// Anchor's entrypoint ties the account slice to the lifetime of the accounts themselves, which
+/// the native processor signature can not express; the slice is leaked for the test's duration.
+fn process_reward_distribution(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    let accounts = Box::leak(Box::new(accounts.to_vec()));
+    reward_distribution::entry(program_id, accounts, data)
+}
+
+/// See [process_reward_distribution].
+fn process_rakurai_activation(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    let accounts = Box::leak(Box::new(accounts.to_vec()));
+    rakurai_activation::entry(program_id, accounts, data)
+}
+
+/// Creates a [ProgramTest] running both programs and the SPL stake pool program natively. The
+/// stake and SPL token programs are part of every bank.
+pub fn program_test() -> ProgramTest {
+    let mut program_test = ProgramTest::new(
+        "reward_distribution",
+        reward_distribution::ID,
+        processor!(process_reward_distribution),
+    );
+    program_test.add_program(
+        "rakurai_activation",
+        rakurai_activation::ID,
+        processor!(process_rakurai_activation),
+    );
+    program_test.add_program(
+        "spl_stake_pool",
+        spl_stake_pool::ID,
+        processor!(spl_stake_pool::processor::Processor::process),
+    );
+    program_test.prefer_bpf(false);
+    program_test
+}
+
+/// Adds a system account holding [DEFAULT_BALANCE] lamports to the genesis.
+pub fn add_funded_keypair(program_test: &mut ProgramTest) -> Keypair {
+    let keypair = Keypair::new();
+    program_test.add_account(
+        keypair.pubkey(),
+        Account::new(DEFAULT_BALANCE, 0, &system_program::id()),
+    );
+    keypair
+}
+
+/// Adds a funded validator identity and its vote account to the genesis.
+pub fn add_validator(program_test: &mut ProgramTest) -> Validator {
+    let identity = add_funded_keypair(program_test);
+    let vote_account = Pubkey::new_unique();
+    program_test.add_account(vote_account, vote_account_for(&identity.pubkey()));
+
+    Validator {
+        identity,
+        vote_account,
+    }
+}
+
+/// Builds a rent-exempt vote account whose node is `node_pubkey`.
+pub fn vote_account_for(node_pubkey: &Pubkey) -> Account {
+    let vote_state = VoteState::new(
+        &VoteInit {
+            node_pubkey: *node_pubkey,
+            authorized_voter: *node_pubkey,
+            authorized_withdrawer: *node_pubkey,
+            commission: 0,
+        },
+        &Clock::default(),
+    );
+    let mut data = vec![0; VoteState::size_of()];
+    VoteState::serialize(&VoteStateVersions::new_current(vote_state), &mut data).unwrap();
+
+    Account {
+        lamports: Rent::default().minimum_balance(data.len()),
+        data,
+        owner: vote::program::id(),
+        executable: false,
+        rent_epoch: 0,
+    }
+}
+
+/// Builds a rent-exempt account owned by `owner` holding the serialized Anchor account `state`.
+pub fn anchor_account<T: AccountSerialize>(owner: &Pubkey, state: &T) -> Account {
+    let mut data = vec![];
+    state.try_serialize(&mut data).unwrap();
+
+    Account {
+        lamports: Rent::default().minimum_balance(data.len()),
+        data,
+        owner: *owner,
+        executable: false,
+        rent_epoch: 0,
+    }
+}
+
+/// Asserts that `result` failed with the custom program error `code`, e.g.
+/// `reward_distribution::ErrorCode::Unauthorized.into()`.
+#[track_caller]
+pub fn assert_custom_error<T: std::fmt::Debug>(result: Result<T, BanksClientError>, code: u32) {
+    match result.expect_err("transaction succeeded").unwrap() {
+        TransactionError::InstructionError(_, InstructionError::Custom(actual)) => {
+            assert_eq!(actual, code, "unexpected custom error")
+        }
+        err => panic!("expected custom error {code}, got {err:?}"),
+    }
+}
+
+impl TestContext {
+    /// Starts the bank of `program_test`.
+    pub async fn start(program_test: ProgramTest) -> Self {
+        Self {
+            context: program_test.start_with_context().await,
+        }
+    }
+
+    /// Public key of the fee payer of every transaction.
+    pub fn payer(&self) -> Pubkey {
+        self.context.payer.pubkey()
+    }
+
+    /// Sends `instructions` in one transaction signed by the fee payer and `signers`. A fresh
+    /// blockhash is used so repeating a transaction is never rejected as a duplicate.
+    pub async fn process(
+        &mut self,
+        instructions: &[Instruction],
+        signers: &[&Keypair],
+    ) -> Result<(), BanksClientError> {
+        let blockhash = self.context.get_new_latest_blockhash().await?;
+        let mut all_signers = vec![&self.context.payer];
+        all_signers.extend_from_slice(signers);
+        let transaction = Transaction::new_signed_with_payer(
+            instructions,
+            Some(&self.context.payer.pubkey()),
+            all_signers.as_slice(),
+            blockhash,
+        );
+
+        self.context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+    }
+
+    /// [TestContext::process], returning the log messages of the transaction, which carry the
+    /// events the programs emitted.
+    pub async fn process_with_logs(
+        &mut self,
+        instructions: &[Instruction],
+        signers: &[&Keypair],
+    ) -> Result<Vec<String>, BanksClientError> {
+        let blockhash = self.context.get_new_latest_blockhash().await?;
+        let mut all_signers = vec![&self.context.payer];
+        all_signers.extend_from_slice(signers);
+        let transaction = Transaction::new_signed_with_payer(
+            instructions,
+            Some(&self.context.payer.pubkey()),
+            all_signers.as_slice(),
+            blockhash,
+        );
+
+        let outcome = self
+            .context
+            .banks_client
+            .process_transaction_with_metadata(transaction)
+            .await?;
+        outcome.result.map_err(BanksClientError::TransactionError)?;
+        Ok(outcome
+            .metadata
+            .map(|metadata| metadata.log_messages)
+            .unwrap_or_default())
+    }
+
+    /// Transfers `lamports` from the fee payer to `to`.
+    pub async fn fund(&mut self, to: &Pubkey, lamports: u64) {
+        let blockhash = self.context.get_new_latest_blockhash().await.unwrap();
+        let transaction =
+            system_transaction::transfer(&self.context.payer, to, lamports, blockhash);
+        self.context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap();
+    }
+
+    /// Current epoch of the bank.
+    pub async fn epoch(&mut self) -> Epoch {
+        self.context
+            .banks_client
+            .get_sysvar::<Clock>()
+            .await
+            .unwrap()
+            .epoch
+    }
+
+    /// Warps the bank to the first slot of `epoch`.
+    pub async fn warp_to_epoch(&mut self, epoch: Epoch) {
+        let slot = self
+            .context
+            .genesis_config()
+            .epoch_schedule
+            .get_first_slot_in_epoch(epoch);
+        self.context.warp_to_slot(slot).unwrap();
+        assert_eq!(self.epoch().await, epoch);
+    }
+
+    /// Overwrites the account at `address` with [anchor_account].
+    pub fn set_anchor_account<T: AccountSerialize>(
+        &mut self,
+        address: &Pubkey,
+        owner: &Pubkey,
+        state: &T,
+    ) {
+        self.context
+            .set_account(address, &anchor_account(owner, state).into());
+    }
+
+    /// Overwrites the account at `address` with a rent-exempt account owned by `owner` holding
+    /// the raw `data`, e.g. an account of an older layout.
+    pub fn set_account_data(&mut self, address: &Pubkey, owner: &Pubkey, data: Vec<u8>) {
+        let account = Account {
+            lamports: Rent::default().minimum_balance(data.len()),
+            data,
+            owner: *owner,
+            executable: false,
+            rent_epoch: 0,
+        };
+        self.context.set_account(address, &account.into());
+    }
+
+    /// Lamports held by `address`, zero if it does not exist.
+    pub async fn balance(&mut self, address: &Pubkey) -> u64 {
+        self.context
+            .banks_client
+            .get_balance(*address)
+            .await
+            .unwrap()
+    }
+
+    /// Rent-exempt minimum of an account holding `data_len` bytes.
+    pub async fn rent(&mut self, data_len: usize) -> u64 {
+        self.context
+            .banks_client
+            .get_rent()
+            .await
+            .unwrap()
+            .minimum_balance(data_len)
+    }
+
+    /// Whether an account exists at `address`.
+    pub async fn exists(&mut self, address: &Pubkey) -> bool {
+        self.context
+            .banks_client
+            .get_account(*address)
+            .await
+            .unwrap()
+            .is_some()
+    }
+
+    /// Fetches the account at `address`, e.g. one of the stake or token program.
+    pub async fn get_raw_account(&mut self, address: &Pubkey) -> Account {
+        self.context
+            .banks_client
+            .get_account(*address)
+            .await
+            .unwrap()
+            .unwrap_or_else(|| panic!("account {address} not found"))
+    }
+
+    /// Fetches and deserializes the Anchor account at `address`.
+    pub async fn get_account<T: AccountDeserialize>(&mut self, address: &Pubkey) -> T {
+        let account = self
+            .context
+            .banks_client
+            .get_account(*address)
+            .await
+            .unwrap()
+            .unwrap_or_else(|| panic!("account {address} not found"));
+        T::try_deserialize(&mut account.data.as_slice()).unwrap()
+    }
+}
@@ -0,0 +1,595 @@
+//! Integration tests of the `rakurai_activation` program.
+//!
+//! `ErrorCode::ArithmeticError` has no test: it guards draining the activation account down to
+//! its rent-exempt minimum, which the `rent_exempt = enforce` constraints never let it fall
+//! below.
+
+use {
+    anchor_lang::system_program,
+    rakurai_activation::{
+        sdk::{
+            derive_activation_account_address, derive_config_account_address,
+            instruction::{
+                close_rakurai_activation_account_ix, initialize_ix,
+                initialize_rakurai_activation_account_ix, update_config_ix,
+                update_rakurai_activation_approval_ix, update_rakurai_activation_commission_ix,
+                CloseRakuraiActivationAccountArgs, CloseRakuraiActivationAccounts,
+                InitializeAccounts, InitializeArgs, InitializeRakuraiActivationAccountAccounts,
+                InitializeRakuraiActivationAccountArgs, UpdateConfigAccounts, UpdateConfigArgs,
+                UpdateRakuraiActivationApprovalAccounts, UpdateRakuraiActivationApprovalArgs,
+                UpdateRakuraiActivationCommissionAccounts, UpdateRakuraiActivationCommissionArgs,
+            },
+        },
+        state::{RakuraiActivationAccount, RakuraiActivationConfigAccount},
+        ErrorCode, ID,
+    },
+    rakurai_integration_tests::{
+        add_funded_keypair, add_validator, assert_custom_error, program_test, TestContext,
+        Validator,
+    },
+    solana_program_test::BanksClientError,
+    solana_sdk::{pubkey::Pubkey, signature::Keypair, signer::Signer},
+};
+
+const BLOCK_BUILDER_COMMISSION_BPS: u16 = 500;
+const VALIDATOR_COMMISSION_BPS: u16 = 1_000;
+const HASH: [u8; 64] = [7; 64];
+const OTHER_HASH: [u8; 64] = [9; 64];
+
+struct Env {
+    ctx: TestContext,
+    config: Pubkey,
+    authority: Keypair,
+    block_builder: Keypair,
+    block_builder_commission_account: Pubkey,
+    validator: Validator,
+    stranger: Keypair,
+}
+
+impl Env {
+    /// Starts a bank with a funded config authority, block builder, validator and stranger. The
+    /// config is not initialized.
+    async fn start() -> Self {
+        let mut program_test = program_test();
+        let authority = add_funded_keypair(&mut program_test);
+        let block_builder = add_funded_keypair(&mut program_test);
+        let validator = add_validator(&mut program_test);
+        let stranger = add_funded_keypair(&mut program_test);
+
+        Self {
+            ctx: TestContext::start(program_test).await,
+            config: derive_config_account_address(&ID).0,
+            authority,
+            block_builder,
+            block_builder_commission_account: Pubkey::new_unique(),
+            validator,
+            stranger,
+        }
+    }
+
+    /// [Env::start] followed by the config initialization.
+    async fn with_config() -> Self {
+        let mut env = Self::start().await;
+        let args = env.initialize_args();
+        env.initialize(args).await.unwrap();
+        env
+    }
+
+    /// [Env::with_config] followed by the validator's activation account initialization.
+    async fn with_activation_account() -> Self {
+        let mut env = Self::with_config().await;
+        env.initialize_activation_account(&env.validator.identity.insecure_clone())
+            .await
+            .unwrap();
+        env
+    }
+
+    fn initialize_args(&self) -> InitializeArgs {
+        InitializeArgs {
+            authority: self.authority.pubkey(),
+            block_builder_authority: self.block_builder.pubkey(),
+            block_builder_commission_account: self.block_builder_commission_account,
+            block_builder_commission_bps: BLOCK_BUILDER_COMMISSION_BPS,
+            bump: derive_config_account_address(&ID).1,
+        }
+    }
+
+    fn activation_account(&self) -> Pubkey {
+        derive_activation_account_address(&ID, &self.validator.identity.pubkey()).0
+    }
+
+    async fn initialize(&mut self, args: InitializeArgs) -> Result<(), BanksClientError> {
+        let ix = initialize_ix(
+            ID,
+            args,
+            InitializeAccounts {
+                config: self.config,
+                system_program: system_program::ID,
+                initializer: self.ctx.payer(),
+            },
+        );
+        self.ctx.process(&[ix], &[]).await
+    }
+
+    async fn update_config(
+        &mut self,
+        new_config: RakuraiActivationConfigAccount,
+        authority: &Keypair,
+    ) -> Result<(), BanksClientError> {
+        let ix = update_config_ix(
+            ID,
+            UpdateConfigArgs { new_config },
+            UpdateConfigAccounts {
+                config: self.config,
+                authority: authority.pubkey(),
+            },
+        );
+        self.ctx.process(&[ix], &[authority]).await
+    }
+
+    /// Initializes the activation account of `signer`, using the validator's vote account.
+    async fn initialize_activation_account(
+        &mut self,
+        signer: &Keypair,
+    ) -> Result<(), BanksClientError> {
+        let vote_account = self.validator.vote_account;
+        self.initialize_activation_account_with_vote(signer, vote_account)
+            .await
+    }
+
+    async fn initialize_activation_account_with_vote(
+        &mut self,
+        signer: &Keypair,
+        validator_vote_account: Pubkey,
+    ) -> Result<(), BanksClientError> {
+        let (activation_account, bump) = derive_activation_account_address(&ID, &signer.pubkey());
+        let ix = initialize_rakurai_activation_account_ix(
+            ID,
+            InitializeRakuraiActivationAccountArgs {
+                validator_commission_bps: VALIDATOR_COMMISSION_BPS,
+                bump,
+            },
+            InitializeRakuraiActivationAccountAccounts {
+                config: self.config,
+                signer: signer.pubkey(),
+                system_program: system_program::ID,
+                activation_account,
+                validator_vote_account,
+                validator_identity_account: signer.pubkey(),
+            },
+        );
+        self.ctx.process(&[ix], &[signer]).await
+    }
+
+    async fn approve(
+        &mut self,
+        signer: &Keypair,
+        grant_approval: bool,
+        hash: Option<[u8; 64]>,
+    ) -> Result<(), BanksClientError> {
+        let ix = update_rakurai_activation_approval_ix(
+            ID,
+            UpdateRakuraiActivationApprovalArgs {
+                grant_approval,
+                hash,
+            },
+            UpdateRakuraiActivationApprovalAccounts {
+                config: self.config,
+                activation_account: self.activation_account(),
+                validator_identity_account: self.validator.identity.pubkey(),
+                signer: signer.pubkey(),
+            },
+        );
+        self.ctx.process(&[ix], &[signer]).await
+    }
+
+    async fn update_commission(
+        &mut self,
+        signer: &Keypair,
+        commission_bps: u16,
+    ) -> Result<(), BanksClientError> {
+        let ix = update_rakurai_activation_commission_ix(
+            ID,
+            UpdateRakuraiActivationCommissionArgs { commission_bps },
+            UpdateRakuraiActivationCommissionAccounts {
+                config: self.config,
+                activation_account: self.activation_account(),
+                validator_identity_account: self.validator.identity.pubkey(),
+                signer: signer.pubkey(),
+            },
+        );
+        self.ctx.process(&[ix], &[signer]).await
+    }
+
+    async fn close(&mut self, signer: &Keypair) -> Result<(), BanksClientError> {
+        let ix = close_rakurai_activation_account_ix(
+            ID,
+            CloseRakuraiActivationAccountArgs,
+            CloseRakuraiActivationAccounts {
+                config: self.config,
+                activation_account: self.activation_account(),
+                validator_identity_account: self.validator.identity.pubkey(),
+                signer: signer.pubkey(),
+            },
+        );
+        self.ctx.process(&[ix], &[signer]).await
+    }
+
+    async fn activation(&mut self) -> RakuraiActivationAccount {
+        let activation_account = self.activation_account();
+        self.ctx.get_account(&activation_account).await
+    }
+
+    /// Asserts the `(is_enabled, proposer, hash)` approval state of the activation account.
+    async fn assert_approval(
+        &mut self,
+        is_enabled: bool,
+        proposer: Option<Pubkey>,
+        hash: Option<[u8; 64]>,
+    ) {
+        let activation = self.activation().await;
+        assert_eq!(activation.is_enabled, is_enabled);
+        assert_eq!(activation.proposer, proposer);
+        assert_eq!(activation.hash, hash);
+    }
+}
+
+#[tokio::test]
+async fn initialize_creates_config() {
+    let mut env = Env::with_config().await;
+
+    let config: RakuraiActivationConfigAccount = env.ctx.get_account(&env.config).await;
+    assert_eq!(config.authority, env.authority.pubkey());
+    assert_eq!(config.block_builder_authority, env.block_builder.pubkey());
+    assert_eq!(
+        config.block_builder_commission_account,
+        env.block_builder_commission_account
+    );
+    assert_eq!(
+        config.block_builder_commission_bps,
+        BLOCK_BUILDER_COMMISSION_BPS
+    );
+    assert_eq!(config.bump, derive_config_account_address(&ID).1);
+
+    let rent = env.ctx.rent(RakuraiActivationConfigAccount::SIZE).await;
+    assert_eq!(env.ctx.balance(&env.config).await, rent);
+}
+
+#[tokio::test]
+async fn initialize_rejects_invalid_config() {
+    let mut env = Env::start().await;
+
+    let args = InitializeArgs {
+        block_builder_commission_account: Pubkey::default(),
+        ..env.initialize_args()
+    };
+    let result = env.initialize(args).await;
+    assert_custom_error(result, ErrorCode::AccountValidationFailure.into());
+
+    let args = InitializeArgs {
+        block_builder_commission_bps: 10_001,
+        ..env.initialize_args()
+    };
+    let result = env.initialize(args).await;
+    assert_custom_error(result, ErrorCode::MaxCommissionBpsExceeded.into());
+
+    assert!(!env.ctx.exists(&env.config).await);
+}
+
+#[tokio::test]
+async fn update_config_by_authority() {
+    let mut env = Env::with_config().await;
+    let new_authority = Keypair::new();
+    let new_block_builder = Keypair::new();
+    let new_commission_account = Pubkey::new_unique();
+
+    let new_config = RakuraiActivationConfigAccount {
+        authority: new_authority.pubkey(),
+        block_builder_authority: new_block_builder.pubkey(),
+        block_builder_commission_bps: 700,
+        block_builder_commission_account: new_commission_account,
+        bump: 0,
+    };
+    let authority = env.authority.insecure_clone();
+    env.update_config(new_config, &authority).await.unwrap();
+
+    let config: RakuraiActivationConfigAccount = env.ctx.get_account(&env.config).await;
+    assert_eq!(config.authority, new_authority.pubkey());
+    assert_eq!(config.block_builder_authority, new_block_builder.pubkey());
+    assert_eq!(config.block_builder_commission_bps, 700);
+    assert_eq!(
+        config.block_builder_commission_account,
+        new_commission_account
+    );
+    // The bump is not updatable.
+    assert_eq!(config.bump, derive_config_account_address(&ID).1);
+
+    // The old authority lost its rights.
+    let result = env
+        .update_config(RakuraiActivationConfigAccount::default(), &authority)
+        .await;
+    assert_custom_error(result, ErrorCode::Unauthorized.into());
+}
+
+#[tokio::test]
+async fn update_config_errors() {
+    let mut env = Env::with_config().await;
+    let authority = env.authority.insecure_clone();
+    let stranger = env.stranger.insecure_clone();
+    let valid_config = RakuraiActivationConfigAccount {
+        authority: authority.pubkey(),
+        block_builder_authority: env.block_builder.pubkey(),
+        block_builder_commission_bps: BLOCK_BUILDER_COMMISSION_BPS,
+        block_builder_commission_account: env.block_builder_commission_account,
+        bump: 0,
+    };
+
+    let result = env.update_config(valid_config.clone(), &stranger).await;
+    assert_custom_error(result, ErrorCode::Unauthorized.into());
+
+    let result = env
+        .update_config(
+            RakuraiActivationConfigAccount {
+                block_builder_commission_account: Pubkey::default(),
+                ..valid_config.clone()
+            },
+            &authority,
+        )
+        .await;
+    assert_custom_error(result, ErrorCode::AccountValidationFailure.into());
+
+    let result = env
+        .update_config(
+            RakuraiActivationConfigAccount {
+                block_builder_commission_bps: 10_001,
+                ..valid_config
+            },
+            &authority,
+        )
+        .await;
+    assert_custom_error(result, ErrorCode::MaxCommissionBpsExceeded.into());
+}
+
+#[tokio::test]
+async fn initialize_activation_account_by_validator() {
+    let mut env = Env::with_config().await;
+    let identity = env.validator.identity.pubkey();
+    let identity_balance = env.ctx.balance(&identity).await;
+
+    env.initialize_activation_account(&env.validator.identity.insecure_clone())
+        .await
+        .unwrap();
+
+    let activation = env.activation().await;
+    assert!(!activation.is_enabled);
+    assert_eq!(activation.proposer, Some(identity));
+    assert_eq!(activation.validator_authority, identity);
+    assert_eq!(
+        activation.validator_commission_bps,
+        VALIDATOR_COMMISSION_BPS
+    );
+    assert_eq!(
+        activation.block_builder_commission_bps,
+        BLOCK_BUILDER_COMMISSION_BPS
+    );
+    assert_eq!(
+        activation.bump,
+        derive_activation_account_address(&ID, &identity).1
+    );
+    assert_eq!(activation.hash, None);
+
+    let rent = env.ctx.rent(RakuraiActivationAccount::SIZE).await;
+    let activation_account = env.activation_account();
+    assert_eq!(env.ctx.balance(&activation_account).await, rent);
+    assert_eq!(env.ctx.balance(&identity).await, identity_balance - rent);
+}
+
+#[tokio::test]
+async fn initialize_activation_account_requires_vote_account_node() {
+    let mut env = Env::with_config().await;
+    let identity = env.validator.identity.insecure_clone();
+    let stranger = env.stranger.insecure_clone();
+
+    // The vote account must be owned by the vote program.
+    let result = env
+        .initialize_activation_account_with_vote(&identity, identity.pubkey())
+        .await;
+    assert_custom_error(result, ErrorCode::Unauthorized.into());
+
+    // The signer must be the node of the vote account.
+    let result = env.initialize_activation_account(&stranger).await;
+    assert_custom_error(result, ErrorCode::Unauthorized.into());
+
+    let activation_account = env.activation_account();
+    assert!(!env.ctx.exists(&activation_account).await);
+}
+
+#[tokio::test]
+async fn validator_proposal_accepted_by_block_builder() {
+    let mut env = Env::with_activation_account().await;
+    let validator = env.validator.identity.insecure_clone();
+    let block_builder = env.block_builder.insecure_clone();
+
+    // Initialization leaves the validator's proposal pending; approving again changes nothing.
+    env.assert_approval(false, Some(validator.pubkey()), None)
+        .await;
+    env.approve(&validator, true, Some(HASH)).await.unwrap();
+    env.assert_approval(false, Some(validator.pubkey()), None)
+        .await;
+
+    // The block builder must provide the hash when accepting.
+    let result = env.approve(&block_builder, true, None).await;
+    assert_custom_error(result, ErrorCode::MissingHashForEnable.into());
+    env.assert_approval(false, Some(validator.pubkey()), None)
+        .await;
+
+    env.approve(&block_builder, true, Some(HASH)).await.unwrap();
+    env.assert_approval(true, None, Some(HASH)).await;
+
+    // Once enabled, the block builder rotates the hash and the validator's approval is a no-op.
+    env.approve(&block_builder, true, Some(OTHER_HASH))
+        .await
+        .unwrap();
+    env.assert_approval(true, None, Some(OTHER_HASH)).await;
+    env.approve(&validator, true, Some(HASH)).await.unwrap();
+    env.assert_approval(true, None, Some(OTHER_HASH)).await;
+}
+
+#[tokio::test]
+async fn block_builder_proposal_accepted_by_validator() {
+    let mut env = Env::with_activation_account().await;
+    let validator = env.validator.identity.insecure_clone();
+    let block_builder = env.block_builder.insecure_clone();
+
+    // Revoking clears the validator's initial proposal.
+    env.approve(&validator, false, None).await.unwrap();
+    env.assert_approval(false, None, None).await;
+
+    // A block builder proposal must carry the hash.
+    let result = env.approve(&block_builder, true, None).await;
+    assert_custom_error(result, ErrorCode::MissingHashForEnable.into());
+
+    env.approve(&block_builder, true, Some(HASH)).await.unwrap();
+    env.assert_approval(false, Some(block_builder.pubkey()), Some(HASH))
+        .await;
+
+    // The proposer approving again keeps the proposal pending and the hash unchanged.
+    env.approve(&block_builder, true, Some(OTHER_HASH))
+        .await
+        .unwrap();
+    env.assert_approval(false, Some(block_builder.pubkey()), Some(HASH))
+        .await;
+
+    // The validator's acceptance enables the account and keeps the proposed hash.
+    env.approve(&validator, true, None).await.unwrap();
+    env.assert_approval(true, None, Some(HASH)).await;
+}
+
+#[tokio::test]
+async fn revoked_validator_proposal_accepted_by_block_builder() {
+    let mut env = Env::with_activation_account().await;
+    let validator = env.validator.identity.insecure_clone();
+    let block_builder = env.block_builder.insecure_clone();
+
+    env.approve(&validator, false, None).await.unwrap();
+    env.approve(&validator, true, None).await.unwrap();
+    env.assert_approval(false, Some(validator.pubkey()), None)
+        .await;
+
+    env.approve(&block_builder, true, Some(HASH)).await.unwrap();
+    env.assert_approval(true, None, Some(HASH)).await;
+}
+
+#[tokio::test]
+async fn either_authority_revokes() {
+    let mut env = Env::with_activation_account().await;
+    let validator = env.validator.identity.insecure_clone();
+    let block_builder = env.block_builder.insecure_clone();
+
+    env.approve(&block_builder, true, Some(HASH)).await.unwrap();
+    env.assert_approval(true, None, Some(HASH)).await;
+    env.approve(&block_builder, false, Some(HASH))
+        .await
+        .unwrap();
+    env.assert_approval(false, None, None).await;
+
+    env.approve(&validator, true, None).await.unwrap();
+    env.approve(&block_builder, true, Some(HASH)).await.unwrap();
+    env.assert_approval(true, None, Some(HASH)).await;
+    env.approve(&validator, false, None).await.unwrap();
+    env.assert_approval(false, None, None).await;
+}
+
+#[tokio::test]
+async fn approval_requires_authority() {
+    let mut env = Env::with_activation_account().await;
+    let stranger = env.stranger.insecure_clone();
+
+    let result = env.approve(&stranger, true, Some(HASH)).await;
+    assert_custom_error(result, ErrorCode::Unauthorized.into());
+    let result = env.approve(&stranger, false, None).await;
+    assert_custom_error(result, ErrorCode::Unauthorized.into());
+
+    env.assert_approval(false, Some(env.validator.identity.pubkey()), None)
+        .await;
+}
+
+#[tokio::test]
+async fn update_commission_by_each_authority() {
+    let mut env = Env::with_activation_account().await;
+    let validator = env.validator.identity.insecure_clone();
+    let block_builder = env.block_builder.insecure_clone();
+
+    env.update_commission(&validator, 2_500).await.unwrap();
+    let activation = env.activation().await;
+    assert_eq!(activation.validator_commission_bps, 2_500);
+    assert_eq!(
+        activation.block_builder_commission_bps,
+        BLOCK_BUILDER_COMMISSION_BPS
+    );
+
+    env.update_commission(&block_builder, 10_000).await.unwrap();
+    let activation = env.activation().await;
+    assert_eq!(activation.validator_commission_bps, 2_500);
+    assert_eq!(activation.block_builder_commission_bps, 10_000);
+}
+
+#[tokio::test]
+async fn update_commission_errors() {
+    let mut env = Env::with_activation_account().await;
+    let validator = env.validator.identity.insecure_clone();
+    let block_builder = env.block_builder.insecure_clone();
+    let stranger = env.stranger.insecure_clone();
+
+    let result = env.update_commission(&validator, 10_001).await;
+    assert_custom_error(result, ErrorCode::MaxCommissionBpsExceeded.into());
+    let result = env.update_commission(&block_builder, 10_001).await;
+    assert_custom_error(result, ErrorCode::MaxCommissionBpsExceeded.into());
+    let result = env.update_commission(&stranger, 100).await;
+    assert_custom_error(result, ErrorCode::Unauthorized.into());
+
+    let activation = env.activation().await;
+    assert_eq!(
+        activation.validator_commission_bps,
+        VALIDATOR_COMMISSION_BPS
+    );
+    assert_eq!(
+        activation.block_builder_commission_bps,
+        BLOCK_BUILDER_COMMISSION_BPS
+    );
+}
+
+#[tokio::test]
+async fn close_by_block_builder_refunds_identity() {
+    let mut env = Env::with_activation_account().await;
+    let block_builder = env.block_builder.insecure_clone();
+    let identity = env.validator.identity.pubkey();
+    let activation_account = env.activation_account();
+    let surplus = 1_000_000;
+    env.ctx.fund(&activation_account, surplus).await;
+
+    let rent = env.ctx.rent(RakuraiActivationAccount::SIZE).await;
+    let identity_balance = env.ctx.balance(&identity).await;
+    env.close(&block_builder).await.unwrap();
+
+    assert!(!env.ctx.exists(&activation_account).await);
+    assert_eq!(
+        env.ctx.balance(&identity).await,
+        identity_balance + rent + surplus
+    );
+}
+
+#[tokio::test]
+async fn close_requires_block_builder() {
+    let mut env = Env::with_activation_account().await;
+    let validator = env.validator.identity.insecure_clone();
+    let stranger = env.stranger.insecure_clone();
+
+    let result = env.close(&validator).await;
+    assert_custom_error(result, ErrorCode::Unauthorized.into());
+    let result = env.close(&stranger).await;
+    assert_custom_error(result, ErrorCode::Unauthorized.into());
+
+    let activation_account = env.activation_account();
+    assert!(env.ctx.exists(&activation_account).await);
+}
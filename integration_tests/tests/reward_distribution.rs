@@ -0,0 +1,3981 @@
+//! Integration tests of the `reward_distribution` program.
+//!
+//! Every test starts in the epoch the `RewardCollectionAccount` is created in and warps forward:
+//! the merkle root can be uploaded from the next epoch on, claims are accepted up to and
+//! including `expires_at`, and the accounts can be closed after it.
+
+use {
+    anchor_lang::{
+        error::ErrorCode as AnchorErrorCode, system_program, AccountDeserialize, AnchorDeserialize,
+        Discriminator, Event,
+    },
+    base64::{prelude::BASE64_STANDARD, Engine},
+    rakurai_activation::{
+        sdk::{
+            derive_activation_account_address,
+            derive_config_account_address as derive_activation_config_address,
+        },
+        state::{RakuraiActivationAccount, RakuraiActivationConfigAccount},
+    },
+    rakurai_cli::crank::{run_once, CrankClient, CrankConfig, CrankState, CrankSummary},
+    rakurai_distribution::distribution::{
+        DistributionFile, StakerClaim, DISTRIBUTION_FILE_VERSION,
+    },
+    rakurai_integration_tests::{
+        add_funded_keypair, add_validator, assert_custom_error, program_test, TestContext,
+        Validator,
+    },
+    rakurai_merkle_tree::{LeafDomain, MerkleTree, TreeNode, LEAF_VERSION_V0, LEAF_VERSION_V1},
+    reward_distribution::{
+        commission::{CategorizedRewards, CategoryCommissions},
+        sdk::{
+            derive_claim_bitmap_account_address, derive_claim_status_account_address,
+            derive_config_account_address, derive_cumulative_claim_status_address,
+            derive_cumulative_distributor_address, derive_proof_buffer_address,
+            derive_reward_collection_account_address, derive_root_attestation_address,
+            derive_stake_pool_deposit_address,
+            instruction::{
+                attest_merkle_root_ix, claim_aggregated_ix, claim_batch_ix, claim_cumulative_ix,
+                claim_indexed_ix, claim_ix, claim_to_stake_account_ix, claim_to_stake_pool_ix,
+                claim_with_proof_buffer_ix, close_claim_bitmap_ix, close_claim_status_ix,
+                close_proof_buffer_ix, close_reward_collection_account_ix,
+                close_root_attestation_ix, deposit_rewards_ix, initialize_claim_bitmap_ix,
+                initialize_cumulative_distributor_ix, initialize_ix, initialize_proof_buffer_ix,
+                initialize_reward_collection_account_ix, initialize_root_attestation_ix,
+                migrate_reward_collection_account_ix, rollover_unclaimed_rewards_ix,
+                sweep_to_cumulative_distributor_ix, transfer_staker_rewards_ix, update_config_ix,
+                update_merkle_root_upload_authority_ix, update_validator_commission_ix,
+                upload_cumulative_merkle_root_ix, upload_merkle_root_ix, withdraw_surplus_ix,
+                write_proof_buffer_ix, AttestMerkleRootAccounts, AttestMerkleRootArgs,
+                ClaimAccounts, ClaimAggregatedAccounts, ClaimAggregatedArgs, ClaimArgs,
+                ClaimBatchAccounts, ClaimBatchArgs, ClaimCumulativeAccounts, ClaimCumulativeArgs,
+                ClaimIndexedAccounts, ClaimIndexedArgs, ClaimToStakeAccountAccounts,
+                ClaimToStakeAccountArgs, ClaimToStakePoolAccounts, ClaimToStakePoolArgs,
+                ClaimWithProofBufferAccounts, ClaimWithProofBufferArgs, CloseClaimBitmapAccounts,
+                CloseClaimBitmapArgs, CloseClaimStatusAccounts, CloseClaimStatusArgs,
+                CloseProofBufferAccounts, CloseProofBufferArgs, CloseRewardCollectionAccountArgs,
+                CloseRewardCollectionAccounts, CloseRootAttestationAccounts,
+                CloseRootAttestationArgs, DepositRewardsAccounts, DepositRewardsArgs,
+                InitializeAccounts, InitializeArgs, InitializeClaimBitmapAccounts,
+                InitializeClaimBitmapArgs, InitializeCumulativeDistributorAccounts,
+                InitializeCumulativeDistributorArgs, InitializeProofBufferAccounts,
+                InitializeProofBufferArgs, InitializeRewardCollectionAccountAccounts,
+                InitializeRewardCollectionAccountArgs, InitializeRootAttestationAccounts,
+                InitializeRootAttestationArgs, MigrateRewardCollectionAccountAccounts,
+                MigrateRewardCollectionAccountArgs, RolloverUnclaimedRewardsAccounts,
+                RolloverUnclaimedRewardsArgs, SweepToCumulativeDistributorAccounts,
+                SweepToCumulativeDistributorArgs, TransferStakerRewardsAccounts,
+                TransferStakerRewardsArgs, UpdateConfigAccounts, UpdateConfigArgs,
+                UpdateMerkleRootUploadAuthorityAccounts, UpdateMerkleRootUploadAuthorityArgs,
+                UpdateValidatorCommissionAccounts, UpdateValidatorCommissionArgs,
+                UploadCumulativeMerkleRootAccounts, UploadCumulativeMerkleRootArgs,
+                UploadMerkleRootAccounts, UploadMerkleRootArgs, WithdrawSurplusAccounts,
+                WithdrawSurplusArgs, WriteProofBufferAccounts, WriteProofBufferArgs,
+            },
+            packing::{
+                lookup_table_addresses, pack_aggregated_claims, plan_claim, transaction_size,
+                AggregatedClaimEntry, ClaimPlanAccounts, PackAccounts, PACKET_DATA_SIZE,
+            },
+        },
+        state::{
+            ClaimBitmap, ClaimScale, ClaimStatus, CumulativeClaimStatus, CumulativeDistributor,
+            DepositSource, LegacyRewardCollectionAccountV0, ProofBuffer, RewardBucket,
+            RewardBuckets, RewardCollectionAccount, RewardDistributionConfigAccount,
+            RootAttestation,
+        },
+        AggregatedClaim, BatchClaim, ErrorCode, RewardsDepositedEvent,
+        ValidatorCommissionBpsUpdatedEvent, ID,
+    },
+    solana_program_test::{BanksClient, BanksClientError},
+    solana_sdk::{
+        account::Account,
+        address_lookup_table::AddressLookupTableAccount,
+        borsh1::{get_instance_packed_len, get_packed_len},
+        clock::{Clock, Epoch},
+        instruction::Instruction,
+        program_pack::Pack,
+        pubkey::Pubkey,
+        signature::{Keypair, Signature},
+        signer::Signer,
+        stake::{
+            self,
+            state::{Authorized, Lockup, StakeStateV2},
+        },
+        system_instruction, sysvar,
+        transaction::Transaction,
+    },
+    spl_stake_pool::{
+        find_withdraw_authority_program_address,
+        state::{Fee, StakePool, ValidatorList},
+        MINIMUM_RESERVE_LAMPORTS,
+    },
+    spl_token::state::{Account as TokenAccount, Mint},
+    std::{fs, time::Duration},
+    tokio::runtime::Handle,
+};
+
+const NUM_EPOCHS_VALID: u64 = 3;
+const MAX_COMMISSION_BPS: u16 = 5_000;
+const VALIDATOR_COMMISSION_BPS: u16 = 500;
+const RAKURAI_COMMISSION_BPS: u16 = 1_000;
+/// Rewards transferred by [Env::with_rewards]; the stakers' share is 8.55 SOL.
+const TOTAL_REWARDS: u64 = 10_000_000_000;
+const STAKER_REWARDS: u64 = 8_550_000_000;
+
+struct Env {
+    ctx: TestContext,
+    config: Pubkey,
+    authority: Keypair,
+    validator: Validator,
+    uploader: Keypair,
+    stranger: Keypair,
+    /// Pays the rent of the claim accounts; fees are paid by the bank's payer.
+    claim_payer: Keypair,
+    rakurai_commission_account: Pubkey,
+    /// `rakurai_activation` config naming `rakurai_commission_account`.
+    activation_config: Pubkey,
+    /// Epoch the reward collection account is created in.
+    epoch: Epoch,
+    reward_collection_account: Pubkey,
+    claimants: Vec<Pubkey>,
+    /// First slot of the next leader turn transferred by [Env::transfer_categorized_rewards].
+    next_turn_slot: u64,
+}
+
+/// Accounts of a stake pool created by [Env::create_stake_pool].
+struct StakePoolAccounts {
+    stake_pool: Pubkey,
+    withdraw_authority: Pubkey,
+    reserve_stake: Pubkey,
+    pool_mint: Pubkey,
+    manager_fee_account: Pubkey,
+    /// Pool token account of the owner passed to [Env::create_stake_pool].
+    pool_tokens_to: Pubkey,
+}
+
+/// The events of type `T` among the `Program data:` lines of `logs`.
+fn events<T: Event + AnchorDeserialize>(logs: &[String]) -> Vec<T> {
+    logs.iter()
+        .filter_map(|log| log.strip_prefix("Program data: "))
+        .filter_map(|data| BASE64_STANDARD.decode(data).ok())
+        .filter_map(|data| T::try_from_slice(data.strip_prefix(T::DISCRIMINATOR)?).ok())
+        .collect()
+}
+
+impl Env {
+    /// Starts a bank with funded accounts for every role and Rakurai enabled for the validator.
+    /// The config is not initialized.
+    async fn start() -> Self {
+        let mut program_test = program_test();
+        let authority = add_funded_keypair(&mut program_test);
+        let validator = add_validator(&mut program_test);
+        let uploader = add_funded_keypair(&mut program_test);
+        let stranger = add_funded_keypair(&mut program_test);
+        let claim_payer = add_funded_keypair(&mut program_test);
+        let rakurai_commission_account = add_funded_keypair(&mut program_test).pubkey();
+
+        let mut ctx = TestContext::start(program_test).await;
+        let epoch = ctx.epoch().await;
+        let reward_collection_account =
+            derive_reward_collection_account_address(&ID, &validator.vote_account, epoch).0;
+
+        let mut env = Self {
+            ctx,
+            config: derive_config_account_address(&ID).0,
+            authority,
+            validator,
+            uploader,
+            stranger,
+            claim_payer,
+            rakurai_commission_account,
+            activation_config: derive_activation_config_address(&rakurai_activation::ID).0,
+            epoch,
+            reward_collection_account,
+            claimants: (0..3).map(|_| Pubkey::new_unique()).collect(),
+            next_turn_slot: 0,
+        };
+
+        let activation_config = env.activation_config;
+        let state = env.activation_config_state();
+        env.ctx
+            .set_anchor_account(&activation_config, &rakurai_activation::ID, &state);
+        let identity = env.validator.identity.pubkey();
+        env.set_activation(&identity, &env.activation_state(&identity));
+        env
+    }
+
+    /// [Env::start] followed by the config initialization.
+    async fn with_config() -> Self {
+        let mut env = Self::start().await;
+        let args = env.initialize_args();
+        env.initialize(args).await.unwrap();
+        env
+    }
+
+    /// [Env::with_config] followed by the validator's reward collection account initialization.
+    async fn with_collection_account() -> Self {
+        let mut env = Self::with_config().await;
+        let args = env.collection_account_args();
+        env.initialize_collection_account(args).await.unwrap();
+        env
+    }
+
+    /// [Env::with_collection_account] followed by a transfer of [TOTAL_REWARDS].
+    async fn with_rewards() -> Self {
+        let mut env = Self::with_collection_account().await;
+        let identity = env.validator.identity.insecure_clone();
+        let rakurai_commission_account = env.rakurai_commission_account;
+        env.transfer_rewards(&identity, rakurai_commission_account, TOTAL_REWARDS)
+            .await
+            .unwrap();
+        env
+    }
+
+    /// [Env::with_rewards] followed by the upload of the claimants' tree in the next epoch.
+    async fn with_root(indexed: bool) -> (Self, MerkleTree) {
+        let mut env = Self::with_rewards().await;
+        let tree = if indexed {
+            env.indexed_tree()
+        } else {
+            env.tree()
+        };
+        env.ctx.warp_to_epoch(env.epoch + 1).await;
+        env.upload_tree(&tree).await.unwrap();
+        (env, tree)
+    }
+
+    /// Claims of 3, 2 and 1 SOL for the three claimants.
+    fn entries(&self) -> Vec<(Pubkey, u64)> {
+        self.claimants
+            .iter()
+            .zip([3_000_000_000, 2_000_000_000, 1_000_000_000])
+            .map(|(claimant, amount)| (*claimant, amount))
+            .collect()
+    }
+
+    /// Domain binding the leaves of [Env::tree] to the reward collection account.
+    fn leaf_domain(&self) -> LeafDomain {
+        LeafDomain {
+            reward_collection_account: self.reward_collection_account,
+            creation_epoch: self.epoch,
+        }
+    }
+
+    fn tree(&self) -> MerkleTree {
+        MerkleTree::new_bound(&self.entries(), self.leaf_domain()).unwrap()
+    }
+
+    fn indexed_tree(&self) -> MerkleTree {
+        MerkleTree::new_indexed_bound(&self.entries(), self.leaf_domain()).unwrap()
+    }
+
+    fn initialize_args(&self) -> InitializeArgs {
+        InitializeArgs {
+            authority: self.authority.pubkey(),
+            num_epochs_valid: NUM_EPOCHS_VALID,
+            max_commission_bps: MAX_COMMISSION_BPS,
+            bump: derive_config_account_address(&ID).1,
+        }
+    }
+
+    /// The activation config, as initialized by the `rakurai_activation` program.
+    fn activation_config_state(&self) -> RakuraiActivationConfigAccount {
+        RakuraiActivationConfigAccount {
+            authority: self.authority.pubkey(),
+            block_builder_authority: self.authority.pubkey(),
+            block_builder_commission_bps: RAKURAI_COMMISSION_BPS,
+            block_builder_commission_account: self.rakurai_commission_account,
+            bump: derive_activation_config_address(&rakurai_activation::ID).1,
+        }
+    }
+
+    /// An enabled activation account of `identity` with [VALIDATOR_COMMISSION_BPS] and
+    /// [RAKURAI_COMMISSION_BPS].
+    fn activation_state(&self, identity: &Pubkey) -> RakuraiActivationAccount {
+        RakuraiActivationAccount {
+            is_enabled: true,
+            proposer: None,
+            validator_authority: *identity,
+            validator_commission_bps: VALIDATOR_COMMISSION_BPS,
+            block_builder_commission_bps: RAKURAI_COMMISSION_BPS,
+            bump: derive_activation_account_address(&rakurai_activation::ID, identity).1,
+            hash: None,
+        }
+    }
+
+    /// Stores `state` as the activation account of `identity`.
+    fn set_activation(&mut self, identity: &Pubkey, state: &RakuraiActivationAccount) {
+        let address = derive_activation_account_address(&rakurai_activation::ID, identity).0;
+        self.ctx
+            .set_anchor_account(&address, &rakurai_activation::ID, state);
+    }
+
+    fn collection_account_args(&self) -> InitializeRewardCollectionAccountArgs {
+        InitializeRewardCollectionAccountArgs {
+            merkle_root_upload_authority: self.uploader.pubkey(),
+            bump: derive_reward_collection_account_address(
+                &ID,
+                &self.validator.vote_account,
+                self.epoch,
+            )
+            .1,
+            rollover_unclaimed: false,
+        }
+    }
+
+    fn claim_status(&self, claimant: &Pubkey) -> Pubkey {
+        derive_claim_status_account_address(&ID, claimant, &self.reward_collection_account).0
+    }
+
+    fn claim_bitmap(&self) -> Pubkey {
+        derive_claim_bitmap_account_address(&ID, &self.reward_collection_account).0
+    }
+
+    async fn initialize(&mut self, args: InitializeArgs) -> Result<(), BanksClientError> {
+        let ix = initialize_ix(
+            ID,
+            args,
+            InitializeAccounts {
+                config: self.config,
+                system_program: system_program::ID,
+                initializer: self.ctx.payer(),
+            },
+        );
+        self.ctx.process(&[ix], &[]).await
+    }
+
+    async fn update_config(
+        &mut self,
+        new_config: RewardDistributionConfigAccount,
+        authority: &Keypair,
+    ) -> Result<(), BanksClientError> {
+        let ix = update_config_ix(
+            ID,
+            UpdateConfigArgs { new_config },
+            UpdateConfigAccounts {
+                config: self.config,
+                authority: authority.pubkey(),
+            },
+        );
+        self.ctx.process(&[ix], &[authority]).await
+    }
+
+    /// Initializes the reward collection account of the validator, signed by its identity.
+    async fn initialize_collection_account(
+        &mut self,
+        args: InitializeRewardCollectionAccountArgs,
+    ) -> Result<(), BanksClientError> {
+        let identity = self.validator.identity.insecure_clone();
+        let accounts =
+            self.collection_account_accounts(&identity.pubkey(), self.validator.vote_account);
+        self.initialize_collection_account_with(args, &identity, accounts)
+            .await
+    }
+
+    /// Accounts initializing the reward collection account of `validator_vote_account`, with the
+    /// activation account of `signer`.
+    fn collection_account_accounts(
+        &self,
+        signer: &Pubkey,
+        validator_vote_account: Pubkey,
+    ) -> InitializeRewardCollectionAccountAccounts {
+        InitializeRewardCollectionAccountAccounts {
+            config: self.config,
+            signer: *signer,
+            system_program: system_program::ID,
+            reward_collection_account: derive_reward_collection_account_address(
+                &ID,
+                &validator_vote_account,
+                self.epoch,
+            )
+            .0,
+            validator_vote_account,
+            activation_account: derive_activation_account_address(&rakurai_activation::ID, signer)
+                .0,
+            activation_config: self.activation_config,
+        }
+    }
+
+    async fn initialize_collection_account_with(
+        &mut self,
+        args: InitializeRewardCollectionAccountArgs,
+        signer: &Keypair,
+        accounts: InitializeRewardCollectionAccountAccounts,
+    ) -> Result<(), BanksClientError> {
+        let ix = initialize_reward_collection_account_ix(ID, args, accounts);
+        self.ctx.process(&[ix], &[signer]).await
+    }
+
+    /// Transfers `total_rewards` of block fees with the account's commissions.
+    async fn transfer_rewards(
+        &mut self,
+        signer: &Keypair,
+        rakurai_commission_account: Pubkey,
+        total_rewards: u64,
+    ) -> Result<(), BanksClientError> {
+        let rewards = CategorizedRewards {
+            block_fees: total_rewards,
+            ..Default::default()
+        };
+        self.transfer_categorized_rewards(signer, rakurai_commission_account, rewards)
+            .await
+    }
+
+    /// Transfers `rewards` as the leader turn of the 4 slots after the previous turn.
+    async fn transfer_categorized_rewards(
+        &mut self,
+        signer: &Keypair,
+        rakurai_commission_account: Pubkey,
+        rewards: CategorizedRewards,
+    ) -> Result<(), BanksClientError> {
+        let start_slot = self.next_turn_slot;
+        self.next_turn_slot += 4;
+        self.transfer_turn(
+            signer,
+            rakurai_commission_account,
+            rewards,
+            start_slot,
+            start_slot + 3,
+        )
+        .await
+    }
+
+    async fn transfer_turn(
+        &mut self,
+        signer: &Keypair,
+        rakurai_commission_account: Pubkey,
+        rewards: CategorizedRewards,
+        start_slot: u64,
+        end_slot: u64,
+    ) -> Result<(), BanksClientError> {
+        let ix = transfer_staker_rewards_ix(
+            ID,
+            TransferStakerRewardsArgs {
+                rewards,
+                start_slot,
+                end_slot,
+            },
+            TransferStakerRewardsAccounts {
+                rakurai_commission_account,
+                reward_collection_account: self.reward_collection_account,
+                system_program: system_program::ID,
+                signer: signer.pubkey(),
+            },
+        );
+        self.ctx.process(&[ix], &[signer]).await
+    }
+
+    async fn migrate(&mut self, payer: &Keypair) -> Result<(), BanksClientError> {
+        let ix = migrate_reward_collection_account_ix(
+            ID,
+            MigrateRewardCollectionAccountArgs,
+            MigrateRewardCollectionAccountAccounts {
+                reward_collection_account: self.reward_collection_account,
+                payer: payer.pubkey(),
+                system_program: system_program::ID,
+            },
+        );
+        self.ctx.process(&[ix], &[payer]).await
+    }
+
+    /// Deposits `amount` lamports of `source` from `depositor`, returning the transaction logs.
+    async fn deposit_rewards(
+        &mut self,
+        depositor: &Keypair,
+        amount: u64,
+        source: DepositSource,
+    ) -> Result<Vec<String>, BanksClientError> {
+        let ix = deposit_rewards_ix(
+            ID,
+            DepositRewardsArgs { amount, source },
+            DepositRewardsAccounts {
+                reward_collection_account: self.reward_collection_account,
+                depositor: depositor.pubkey(),
+                system_program: system_program::ID,
+            },
+        );
+        self.ctx.process_with_logs(&[ix], &[depositor]).await
+    }
+
+    /// Uploads a root of [LEAF_VERSION_V1] leaves, like the trees of [Env::tree].
+    async fn upload(
+        &mut self,
+        signer: &Keypair,
+        root: [u8; 32],
+        max_total_claim: u64,
+        max_num_nodes: u64,
+    ) -> Result<(), BanksClientError> {
+        self.upload_version(
+            signer,
+            root,
+            max_total_claim,
+            max_num_nodes,
+            LEAF_VERSION_V1,
+        )
+        .await
+    }
+
+    async fn upload_version(
+        &mut self,
+        signer: &Keypair,
+        root: [u8; 32],
+        max_total_claim: u64,
+        max_num_nodes: u64,
+        version: u8,
+    ) -> Result<(), BanksClientError> {
+        self.upload_with(
+            signer,
+            UploadMerkleRootArgs {
+                root,
+                max_total_claim,
+                max_num_nodes,
+                version,
+                pro_rata: false,
+            },
+        )
+        .await
+    }
+
+    async fn upload_with(
+        &mut self,
+        signer: &Keypair,
+        args: UploadMerkleRootArgs,
+    ) -> Result<(), BanksClientError> {
+        let ix = upload_merkle_root_ix(
+            ID,
+            args,
+            UploadMerkleRootAccounts {
+                config: self.config,
+                merkle_root_upload_authority: signer.pubkey(),
+                reward_collection_account: self.reward_collection_account,
+            },
+        );
+        self.ctx.process(&[ix], &[signer]).await
+    }
+
+    async fn update_upload_authority(
+        &mut self,
+        signer: &Keypair,
+        new_merkle_root_upload_authority: Pubkey,
+    ) -> Result<(), BanksClientError> {
+        let ix = update_merkle_root_upload_authority_ix(
+            ID,
+            UpdateMerkleRootUploadAuthorityArgs {
+                new_merkle_root_upload_authority,
+            },
+            UpdateMerkleRootUploadAuthorityAccounts {
+                reward_collection_account: self.reward_collection_account,
+                signer: signer.pubkey(),
+            },
+        );
+        self.ctx.process(&[ix], &[signer]).await
+    }
+
+    fn root_attestation(&self) -> Pubkey {
+        derive_root_attestation_address(&ID, &self.reward_collection_account).0
+    }
+
+    async fn initialize_root_attestation(
+        &mut self,
+        signer: &Keypair,
+        attesters: Vec<Pubkey>,
+        threshold: u8,
+    ) -> Result<(), BanksClientError> {
+        let ix = initialize_root_attestation_ix(
+            ID,
+            InitializeRootAttestationArgs {
+                attesters,
+                threshold,
+            },
+            InitializeRootAttestationAccounts {
+                reward_collection_account: self.reward_collection_account,
+                root_attestation: self.root_attestation(),
+                signer: signer.pubkey(),
+                system_program: system_program::ID,
+            },
+        );
+        self.ctx.process(&[ix], &[signer]).await
+    }
+
+    /// Submits `tree` as the root of `attester`.
+    async fn attest_tree(
+        &mut self,
+        attester: &Keypair,
+        tree: &MerkleTree,
+    ) -> Result<(), BanksClientError> {
+        let ix = attest_merkle_root_ix(
+            ID,
+            AttestMerkleRootArgs {
+                root: tree.root,
+                max_total_claim: tree.max_total_claim,
+                max_num_nodes: tree.max_num_nodes,
+                version: tree.version(),
+                pro_rata: false,
+            },
+            AttestMerkleRootAccounts {
+                config: self.config,
+                reward_collection_account: self.reward_collection_account,
+                root_attestation: self.root_attestation(),
+                attester: attester.pubkey(),
+            },
+        );
+        self.ctx.process(&[ix], &[attester]).await
+    }
+
+    async fn close_root_attestation(&mut self, payer: Pubkey) -> Result<(), BanksClientError> {
+        let ix = close_root_attestation_ix(
+            ID,
+            CloseRootAttestationArgs,
+            CloseRootAttestationAccounts {
+                root_attestation: self.root_attestation(),
+                payer,
+            },
+        );
+        self.ctx.process(&[ix], &[]).await
+    }
+
+    /// Sets the validator commission without category commissions.
+    async fn update_commission(
+        &mut self,
+        signer: &Keypair,
+        new_validator_commission_bps: u16,
+    ) -> Result<(), BanksClientError> {
+        self.update_commissions(
+            signer,
+            new_validator_commission_bps,
+            CategoryCommissions::default(),
+        )
+        .await
+        .map(drop)
+    }
+
+    /// Sets the validator commission and the category commissions, returning the transaction
+    /// logs.
+    async fn update_commissions(
+        &mut self,
+        signer: &Keypair,
+        new_validator_commission_bps: u16,
+        new_category_commissions: CategoryCommissions,
+    ) -> Result<Vec<String>, BanksClientError> {
+        let ix = update_validator_commission_ix(
+            ID,
+            UpdateValidatorCommissionArgs {
+                new_validator_commission_bps,
+                new_category_commissions,
+            },
+            UpdateValidatorCommissionAccounts {
+                config: self.config,
+                reward_collection_account: self.reward_collection_account,
+                signer: signer.pubkey(),
+            },
+        );
+        self.ctx.process_with_logs(&[ix], &[signer]).await
+    }
+
+    async fn upload_tree(&mut self, tree: &MerkleTree) -> Result<(), BanksClientError> {
+        let uploader = self.uploader.insecure_clone();
+        self.upload_version(
+            &uploader,
+            tree.root,
+            tree.max_total_claim,
+            tree.max_num_nodes,
+            tree.version(),
+        )
+        .await
+    }
+
+    /// Uploads `tree` in pro-rata mode.
+    async fn upload_tree_pro_rata(&mut self, tree: &MerkleTree) -> Result<(), BanksClientError> {
+        let uploader = self.uploader.insecure_clone();
+        self.upload_with(
+            &uploader,
+            UploadMerkleRootArgs {
+                root: tree.root,
+                max_total_claim: tree.max_total_claim,
+                max_num_nodes: tree.max_num_nodes,
+                version: tree.version(),
+                pro_rata: true,
+            },
+        )
+        .await
+    }
+
+    async fn claim(&mut self, node: &TreeNode) -> Result<(), BanksClientError> {
+        self.claim_amount(node, node.amount).await
+    }
+
+    async fn claim_amount(&mut self, node: &TreeNode, amount: u64) -> Result<(), BanksClientError> {
+        let (claim_status, bump) = derive_claim_status_account_address(
+            &ID,
+            &node.claimant,
+            &self.reward_collection_account,
+        );
+        let ix = claim_ix(
+            ID,
+            ClaimArgs {
+                proof: node.proof.clone(),
+                amount,
+                bump,
+            },
+            ClaimAccounts {
+                config: self.config,
+                reward_collection_account: self.reward_collection_account,
+                claim_status,
+                claimant: node.claimant,
+                payer: self.claim_payer.pubkey(),
+                system_program: system_program::ID,
+            },
+        );
+        let claim_payer = self.claim_payer.insecure_clone();
+        self.ctx.process(&[ix], &[&claim_payer]).await
+    }
+
+    /// Claims `node` of `claimant` into the new stake account `stake_account`.
+    async fn claim_to_stake_account(
+        &mut self,
+        node: &TreeNode,
+        claimant: &Keypair,
+        stake_account: &Keypair,
+    ) -> Result<(), BanksClientError> {
+        let ix = claim_to_stake_account_ix(
+            ID,
+            ClaimToStakeAccountArgs {
+                proof: node.proof.clone(),
+                amount: node.amount,
+            },
+            ClaimToStakeAccountAccounts {
+                config: self.config,
+                reward_collection_account: self.reward_collection_account,
+                claim_status: self.claim_status(&node.claimant),
+                claimant: claimant.pubkey(),
+                stake_account: stake_account.pubkey(),
+                validator_vote_account: self.validator.vote_account,
+                #[allow(deprecated)]
+                stake_config: stake::config::ID,
+                stake_history: sysvar::stake_history::ID,
+                clock: sysvar::clock::ID,
+                rent: sysvar::rent::ID,
+                stake_program: stake::program::ID,
+                payer: self.claim_payer.pubkey(),
+                system_program: system_program::ID,
+            },
+        );
+        let claim_payer = self.claim_payer.insecure_clone();
+        self.ctx
+            .process(&[ix], &[&claim_payer, claimant, stake_account])
+            .await
+    }
+
+    /// The stake program's minimum delegation, returned by a simulated `get_minimum_delegation`.
+    async fn minimum_delegation(&mut self) -> u64 {
+        let context = &mut self.ctx.context;
+        let blockhash = context.get_new_latest_blockhash().await.unwrap();
+        let transaction = Transaction::new_signed_with_payer(
+            &[stake::instruction::get_minimum_delegation()],
+            Some(&context.payer.pubkey()),
+            &[&context.payer],
+            blockhash,
+        );
+        let simulation = context
+            .banks_client
+            .simulate_transaction(transaction)
+            .await
+            .unwrap();
+        let return_data = simulation.simulation_details.unwrap().return_data.unwrap();
+        u64::from_le_bytes(return_data.data.try_into().unwrap())
+    }
+
+    /// Creates a stake pool without fees, managed by the bank's payer, and a token account of
+    /// `owner` for its pool tokens. The pool is updated in the current epoch only.
+    async fn create_stake_pool(&mut self, owner: &Pubkey) -> StakePoolAccounts {
+        let payer = self.ctx.payer();
+        let stake_pool = Keypair::new();
+        let validator_list = Keypair::new();
+        let reserve_stake = Keypair::new();
+        let pool_mint = Keypair::new();
+        let manager_fee_account = Keypair::new();
+        let pool_tokens_to = Keypair::new();
+        let withdraw_authority =
+            find_withdraw_authority_program_address(&spl_stake_pool::ID, &stake_pool.pubkey()).0;
+
+        let mint_rent = self.ctx.rent(Mint::LEN).await;
+        let token_account_rent = self.ctx.rent(TokenAccount::LEN).await;
+        let mut ixs = vec![
+            system_instruction::create_account(
+                &payer,
+                &pool_mint.pubkey(),
+                mint_rent,
+                Mint::LEN as u64,
+                &spl_token::ID,
+            ),
+            spl_token::instruction::initialize_mint2(
+                &spl_token::ID,
+                &pool_mint.pubkey(),
+                &withdraw_authority,
+                None,
+                9,
+            )
+            .unwrap(),
+        ];
+        for (account, owner) in [(&manager_fee_account, &payer), (&pool_tokens_to, owner)] {
+            ixs.push(system_instruction::create_account(
+                &payer,
+                &account.pubkey(),
+                token_account_rent,
+                TokenAccount::LEN as u64,
+                &spl_token::ID,
+            ));
+            ixs.push(
+                spl_token::instruction::initialize_account3(
+                    &spl_token::ID,
+                    &account.pubkey(),
+                    &pool_mint.pubkey(),
+                    owner,
+                )
+                .unwrap(),
+            );
+        }
+        self.ctx
+            .process(&ixs, &[&pool_mint, &manager_fee_account, &pool_tokens_to])
+            .await
+            .unwrap();
+
+        let reserve_lamports =
+            self.ctx.rent(StakeStateV2::size_of()).await + MINIMUM_RESERVE_LAMPORTS;
+        let ixs = stake::instruction::create_account(
+            &payer,
+            &reserve_stake.pubkey(),
+            &Authorized::auto(&withdraw_authority),
+            &Lockup::default(),
+            reserve_lamports,
+        );
+        self.ctx.process(&ixs, &[&reserve_stake]).await.unwrap();
+
+        let max_validators = 1;
+        let stake_pool_len = get_packed_len::<StakePool>();
+        let validator_list_len =
+            get_instance_packed_len(&ValidatorList::new(max_validators)).unwrap();
+        let mut ixs = vec![];
+        for (account, len) in [
+            (&stake_pool, stake_pool_len),
+            (&validator_list, validator_list_len),
+        ] {
+            let lamports = self.ctx.rent(len).await;
+            ixs.push(system_instruction::create_account(
+                &payer,
+                &account.pubkey(),
+                lamports,
+                len as u64,
+                &spl_stake_pool::ID,
+            ));
+        }
+        ixs.push(spl_stake_pool::instruction::initialize(
+            &spl_stake_pool::ID,
+            &stake_pool.pubkey(),
+            &payer,
+            &payer,
+            &withdraw_authority,
+            &validator_list.pubkey(),
+            &reserve_stake.pubkey(),
+            &pool_mint.pubkey(),
+            &manager_fee_account.pubkey(),
+            &spl_token::ID,
+            None,
+            Fee::default(),
+            Fee::default(),
+            Fee::default(),
+            0,
+            max_validators,
+        ));
+        self.ctx
+            .process(&ixs, &[&stake_pool, &validator_list])
+            .await
+            .unwrap();
+
+        StakePoolAccounts {
+            stake_pool: stake_pool.pubkey(),
+            withdraw_authority,
+            reserve_stake: reserve_stake.pubkey(),
+            pool_mint: pool_mint.pubkey(),
+            manager_fee_account: manager_fee_account.pubkey(),
+            pool_tokens_to: pool_tokens_to.pubkey(),
+        }
+    }
+
+    /// Claims `node` of `claimant` into `pool`, minting the pool tokens to `pool_tokens_to`.
+    async fn claim_to_stake_pool(
+        &mut self,
+        node: &TreeNode,
+        claimant: &Keypair,
+        pool: &StakePoolAccounts,
+        pool_tokens_to: Pubkey,
+    ) -> Result<(), BanksClientError> {
+        let ix = claim_to_stake_pool_ix(
+            ID,
+            ClaimToStakePoolArgs {
+                proof: node.proof.clone(),
+                amount: node.amount,
+            },
+            ClaimToStakePoolAccounts {
+                config: self.config,
+                reward_collection_account: self.reward_collection_account,
+                claim_status: self.claim_status(&node.claimant),
+                claimant: claimant.pubkey(),
+                deposit_account: derive_stake_pool_deposit_address(
+                    &ID,
+                    &self.reward_collection_account,
+                )
+                .0,
+                stake_pool: pool.stake_pool,
+                stake_pool_withdraw_authority: pool.withdraw_authority,
+                reserve_stake: pool.reserve_stake,
+                pool_tokens_to,
+                manager_fee_account: pool.manager_fee_account,
+                pool_mint: pool.pool_mint,
+                token_program: spl_token::ID,
+                stake_pool_program: spl_stake_pool::ID,
+                payer: self.claim_payer.pubkey(),
+                system_program: system_program::ID,
+            },
+        );
+        let claim_payer = self.claim_payer.insecure_clone();
+        self.ctx.process(&[ix], &[&claim_payer, claimant]).await
+    }
+
+    async fn claim_batch(
+        &mut self,
+        claims: Vec<BatchClaim>,
+        claimants: Vec<(Pubkey, Pubkey)>,
+    ) -> Result<(), BanksClientError> {
+        let ix = claim_batch_ix(
+            ID,
+            ClaimBatchArgs { claims },
+            ClaimBatchAccounts {
+                config: self.config,
+                reward_collection_account: self.reward_collection_account,
+                payer: self.claim_payer.pubkey(),
+                system_program: system_program::ID,
+                claimants,
+            },
+        );
+        let claim_payer = self.claim_payer.insecure_clone();
+        self.ctx.process(&[ix], &[&claim_payer]).await
+    }
+
+    /// Claims `nodes` in one [claim_batch_ix].
+    async fn claim_nodes(&mut self, nodes: &[TreeNode]) -> Result<(), BanksClientError> {
+        let claims = nodes
+            .iter()
+            .map(|node| BatchClaim {
+                amount: node.amount,
+                proof: node.proof.clone(),
+            })
+            .collect();
+        let claimants = nodes
+            .iter()
+            .map(|node| (node.claimant, self.claim_status(&node.claimant)))
+            .collect();
+        self.claim_batch(claims, claimants).await
+    }
+
+    /// Claims the nodes of `claims`, each next to the reward collection account of its tree, for
+    /// `claimant` in one [claim_aggregated_ix].
+    async fn claim_aggregated(
+        &mut self,
+        claimant: Pubkey,
+        claims: &[(Pubkey, TreeNode)],
+    ) -> Result<(), BanksClientError> {
+        let ix = claim_aggregated_ix(
+            ID,
+            ClaimAggregatedArgs {
+                claims: claims
+                    .iter()
+                    .map(|(_, node)| AggregatedClaim {
+                        amount: node.amount,
+                        proof: node.proof.clone(),
+                    })
+                    .collect(),
+            },
+            ClaimAggregatedAccounts {
+                config: self.config,
+                claimant,
+                payer: self.claim_payer.pubkey(),
+                system_program: system_program::ID,
+                reward_collection_accounts: claims
+                    .iter()
+                    .map(|(reward_collection_account, _)| {
+                        (
+                            *reward_collection_account,
+                            derive_claim_status_account_address(
+                                &ID,
+                                &claimant,
+                                reward_collection_account,
+                            )
+                            .0,
+                        )
+                    })
+                    .collect(),
+            },
+        );
+        let claim_payer = self.claim_payer.insecure_clone();
+        self.ctx.process(&[ix], &[&claim_payer]).await
+    }
+
+    /// Proof buffer of the claim payer for `claimant`.
+    fn proof_buffer(&self, claimant: &Pubkey) -> Pubkey {
+        derive_proof_buffer_address(
+            &ID,
+            &self.reward_collection_account,
+            claimant,
+            &self.claim_payer.pubkey(),
+        )
+        .0
+    }
+
+    async fn initialize_proof_buffer(
+        &mut self,
+        claimant: Pubkey,
+        proof_len: u8,
+    ) -> Result<(), BanksClientError> {
+        let ix = initialize_proof_buffer_ix(
+            ID,
+            InitializeProofBufferArgs {
+                claimant,
+                proof_len,
+            },
+            InitializeProofBufferAccounts {
+                config: self.config,
+                reward_collection_account: self.reward_collection_account,
+                proof_buffer: self.proof_buffer(&claimant),
+                payer: self.claim_payer.pubkey(),
+                system_program: system_program::ID,
+            },
+        );
+        let claim_payer = self.claim_payer.insecure_clone();
+        self.ctx.process(&[ix], &[&claim_payer]).await
+    }
+
+    async fn write_proof_buffer(
+        &mut self,
+        signer: &Keypair,
+        claimant: &Pubkey,
+        offset: u8,
+        hashes: &[[u8; 32]],
+    ) -> Result<(), BanksClientError> {
+        let ix = write_proof_buffer_ix(
+            ID,
+            WriteProofBufferArgs {
+                offset,
+                hashes: hashes.to_vec(),
+            },
+            WriteProofBufferAccounts {
+                proof_buffer: self.proof_buffer(claimant),
+                payer: signer.pubkey(),
+            },
+        );
+        self.ctx.process(&[ix], &[signer]).await
+    }
+
+    async fn claim_with_proof_buffer(&mut self, node: &TreeNode) -> Result<(), BanksClientError> {
+        let ix = claim_with_proof_buffer_ix(
+            ID,
+            ClaimWithProofBufferArgs {
+                amount: node.amount,
+            },
+            ClaimWithProofBufferAccounts {
+                config: self.config,
+                reward_collection_account: self.reward_collection_account,
+                claim_status: self.claim_status(&node.claimant),
+                claimant: node.claimant,
+                proof_buffer: self.proof_buffer(&node.claimant),
+                payer: self.claim_payer.pubkey(),
+                system_program: system_program::ID,
+            },
+        );
+        let claim_payer = self.claim_payer.insecure_clone();
+        self.ctx.process(&[ix], &[&claim_payer]).await
+    }
+
+    async fn close_proof_buffer(
+        &mut self,
+        signer: &Keypair,
+        claimant: &Pubkey,
+    ) -> Result<(), BanksClientError> {
+        let ix = close_proof_buffer_ix(
+            ID,
+            CloseProofBufferArgs,
+            CloseProofBufferAccounts {
+                proof_buffer: self.proof_buffer(claimant),
+                payer: signer.pubkey(),
+            },
+        );
+        self.ctx.process(&[ix], &[signer]).await
+    }
+
+    async fn initialize_claim_bitmap(&mut self) -> Result<(), BanksClientError> {
+        let ix = initialize_claim_bitmap_ix(
+            ID,
+            InitializeClaimBitmapArgs,
+            InitializeClaimBitmapAccounts {
+                config: self.config,
+                reward_collection_account: self.reward_collection_account,
+                claim_bitmap: self.claim_bitmap(),
+                payer: self.claim_payer.pubkey(),
+                system_program: system_program::ID,
+            },
+        );
+        let claim_payer = self.claim_payer.insecure_clone();
+        self.ctx.process(&[ix], &[&claim_payer]).await
+    }
+
+    async fn claim_indexed(&mut self, node: &TreeNode, index: u64) -> Result<(), BanksClientError> {
+        let ix = claim_indexed_ix(
+            ID,
+            ClaimIndexedArgs {
+                index,
+                proof: node.proof.clone(),
+                amount: node.amount,
+            },
+            ClaimIndexedAccounts {
+                config: self.config,
+                reward_collection_account: self.reward_collection_account,
+                claim_bitmap: self.claim_bitmap(),
+                claimant: node.claimant,
+                payer: self.claim_payer.pubkey(),
+                system_program: system_program::ID,
+            },
+        );
+        let claim_payer = self.claim_payer.insecure_clone();
+        self.ctx.process(&[ix], &[&claim_payer]).await
+    }
+
+    async fn close_claim_bitmap(&mut self) -> Result<(), BanksClientError> {
+        let ix = close_claim_bitmap_ix(
+            ID,
+            CloseClaimBitmapArgs,
+            CloseClaimBitmapAccounts {
+                claim_bitmap: self.claim_bitmap(),
+                payer: self.claim_payer.pubkey(),
+            },
+        );
+        self.ctx.process(&[ix], &[]).await
+    }
+
+    async fn close_claim_status(&mut self, claimant: &Pubkey) -> Result<(), BanksClientError> {
+        let ix = close_claim_status_ix(
+            ID,
+            CloseClaimStatusArgs,
+            CloseClaimStatusAccounts {
+                config: self.config,
+                claim_status: self.claim_status(claimant),
+                claim_status_payer: self.claim_payer.pubkey(),
+            },
+        );
+        self.ctx.process(&[ix], &[]).await
+    }
+
+    async fn close_collection_account(
+        &mut self,
+        initializer: Pubkey,
+    ) -> Result<(), BanksClientError> {
+        let ix = close_reward_collection_account_ix(
+            ID,
+            CloseRewardCollectionAccountArgs { _epoch: self.epoch },
+            CloseRewardCollectionAccounts {
+                config: self.config,
+                initializer,
+                reward_collection_account: self.reward_collection_account,
+                validator_vote_account: self.validator.vote_account,
+                signer: self.ctx.payer(),
+            },
+        );
+        self.ctx.process(&[ix], &[]).await
+    }
+
+    /// Rolls the unclaimed funds of the reward collection account created in `epoch` over to the
+    /// one of the env.
+    async fn rollover(&mut self, epoch: Epoch) -> Result<(), BanksClientError> {
+        let vote_account = self.validator.vote_account;
+        let ix = rollover_unclaimed_rewards_ix(
+            ID,
+            RolloverUnclaimedRewardsArgs { epoch },
+            RolloverUnclaimedRewardsAccounts {
+                reward_collection_account: derive_reward_collection_account_address(
+                    &ID,
+                    &vote_account,
+                    epoch,
+                )
+                .0,
+                current_reward_collection_account: self.reward_collection_account,
+                validator_vote_account: vote_account,
+            },
+        );
+        self.ctx.process(&[ix], &[]).await
+    }
+
+    async fn withdraw_surplus(&mut self, initializer: &Keypair) -> Result<(), BanksClientError> {
+        let ix = withdraw_surplus_ix(
+            ID,
+            WithdrawSurplusArgs,
+            WithdrawSurplusAccounts {
+                reward_collection_account: self.reward_collection_account,
+                initializer: initializer.pubkey(),
+            },
+        );
+        self.ctx.process(&[ix], &[initializer]).await
+    }
+
+    async fn collection_account(&mut self) -> RewardCollectionAccount {
+        let reward_collection_account = self.reward_collection_account;
+        self.ctx.get_account(&reward_collection_account).await
+    }
+
+    /// Warps to the first epoch the reward collection account is expired in.
+    async fn warp_past_expiry(&mut self) {
+        self.ctx
+            .warp_to_epoch(self.epoch + NUM_EPOCHS_VALID + 1)
+            .await;
+    }
+
+    /// Warps to `epoch` and makes it the epoch of the env: its reward collection account is
+    /// initialized and receives [TOTAL_REWARDS].
+    async fn with_rewards_in_epoch(&mut self, epoch: Epoch) {
+        self.ctx.warp_to_epoch(epoch).await;
+        self.epoch = epoch;
+        self.reward_collection_account =
+            derive_reward_collection_account_address(&ID, &self.validator.vote_account, epoch).0;
+        let args = self.collection_account_args();
+        self.initialize_collection_account(args).await.unwrap();
+        let identity = self.validator.identity.insecure_clone();
+        let rakurai_commission_account = self.rakurai_commission_account;
+        self.transfer_rewards(&identity, rakurai_commission_account, TOTAL_REWARDS)
+            .await
+            .unwrap();
+    }
+
+    /// Makes the reward collection account created in `epoch` the one of the env.
+    fn select_epoch(&mut self, epoch: Epoch) {
+        self.epoch = epoch;
+        self.reward_collection_account =
+            derive_reward_collection_account_address(&ID, &self.validator.vote_account, epoch).0;
+    }
+
+    /// [Env::with_rewards] in two consecutive epochs, each with [Env::tree] uploaded. Ends in the
+    /// epoch after the second one, with the second reward collection account selected. Returns
+    /// the reward collection accounts and their trees.
+    async fn with_roots_in_two_epochs() -> (Self, Vec<(Pubkey, MerkleTree)>) {
+        let mut env = Self::with_rewards().await;
+        let first = env.epoch;
+        env.with_rewards_in_epoch(first + 1).await;
+
+        let mut roots = Vec::new();
+        env.select_epoch(first);
+        let tree = env.tree();
+        env.upload_tree(&tree).await.unwrap();
+        roots.push((env.reward_collection_account, tree));
+
+        env.ctx.warp_to_epoch(first + 2).await;
+        env.select_epoch(first + 1);
+        let tree = env.tree();
+        env.upload_tree(&tree).await.unwrap();
+        roots.push((env.reward_collection_account, tree));
+        (env, roots)
+    }
+
+    fn distributor(&self) -> Pubkey {
+        derive_cumulative_distributor_address(&ID, &self.validator.vote_account).0
+    }
+
+    fn cumulative_claim_status(&self, claimant: &Pubkey) -> Pubkey {
+        derive_cumulative_claim_status_address(&ID, claimant, &self.distributor()).0
+    }
+
+    /// Cumulative tree of [Env::entries] with every total multiplied by `num_epochs`.
+    fn cumulative_tree(&self, num_epochs: u64) -> MerkleTree {
+        let entries: Vec<(Pubkey, u64)> = self
+            .entries()
+            .into_iter()
+            .map(|(claimant, amount)| (claimant, amount * num_epochs))
+            .collect();
+        MerkleTree::new_cumulative(&entries, self.distributor()).unwrap()
+    }
+
+    async fn initialize_distributor(&mut self, signer: &Keypair) -> Result<(), BanksClientError> {
+        let ix = initialize_cumulative_distributor_ix(
+            ID,
+            InitializeCumulativeDistributorArgs {
+                merkle_root_upload_authority: self.uploader.pubkey(),
+            },
+            InitializeCumulativeDistributorAccounts {
+                config: self.config,
+                distributor: self.distributor(),
+                validator_vote_account: self.validator.vote_account,
+                signer: signer.pubkey(),
+                system_program: system_program::ID,
+            },
+        );
+        self.ctx.process(&[ix], &[signer]).await
+    }
+
+    /// Sweeps the reward collection account of the current env epoch.
+    async fn sweep(&mut self, signer: &Keypair) -> Result<(), BanksClientError> {
+        let ix = sweep_to_cumulative_distributor_ix(
+            ID,
+            SweepToCumulativeDistributorArgs { epoch: self.epoch },
+            SweepToCumulativeDistributorAccounts {
+                reward_collection_account: self.reward_collection_account,
+                distributor: self.distributor(),
+                signer: signer.pubkey(),
+            },
+        );
+        self.ctx.process(&[ix], &[signer]).await
+    }
+
+    async fn upload_cumulative(
+        &mut self,
+        signer: &Keypair,
+        root: [u8; 32],
+        max_total_claim: u64,
+    ) -> Result<(), BanksClientError> {
+        let ix = upload_cumulative_merkle_root_ix(
+            ID,
+            UploadCumulativeMerkleRootArgs {
+                root,
+                max_total_claim,
+            },
+            UploadCumulativeMerkleRootAccounts {
+                distributor: self.distributor(),
+                merkle_root_upload_authority: signer.pubkey(),
+            },
+        );
+        self.ctx.process(&[ix], &[signer]).await
+    }
+
+    async fn upload_cumulative_tree(&mut self, tree: &MerkleTree) -> Result<(), BanksClientError> {
+        let uploader = self.uploader.insecure_clone();
+        self.upload_cumulative(&uploader, tree.root, tree.max_total_claim)
+            .await
+    }
+
+    async fn claim_cumulative(&mut self, node: &TreeNode) -> Result<(), BanksClientError> {
+        let ix = claim_cumulative_ix(
+            ID,
+            ClaimCumulativeArgs {
+                total_amount: node.amount,
+                proof: node.proof.clone(),
+            },
+            ClaimCumulativeAccounts {
+                distributor: self.distributor(),
+                claim_status: self.cumulative_claim_status(&node.claimant),
+                claimant: node.claimant,
+                payer: self.claim_payer.pubkey(),
+                system_program: system_program::ID,
+            },
+        );
+        let claim_payer = self.claim_payer.insecure_clone();
+        self.ctx.process(&[ix], &[&claim_payer]).await
+    }
+
+    async fn distributor_state(&mut self) -> CumulativeDistributor {
+        let distributor = self.distributor();
+        self.ctx.get_account(&distributor).await
+    }
+}
+
+#[tokio::test]
+async fn initialize_creates_config() {
+    let mut env = Env::with_config().await;
+
+    let config: RewardDistributionConfigAccount = env.ctx.get_account(&env.config).await;
+    assert_eq!(config.authority, env.authority.pubkey());
+    assert_eq!(config.num_epochs_valid, NUM_EPOCHS_VALID);
+    assert_eq!(config.max_commission_bps, MAX_COMMISSION_BPS);
+    assert_eq!(config.bump, derive_config_account_address(&ID).1);
+
+    let rent = env.ctx.rent(RewardDistributionConfigAccount::SIZE).await;
+    assert_eq!(env.ctx.balance(&env.config).await, rent);
+}
+
+#[tokio::test]
+async fn initialize_rejects_invalid_config() {
+    let mut env = Env::start().await;
+
+    for args in [
+        InitializeArgs {
+            num_epochs_valid: 0,
+            ..env.initialize_args()
+        },
+        InitializeArgs {
+            num_epochs_valid: 11,
+            ..env.initialize_args()
+        },
+        InitializeArgs {
+            max_commission_bps: 10_001,
+            ..env.initialize_args()
+        },
+    ] {
+        let result = env.initialize(args).await;
+        assert_custom_error(result, ErrorCode::AccountValidationFailure.into());
+    }
+
+    assert!(!env.ctx.exists(&env.config).await);
+}
+
+#[tokio::test]
+async fn update_config_by_authority() {
+    let mut env = Env::with_config().await;
+    let authority = env.authority.insecure_clone();
+    let new_authority = Keypair::new();
+
+    let new_config = RewardDistributionConfigAccount {
+        authority: new_authority.pubkey(),
+        num_epochs_valid: 10,
+        max_commission_bps: 10_000,
+        bump: 0,
+    };
+    env.update_config(new_config, &authority).await.unwrap();
+
+    let config: RewardDistributionConfigAccount = env.ctx.get_account(&env.config).await;
+    assert_eq!(config.authority, new_authority.pubkey());
+    assert_eq!(config.num_epochs_valid, 10);
+    assert_eq!(config.max_commission_bps, 10_000);
+    // The bump is not updatable.
+    assert_eq!(config.bump, derive_config_account_address(&ID).1);
+
+    // The old authority lost its rights.
+    let result = env
+        .update_config(RewardDistributionConfigAccount::default(), &authority)
+        .await;
+    assert_custom_error(result, ErrorCode::Unauthorized.into());
+}
+
+#[tokio::test]
+async fn update_config_errors() {
+    let mut env = Env::with_config().await;
+    let authority = env.authority.insecure_clone();
+    let stranger = env.stranger.insecure_clone();
+    let valid_config = RewardDistributionConfigAccount {
+        authority: authority.pubkey(),
+        num_epochs_valid: NUM_EPOCHS_VALID,
+        max_commission_bps: MAX_COMMISSION_BPS,
+        bump: 0,
+    };
+
+    let result = env.update_config(valid_config.clone(), &stranger).await;
+    assert_custom_error(result, ErrorCode::Unauthorized.into());
+
+    let result = env
+        .update_config(
+            RewardDistributionConfigAccount {
+                num_epochs_valid: 0,
+                ..valid_config.clone()
+            },
+            &authority,
+        )
+        .await;
+    assert_custom_error(result, ErrorCode::AccountValidationFailure.into());
+
+    let result = env
+        .update_config(
+            RewardDistributionConfigAccount {
+                max_commission_bps: 10_001,
+                ..valid_config
+            },
+            &authority,
+        )
+        .await;
+    assert_custom_error(result, ErrorCode::AccountValidationFailure.into());
+}
+
+#[tokio::test]
+async fn initialize_collection_account_by_validator() {
+    let mut env = Env::with_config().await;
+    let identity = env.validator.identity.pubkey();
+    let identity_balance = env.ctx.balance(&identity).await;
+
+    let args = env.collection_account_args();
+    env.initialize_collection_account(args).await.unwrap();
+
+    let account = env.collection_account().await;
+    assert_eq!(account.validator_vote_account, env.validator.vote_account);
+    assert_eq!(account.merkle_root_upload_authority, env.uploader.pubkey());
+    assert!(account.merkle_root.is_none());
+    assert_eq!(account.creation_epoch, env.epoch);
+    assert_eq!(account.validator_commission_bps, VALIDATOR_COMMISSION_BPS);
+    assert_eq!(account.rakurai_commission_bps, RAKURAI_COMMISSION_BPS);
+    assert_eq!(
+        account.rakurai_commission_account,
+        env.rakurai_commission_account
+    );
+    assert_eq!(account.expires_at, env.epoch + NUM_EPOCHS_VALID);
+    assert_eq!(account.initializer, identity);
+
+    let rent = env.ctx.rent(RewardCollectionAccount::SIZE).await;
+    let reward_collection_account = env.reward_collection_account;
+    assert_eq!(env.ctx.balance(&reward_collection_account).await, rent);
+    assert_eq!(env.ctx.balance(&identity).await, identity_balance - rent);
+}
+
+#[tokio::test]
+async fn initialize_collection_account_errors() {
+    let mut env = Env::with_config().await;
+    let identity = env.validator.identity.insecure_clone();
+    let stranger = env.stranger.insecure_clone();
+
+    for (validator_commission_bps, rakurai_commission_bps) in
+        [(5_001, 0), (0, 5_001), (3_000, 3_000)]
+    {
+        let activation = RakuraiActivationAccount {
+            validator_commission_bps,
+            block_builder_commission_bps: rakurai_commission_bps,
+            ..env.activation_state(&identity.pubkey())
+        };
+        env.set_activation(&identity.pubkey(), &activation);
+        let args = env.collection_account_args();
+        let result = env.initialize_collection_account(args).await;
+        assert_custom_error(result, ErrorCode::MaxCommissionFeeBpsExceeded.into());
+    }
+    env.set_activation(
+        &identity.pubkey(),
+        &env.activation_state(&identity.pubkey()),
+    );
+
+    // The vote account must be owned by the vote program.
+    let args = env.collection_account_args();
+    let accounts = env.collection_account_accounts(&identity.pubkey(), identity.pubkey());
+    let result = env
+        .initialize_collection_account_with(args, &identity, accounts)
+        .await;
+    assert_custom_error(result, ErrorCode::Unauthorized.into());
+
+    // The signer must be the node of the vote account, even with Rakurai enabled.
+    env.set_activation(
+        &stranger.pubkey(),
+        &env.activation_state(&stranger.pubkey()),
+    );
+    let args = env.collection_account_args();
+    let accounts = env.collection_account_accounts(&stranger.pubkey(), env.validator.vote_account);
+    let result = env
+        .initialize_collection_account_with(args, &stranger, accounts)
+        .await;
+    assert_custom_error(result, ErrorCode::Unauthorized.into());
+
+    let args = InitializeRewardCollectionAccountArgs {
+        merkle_root_upload_authority: Pubkey::default(),
+        ..env.collection_account_args()
+    };
+    let result = env.initialize_collection_account(args).await;
+    assert_custom_error(result, ErrorCode::AccountValidationFailure.into());
+
+    let reward_collection_account = env.reward_collection_account;
+    assert!(!env.ctx.exists(&reward_collection_account).await);
+}
+
+#[tokio::test]
+async fn initialize_collection_account_copies_activation_commissions() {
+    let mut env = Env::with_config().await;
+    let identity = env.validator.identity.pubkey();
+    let rakurai_commission_account = Pubkey::new_unique();
+
+    let activation = RakuraiActivationAccount {
+        validator_commission_bps: 1_500,
+        block_builder_commission_bps: 2_000,
+        ..env.activation_state(&identity)
+    };
+    env.set_activation(&identity, &activation);
+    let activation_config = env.activation_config;
+    let config = RakuraiActivationConfigAccount {
+        block_builder_commission_account: rakurai_commission_account,
+        ..env.activation_config_state()
+    };
+    env.ctx
+        .set_anchor_account(&activation_config, &rakurai_activation::ID, &config);
+
+    let args = env.collection_account_args();
+    env.initialize_collection_account(args).await.unwrap();
+
+    let account = env.collection_account().await;
+    assert_eq!(account.validator_commission_bps, 1_500);
+    assert_eq!(account.rakurai_commission_bps, 2_000);
+    assert_eq!(
+        account.rakurai_commission_account,
+        rakurai_commission_account
+    );
+}
+
+#[tokio::test]
+async fn initialize_collection_account_requires_enabled_activation() {
+    let mut env = Env::with_config().await;
+    let identity = env.validator.identity.pubkey();
+
+    let activation = RakuraiActivationAccount {
+        is_enabled: false,
+        ..env.activation_state(&identity)
+    };
+    env.set_activation(&identity, &activation);
+    let args = env.collection_account_args();
+    let result = env.initialize_collection_account(args).await;
+    assert_custom_error(result, ErrorCode::RakuraiActivationDisabled.into());
+
+    // A validator without an activation account is refused as well.
+    let stranger = env.stranger.insecure_clone();
+    let vote_account = env.validator.vote_account;
+    let args = env.collection_account_args();
+    let accounts = env.collection_account_accounts(&stranger.pubkey(), vote_account);
+    let result = env
+        .initialize_collection_account_with(args, &stranger, accounts)
+        .await;
+    assert_custom_error(result, AnchorErrorCode::AccountNotInitialized.into());
+
+    let reward_collection_account = env.reward_collection_account;
+    assert!(!env.ctx.exists(&reward_collection_account).await);
+}
+
+#[tokio::test]
+async fn initialize_collection_account_checks_activation_accounts() {
+    let mut env = Env::with_config().await;
+    let identity = env.validator.identity.insecure_clone();
+    let vote_account = env.validator.vote_account;
+    let activation_account =
+        derive_activation_account_address(&rakurai_activation::ID, &identity.pubkey()).0;
+
+    // The activation account must be owned by the rakurai_activation program.
+    let activation = env.activation_state(&identity.pubkey());
+    env.ctx
+        .set_anchor_account(&activation_account, &ID, &activation);
+    let args = env.collection_account_args();
+    let result = env.initialize_collection_account(args).await;
+    assert_custom_error(result, AnchorErrorCode::AccountOwnedByWrongProgram.into());
+    env.set_activation(&identity.pubkey(), &activation);
+
+    // The activation account must be the signer's PDA: another validator's one is refused.
+    let stranger = env.stranger.pubkey();
+    env.set_activation(&stranger, &env.activation_state(&stranger));
+    let args = env.collection_account_args();
+    let accounts = InitializeRewardCollectionAccountAccounts {
+        activation_account: derive_activation_account_address(&rakurai_activation::ID, &stranger).0,
+        ..env.collection_account_accounts(&identity.pubkey(), vote_account)
+    };
+    let result = env
+        .initialize_collection_account_with(args, &identity, accounts)
+        .await;
+    assert_custom_error(result, AnchorErrorCode::ConstraintSeeds.into());
+
+    // The activation config must be the config PDA, not a copy elsewhere.
+    let fake_config = Pubkey::new_unique();
+    let config = RakuraiActivationConfigAccount {
+        block_builder_commission_account: stranger,
+        ..env.activation_config_state()
+    };
+    env.ctx
+        .set_anchor_account(&fake_config, &rakurai_activation::ID, &config);
+    let args = env.collection_account_args();
+    let accounts = InitializeRewardCollectionAccountAccounts {
+        activation_config: fake_config,
+        ..env.collection_account_accounts(&identity.pubkey(), vote_account)
+    };
+    let result = env
+        .initialize_collection_account_with(args, &identity, accounts)
+        .await;
+    assert_custom_error(result, AnchorErrorCode::ConstraintSeeds.into());
+
+    let reward_collection_account = env.reward_collection_account;
+    assert!(!env.ctx.exists(&reward_collection_account).await);
+}
+
+#[tokio::test]
+async fn transfer_staker_rewards_splits_commissions() {
+    let mut env = Env::with_collection_account().await;
+    let identity = env.validator.identity.insecure_clone();
+    let rakurai_commission_account = env.rakurai_commission_account;
+    let reward_collection_account = env.reward_collection_account;
+
+    // (total, rakurai fee, validator fee, staker rewards); both fees round down.
+    for (total, rakurai_fee, validator_fee, staker_rewards) in [
+        (TOTAL_REWARDS, 1_000_000_000, 450_000_000, STAKER_REWARDS),
+        (10_001, 1_000, 450, 8_551),
+        (1, 0, 0, 1),
+    ] {
+        assert_eq!(rakurai_fee + validator_fee + staker_rewards, total);
+        let identity_balance = env.ctx.balance(&identity.pubkey()).await;
+        let rakurai_balance = env.ctx.balance(&rakurai_commission_account).await;
+        let collection_balance = env.ctx.balance(&reward_collection_account).await;
+
+        env.transfer_rewards(&identity, rakurai_commission_account, total)
+            .await
+            .unwrap();
+
+        // The validator's fee never leaves the identity.
+        assert_eq!(
+            env.ctx.balance(&identity.pubkey()).await,
+            identity_balance - rakurai_fee - staker_rewards
+        );
+        assert_eq!(
+            env.ctx.balance(&rakurai_commission_account).await,
+            rakurai_balance + rakurai_fee
+        );
+        assert_eq!(
+            env.ctx.balance(&reward_collection_account).await,
+            collection_balance + staker_rewards
+        );
+    }
+}
+
+#[tokio::test]
+async fn transfer_staker_rewards_errors() {
+    let mut env = Env::with_collection_account().await;
+    let identity = env.validator.identity.insecure_clone();
+    let stranger = env.stranger.insecure_clone();
+    let rakurai_commission_account = env.rakurai_commission_account;
+
+    let result = env
+        .transfer_rewards(&identity, rakurai_commission_account, 0)
+        .await;
+    assert_custom_error(result, ErrorCode::RewardsTooLow.into());
+
+    let result = env
+        .transfer_rewards(&stranger, rakurai_commission_account, TOTAL_REWARDS)
+        .await;
+    assert_custom_error(result, ErrorCode::Unauthorized.into());
+
+    let result = env
+        .transfer_rewards(&identity, stranger.pubkey(), TOTAL_REWARDS)
+        .await;
+    assert_custom_error(result, ErrorCode::InvalidRakuraiCommissionAccount.into());
+
+    // The split never overflows; the transfer itself fails for lack of funds.
+    let result = env
+        .transfer_rewards(&identity, rakurai_commission_account, u64::MAX)
+        .await;
+    assert!(result.is_err());
+
+    let rent = env.ctx.rent(RewardCollectionAccount::SIZE).await;
+    let reward_collection_account = env.reward_collection_account;
+    assert_eq!(env.ctx.balance(&reward_collection_account).await, rent);
+}
+
+#[tokio::test]
+async fn transfer_staker_rewards_by_category() {
+    let mut env = Env::with_collection_account().await;
+    let identity = env.validator.identity.insecure_clone();
+    let rakurai_commission_account = env.rakurai_commission_account;
+    let reward_collection_account = env.reward_collection_account;
+    let rakurai_balance = env.ctx.balance(&rakurai_commission_account).await;
+    let collection_balance = env.ctx.balance(&reward_collection_account).await;
+
+    let category_commissions = CategoryCommissions {
+        block_fees: None,
+        priority_fees: Some(MAX_COMMISSION_BPS - RAKURAI_COMMISSION_BPS),
+        tips: Some(0),
+    };
+    env.update_commissions(&identity, VALIDATOR_COMMISSION_BPS, category_commissions)
+        .await
+        .unwrap();
+    let rewards = CategorizedRewards {
+        block_fees: 10_000,
+        priority_fees: 20_000,
+        tips: 30_000,
+    };
+    for _ in 0..2 {
+        env.transfer_categorized_rewards(&identity, rakurai_commission_account, rewards)
+            .await
+            .unwrap();
+    }
+
+    // Rakurai takes 10% of every category; the validator 5%, 40% and 0% of what is left.
+    let buckets = RewardBuckets {
+        block_fees: RewardBucket {
+            total_rewards: 20_000,
+            staker_rewards: 17_100,
+        },
+        priority_fees: RewardBucket {
+            total_rewards: 40_000,
+            staker_rewards: 21_600,
+        },
+        tips: RewardBucket {
+            total_rewards: 60_000,
+            staker_rewards: 54_000,
+        },
+    };
+    let account = env.collection_account().await;
+    assert_eq!(account.reward_buckets, buckets);
+    assert_eq!(account.total_rewards, 120_000);
+    assert_eq!(account.block_builder_fees, 12_000);
+    assert_eq!(account.validator_fees, 15_300);
+    assert_eq!(account.staker_rewards, 92_700);
+    assert_eq!(account.num_transfers, 2);
+    assert_eq!(
+        env.ctx.balance(&rakurai_commission_account).await,
+        rakurai_balance + 12_000
+    );
+    assert_eq!(
+        env.ctx.balance(&reward_collection_account).await,
+        collection_balance + 17_100 + 21_600 + 54_000
+    );
+}
+
+#[tokio::test]
+async fn transfer_staker_rewards_rejects_empty_turn() {
+    let mut env = Env::with_collection_account().await;
+    let identity = env.validator.identity.insecure_clone();
+    let rakurai_commission_account = env.rakurai_commission_account;
+
+    let result = env
+        .transfer_categorized_rewards(
+            &identity,
+            rakurai_commission_account,
+            CategorizedRewards::default(),
+        )
+        .await;
+    assert_custom_error(result, ErrorCode::RewardsTooLow.into());
+
+    assert_eq!(
+        env.collection_account().await.reward_buckets,
+        RewardBuckets::default()
+    );
+}
+
+#[tokio::test]
+async fn transfer_staker_rewards_settles_each_turn_once() {
+    let mut env = Env::with_collection_account().await;
+    let identity = env.validator.identity.insecure_clone();
+    let rakurai_commission_account = env.rakurai_commission_account;
+    let reward_collection_account = env.reward_collection_account;
+    let rewards = CategorizedRewards {
+        block_fees: TOTAL_REWARDS,
+        ..Default::default()
+    };
+
+    env.transfer_turn(&identity, rakurai_commission_account, rewards, 100, 103)
+        .await
+        .unwrap();
+    let collection_balance = env.ctx.balance(&reward_collection_account).await;
+
+    // A retry of the same turn, an overlapping turn and an earlier turn are rejected.
+    for (start_slot, end_slot) in [(100, 103), (103, 107), (96, 99)] {
+        let result = env
+            .transfer_turn(
+                &identity,
+                rakurai_commission_account,
+                rewards,
+                start_slot,
+                end_slot,
+            )
+            .await;
+        assert_custom_error(result, ErrorCode::SlotRangeAlreadySettled.into());
+    }
+    let result = env
+        .transfer_turn(&identity, rakurai_commission_account, rewards, 108, 104)
+        .await;
+    assert_custom_error(result, ErrorCode::InvalidSlotRange.into());
+    assert_eq!(
+        env.ctx.balance(&reward_collection_account).await,
+        collection_balance
+    );
+
+    // Turns may skip slots, e.g. those of other leaders.
+    env.transfer_turn(&identity, rakurai_commission_account, rewards, 112, 115)
+        .await
+        .unwrap();
+    let account = env.collection_account().await;
+    assert_eq!(account.last_settled_slot, Some(115));
+    assert_eq!(account.num_transfers, 2);
+    assert_eq!(account.staker_rewards, 2 * STAKER_REWARDS);
+}
+
+#[tokio::test]
+async fn migrate_legacy_collection_account() {
+    let mut env = Env::with_collection_account().await;
+    let identity = env.validator.identity.insecure_clone();
+    let stranger = env.stranger.insecure_clone();
+    let rakurai_commission_account = env.rakurai_commission_account;
+    let reward_collection_account = env.reward_collection_account;
+    let bump =
+        derive_reward_collection_account_address(&ID, &env.validator.vote_account, env.epoch).1;
+
+    // A version 0 account, written field by field in the layout it was created with: the merkle
+    // root has no leaf version, and the account ends after `bump`. Its root of legacy leaves was
+    // uploaded and claimed once already.
+    let tree = MerkleTree::new(&env.entries()).unwrap();
+    let claimed = tree.tree_nodes[0].clone();
+    let mut data = RewardCollectionAccount::DISCRIMINATOR.to_vec();
+    data.extend_from_slice(env.validator.vote_account.as_ref());
+    data.extend_from_slice(env.uploader.pubkey().as_ref());
+    data.push(1);
+    data.extend_from_slice(&tree.root);
+    data.extend_from_slice(&tree.max_total_claim.to_le_bytes());
+    data.extend_from_slice(&tree.max_num_nodes.to_le_bytes());
+    data.extend_from_slice(&claimed.amount.to_le_bytes());
+    data.extend_from_slice(&1u64.to_le_bytes());
+    data.extend_from_slice(&env.epoch.to_le_bytes());
+    data.extend_from_slice(&VALIDATOR_COMMISSION_BPS.to_le_bytes());
+    data.extend_from_slice(&RAKURAI_COMMISSION_BPS.to_le_bytes());
+    data.extend_from_slice(rakurai_commission_account.as_ref());
+    data.extend_from_slice(&(env.epoch + NUM_EPOCHS_VALID).to_le_bytes());
+    data.extend_from_slice(identity.pubkey().as_ref());
+    data.push(bump);
+    assert!(data.len() <= LegacyRewardCollectionAccountV0::SIZE);
+    data.resize(LegacyRewardCollectionAccountV0::SIZE, 0);
+    env.ctx
+        .set_account_data(&reward_collection_account, &ID, data);
+
+    let result = env
+        .transfer_rewards(&identity, rakurai_commission_account, TOTAL_REWARDS)
+        .await;
+    assert_custom_error(result, AnchorErrorCode::AccountDidNotDeserialize.into());
+
+    env.migrate(&stranger).await.unwrap();
+    let rent = env.ctx.rent(RewardCollectionAccount::SIZE).await;
+    assert_eq!(env.ctx.balance(&reward_collection_account).await, rent);
+    let migrated = env.collection_account().await;
+    assert_eq!(
+        migrated.account_version,
+        RewardCollectionAccount::CURRENT_VERSION
+    );
+    assert_eq!(migrated.validator_vote_account, env.validator.vote_account);
+    assert_eq!(migrated.merkle_root_upload_authority, env.uploader.pubkey());
+    let merkle_root = migrated.merkle_root.clone().unwrap();
+    assert_eq!(merkle_root.root, tree.root);
+    assert_eq!(merkle_root.max_total_claim, tree.max_total_claim);
+    assert_eq!(merkle_root.max_num_nodes, tree.max_num_nodes);
+    assert_eq!(merkle_root.total_funds_claimed, claimed.amount);
+    assert_eq!(merkle_root.num_nodes_claimed, 1);
+    assert_eq!(migrated.creation_epoch, env.epoch);
+    assert_eq!(migrated.validator_commission_bps, VALIDATOR_COMMISSION_BPS);
+    assert_eq!(migrated.rakurai_commission_bps, RAKURAI_COMMISSION_BPS);
+    assert_eq!(
+        migrated.rakurai_commission_account,
+        rakurai_commission_account
+    );
+    assert_eq!(migrated.expires_at, env.epoch + NUM_EPOCHS_VALID);
+    assert_eq!(migrated.initializer, identity.pubkey());
+    assert_eq!(migrated.bump, bump);
+    assert_eq!(migrated.leaf_version, LEAF_VERSION_V0);
+    assert_eq!(migrated.reward_buckets, RewardBuckets::default());
+    assert_eq!(migrated.num_transfers, 0);
+    assert_eq!(migrated.last_settled_slot, None);
+    assert_eq!(migrated.claim_scale, None);
+    assert!(!migrated.rollover_unclaimed);
+    assert_eq!(migrated.deposited_rewards, 0);
+    assert_eq!(
+        migrated.category_commissions,
+        CategoryCommissions::default()
+    );
+
+    let result = env.migrate(&stranger).await;
+    assert_custom_error(result, ErrorCode::AccountAlreadyMigrated.into());
+
+    env.transfer_rewards(&identity, rakurai_commission_account, TOTAL_REWARDS)
+        .await
+        .unwrap();
+    let account = env.collection_account().await;
+    assert_eq!(account.total_rewards, TOTAL_REWARDS);
+    assert_eq!(account.staker_rewards, STAKER_REWARDS);
+    assert_eq!(account.num_transfers, 1);
+
+    // The legacy root still verifies its leaves.
+    let node = tree.tree_nodes[1].clone();
+    env.claim(&node).await.unwrap();
+    assert_eq!(env.ctx.balance(&node.claimant).await, node.amount);
+}
+
+#[tokio::test]
+async fn deposit_rewards_by_anyone_without_commissions() {
+    let mut env = Env::with_rewards().await;
+    let stranger = env.stranger.insecure_clone();
+    let rakurai_commission_account = env.rakurai_commission_account;
+    let reward_collection_account = env.reward_collection_account;
+    let stranger_balance = env.ctx.balance(&stranger.pubkey()).await;
+    let rakurai_balance = env.ctx.balance(&rakurai_commission_account).await;
+    let collection_balance = env.ctx.balance(&reward_collection_account).await;
+
+    let logs = env
+        .deposit_rewards(&stranger, 1_000_000_000, DepositSource::Tips)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        env.ctx.balance(&stranger.pubkey()).await,
+        stranger_balance - 1_000_000_000
+    );
+    assert_eq!(
+        env.ctx.balance(&rakurai_commission_account).await,
+        rakurai_balance
+    );
+    assert_eq!(
+        env.ctx.balance(&reward_collection_account).await,
+        collection_balance + 1_000_000_000
+    );
+    let account = env.collection_account().await;
+    assert_eq!(account.deposited_rewards, 1_000_000_000);
+    assert_eq!(account.total_rewards, TOTAL_REWARDS);
+
+    // The event tells the off-chain distribution where the deposit came from.
+    let events = events::<RewardsDepositedEvent>(&logs);
+    assert_eq!(events.len(), 1);
+    let event = &events[0];
+    assert_eq!(event.reward_collection_account, reward_collection_account);
+    assert_eq!(event.depositor, stranger.pubkey());
+    assert_eq!(event.amount, 1_000_000_000);
+    assert_eq!(event.source, DepositSource::Tips);
+}
+
+#[tokio::test]
+async fn deposit_rewards_errors() {
+    let mut env = Env::with_collection_account().await;
+    let stranger = env.stranger.insecure_clone();
+
+    let result = env
+        .deposit_rewards(&stranger, 0, DepositSource::Other)
+        .await;
+    assert_custom_error(result, ErrorCode::RewardsTooLow.into());
+
+    env.ctx.warp_to_epoch(env.epoch + 1).await;
+    let tree = env.tree();
+    env.upload_tree(&tree).await.unwrap();
+    let result = env
+        .deposit_rewards(&stranger, 1, DepositSource::Other)
+        .await;
+    assert_custom_error(result, ErrorCode::MerkleRootAlreadyUploaded.into());
+
+    env.warp_past_expiry().await;
+    let result = env
+        .deposit_rewards(&stranger, 1, DepositSource::Other)
+        .await;
+    assert_custom_error(result, ErrorCode::ExpiredRewardCollectionAccount.into());
+}
+
+#[tokio::test]
+async fn upload_merkle_root_within_validity_window() {
+    let mut env = Env::with_rewards().await;
+    let uploader = env.uploader.insecure_clone();
+    let stranger = env.stranger.insecure_clone();
+    let tree = env.tree();
+
+    // Not before the creation epoch has ended.
+    let result = env.upload_tree(&tree).await;
+    assert_custom_error(result, ErrorCode::PrematureMerkleRootUpload.into());
+
+    env.ctx.warp_to_epoch(env.epoch + 1).await;
+    let result = env
+        .upload(
+            &stranger,
+            tree.root,
+            tree.max_total_claim,
+            tree.max_num_nodes,
+        )
+        .await;
+    assert_custom_error(result, ErrorCode::Unauthorized.into());
+
+    env.upload(&uploader, [1; 32], 1, 1).await.unwrap();
+    // Until the first claim a new root replaces the old one.
+    env.upload_tree(&tree).await.unwrap();
+
+    let merkle_root = env.collection_account().await.merkle_root.unwrap();
+    assert_eq!(merkle_root.root, tree.root);
+    assert_eq!(merkle_root.max_total_claim, 6_000_000_000);
+    assert_eq!(merkle_root.max_num_nodes, 3);
+    assert_eq!(merkle_root.total_funds_claimed, 0);
+    assert_eq!(merkle_root.num_nodes_claimed, 0);
+
+    // The last epoch of the window still accepts uploads.
+    env.ctx.warp_to_epoch(env.epoch + NUM_EPOCHS_VALID).await;
+    env.upload_tree(&tree).await.unwrap();
+
+    env.warp_past_expiry().await;
+    let result = env.upload_tree(&tree).await;
+    assert_custom_error(result, ErrorCode::ExpiredRewardCollectionAccount.into());
+}
+
+#[tokio::test]
+async fn update_upload_authority_before_upload() {
+    let mut env = Env::with_rewards().await;
+    let identity = env.validator.identity.insecure_clone();
+    let uploader = env.uploader.insecure_clone();
+    let new_uploader = env.stranger.insecure_clone();
+    let tree = env.tree();
+
+    env.update_upload_authority(&identity, new_uploader.pubkey())
+        .await
+        .unwrap();
+    assert_eq!(
+        env.collection_account().await.merkle_root_upload_authority,
+        new_uploader.pubkey()
+    );
+
+    env.ctx.warp_to_epoch(env.epoch + 1).await;
+    let result = env
+        .upload(
+            &uploader,
+            tree.root,
+            tree.max_total_claim,
+            tree.max_num_nodes,
+        )
+        .await;
+    assert_custom_error(result, ErrorCode::Unauthorized.into());
+    env.upload(
+        &new_uploader,
+        tree.root,
+        tree.max_total_claim,
+        tree.max_num_nodes,
+    )
+    .await
+    .unwrap();
+
+    // Frozen once a root is uploaded.
+    let result = env
+        .update_upload_authority(&identity, uploader.pubkey())
+        .await;
+    assert_custom_error(result, ErrorCode::MerkleRootAlreadyUploaded.into());
+}
+
+#[tokio::test]
+async fn update_upload_authority_errors() {
+    let mut env = Env::with_collection_account().await;
+    let identity = env.validator.identity.insecure_clone();
+    let uploader = env.uploader.insecure_clone();
+
+    // Only the validator identity, not the current upload authority, can rotate it.
+    let result = env
+        .update_upload_authority(&uploader, Pubkey::new_unique())
+        .await;
+    assert_custom_error(result, ErrorCode::Unauthorized.into());
+
+    let result = env
+        .update_upload_authority(&identity, Pubkey::default())
+        .await;
+    assert_custom_error(result, ErrorCode::AccountValidationFailure.into());
+
+    assert_eq!(
+        env.collection_account().await.merkle_root_upload_authority,
+        uploader.pubkey()
+    );
+}
+
+#[tokio::test]
+async fn attested_root_opens_claims_at_quorum() {
+    let mut env = Env::with_rewards().await;
+    let identity = env.validator.identity.insecure_clone();
+    let attesters = [Keypair::new(), Keypair::new(), Keypair::new()];
+    let attester_keys: Vec<_> = attesters.iter().map(|attester| attester.pubkey()).collect();
+    env.initialize_root_attestation(&identity, attester_keys.clone(), 2)
+        .await
+        .unwrap();
+    let root_attestation = env.root_attestation();
+    assert_eq!(
+        env.collection_account().await.merkle_root_upload_authority,
+        root_attestation
+    );
+    let state: RootAttestation = env.ctx.get_account(&root_attestation).await;
+    assert_eq!(state.attesters, attester_keys);
+    assert_eq!(state.threshold, 2);
+
+    // Submissions are refused until the epoch is over, the same as uploads.
+    let tree = env.tree();
+    let result = env.attest_tree(&attesters[0], &tree).await;
+    assert_custom_error(result, ErrorCode::PrematureMerkleRootUpload.into());
+    env.ctx.warp_to_epoch(env.epoch + 1).await;
+
+    // The single upload authority is replaced.
+    let uploader = env.uploader.insecure_clone();
+    let result = env
+        .upload(
+            &uploader,
+            tree.root,
+            tree.max_total_claim,
+            tree.max_num_nodes,
+        )
+        .await;
+    assert_custom_error(result, ErrorCode::Unauthorized.into());
+    let stranger = env.stranger.insecure_clone();
+    let result = env.attest_tree(&stranger, &tree).await;
+    assert_custom_error(result, ErrorCode::Unauthorized.into());
+
+    // Below the threshold, or with the attesters disagreeing, claims stay closed.
+    let node = tree.tree_nodes[0].clone();
+    env.attest_tree(&attesters[0], &tree).await.unwrap();
+    let other_tree = MerkleTree::new_bound(&env.entries()[1..], env.leaf_domain()).unwrap();
+    env.attest_tree(&attesters[1], &other_tree).await.unwrap();
+    assert!(env.collection_account().await.merkle_root.is_none());
+    let result = env.claim(&node).await;
+    assert_custom_error(result, ErrorCode::RootNotUploaded.into());
+    let state: RootAttestation = env.ctx.get_account(&root_attestation).await;
+    assert_eq!(
+        state.submissions[..2]
+            .iter()
+            .map(|submission| submission.unwrap().root)
+            .collect::<Vec<_>>(),
+        [tree.root, other_tree.root]
+    );
+
+    // An attester revising their submission to match reaches the quorum.
+    env.attest_tree(&attesters[1], &tree).await.unwrap();
+    let merkle_root = env.collection_account().await.merkle_root.unwrap();
+    assert_eq!(merkle_root.root, tree.root);
+    assert_eq!(merkle_root.max_total_claim, tree.max_total_claim);
+    assert_eq!(merkle_root.max_num_nodes, tree.max_num_nodes);
+    env.claim(&node).await.unwrap();
+    assert_eq!(env.ctx.balance(&node.claimant).await, node.amount);
+
+    // The attested root is final.
+    let result = env.attest_tree(&attesters[2], &other_tree).await;
+    assert_custom_error(result, ErrorCode::MerkleRootAlreadyUploaded.into());
+}
+
+#[tokio::test]
+async fn root_attestation_errors() {
+    let mut env = Env::with_rewards().await;
+    let identity = env.validator.identity.insecure_clone();
+    let stranger = env.stranger.insecure_clone();
+    let attesters = [Keypair::new(), Keypair::new()];
+    let attester_keys: Vec<_> = attesters.iter().map(|attester| attester.pubkey()).collect();
+
+    let result = env
+        .initialize_root_attestation(&stranger, attester_keys.clone(), 1)
+        .await;
+    assert_custom_error(result, ErrorCode::Unauthorized.into());
+    for (attesters, threshold) in [
+        (attester_keys.clone(), 0),
+        (attester_keys.clone(), 3),
+        (vec![attester_keys[0]; 2], 1),
+        (
+            (0..=RootAttestation::MAX_ATTESTERS)
+                .map(|_| Pubkey::new_unique())
+                .collect(),
+            1,
+        ),
+    ] {
+        let result = env
+            .initialize_root_attestation(&identity, attesters, threshold)
+            .await;
+        assert_custom_error(result, ErrorCode::InvalidAttesters.into());
+    }
+
+    // The validator identity can not take the upload back from the attesters ...
+    env.initialize_root_attestation(&identity, attester_keys, 1)
+        .await
+        .unwrap();
+    let root_attestation = env.root_attestation();
+    env.ctx.warp_to_epoch(env.epoch + 1).await;
+    let uploader = env.uploader.insecure_clone();
+    let result = env
+        .update_upload_authority(&identity, uploader.pubkey())
+        .await;
+    assert_custom_error(result, ErrorCode::RootAttestationInUse.into());
+    assert_eq!(
+        env.collection_account().await.merkle_root_upload_authority,
+        root_attestation
+    );
+
+    // ... so the root is only uploaded through their quorum.
+    let tree = env.tree();
+    let result = env
+        .upload(
+            &uploader,
+            tree.root,
+            tree.max_total_claim,
+            tree.max_num_nodes,
+        )
+        .await;
+    assert_custom_error(result, ErrorCode::Unauthorized.into());
+    env.attest_tree(&attesters[0], &tree).await.unwrap();
+    assert_eq!(
+        env.collection_account().await.merkle_root.unwrap().root,
+        tree.root
+    );
+}
+
+#[tokio::test]
+async fn close_root_attestation_after_expiry() {
+    let mut env = Env::with_rewards().await;
+    let identity = env.validator.identity.insecure_clone();
+    let stranger = env.stranger.insecure_clone();
+    let attesters = [Keypair::new(), Keypair::new()];
+    let attester_keys: Vec<_> = attesters.iter().map(|attester| attester.pubkey()).collect();
+    env.initialize_root_attestation(&identity, attester_keys, 2)
+        .await
+        .unwrap();
+    let root_attestation = env.root_attestation();
+
+    let result = env.close_root_attestation(identity.pubkey()).await;
+    assert_custom_error(result, ErrorCode::PrematureCloseRootAttestation.into());
+
+    // Past expiry anyone can close it, but the rent only goes back to the payer.
+    env.warp_past_expiry().await;
+    let result = env.close_root_attestation(stranger.pubkey()).await;
+    assert_custom_error(result, AnchorErrorCode::ConstraintRaw.into());
+
+    let balance = env.ctx.balance(&root_attestation).await;
+    assert_eq!(balance, env.ctx.rent(RootAttestation::size(2)).await);
+    let identity_balance = env.ctx.balance(&identity.pubkey()).await;
+    env.close_root_attestation(identity.pubkey()).await.unwrap();
+    assert!(!env.ctx.exists(&root_attestation).await);
+    assert_eq!(
+        env.ctx.balance(&identity.pubkey()).await,
+        identity_balance + balance
+    );
+}
+
+#[tokio::test]
+async fn update_commission_before_upload() {
+    let mut env = Env::with_collection_account().await;
+    let identity = env.validator.identity.insecure_clone();
+    let rakurai_commission_account = env.rakurai_commission_account;
+    let reward_collection_account = env.reward_collection_account;
+
+    // Up to the config's maximum, together with Rakurai's commission.
+    env.update_commission(&identity, MAX_COMMISSION_BPS - RAKURAI_COMMISSION_BPS)
+        .await
+        .unwrap();
+    assert_eq!(
+        env.collection_account().await.validator_commission_bps,
+        4_000
+    );
+
+    // Later transfers use the new commission: 10% to Rakurai, 40% of the rest to the validator.
+    let rent = env.ctx.rent(RewardCollectionAccount::SIZE).await;
+    env.transfer_rewards(&identity, rakurai_commission_account, TOTAL_REWARDS)
+        .await
+        .unwrap();
+    assert_eq!(
+        env.ctx.balance(&reward_collection_account).await,
+        rent + 5_400_000_000
+    );
+
+    // Category commissions are stored alongside, and reported with the old values.
+    let category_commissions = CategoryCommissions {
+        tips: Some(1_000),
+        ..Default::default()
+    };
+    let logs = env
+        .update_commissions(&identity, 0, category_commissions)
+        .await
+        .unwrap();
+    let account = env.collection_account().await;
+    assert_eq!(account.validator_commission_bps, 0);
+    assert_eq!(account.category_commissions, category_commissions);
+    let events = events::<ValidatorCommissionBpsUpdatedEvent>(&logs);
+    assert_eq!(events.len(), 1);
+    assert_eq!(
+        events[0].reward_collection_account,
+        reward_collection_account
+    );
+    assert_eq!(events[0].old_commission_bps, 4_000);
+    assert_eq!(events[0].new_commission_bps, 0);
+    assert_eq!(
+        events[0].old_category_commissions,
+        CategoryCommissions::default()
+    );
+    assert_eq!(events[0].new_category_commissions, category_commissions);
+
+    env.ctx.warp_to_epoch(env.epoch + 1).await;
+    let tree = env.tree();
+    env.upload_tree(&tree).await.unwrap();
+    let result = env.update_commission(&identity, 100).await;
+    assert_custom_error(result, ErrorCode::MerkleRootAlreadyUploaded.into());
+}
+
+#[tokio::test]
+async fn update_commission_errors() {
+    let mut env = Env::with_collection_account().await;
+    let identity = env.validator.identity.insecure_clone();
+    let stranger = env.stranger.insecure_clone();
+
+    let result = env.update_commission(&stranger, 100).await;
+    assert_custom_error(result, ErrorCode::Unauthorized.into());
+
+    for validator_commission_bps in [MAX_COMMISSION_BPS - RAKURAI_COMMISSION_BPS + 1, 10_000] {
+        let result = env
+            .update_commission(&identity, validator_commission_bps)
+            .await;
+        assert_custom_error(result, ErrorCode::MaxCommissionFeeBpsExceeded.into());
+
+        // Each category commission is held to the same maximum.
+        let category_commissions = CategoryCommissions {
+            priority_fees: Some(validator_commission_bps),
+            ..Default::default()
+        };
+        let result = env
+            .update_commissions(&identity, VALIDATOR_COMMISSION_BPS, category_commissions)
+            .await;
+        assert_custom_error(result, ErrorCode::MaxCommissionFeeBpsExceeded.into());
+    }
+
+    let account = env.collection_account().await;
+    assert_eq!(account.validator_commission_bps, VALIDATOR_COMMISSION_BPS);
+    assert_eq!(account.category_commissions, CategoryCommissions::default());
+}
+
+#[tokio::test]
+async fn upload_rejects_unknown_leaf_version() {
+    let mut env = Env::with_rewards().await;
+    let uploader = env.uploader.insecure_clone();
+    let tree = env.tree();
+    env.ctx.warp_to_epoch(env.epoch + 1).await;
+
+    let result = env
+        .upload_version(
+            &uploader,
+            tree.root,
+            tree.max_total_claim,
+            tree.max_num_nodes,
+            LEAF_VERSION_V1 + 1,
+        )
+        .await;
+    assert_custom_error(result, ErrorCode::UnsupportedLeafVersion.into());
+
+    env.upload_tree(&tree).await.unwrap();
+    assert_eq!(env.collection_account().await.leaf_version, LEAF_VERSION_V1);
+}
+
+#[tokio::test]
+async fn claim_legacy_leaves() {
+    let mut env = Env::with_rewards().await;
+    let entries = env.entries();
+    env.ctx.warp_to_epoch(env.epoch + 1).await;
+
+    let tree = MerkleTree::new(&entries).unwrap();
+    assert_eq!(tree.version(), LEAF_VERSION_V0);
+    env.upload_tree(&tree).await.unwrap();
+    let node = tree.tree_nodes[0].clone();
+    env.claim(&node).await.unwrap();
+    assert_eq!(env.ctx.balance(&node.claimant).await, node.amount);
+
+    // Bound proofs do not verify against a version 0 root.
+    let bound_node = env.tree().tree_nodes[1].clone();
+    let result = env.claim(&bound_node).await;
+    assert_custom_error(result, ErrorCode::InvalidProof.into());
+}
+
+#[tokio::test]
+async fn claim_rejects_leaves_bound_elsewhere() {
+    let mut env = Env::with_rewards().await;
+    let entries = env.entries();
+    env.ctx.warp_to_epoch(env.epoch + 1).await;
+
+    // Trees bound to another collection account or another epoch, uploaded as version 1, and a
+    // legacy tree uploaded as version 1, all leave the claimants unable to prove their leaves.
+    let other_account = LeafDomain {
+        reward_collection_account: Pubkey::new_unique(),
+        ..env.leaf_domain()
+    };
+    let other_epoch = LeafDomain {
+        creation_epoch: env.epoch + 1,
+        ..env.leaf_domain()
+    };
+    for tree in [
+        MerkleTree::new_bound(&entries, other_account).unwrap(),
+        MerkleTree::new_bound(&entries, other_epoch).unwrap(),
+        MerkleTree::new(&entries).unwrap(),
+    ] {
+        let uploader = env.uploader.insecure_clone();
+        env.upload(
+            &uploader,
+            tree.root,
+            tree.max_total_claim,
+            tree.max_num_nodes,
+        )
+        .await
+        .unwrap();
+        let result = env.claim(&tree.tree_nodes[0]).await;
+        assert_custom_error(result, ErrorCode::InvalidProof.into());
+    }
+}
+
+#[tokio::test]
+async fn claim_before_upload_fails() {
+    let mut env = Env::with_rewards().await;
+    let tree = env.tree();
+    let node = tree.tree_nodes[0].clone();
+
+    let result = env.claim(&node).await;
+    assert_custom_error(result, ErrorCode::RootNotUploaded.into());
+    let result = env.claim_nodes(&[node]).await;
+    assert_custom_error(result, ErrorCode::RootNotUploaded.into());
+}
+
+#[tokio::test]
+async fn upload_after_claim_fails() {
+    let (mut env, tree) = Env::with_root(false).await;
+    let node = tree.tree_nodes[0].clone();
+
+    env.claim(&node).await.unwrap();
+    let result = env.upload_tree(&tree).await;
+    assert_custom_error(result, ErrorCode::Unauthorized.into());
+}
+
+#[tokio::test]
+async fn claim_pays_claimant() {
+    let (mut env, tree) = Env::with_root(false).await;
+    let reward_collection_account = env.reward_collection_account;
+    let claim_payer = env.claim_payer.pubkey();
+    let claim_status_rent = env.ctx.rent(ClaimStatus::SIZE).await;
+    let mut claimed = 0;
+    for (i, node) in tree.tree_nodes.iter().enumerate() {
+        let collection_balance = env.ctx.balance(&reward_collection_account).await;
+        let payer_balance = env.ctx.balance(&claim_payer).await;
+        env.claim(node).await.unwrap();
+        claimed += node.amount;
+
+        assert_eq!(env.ctx.balance(&node.claimant).await, node.amount);
+        assert_eq!(
+            env.ctx.balance(&reward_collection_account).await,
+            collection_balance - node.amount
+        );
+        assert_eq!(
+            env.ctx.balance(&claim_payer).await,
+            payer_balance - claim_status_rent
+        );
+
+        let claim_status_address = env.claim_status(&node.claimant);
+        assert_eq!(
+            env.ctx.balance(&claim_status_address).await,
+            claim_status_rent
+        );
+        let claim_status: ClaimStatus = env.ctx.get_account(&claim_status_address).await;
+        assert!(claim_status.is_claimed);
+        assert_eq!(claim_status.claimant, node.claimant);
+        assert_eq!(claim_status.claim_status_payer, env.claim_payer.pubkey());
+        assert_eq!(claim_status.amount, node.amount);
+        assert_eq!(claim_status.expires_at, env.epoch + NUM_EPOCHS_VALID);
+
+        let merkle_root = env.collection_account().await.merkle_root.unwrap();
+        assert_eq!(merkle_root.total_funds_claimed, claimed);
+        assert_eq!(merkle_root.num_nodes_claimed, i as u64 + 1);
+    }
+
+    let rent = env.ctx.rent(RewardCollectionAccount::SIZE).await;
+    assert_eq!(
+        env.ctx.balance(&reward_collection_account).await,
+        rent + STAKER_REWARDS - claimed
+    );
+}
+
+#[tokio::test]
+async fn claim_errors() {
+    let (mut env, tree) = Env::with_root(false).await;
+    let node = tree.tree_nodes[0].clone();
+
+    let result = env.claim_amount(&node, node.amount + 1).await;
+    assert_custom_error(result, ErrorCode::InvalidProof.into());
+
+    let mut wrong_claimant = node.clone();
+    wrong_claimant.claimant = Pubkey::new_unique();
+    let result = env.claim(&wrong_claimant).await;
+    assert_custom_error(result, ErrorCode::InvalidProof.into());
+
+    // The ClaimStatus account already exists for a second claim.
+    env.claim(&node).await.unwrap();
+    assert!(env.claim(&node).await.is_err());
+    assert_eq!(env.ctx.balance(&node.claimant).await, node.amount);
+}
+
+#[tokio::test]
+async fn claim_more_than_collected_fails() {
+    let mut env = Env::with_rewards().await;
+    let reward_collection_account = env.reward_collection_account;
+    let balance = env.ctx.balance(&reward_collection_account).await;
+    let tree =
+        MerkleTree::new_bound(&[(env.claimants[0], balance + 1)], env.leaf_domain()).unwrap();
+    env.ctx.warp_to_epoch(env.epoch + 1).await;
+    env.upload_tree(&tree).await.unwrap();
+
+    let result = env.claim(&tree.tree_nodes[0]).await;
+    assert_custom_error(result, ErrorCode::ArithmeticError.into());
+    assert_eq!(env.ctx.balance(&reward_collection_account).await, balance);
+}
+
+#[tokio::test]
+async fn claim_pro_rata_when_underfunded() {
+    let mut env = Env::with_rewards().await;
+    let reward_collection_account = env.reward_collection_account;
+    env.ctx.warp_to_epoch(env.epoch + 1).await;
+
+    // A funded account pays claims in full, even in pro-rata mode.
+    let tree = env.tree();
+    env.upload_tree_pro_rata(&tree).await.unwrap();
+    assert_eq!(env.collection_account().await.claim_scale, None);
+
+    // Claims of twice the staker rewards are all halved.
+    let entries: Vec<_> = env
+        .claimants
+        .iter()
+        .zip([STAKER_REWARDS, STAKER_REWARDS * 2 / 3, STAKER_REWARDS / 3])
+        .map(|(claimant, amount)| (*claimant, amount))
+        .collect();
+    let tree = MerkleTree::new_bound(&entries, env.leaf_domain()).unwrap();
+    env.upload_tree(&tree).await.unwrap();
+    assert_eq!(env.collection_account().await.claim_scale, None);
+    env.upload_tree_pro_rata(&tree).await.unwrap();
+    assert_eq!(
+        env.collection_account().await.claim_scale,
+        Some(ClaimScale {
+            available: STAKER_REWARDS,
+            outstanding: 2 * STAKER_REWARDS,
+        })
+    );
+
+    for node in &tree.tree_nodes {
+        env.claim(node).await.unwrap();
+        assert_eq!(env.ctx.balance(&node.claimant).await, node.amount / 2);
+        let claim_status_address = env.claim_status(&node.claimant);
+        let claim_status: ClaimStatus = env.ctx.get_account(&claim_status_address).await;
+        assert_eq!(claim_status.amount, node.amount / 2);
+    }
+
+    // Even the last claimant is paid, and nothing is left behind.
+    let rent = env.ctx.rent(RewardCollectionAccount::SIZE).await;
+    assert_eq!(env.ctx.balance(&reward_collection_account).await, rent);
+    let merkle_root = env.collection_account().await.merkle_root.unwrap();
+    assert_eq!(merkle_root.total_funds_claimed, tree.max_total_claim);
+    assert!(merkle_root.is_fully_claimed());
+}
+
+#[tokio::test]
+async fn claim_enforces_root_limits() {
+    let (mut env, tree) = Env::with_root(false).await;
+    let uploader = env.uploader.insecure_clone();
+    let nodes = tree.tree_nodes.clone();
+
+    // Leaves are paid up to max_total_claim.
+    env.upload(
+        &uploader,
+        tree.root,
+        nodes[1].amount - 1,
+        tree.max_num_nodes,
+    )
+    .await
+    .unwrap();
+    let result = env.claim(&nodes[1]).await;
+    assert_custom_error(result, ErrorCode::ExceedsMaxClaim.into());
+    assert_eq!(env.ctx.balance(&nodes[1].claimant).await, 0);
+
+    // ... and to max_num_nodes.
+    env.upload(&uploader, tree.root, tree.max_total_claim, 1)
+        .await
+        .unwrap();
+    env.claim(&nodes[2]).await.unwrap();
+    let result = env.claim(&nodes[1]).await;
+    assert_custom_error(result, ErrorCode::ExceedsMaxNumNodes.into());
+    let result = env.claim_nodes(&nodes[..2]).await;
+    assert_custom_error(result, ErrorCode::ExceedsMaxNumNodes.into());
+}
+
+#[tokio::test]
+async fn claim_until_expiry() {
+    let (mut env, tree) = Env::with_root(false).await;
+    let nodes = tree.tree_nodes.clone();
+
+    // The expiry epoch itself still accepts claims.
+    env.ctx.warp_to_epoch(env.epoch + NUM_EPOCHS_VALID).await;
+    env.claim(&nodes[0]).await.unwrap();
+
+    env.warp_past_expiry().await;
+    let result = env.claim(&nodes[1]).await;
+    assert_custom_error(result, ErrorCode::ExpiredRewardCollectionAccount.into());
+    let result = env.claim_nodes(&nodes[1..]).await;
+    assert_custom_error(result, ErrorCode::ExpiredRewardCollectionAccount.into());
+}
+
+#[tokio::test]
+async fn claim_to_stake_account_delegates_to_validator() {
+    let mut env = Env::with_rewards().await;
+    env.ctx.warp_to_epoch(env.epoch + 1).await;
+    let stake_rent = env.ctx.rent(StakeStateV2::size_of()).await;
+    let minimum_delegation = env.minimum_delegation().await;
+    let claimants = [Keypair::new(), Keypair::new()];
+    let entries = [
+        (claimants[0].pubkey(), stake_rent + minimum_delegation),
+        (claimants[1].pubkey(), stake_rent + minimum_delegation - 1),
+    ];
+    let tree = MerkleTree::new_bound(&entries, env.leaf_domain()).unwrap();
+    env.upload_tree(&tree).await.unwrap();
+    let nodes = tree.tree_nodes.clone();
+
+    // The claim must be signed by its claimant.
+    let stake_account = Keypair::new();
+    let result = env
+        .claim_to_stake_account(&nodes[0], &claimants[1], &stake_account)
+        .await;
+    assert_custom_error(result, AnchorErrorCode::ConstraintSeeds.into());
+
+    env.claim_to_stake_account(&nodes[0], &claimants[0], &stake_account)
+        .await
+        .unwrap();
+    let account = env.ctx.get_raw_account(&stake_account.pubkey()).await;
+    assert_eq!(account.owner, stake::program::ID);
+    assert_eq!(account.lamports, nodes[0].amount);
+    let StakeStateV2::Stake(meta, stake, _) = account.deserialize_data().unwrap() else {
+        panic!("stake account not delegated");
+    };
+    assert_eq!(meta.authorized, Authorized::auto(&claimants[0].pubkey()));
+    assert_eq!(meta.rent_exempt_reserve, stake_rent);
+    assert_eq!(stake.delegation.voter_pubkey, env.validator.vote_account);
+    assert_eq!(stake.delegation.stake, nodes[0].amount - stake_rent);
+    let claim_status_address = env.claim_status(&nodes[0].claimant);
+    let claim_status: ClaimStatus = env.ctx.get_account(&claim_status_address).await;
+    assert_eq!(claim_status.amount, nodes[0].amount);
+    let merkle_root = env.collection_account().await.merkle_root.unwrap();
+    assert_eq!(merkle_root.total_funds_claimed, nodes[0].amount);
+
+    // A claim that leaves less than the minimum delegation after the rent-exempt reserve can
+    // not be staked.
+    let result = env
+        .claim_to_stake_account(&nodes[1], &claimants[1], &Keypair::new())
+        .await;
+    assert_custom_error(result, ErrorCode::ClaimBelowStakeReserve.into());
+}
+
+#[tokio::test]
+async fn claim_to_stake_pool_mints_pool_tokens() {
+    let mut env = Env::with_rewards().await;
+    let reward_collection_account = env.reward_collection_account;
+    env.ctx.warp_to_epoch(env.epoch + 1).await;
+    let claimants = [Keypair::new(), Keypair::new()];
+    let entries = [
+        (claimants[0].pubkey(), 3_000_000_000),
+        (claimants[1].pubkey(), 2_000_000_000),
+    ];
+    let tree = MerkleTree::new_bound(&entries, env.leaf_domain()).unwrap();
+    env.upload_tree(&tree).await.unwrap();
+    let nodes = tree.tree_nodes.clone();
+    let pool = env.create_stake_pool(&claimants[0].pubkey()).await;
+    let collection_balance = env.ctx.balance(&reward_collection_account).await;
+    let reserve_balance = env.ctx.balance(&pool.reserve_stake).await;
+
+    // Pool tokens go to a token account of the claimant.
+    let result = env
+        .claim_to_stake_pool(&nodes[1], &claimants[1], &pool, pool.pool_tokens_to)
+        .await;
+    assert_custom_error(result, ErrorCode::InvalidPoolTokenAccount.into());
+    let result = env
+        .claim_to_stake_pool(&nodes[0], &claimants[0], &pool, pool.manager_fee_account)
+        .await;
+    assert_custom_error(result, ErrorCode::InvalidPoolTokenAccount.into());
+
+    env.claim_to_stake_pool(&nodes[0], &claimants[0], &pool, pool.pool_tokens_to)
+        .await
+        .unwrap();
+    let account = env.ctx.get_raw_account(&pool.pool_tokens_to).await;
+    let pool_tokens = TokenAccount::unpack(&account.data).unwrap();
+    // The first deposit into an empty pool mints one token per lamport.
+    assert_eq!(pool_tokens.amount, nodes[0].amount);
+    assert_eq!(
+        env.ctx.balance(&pool.reserve_stake).await,
+        reserve_balance + nodes[0].amount
+    );
+    assert_eq!(
+        env.ctx.balance(&reward_collection_account).await,
+        collection_balance - nodes[0].amount
+    );
+    let deposit_account = derive_stake_pool_deposit_address(&ID, &reward_collection_account).0;
+    assert!(!env.ctx.exists(&deposit_account).await);
+}
+
+#[tokio::test]
+async fn claim_batch_pays_claimants() {
+    let (mut env, tree) = Env::with_root(false).await;
+    let reward_collection_account = env.reward_collection_account;
+    let collection_balance = env.ctx.balance(&reward_collection_account).await;
+
+    env.claim_nodes(&tree.tree_nodes).await.unwrap();
+
+    for node in &tree.tree_nodes {
+        assert_eq!(env.ctx.balance(&node.claimant).await, node.amount);
+        let claim_status_address = env.claim_status(&node.claimant);
+        let claim_status: ClaimStatus = env.ctx.get_account(&claim_status_address).await;
+        assert!(claim_status.is_claimed);
+        assert_eq!(claim_status.claimant, node.claimant);
+        assert_eq!(claim_status.claim_status_payer, env.claim_payer.pubkey());
+        assert_eq!(claim_status.amount, node.amount);
+        assert_eq!(claim_status.expires_at, env.epoch + NUM_EPOCHS_VALID);
+        assert_eq!(
+            claim_status.bump,
+            derive_claim_status_account_address(&ID, &node.claimant, &reward_collection_account).1
+        );
+    }
+
+    assert_eq!(
+        env.ctx.balance(&reward_collection_account).await,
+        collection_balance - tree.max_total_claim
+    );
+    let merkle_root = env.collection_account().await.merkle_root.unwrap();
+    assert_eq!(merkle_root.total_funds_claimed, tree.max_total_claim);
+    assert_eq!(merkle_root.num_nodes_claimed, 3);
+}
+
+#[tokio::test]
+async fn claim_batch_errors() {
+    let (mut env, tree) = Env::with_root(false).await;
+    let nodes = tree.tree_nodes.clone();
+    let batch_claim = |node: &TreeNode| BatchClaim {
+        amount: node.amount,
+        proof: node.proof.clone(),
+    };
+
+    let result = env.claim_batch(vec![], vec![]).await;
+    assert_custom_error(result, ErrorCode::InvalidRemainingAccounts.into());
+
+    let claimants = vec![(nodes[0].claimant, env.claim_status(&nodes[0].claimant))];
+    let result = env
+        .claim_batch(
+            vec![batch_claim(&nodes[0]), batch_claim(&nodes[1])],
+            claimants,
+        )
+        .await;
+    assert_custom_error(result, ErrorCode::InvalidRemainingAccounts.into());
+
+    let claimants = vec![(nodes[0].claimant, env.claim_status(&nodes[1].claimant))];
+    let result = env
+        .claim_batch(vec![batch_claim(&nodes[0])], claimants)
+        .await;
+    assert_custom_error(result, ErrorCode::InvalidClaimStatusAccount.into());
+
+    let mut wrong_amount = batch_claim(&nodes[0]);
+    wrong_amount.amount += 1;
+    let claimants = vec![(nodes[0].claimant, env.claim_status(&nodes[0].claimant))];
+    let result = env.claim_batch(vec![wrong_amount], claimants).await;
+    assert_custom_error(result, ErrorCode::InvalidProof.into());
+
+    // A leaf claimed through `claim` can not be claimed again in a batch, and a failing leaf
+    // reverts the whole batch.
+    env.claim(&nodes[0]).await.unwrap();
+    let result = env.claim_nodes(&nodes).await;
+    assert_custom_error(result, ErrorCode::FundsAlreadyClaimed.into());
+    let result = env.claim_nodes(&[nodes[1].clone(), nodes[1].clone()]).await;
+    assert_custom_error(result, ErrorCode::FundsAlreadyClaimed.into());
+    assert_eq!(env.ctx.balance(&nodes[1].claimant).await, 0);
+    assert_eq!(env.ctx.balance(&nodes[2].claimant).await, 0);
+}
+
+#[tokio::test]
+async fn claim_indexed_pays_claimants() {
+    let (mut env, tree) = Env::with_root(true).await;
+
+    env.initialize_claim_bitmap().await.unwrap();
+    let claim_bitmap_address = env.claim_bitmap();
+    let bitmap_rent = env.ctx.rent(ClaimBitmap::size(3)).await;
+    assert_eq!(env.ctx.balance(&claim_bitmap_address).await, bitmap_rent);
+    let claim_bitmap: ClaimBitmap = env.ctx.get_account(&claim_bitmap_address).await;
+    assert_eq!(
+        claim_bitmap.reward_collection_account,
+        env.reward_collection_account
+    );
+    assert_eq!(claim_bitmap.payer, env.claim_payer.pubkey());
+    assert_eq!(claim_bitmap.expires_at, env.epoch + NUM_EPOCHS_VALID);
+    assert_eq!(claim_bitmap.bits, vec![0]);
+
+    let reward_collection_account = env.reward_collection_account;
+    let collection_balance = env.ctx.balance(&reward_collection_account).await;
+    for node in &tree.tree_nodes {
+        env.claim_indexed(node, node.index).await.unwrap();
+        assert_eq!(env.ctx.balance(&node.claimant).await, node.amount);
+        // No ClaimStatus account is created.
+        let claim_status = env.claim_status(&node.claimant);
+        assert!(!env.ctx.exists(&claim_status).await);
+    }
+
+    let claim_bitmap: ClaimBitmap = env.ctx.get_account(&claim_bitmap_address).await;
+    assert_eq!(claim_bitmap.bits, vec![0b111]);
+    assert_eq!(
+        env.ctx.balance(&reward_collection_account).await,
+        collection_balance - tree.max_total_claim
+    );
+    let merkle_root = env.collection_account().await.merkle_root.unwrap();
+    assert_eq!(merkle_root.total_funds_claimed, tree.max_total_claim);
+    assert_eq!(merkle_root.num_nodes_claimed, 3);
+}
+
+#[tokio::test]
+async fn claim_indexed_errors() {
+    let mut env = Env::with_rewards().await;
+    let uploader = env.uploader.insecure_clone();
+    let tree = env.indexed_tree();
+    let node = tree.tree_nodes[1].clone();
+
+    env.ctx.warp_to_epoch(env.epoch + 1).await;
+    let result = env.initialize_claim_bitmap().await;
+    assert_custom_error(result, ErrorCode::RootNotUploaded.into());
+
+    env.upload(
+        &uploader,
+        tree.root,
+        tree.max_total_claim,
+        ClaimBitmap::MAX_LEAVES + 1,
+    )
+    .await
+    .unwrap();
+    let result = env.initialize_claim_bitmap().await;
+    assert_custom_error(result, ErrorCode::ClaimBitmapTooLarge.into());
+
+    env.upload_tree(&tree).await.unwrap();
+    env.initialize_claim_bitmap().await.unwrap();
+
+    let result = env.claim_indexed(&node, tree.max_num_nodes).await;
+    assert_custom_error(result, ErrorCode::InvalidLeafIndex.into());
+    let result = env.claim_indexed(&node, node.index + 1).await;
+    assert_custom_error(result, ErrorCode::InvalidProof.into());
+    // Indexed leaves never verify through `claim`, and plain leaves never through
+    // `claim_indexed`.
+    let result = env.claim(&node).await;
+    assert_custom_error(result, ErrorCode::InvalidProof.into());
+    let plain_node = env.tree().tree_nodes[1].clone();
+    let result = env.claim_indexed(&plain_node, node.index).await;
+    assert_custom_error(result, ErrorCode::InvalidProof.into());
+
+    env.claim_indexed(&node, node.index).await.unwrap();
+    let result = env.claim_indexed(&node, node.index).await;
+    assert_custom_error(result, ErrorCode::FundsAlreadyClaimed.into());
+    assert_eq!(env.ctx.balance(&node.claimant).await, node.amount);
+
+    env.warp_past_expiry().await;
+    let other = tree.tree_nodes[0].clone();
+    let result = env.claim_indexed(&other, other.index).await;
+    assert_custom_error(result, ErrorCode::ExpiredRewardCollectionAccount.into());
+}
+
+#[tokio::test]
+async fn claim_indexed_grows_bitmap_for_larger_root() {
+    let mut env = Env::with_rewards().await;
+    let small_tree = MerkleTree::new_indexed_bound(&env.entries()[..1], env.leaf_domain()).unwrap();
+    env.ctx.warp_to_epoch(env.epoch + 1).await;
+    env.upload_tree(&small_tree).await.unwrap();
+    env.initialize_claim_bitmap().await.unwrap();
+    let claim_bitmap_address = env.claim_bitmap();
+    let claim_bitmap: ClaimBitmap = env.ctx.get_account(&claim_bitmap_address).await;
+    assert_eq!(claim_bitmap.bits, vec![0]);
+
+    // The root is replaced before the first claim by one with more leaves than the bitmap was
+    // sized for.
+    let entries: Vec<_> = (0..12)
+        .map(|_| (Pubkey::new_unique(), 10_000_000))
+        .collect();
+    let tree = MerkleTree::new_indexed_bound(&entries, env.leaf_domain()).unwrap();
+    env.upload_tree(&tree).await.unwrap();
+
+    let last = tree
+        .tree_nodes
+        .iter()
+        .find(|node| node.index == 11)
+        .unwrap()
+        .clone();
+    env.claim_indexed(&last, last.index).await.unwrap();
+    assert_eq!(env.ctx.balance(&last.claimant).await, last.amount);
+    let claim_bitmap: ClaimBitmap = env.ctx.get_account(&claim_bitmap_address).await;
+    assert_eq!(claim_bitmap.bits, vec![0, 0b1000]);
+    let bitmap_rent = env.ctx.rent(ClaimBitmap::size(12)).await;
+    assert_eq!(env.ctx.balance(&claim_bitmap_address).await, bitmap_rent);
+
+    for node in tree.tree_nodes.iter().filter(|node| node.index != 11) {
+        env.claim_indexed(node, node.index).await.unwrap();
+    }
+    let claim_bitmap: ClaimBitmap = env.ctx.get_account(&claim_bitmap_address).await;
+    assert_eq!(claim_bitmap.bits, vec![0xff, 0b1111]);
+    let merkle_root = env.collection_account().await.merkle_root.unwrap();
+    assert_eq!(merkle_root.num_nodes_claimed, 12);
+}
+
+#[tokio::test]
+async fn close_claim_bitmap_after_expiry() {
+    let (mut env, tree) = Env::with_root(true).await;
+    env.initialize_claim_bitmap().await.unwrap();
+
+    env.ctx.warp_to_epoch(env.epoch + NUM_EPOCHS_VALID).await;
+    let result = env.close_claim_bitmap().await;
+    assert_custom_error(result, ErrorCode::PrematureCloseClaimBitmap.into());
+
+    env.warp_past_expiry().await;
+    let claim_bitmap = env.claim_bitmap();
+    let rent = env.ctx.rent(ClaimBitmap::size(tree.max_num_nodes)).await;
+    let claim_payer = env.claim_payer.pubkey();
+    let payer_balance = env.ctx.balance(&claim_payer).await;
+    env.close_claim_bitmap().await.unwrap();
+
+    assert!(!env.ctx.exists(&claim_bitmap).await);
+    assert_eq!(env.ctx.balance(&claim_payer).await, payer_balance + rent);
+}
+
+#[tokio::test]
+async fn close_claim_status_after_expiry() {
+    let (mut env, tree) = Env::with_root(false).await;
+    let node = tree.tree_nodes[0].clone();
+    env.claim(&node).await.unwrap();
+
+    env.ctx.warp_to_epoch(env.epoch + NUM_EPOCHS_VALID).await;
+    let result = env.close_claim_status(&node.claimant).await;
+    assert_custom_error(result, ErrorCode::PrematureCloseClaimStatus.into());
+
+    env.warp_past_expiry().await;
+    let claim_status = env.claim_status(&node.claimant);
+    let rent = env.ctx.rent(ClaimStatus::SIZE).await;
+    let claim_payer = env.claim_payer.pubkey();
+    let payer_balance = env.ctx.balance(&claim_payer).await;
+    env.close_claim_status(&node.claimant).await.unwrap();
+
+    assert!(!env.ctx.exists(&claim_status).await);
+    assert_eq!(env.ctx.balance(&claim_payer).await, payer_balance + rent);
+}
+
+#[tokio::test]
+async fn close_collection_account_after_expiry() {
+    let (mut env, tree) = Env::with_root(false).await;
+    let stranger = env.stranger.pubkey();
+    let identity = env.validator.identity.pubkey();
+    let vote_account = env.validator.vote_account;
+    let reward_collection_account = env.reward_collection_account;
+    env.claim(&tree.tree_nodes[0]).await.unwrap();
+
+    env.ctx.warp_to_epoch(env.epoch + NUM_EPOCHS_VALID).await;
+    let result = env.close_collection_account(identity).await;
+    assert_custom_error(
+        result,
+        ErrorCode::PrematureCloseRewardCollectionAccount.into(),
+    );
+
+    env.warp_past_expiry().await;
+    let result = env.close_collection_account(stranger).await;
+    assert_custom_error(result, ErrorCode::Unauthorized.into());
+
+    let rent = env.ctx.rent(RewardCollectionAccount::SIZE).await;
+    let unclaimed = STAKER_REWARDS - tree.tree_nodes[0].amount;
+    assert_eq!(
+        env.ctx.balance(&reward_collection_account).await,
+        rent + unclaimed
+    );
+    let identity_balance = env.ctx.balance(&identity).await;
+    let vote_balance = env.ctx.balance(&vote_account).await;
+
+    env.close_collection_account(identity).await.unwrap();
+
+    // Unclaimed rewards go back to the initializer and the rent to the vote account.
+    assert!(!env.ctx.exists(&reward_collection_account).await);
+    assert_eq!(
+        env.ctx.balance(&identity).await,
+        identity_balance + unclaimed
+    );
+    assert_eq!(env.ctx.balance(&vote_account).await, vote_balance + rent);
+}
+
+#[tokio::test]
+async fn rollover_unclaimed_rewards_after_expiry() {
+    let mut env = Env::with_config().await;
+    let identity = env.validator.identity.insecure_clone();
+    let vote_account = env.validator.vote_account;
+    let rakurai_commission_account = env.rakurai_commission_account;
+    let args = InitializeRewardCollectionAccountArgs {
+        rollover_unclaimed: true,
+        ..env.collection_account_args()
+    };
+    env.initialize_collection_account(args).await.unwrap();
+    env.transfer_rewards(&identity, rakurai_commission_account, TOTAL_REWARDS)
+        .await
+        .unwrap();
+    assert!(env.collection_account().await.rollover_unclaimed);
+
+    let first = env.epoch;
+    let expired = env.reward_collection_account;
+    let tree = env.tree();
+    env.ctx.warp_to_epoch(first + 1).await;
+    env.upload_tree(&tree).await.unwrap();
+    env.claim(&tree.tree_nodes[0]).await.unwrap();
+
+    env.with_rewards_in_epoch(first + NUM_EPOCHS_VALID).await;
+    let result = env.rollover(first).await;
+    assert_custom_error(
+        result,
+        ErrorCode::PrematureCloseRewardCollectionAccount.into(),
+    );
+
+    let current_epoch = first + NUM_EPOCHS_VALID + 1;
+    env.with_rewards_in_epoch(current_epoch).await;
+
+    // Past expiry, the unclaimed rewards do not go back to the validator identity ...
+    env.select_epoch(first);
+    let result = env.close_collection_account(identity.pubkey()).await;
+    assert_custom_error(result, ErrorCode::UnclaimedRewardsMustRollOver.into());
+
+    // ... and only accounts created with the policy roll over.
+    env.select_epoch(current_epoch);
+    let result = env.rollover(first + NUM_EPOCHS_VALID).await;
+    assert_custom_error(result, ErrorCode::RolloverNotEnabled.into());
+
+    let rent = env.ctx.rent(RewardCollectionAccount::SIZE).await;
+    let unclaimed = STAKER_REWARDS - tree.tree_nodes[0].amount;
+    let identity_balance = env.ctx.balance(&identity.pubkey()).await;
+    let vote_balance = env.ctx.balance(&vote_account).await;
+
+    env.rollover(first).await.unwrap();
+
+    // The unclaimed rewards are added to the current epoch's staker rewards.
+    assert!(!env.ctx.exists(&expired).await);
+    let current = env.reward_collection_account;
+    assert_eq!(
+        env.ctx.balance(&current).await,
+        rent + STAKER_REWARDS + unclaimed
+    );
+    assert_eq!(env.ctx.balance(&vote_account).await, vote_balance + rent);
+    assert_eq!(env.ctx.balance(&identity.pubkey()).await, identity_balance);
+    assert_eq!(env.collection_account().await.deposited_rewards, unclaimed);
+}
+
+#[tokio::test]
+async fn rollover_unclaimed_rewards_without_current_account() {
+    let mut env = Env::with_config().await;
+    let identity = env.validator.identity.insecure_clone();
+    let vote_account = env.validator.vote_account;
+    let rakurai_commission_account = env.rakurai_commission_account;
+    let args = InitializeRewardCollectionAccountArgs {
+        rollover_unclaimed: true,
+        ..env.collection_account_args()
+    };
+    env.initialize_collection_account(args).await.unwrap();
+    env.transfer_rewards(&identity, rakurai_commission_account, TOTAL_REWARDS)
+        .await
+        .unwrap();
+
+    let first = env.epoch;
+    let expired = env.reward_collection_account;
+    let current_epoch = first + NUM_EPOCHS_VALID + 1;
+    env.ctx.warp_to_epoch(current_epoch).await;
+    env.select_epoch(current_epoch);
+    assert!(!env.ctx.exists(&env.reward_collection_account).await);
+
+    let rent = env.ctx.rent(RewardCollectionAccount::SIZE).await;
+    let identity_balance = env.ctx.balance(&identity.pubkey()).await;
+    let vote_balance = env.ctx.balance(&vote_account).await;
+
+    let result = env.rollover(first).await;
+    assert_custom_error(result, ErrorCode::NoRolloverTarget.into());
+
+    // No account of the current epoch takes the funds, so they stay where they are ...
+    assert_eq!(env.ctx.balance(&expired).await, rent + STAKER_REWARDS);
+    assert_eq!(env.ctx.balance(&identity.pubkey()).await, identity_balance);
+    assert_eq!(env.ctx.balance(&vote_account).await, vote_balance);
+
+    // ... until the validator creates one.
+    env.with_rewards_in_epoch(current_epoch + 1).await;
+    env.rollover(first).await.unwrap();
+
+    assert!(!env.ctx.exists(&expired).await);
+    assert_eq!(
+        env.ctx.balance(&env.reward_collection_account).await,
+        rent + 2 * STAKER_REWARDS
+    );
+    assert_eq!(
+        env.collection_account().await.deposited_rewards,
+        STAKER_REWARDS
+    );
+}
+
+#[tokio::test]
+async fn close_collection_account_once_fully_claimed() {
+    let (mut env, tree) = Env::with_root(false).await;
+    let identity = env.validator.identity.pubkey();
+    let reward_collection_account = env.reward_collection_account;
+
+    for node in &tree.tree_nodes[..2] {
+        env.claim(node).await.unwrap();
+    }
+    let result = env.close_collection_account(identity).await;
+    assert_custom_error(
+        result,
+        ErrorCode::PrematureCloseRewardCollectionAccount.into(),
+    );
+
+    env.claim(&tree.tree_nodes[2]).await.unwrap();
+    let identity_balance = env.ctx.balance(&identity).await;
+
+    env.close_collection_account(identity).await.unwrap();
+
+    // Long before expiry; what the stakers did not need goes back to the initializer.
+    assert!(!env.ctx.exists(&reward_collection_account).await);
+    assert_eq!(
+        env.ctx.balance(&identity).await,
+        identity_balance + STAKER_REWARDS - 6_000_000_000
+    );
+}
+
+#[tokio::test]
+async fn withdraw_surplus_after_upload() {
+    let mut env = Env::with_rewards().await;
+    let identity = env.validator.identity.insecure_clone();
+    let stranger = env.stranger.insecure_clone();
+    let reward_collection_account = env.reward_collection_account;
+
+    let result = env.withdraw_surplus(&identity).await;
+    assert_custom_error(result, ErrorCode::RootNotUploaded.into());
+
+    let tree = env.tree();
+    env.ctx.warp_to_epoch(env.epoch + 1).await;
+    env.upload_tree(&tree).await.unwrap();
+    env.claim(&tree.tree_nodes[0]).await.unwrap();
+
+    let result = env.withdraw_surplus(&stranger).await;
+    assert_custom_error(result, ErrorCode::Unauthorized.into());
+
+    let identity_balance = env.ctx.balance(&identity.pubkey()).await;
+    env.withdraw_surplus(&identity).await.unwrap();
+
+    // The claims still outstanding stay behind, and can all be paid.
+    let rent = env.ctx.rent(RewardCollectionAccount::SIZE).await;
+    let outstanding = 6_000_000_000 - tree.tree_nodes[0].amount;
+    assert_eq!(
+        env.ctx.balance(&reward_collection_account).await,
+        rent + outstanding
+    );
+    assert_eq!(
+        env.ctx.balance(&identity.pubkey()).await,
+        identity_balance + STAKER_REWARDS - 6_000_000_000
+    );
+    let result = env.withdraw_surplus(&identity).await;
+    assert_custom_error(result, ErrorCode::NoSurplus.into());
+
+    for node in &tree.tree_nodes[1..] {
+        env.claim(node).await.unwrap();
+    }
+    assert_eq!(env.ctx.balance(&reward_collection_account).await, rent);
+}
+
+#[tokio::test]
+async fn initialize_distributor_by_validator() {
+    let mut env = Env::with_config().await;
+    let stranger = env.stranger.insecure_clone();
+    let result = env.initialize_distributor(&stranger).await;
+    assert_custom_error(result, ErrorCode::Unauthorized.into());
+
+    let identity = env.validator.identity.insecure_clone();
+    env.initialize_distributor(&identity).await.unwrap();
+
+    let distributor = env.distributor_state().await;
+    assert_eq!(
+        distributor.validator_vote_account,
+        env.validator.vote_account
+    );
+    assert_eq!(distributor.initializer, identity.pubkey());
+    assert_eq!(
+        distributor.merkle_root_upload_authority,
+        env.uploader.pubkey()
+    );
+    assert_eq!(distributor.max_total_claim, 0);
+    assert_eq!(distributor.total_funds_deposited, 0);
+    assert_eq!(
+        distributor.bump,
+        derive_cumulative_distributor_address(&ID, &env.validator.vote_account).1
+    );
+}
+
+#[tokio::test]
+async fn sweep_to_distributor_moves_staker_rewards() {
+    let mut env = Env::with_rewards().await;
+    let identity = env.validator.identity.insecure_clone();
+    let stranger = env.stranger.insecure_clone();
+    env.initialize_distributor(&identity).await.unwrap();
+
+    let result = env.sweep(&identity).await;
+    assert_custom_error(result, ErrorCode::PrematureSweep.into());
+
+    env.ctx.warp_to_epoch(env.epoch + 1).await;
+    let result = env.sweep(&stranger).await;
+    assert_custom_error(result, ErrorCode::Unauthorized.into());
+
+    let distributor = env.distributor();
+    let distributor_balance = env.ctx.balance(&distributor).await;
+    env.sweep(&identity).await.unwrap();
+
+    let rent = env.ctx.rent(RewardCollectionAccount::SIZE).await;
+    let reward_collection_account = env.reward_collection_account;
+    assert_eq!(env.ctx.balance(&reward_collection_account).await, rent);
+    assert_eq!(
+        env.ctx.balance(&distributor).await,
+        distributor_balance + STAKER_REWARDS
+    );
+    assert_eq!(
+        env.distributor_state().await.total_funds_deposited,
+        STAKER_REWARDS
+    );
+}
+
+#[tokio::test]
+async fn sweep_after_upload_fails() {
+    let (mut env, _) = Env::with_root(false).await;
+    let identity = env.validator.identity.insecure_clone();
+    env.initialize_distributor(&identity).await.unwrap();
+
+    let result = env.sweep(&identity).await;
+    assert_custom_error(result, ErrorCode::MerkleRootAlreadyUploaded.into());
+}
+
+#[tokio::test]
+async fn claim_cumulative_pays_the_difference() {
+    let mut env = Env::with_rewards().await;
+    let identity = env.validator.identity.insecure_clone();
+    let distributor = env.distributor();
+    env.initialize_distributor(&identity).await.unwrap();
+
+    env.ctx.warp_to_epoch(env.epoch + 1).await;
+    env.sweep(&identity).await.unwrap();
+    let tree = env.cumulative_tree(1);
+    env.upload_cumulative_tree(&tree).await.unwrap();
+
+    let first = tree.tree_nodes[0].clone();
+    env.claim_cumulative(&first).await.unwrap();
+    assert_eq!(env.ctx.balance(&first.claimant).await, first.amount);
+
+    // A second epoch is swept in and the totals grow; the next claim pays only the difference.
+    let epoch = env.epoch + 1;
+    env.with_rewards_in_epoch(epoch).await;
+    env.ctx.warp_to_epoch(epoch + 1).await;
+    env.sweep(&identity).await.unwrap();
+    let tree = env.cumulative_tree(2);
+    env.upload_cumulative_tree(&tree).await.unwrap();
+
+    let distributor_balance = env.ctx.balance(&distributor).await;
+    let mut claimed = 0;
+    for node in &tree.tree_nodes {
+        env.claim_cumulative(node).await.unwrap();
+        assert_eq!(env.ctx.balance(&node.claimant).await, node.amount);
+        claimed += node.amount;
+
+        let claim_status: CumulativeClaimStatus = env
+            .ctx
+            .get_account(&env.cumulative_claim_status(&node.claimant))
+            .await;
+        assert_eq!(claim_status.distributor, distributor);
+        assert_eq!(claim_status.claimant, node.claimant);
+        assert_eq!(claim_status.claimed_amount, node.amount);
+    }
+    claimed -= first.amount;
+    assert_eq!(
+        env.ctx.balance(&distributor).await,
+        distributor_balance - claimed
+    );
+
+    let state = env.distributor_state().await;
+    assert_eq!(state.total_funds_deposited, 2 * STAKER_REWARDS);
+    assert_eq!(state.total_funds_claimed, tree.max_total_claim);
+    assert_eq!(state.max_total_claim, tree.max_total_claim);
+
+    let result = env.claim_cumulative(&tree.tree_nodes[0]).await;
+    assert_custom_error(result, ErrorCode::FundsAlreadyClaimed.into());
+}
+
+#[tokio::test]
+async fn cumulative_errors() {
+    let mut env = Env::with_rewards().await;
+    let identity = env.validator.identity.insecure_clone();
+    let stranger = env.stranger.insecure_clone();
+    env.initialize_distributor(&identity).await.unwrap();
+    env.ctx.warp_to_epoch(env.epoch + 1).await;
+    env.sweep(&identity).await.unwrap();
+
+    let tree = env.cumulative_tree(1);
+    let result = env
+        .upload_cumulative(&stranger, tree.root, tree.max_total_claim)
+        .await;
+    assert_custom_error(result, ErrorCode::Unauthorized.into());
+
+    let uploader = env.uploader.insecure_clone();
+    let result = env
+        .upload_cumulative(&uploader, tree.root, STAKER_REWARDS + 1)
+        .await;
+    assert_custom_error(result, ErrorCode::InsufficientDistributorFunds.into());
+
+    env.upload_cumulative_tree(&tree).await.unwrap();
+    let result = env
+        .upload_cumulative(&uploader, tree.root, tree.max_total_claim - 1)
+        .await;
+    assert_custom_error(result, ErrorCode::CumulativeTotalDecreased.into());
+
+    // Proofs of a collection account's tree do not verify against the distributor.
+    let epoch_tree = env.tree();
+    let result = env.claim_cumulative(&epoch_tree.tree_nodes[0]).await;
+    assert_custom_error(result, ErrorCode::InvalidProof.into());
+
+    let mut node = tree.tree_nodes[0].clone();
+    node.amount += 1;
+    let result = env.claim_cumulative(&node).await;
+    assert_custom_error(result, ErrorCode::InvalidProof.into());
+}
+
+#[tokio::test]
+async fn claim_with_proof_buffer_pays_claimant() {
+    let (mut env, tree) = Env::with_root(false).await;
+    let node = tree.tree_nodes[0].clone();
+    let proof_buffer = env.proof_buffer(&node.claimant);
+    let claim_payer = env.claim_payer.insecure_clone();
+    let payer_balance = env.ctx.balance(&claim_payer.pubkey()).await;
+
+    env.initialize_proof_buffer(node.claimant, node.proof.len() as u8)
+        .await
+        .unwrap();
+    for (offset, hash) in node.proof.iter().enumerate() {
+        env.write_proof_buffer(&claim_payer, &node.claimant, offset as u8, &[*hash])
+            .await
+            .unwrap();
+    }
+    let buffer: ProofBuffer = env.ctx.get_account(&proof_buffer).await;
+    assert_eq!(
+        buffer.reward_collection_account,
+        env.reward_collection_account
+    );
+    assert_eq!(buffer.claimant, node.claimant);
+    assert_eq!(buffer.payer, claim_payer.pubkey());
+    assert_eq!(buffer.proof, node.proof);
+
+    env.claim_with_proof_buffer(&node).await.unwrap();
+
+    assert_eq!(env.ctx.balance(&node.claimant).await, node.amount);
+    assert!(!env.ctx.exists(&proof_buffer).await);
+    let claim_status_rent = env.ctx.rent(ClaimStatus::SIZE).await;
+    assert_eq!(
+        env.ctx.balance(&claim_payer.pubkey()).await,
+        payer_balance - claim_status_rent
+    );
+    let claim_status: ClaimStatus = env.ctx.get_account(&env.claim_status(&node.claimant)).await;
+    assert!(claim_status.is_claimed);
+    assert_eq!(claim_status.amount, node.amount);
+
+    let result = env.claim(&node).await;
+    assert!(result.is_err());
+}
+
+#[tokio::test]
+async fn proof_buffer_errors() {
+    let (mut env, tree) = Env::with_root(false).await;
+    let node = tree.tree_nodes[0].clone();
+    let claim_payer = env.claim_payer.insecure_clone();
+    let stranger = env.stranger.insecure_clone();
+
+    let result = env.initialize_proof_buffer(node.claimant, 0).await;
+    assert_custom_error(result, ErrorCode::InvalidProofLength.into());
+    let result = env
+        .initialize_proof_buffer(node.claimant, ProofBuffer::MAX_PROOF_LEN + 1)
+        .await;
+    assert_custom_error(result, ErrorCode::InvalidProofLength.into());
+
+    let proof_len = node.proof.len() as u8;
+    env.initialize_proof_buffer(node.claimant, proof_len)
+        .await
+        .unwrap();
+    let result = env.claim_with_proof_buffer(&node).await;
+    assert_custom_error(result, ErrorCode::ProofBufferIncomplete.into());
+
+    let result = env
+        .write_proof_buffer(&claim_payer, &node.claimant, 1, &node.proof[..1])
+        .await;
+    assert_custom_error(result, ErrorCode::InvalidProofBufferWrite.into());
+    let mut too_long = node.proof.clone();
+    too_long.push([0; 32]);
+    let result = env
+        .write_proof_buffer(&claim_payer, &node.claimant, 0, &too_long)
+        .await;
+    assert_custom_error(result, ErrorCode::InvalidProofBufferWrite.into());
+    let result = env
+        .write_proof_buffer(&stranger, &node.claimant, 0, &node.proof)
+        .await;
+    assert_custom_error(result, AnchorErrorCode::ConstraintHasOne.into());
+
+    // A wrong proof fails the claim and keeps the buffer, which its payer can close.
+    let mut wrong_proof = node.proof.clone();
+    wrong_proof[0][0] ^= 1;
+    env.write_proof_buffer(&claim_payer, &node.claimant, 0, &wrong_proof)
+        .await
+        .unwrap();
+    let result = env.claim_with_proof_buffer(&node).await;
+    assert_custom_error(result, ErrorCode::InvalidProof.into());
+
+    let result = env.close_proof_buffer(&stranger, &node.claimant).await;
+    assert_custom_error(result, AnchorErrorCode::ConstraintHasOne.into());
+    env.close_proof_buffer(&claim_payer, &node.claimant)
+        .await
+        .unwrap();
+    assert!(!env.ctx.exists(&env.proof_buffer(&node.claimant)).await);
+}
+
+#[test]
+fn plan_claim_buffers_long_proofs() {
+    let accounts = ClaimPlanAccounts {
+        config: derive_config_account_address(&ID).0,
+        reward_collection_account: Pubkey::new_unique(),
+        claimant: Pubkey::new_unique(),
+        payer: Pubkey::new_unique(),
+        system_program: system_program::ID,
+    };
+
+    let inline = plan_claim(ID, accounts, 1, vec![[1; 32]; 4], &[]);
+    assert_eq!(inline.len(), 1);
+    assert_eq!(inline[0].len(), 1);
+
+    let buffered = plan_claim(
+        ID,
+        accounts,
+        1,
+        vec![[1; 32]; ProofBuffer::MAX_PROOF_LEN as usize],
+        &[],
+    );
+    assert!(buffered.len() > 1);
+    for transaction in &buffered {
+        assert!(transaction_size(&accounts.payer, transaction, &[]) <= PACKET_DATA_SIZE);
+    }
+    let proof_buffer = derive_proof_buffer_address(
+        &ID,
+        &accounts.reward_collection_account,
+        &accounts.claimant,
+        &accounts.payer,
+    )
+    .0;
+    assert!(buffered.iter().flatten().all(|instruction| instruction
+        .accounts
+        .iter()
+        .any(|meta| meta.pubkey == proof_buffer)));
+}
+
+#[tokio::test]
+async fn claim_aggregated_pays_across_collection_accounts() {
+    let (mut env, roots) = Env::with_roots_in_two_epochs().await;
+    let claimant = env.claimants[0];
+    let claims: Vec<(Pubkey, TreeNode)> = roots
+        .iter()
+        .map(|(reward_collection_account, tree)| {
+            (
+                *reward_collection_account,
+                tree.find_node(&claimant).unwrap().clone(),
+            )
+        })
+        .collect();
+
+    env.claim_aggregated(claimant, &claims).await.unwrap();
+
+    let total: u64 = claims.iter().map(|(_, node)| node.amount).sum();
+    assert_eq!(env.ctx.balance(&claimant).await, total);
+    for (reward_collection_account, node) in &claims {
+        let claim_status_address =
+            derive_claim_status_account_address(&ID, &claimant, reward_collection_account).0;
+        let claim_status: ClaimStatus = env.ctx.get_account(&claim_status_address).await;
+        assert!(claim_status.is_claimed);
+        assert_eq!(claim_status.claimant, claimant);
+        assert_eq!(claim_status.amount, node.amount);
+
+        let collection_account: RewardCollectionAccount =
+            env.ctx.get_account(reward_collection_account).await;
+        let merkle_root = collection_account.merkle_root.unwrap();
+        assert_eq!(merkle_root.total_funds_claimed, node.amount);
+        assert_eq!(merkle_root.num_nodes_claimed, 1);
+    }
+
+    let result = env.claim_aggregated(claimant, &claims[1..]).await;
+    assert_custom_error(result, ErrorCode::FundsAlreadyClaimed.into());
+}
+
+#[tokio::test]
+async fn claim_aggregated_errors() {
+    let (mut env, roots) = Env::with_roots_in_two_epochs().await;
+    let claimant = env.claimants[0];
+    let claims: Vec<(Pubkey, TreeNode)> = roots
+        .iter()
+        .map(|(reward_collection_account, tree)| {
+            (
+                *reward_collection_account,
+                tree.find_node(&claimant).unwrap().clone(),
+            )
+        })
+        .collect();
+
+    let result = env.claim_aggregated(claimant, &[]).await;
+    assert_custom_error(result, ErrorCode::InvalidRemainingAccounts.into());
+
+    let result = env.claim_aggregated(env.claimants[1], &claims).await;
+    assert_custom_error(result, ErrorCode::InvalidProof.into());
+
+    // The leaf of the second tree does not verify against the first root.
+    let swapped = vec![(claims[0].0, claims[1].1.clone())];
+    let result = env.claim_aggregated(claimant, &swapped).await;
+    assert_custom_error(result, ErrorCode::InvalidProof.into());
+
+    // A claim settled through `claim` fails the aggregated claim and reverts its other claims.
+    env.claim(&claims[1].1).await.unwrap();
+    let balance = env.ctx.balance(&claimant).await;
+    let result = env.claim_aggregated(claimant, &claims).await;
+    assert_custom_error(result, ErrorCode::FundsAlreadyClaimed.into());
+    assert_eq!(env.ctx.balance(&claimant).await, balance);
+
+    // The first reward collection account expires one epoch before the second one.
+    env.select_epoch(env.epoch - 1);
+    env.warp_past_expiry().await;
+    let result = env.claim_aggregated(claimant, &claims[..1]).await;
+    assert_custom_error(result, ErrorCode::ExpiredRewardCollectionAccount.into());
+}
+
+#[test]
+fn pack_aggregated_claims_fits_transactions() {
+    let accounts = PackAccounts {
+        config: derive_config_account_address(&ID).0,
+        claimant: Pubkey::new_unique(),
+        payer: Pubkey::new_unique(),
+        system_program: system_program::ID,
+    };
+    let entries: Vec<AggregatedClaimEntry> = (0..24)
+        .map(|i| AggregatedClaimEntry {
+            reward_collection_account: Pubkey::new_unique(),
+            claim_status: Pubkey::new_unique(),
+            amount: i,
+            proof: vec![[i as u8; 32]; 4],
+        })
+        .collect();
+    let lookup_tables = [AddressLookupTableAccount {
+        key: Pubkey::new_unique(),
+        addresses: lookup_table_addresses(&accounts, &entries),
+    }];
+
+    let legacy = pack_aggregated_claims(ID, accounts, entries.clone(), &[], &[]);
+    let versioned = pack_aggregated_claims(ID, accounts, entries.clone(), &[], &lookup_tables);
+    assert!(versioned.len() > 1);
+    assert!(versioned.len() < legacy.len());
+
+    for (instructions, lookup_tables) in [(&legacy, &[][..]), (&versioned, &lookup_tables[..])] {
+        let mut packed_claims = Vec::new();
+        for instruction in instructions.iter() {
+            assert!(
+                transaction_size(
+                    &accounts.payer,
+                    std::slice::from_ref(instruction),
+                    lookup_tables
+                ) <= PACKET_DATA_SIZE
+            );
+            packed_claims.extend(
+                instruction.accounts[4..]
+                    .chunks(2)
+                    .map(|metas| metas[0].pubkey),
+            );
+        }
+        let expected: Vec<Pubkey> = entries
+            .iter()
+            .map(|entry| entry.reward_collection_account)
+            .collect();
+        assert_eq!(packed_claims, expected);
+    }
+}
+
+/// [CrankClient] over the bank of an [Env]. The crank is synchronous, so it runs on a blocking
+/// thread and waits on the bank through the test's runtime. The bank can not list program
+/// accounts, so the reward collection accounts to crank are given up front.
+struct BanksCrankClient {
+    banks_client: BanksClient,
+    runtime: Handle,
+    reward_collection_accounts: Vec<Pubkey>,
+}
+
+impl CrankClient for BanksCrankClient {
+    fn get_reward_collection_accounts(
+        &self,
+        _program_id: &Pubkey,
+    ) -> Result<Vec<(Pubkey, RewardCollectionAccount)>, Box<dyn std::error::Error>> {
+        let mut reward_collection_accounts = Vec::new();
+        for (pubkey, account) in self
+            .reward_collection_accounts
+            .iter()
+            .zip(self.get_multiple_accounts(&self.reward_collection_accounts)?)
+        {
+            let Some(account) = account else {
+                continue;
+            };
+            let reward_collection_account =
+                RewardCollectionAccount::try_deserialize(&mut account.data.as_slice())?;
+            if reward_collection_account.merkle_root.is_some() {
+                reward_collection_accounts.push((*pubkey, reward_collection_account));
+            }
+        }
+        Ok(reward_collection_accounts)
+    }
+
+    fn get_multiple_accounts(
+        &self,
+        pubkeys: &[Pubkey],
+    ) -> Result<Vec<Option<Account>>, Box<dyn std::error::Error>> {
+        let mut accounts = Vec::with_capacity(pubkeys.len());
+        for pubkey in pubkeys {
+            let mut banks_client = self.banks_client.clone();
+            accounts.push(self.runtime.block_on(banks_client.get_account(*pubkey))?);
+        }
+        Ok(accounts)
+    }
+
+    fn get_epoch(&self) -> Result<Epoch, Box<dyn std::error::Error>> {
+        let mut banks_client = self.banks_client.clone();
+        Ok(self
+            .runtime
+            .block_on(banks_client.get_sysvar::<Clock>())?
+            .epoch)
+    }
+
+    fn send_transaction(
+        &self,
+        instructions: &[Instruction],
+        payer: &Keypair,
+    ) -> Result<Signature, Box<dyn std::error::Error>> {
+        let mut banks_client = self.banks_client.clone();
+        let blockhash = self.runtime.block_on(banks_client.get_latest_blockhash())?;
+        let transaction = Transaction::new_signed_with_payer(
+            instructions,
+            Some(&payer.pubkey()),
+            &[payer],
+            blockhash,
+        );
+        let signature = transaction.signatures[0];
+        self.runtime
+            .block_on(banks_client.process_transaction(transaction))?;
+        Ok(signature)
+    }
+}
+
+impl Env {
+    /// Distribution file of `tree`, with a claim per leaf in leaf order.
+    fn distribution(&self, tree: &MerkleTree) -> DistributionFile {
+        let mut nodes = tree.tree_nodes.clone();
+        nodes.sort_by_key(|node| node.index);
+        DistributionFile {
+            version: DISTRIBUTION_FILE_VERSION,
+            vote_account: self.validator.vote_account,
+            epoch: self.epoch,
+            reward_collection_account: self.reward_collection_account,
+            total_rewards: STAKER_REWARDS,
+            total_stake: tree.max_total_claim,
+            max_total_claim: tree.max_total_claim,
+            max_num_nodes: tree.max_num_nodes,
+            indexed: tree.indexed,
+            leaf_version: tree.version(),
+            claims: nodes
+                .into_iter()
+                .map(|node| StakerClaim {
+                    claimant: node.claimant,
+                    amount: node.amount,
+                    stake: node.amount,
+                    stake_accounts: vec![],
+                })
+                .collect(),
+        }
+    }
+
+    /// Crank settings with a fresh directory holding the distribution file of `tree`.
+    fn crank_config(&self, tree: &MerkleTree) -> CrankConfig {
+        let dir =
+            std::env::temp_dir().join(format!("claim-crank-{}", self.reward_collection_account));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("distributions")).unwrap();
+        self.distribution(tree)
+            .write(&dir.join("distributions").join("distribution.json"))
+            .unwrap();
+        CrankConfig {
+            program_id: ID,
+            distributions_dir: dir.join("distributions"),
+            state_path: dir.join("state.json"),
+            priority_fee: 0,
+            compute_unit_limit: None,
+            max_transaction_size: PACKET_DATA_SIZE,
+            max_retries: 1,
+            retry_delay: Duration::ZERO,
+        }
+    }
+
+    /// Runs one crank pass over the reward collection account, paid for by the claim payer.
+    async fn crank(
+        &mut self,
+        config: &CrankConfig,
+        mut state: CrankState,
+    ) -> (CrankSummary, CrankState) {
+        let client = BanksCrankClient {
+            banks_client: self.ctx.context.banks_client.clone(),
+            runtime: Handle::current(),
+            reward_collection_accounts: vec![self.reward_collection_account],
+        };
+        let payer = self.claim_payer.insecure_clone();
+        let config = config.clone();
+        tokio::task::spawn_blocking(move || {
+            let summary = run_once(&client, &payer, &config, &mut state)
+                .map_err(|err| err.to_string())
+                .unwrap();
+            (summary, state)
+        })
+        .await
+        .unwrap()
+    }
+}
+
+fn claimed(state: &CrankState, reward_collection_account: &Pubkey) -> Vec<String> {
+    state
+        .claimed
+        .get(&reward_collection_account.to_string())
+        .map_or(vec![], |claimed| claimed.iter().cloned().collect())
+}
+
+fn claimant_strings(nodes: &[TreeNode]) -> Vec<String> {
+    let mut claimants: Vec<String> = nodes.iter().map(|node| node.claimant.to_string()).collect();
+    claimants.sort();
+    claimants
+}
+
+#[tokio::test]
+async fn crank_claims_every_leaf() {
+    let (mut env, tree) = Env::with_root(false).await;
+    let config = env.crank_config(&tree);
+
+    let (summary, state) = env.crank(&config, CrankState::default()).await;
+    assert_eq!(
+        summary,
+        CrankSummary {
+            reward_collection_accounts: 1,
+            claims: 3,
+            lamports: tree.max_total_claim,
+            failed_claims: 0,
+            unclaimable_claims: 0,
+        }
+    );
+    for node in &tree.tree_nodes {
+        assert_eq!(env.ctx.balance(&node.claimant).await, node.amount);
+    }
+    assert_eq!(
+        claimed(&state, &env.reward_collection_account),
+        claimant_strings(&tree.tree_nodes)
+    );
+    assert_eq!(CrankState::load(&config.state_path).unwrap(), state);
+
+    // The next pass finds nothing left and completes the account.
+    let (summary, state) = env.crank(&config, state).await;
+    assert_eq!(summary, CrankSummary::default());
+    assert!(state
+        .completed
+        .contains(&env.reward_collection_account.to_string()));
+    assert!(state.claimed.is_empty());
+    assert_eq!(CrankState::load(&config.state_path).unwrap(), state);
+}
+
+#[tokio::test]
+async fn crank_resumes_from_state_file() {
+    let (mut env, tree) = Env::with_root(false).await;
+    let config = env.crank_config(&tree);
+    let nodes = tree.tree_nodes.clone();
+
+    // A previous run recorded the first leaf as claimed before it stopped.
+    let mut state = CrankState::default();
+    state.claimed.insert(
+        env.reward_collection_account.to_string(),
+        [nodes[0].claimant.to_string()].into(),
+    );
+    state.save(&config.state_path).unwrap();
+
+    let state = CrankState::load(&config.state_path).unwrap();
+    let (summary, state) = env.crank(&config, state).await;
+    assert_eq!(summary.claims, 2);
+    assert_eq!(summary.lamports, nodes[1].amount + nodes[2].amount);
+    assert_eq!(env.ctx.balance(&nodes[0].claimant).await, 0);
+    assert_eq!(env.ctx.balance(&nodes[1].claimant).await, nodes[1].amount);
+    assert_eq!(env.ctx.balance(&nodes[2].claimant).await, nodes[2].amount);
+    assert_eq!(
+        claimed(&state, &env.reward_collection_account),
+        claimant_strings(&nodes)
+    );
+}
+
+#[tokio::test]
+async fn crank_skips_leaves_claimed_on_chain() {
+    let (mut env, tree) = Env::with_root(false).await;
+    let config = env.crank_config(&tree);
+    let nodes = tree.tree_nodes.clone();
+    env.claim(&nodes[1]).await.unwrap();
+
+    let (summary, state) = env.crank(&config, CrankState::default()).await;
+    assert_eq!(summary.claims, 2);
+    assert_eq!(summary.lamports, nodes[0].amount + nodes[2].amount);
+    assert_eq!(summary.failed_claims, 0);
+    for node in &nodes {
+        assert_eq!(env.ctx.balance(&node.claimant).await, node.amount);
+    }
+    assert_eq!(
+        claimed(&state, &env.reward_collection_account),
+        claimant_strings(&nodes)
+    );
+}
+
+#[tokio::test]
+async fn crank_claims_oversized_proofs_through_proof_buffer() {
+    let (mut env, tree) = Env::with_root(false).await;
+    let mut config = env.crank_config(&tree);
+    // No claim fits a transaction this small, so every proof is written to a buffer first.
+    config.max_transaction_size = 400;
+
+    let (summary, _) = env.crank(&config, CrankState::default()).await;
+    assert_eq!(summary.claims, 3);
+    assert_eq!(summary.failed_claims, 0);
+    for node in &tree.tree_nodes {
+        assert_eq!(env.ctx.balance(&node.claimant).await, node.amount);
+        let claim_status = env.claim_status(&node.claimant);
+        assert!(env.ctx.exists(&claim_status).await);
+        let proof_buffer = env.proof_buffer(&node.claimant);
+        assert!(!env.ctx.exists(&proof_buffer).await);
+    }
+}
+
+#[tokio::test]
+async fn crank_creates_claim_bitmap_for_indexed_tree() {
+    let (mut env, tree) = Env::with_root(true).await;
+    let config = env.crank_config(&tree);
+    let claim_bitmap_address = env.claim_bitmap();
+    assert!(!env.ctx.exists(&claim_bitmap_address).await);
+
+    let (summary, _) = env.crank(&config, CrankState::default()).await;
+    assert_eq!(summary.claims, 3);
+    assert_eq!(summary.lamports, tree.max_total_claim);
+    let claim_bitmap: ClaimBitmap = env.ctx.get_account(&claim_bitmap_address).await;
+    assert_eq!(claim_bitmap.bits, vec![0b111]);
+    for node in &tree.tree_nodes {
+        assert_eq!(env.ctx.balance(&node.claimant).await, node.amount);
+    }
+}
+
+#[tokio::test]
+async fn crank_records_oversized_indexed_claims_as_unclaimable() {
+    let (mut env, tree) = Env::with_root(true).await;
+    let mut config = env.crank_config(&tree);
+    config.max_transaction_size = 400;
+
+    let (summary, state) = env.crank(&config, CrankState::default()).await;
+    assert_eq!(summary.claims, 0);
+    assert_eq!(summary.unclaimable_claims, 3);
+    assert_eq!(
+        state
+            .unclaimable
+            .get(&env.reward_collection_account.to_string())
+            .map(|claimants| claimants.iter().cloned().collect::<Vec<_>>()),
+        Some(claimant_strings(&tree.tree_nodes))
+    );
+    for node in &tree.tree_nodes {
+        assert_eq!(env.ctx.balance(&node.claimant).await, 0);
+    }
+
+    // They are reported once, not on every pass.
+    let (summary, _) = env.crank(&config, state).await;
+    assert_eq!(summary, CrankSummary::default());
+}
+
+#[tokio::test]
+async fn crank_reports_scaled_payouts() {
+    let mut env = Env::with_rewards().await;
+    env.ctx.warp_to_epoch(env.epoch + 1).await;
+    // Claims of twice the staker rewards are all halved.
+    let entries: Vec<_> = env
+        .claimants
+        .iter()
+        .zip([STAKER_REWARDS, STAKER_REWARDS * 2 / 3, STAKER_REWARDS / 3])
+        .map(|(claimant, amount)| (*claimant, amount))
+        .collect();
+    let tree = MerkleTree::new_bound(&entries, env.leaf_domain()).unwrap();
+    env.upload_tree_pro_rata(&tree).await.unwrap();
+
+    let config = env.crank_config(&tree);
+    let (summary, _) = env.crank(&config, CrankState::default()).await;
+    assert_eq!(summary.claims, 3);
+    assert_eq!(summary.lamports, tree.max_total_claim / 2);
+    for node in &tree.tree_nodes {
+        assert_eq!(env.ctx.balance(&node.claimant).await, node.amount / 2);
+    }
+}
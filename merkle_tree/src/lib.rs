@@ -0,0 +1,595 @@
+//! Off-chain builder for the merkle trees verified by the `reward_distribution` program.
+//!
+//! The program hashes leaves and verifies proofs with the functions of this crate, so the trees
+//! built here always verify on-chain:
+//! - leaf: `hashv([0u8, hashv([claimant, amount_le])])`
+//! - indexed leaf, claimed through a `ClaimBitmap`:
+//!   `hashv([0u8, hashv([index_le, claimant, amount_le])])`
+//! - intermediate node: `hashv([1u8, min(left, right), max(left, right)])`
+//!
+//! Those are the [LEAF_VERSION_V0] leaves. [LEAF_VERSION_V1] leaves are bound to one
+//! `RewardCollectionAccount` through a [LeafDomain], prepended to the inner preimage:
+//! `hashv([0u8, hashv([1u8, reward_collection_account, creation_epoch_le, ..])])`.
+//!
+//! Cumulative trees, claimed from a `CumulativeDistributor`, hold every claimant's lifetime total
+//! and are bound to the distributor: `hashv([0u8, hashv([distributor, claimant, total_le])])`.
+//!
+//! When a level has an odd number of nodes the last node is paired with itself.
+
+use {
+    solana_program::{hash::hashv, pubkey::Pubkey},
+    std::{collections::HashSet, fmt},
+};
+
+/// Prefix used when hashing leaves.
+const LEAF_PREFIX: &[u8] = &[0];
+/// Prefix used when hashing intermediate nodes.
+const INTERMEDIATE_PREFIX: &[u8] = &[1];
+
+/// Leaves commit to the claim only. Passed to `upload_merkle_root` as the root's version.
+pub const LEAF_VERSION_V0: u8 = 0;
+/// Leaves also commit to a [LeafDomain].
+pub const LEAF_VERSION_V1: u8 = 1;
+
+/// The `RewardCollectionAccount` a [LEAF_VERSION_V1] tree is bound to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LeafDomain {
+    /// Address of the `RewardCollectionAccount` claimed from.
+    pub reward_collection_account: Pubkey,
+    /// `creation_epoch` of the `RewardCollectionAccount`.
+    pub creation_epoch: u64,
+}
+
+/// Errors returned while building a [MerkleTree].
+#[derive(Debug, PartialEq, Eq)]
+pub enum MerkleTreeError {
+    /// No entries were provided.
+    EmptyTree,
+    /// The same claimant appears more than once. Only one `ClaimStatus` can exist per claimant,
+    /// so a second leaf for the same claimant could never be claimed.
+    DuplicateClaimant(Pubkey),
+    /// The sum of all amounts overflows a u64.
+    ArithmeticError,
+}
+
+impl fmt::Display for MerkleTreeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MerkleTreeError::EmptyTree => write!(f, "Cannot build a merkle tree without entries"),
+            MerkleTreeError::DuplicateClaimant(claimant) => {
+                write!(f, "Claimant {} appears more than once", claimant)
+            }
+            MerkleTreeError::ArithmeticError => {
+                write!(f, "Encountered an arithmetic overflow while summing claims")
+            }
+        }
+    }
+}
+
+impl std::error::Error for MerkleTreeError {}
+
+/// A single claim in the tree together with the proof expected by the `claim` instruction.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TreeNode {
+    /// Position of the leaf in the tree.
+    pub index: u64,
+    /// Receiver of the funds.
+    pub claimant: Pubkey,
+    /// Lamports the claimant is entitled to.
+    pub amount: u64,
+    /// Sibling hashes from the leaf up to (but excluding) the root.
+    pub proof: Vec<[u8; 32]>,
+}
+
+/// A fully built merkle tree along with the values passed to `upload_merkle_root`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MerkleTree {
+    /// Merkle root hash.
+    pub root: [u8; 32],
+    /// Sum of all claim amounts.
+    pub max_total_claim: u64,
+    /// Number of leaves in the tree.
+    pub max_num_nodes: u64,
+    /// Whether the leaves commit to their index, see [MerkleTree::new_indexed].
+    pub indexed: bool,
+    /// Collection account the leaves are bound to, see [MerkleTree::new_bound].
+    pub domain: Option<LeafDomain>,
+    /// Cumulative distributor the leaves are bound to, see [MerkleTree::new_cumulative].
+    pub distributor: Option<Pubkey>,
+    /// Leaves in the same order as the entries the tree was built from.
+    pub tree_nodes: Vec<TreeNode>,
+}
+
+impl MerkleTree {
+    /// Builds a tree from `(claimant, amount)` entries. The order of the entries determines the
+    /// shape of the tree, so the same input always yields the same root.
+    pub fn new(entries: &[(Pubkey, u64)]) -> Result<Self, MerkleTreeError> {
+        Self::build(entries, false, None, None)
+    }
+
+    /// Builds a tree whose leaves also commit to their position, for claims recorded in a
+    /// `ClaimBitmap` through `claim_indexed`. The index of a leaf is its position in `entries`.
+    pub fn new_indexed(entries: &[(Pubkey, u64)]) -> Result<Self, MerkleTreeError> {
+        Self::build(entries, true, None, None)
+    }
+
+    /// Like [MerkleTree::new], with [LEAF_VERSION_V1] leaves bound to `domain`.
+    pub fn new_bound(
+        entries: &[(Pubkey, u64)],
+        domain: LeafDomain,
+    ) -> Result<Self, MerkleTreeError> {
+        Self::build(entries, false, Some(domain), None)
+    }
+
+    /// Like [MerkleTree::new_indexed], with [LEAF_VERSION_V1] leaves bound to `domain`.
+    pub fn new_indexed_bound(
+        entries: &[(Pubkey, u64)],
+        domain: LeafDomain,
+    ) -> Result<Self, MerkleTreeError> {
+        Self::build(entries, true, Some(domain), None)
+    }
+
+    /// Builds a tree of `(claimant, total_amount)` entries for the `CumulativeDistributor` at
+    /// `distributor`. Each amount is the claimant's lifetime total, so `max_total_claim` is the
+    /// sum of all lifetime totals.
+    pub fn new_cumulative(
+        entries: &[(Pubkey, u64)],
+        distributor: Pubkey,
+    ) -> Result<Self, MerkleTreeError> {
+        Self::build(entries, false, None, Some(distributor))
+    }
+
+    fn build(
+        entries: &[(Pubkey, u64)],
+        indexed: bool,
+        domain: Option<LeafDomain>,
+        distributor: Option<Pubkey>,
+    ) -> Result<Self, MerkleTreeError> {
+        if entries.is_empty() {
+            return Err(MerkleTreeError::EmptyTree);
+        }
+
+        let mut claimants = HashSet::with_capacity(entries.len());
+        let mut max_total_claim = 0u64;
+        for (claimant, amount) in entries {
+            if !claimants.insert(*claimant) {
+                return Err(MerkleTreeError::DuplicateClaimant(*claimant));
+            }
+            max_total_claim = max_total_claim
+                .checked_add(*amount)
+                .ok_or(MerkleTreeError::ArithmeticError)?;
+        }
+
+        let leaves = entries
+            .iter()
+            .enumerate()
+            .map(|(index, (claimant, amount))| {
+                hash_entry(
+                    indexed,
+                    domain.as_ref(),
+                    distributor.as_ref(),
+                    index as u64,
+                    claimant,
+                    *amount,
+                )
+            })
+            .collect();
+        let levels = build_levels(leaves);
+        let root = levels[levels.len() - 1][0];
+
+        let tree_nodes = entries
+            .iter()
+            .enumerate()
+            .map(|(index, (claimant, amount))| TreeNode {
+                index: index as u64,
+                claimant: *claimant,
+                amount: *amount,
+                proof: build_proof(&levels, index),
+            })
+            .collect();
+
+        Ok(Self {
+            root,
+            max_total_claim,
+            max_num_nodes: entries.len() as u64,
+            indexed,
+            domain,
+            distributor,
+            tree_nodes,
+        })
+    }
+
+    /// Leaf version passed to `upload_merkle_root` along with the root.
+    pub fn version(&self) -> u8 {
+        if self.domain.is_some() {
+            LEAF_VERSION_V1
+        } else {
+            LEAF_VERSION_V0
+        }
+    }
+
+    /// Returns the node belonging to `claimant`, if any.
+    pub fn find_node(&self, claimant: &Pubkey) -> Option<&TreeNode> {
+        self.tree_nodes
+            .iter()
+            .find(|node| &node.claimant == claimant)
+    }
+
+    /// Checks every proof against the root the same way the program does.
+    pub fn verify_proofs(&self) -> bool {
+        self.tree_nodes
+            .iter()
+            .all(|node| verify(&node.proof, self.root, self.hash_node(node)))
+    }
+
+    /// Hashes the leaf of `node` the way this tree was built.
+    pub fn hash_node(&self, node: &TreeNode) -> [u8; 32] {
+        hash_entry(
+            self.indexed,
+            self.domain.as_ref(),
+            self.distributor.as_ref(),
+            node.index,
+            &node.claimant,
+            node.amount,
+        )
+    }
+}
+
+/// Hashes the leaf of a claim in the format selected by `indexed`, `domain` and `distributor`.
+fn hash_entry(
+    indexed: bool,
+    domain: Option<&LeafDomain>,
+    distributor: Option<&Pubkey>,
+    index: u64,
+    claimant: &Pubkey,
+    amount: u64,
+) -> [u8; 32] {
+    if let Some(distributor) = distributor {
+        return hash_cumulative_leaf(distributor, claimant, amount);
+    }
+    match (indexed, domain) {
+        (false, None) => hash_leaf(claimant, amount),
+        (true, None) => hash_indexed_leaf(index, claimant, amount),
+        (false, Some(domain)) => hash_bound_leaf(domain, claimant, amount),
+        (true, Some(domain)) => hash_bound_indexed_leaf(domain, index, claimant, amount),
+    }
+}
+
+/// Hashes a `(claimant, amount)` leaf, claimed through the `claim` instruction.
+pub fn hash_leaf(claimant: &Pubkey, amount: u64) -> [u8; 32] {
+    hashv(&[
+        LEAF_PREFIX,
+        &hashv(&[&claimant.to_bytes(), &amount.to_le_bytes()]).to_bytes(),
+    ])
+    .to_bytes()
+}
+
+/// Hashes an `(index, claimant, amount)` leaf, claimed through the `claim_indexed` instruction.
+/// The preimage is longer than that of [hash_leaf], so a leaf can never verify in both modes.
+pub fn hash_indexed_leaf(index: u64, claimant: &Pubkey, amount: u64) -> [u8; 32] {
+    hashv(&[
+        LEAF_PREFIX,
+        &hashv(&[
+            &index.to_le_bytes(),
+            &claimant.to_bytes(),
+            &amount.to_le_bytes(),
+        ])
+        .to_bytes(),
+    ])
+    .to_bytes()
+}
+
+/// Hashes a [LEAF_VERSION_V1] `(claimant, amount)` leaf. The preimage starts with the version
+/// and the [LeafDomain], and is longer than any version 0 preimage.
+pub fn hash_bound_leaf(domain: &LeafDomain, claimant: &Pubkey, amount: u64) -> [u8; 32] {
+    hashv(&[
+        LEAF_PREFIX,
+        &hashv(&[
+            &[LEAF_VERSION_V1],
+            &domain.reward_collection_account.to_bytes(),
+            &domain.creation_epoch.to_le_bytes(),
+            &claimant.to_bytes(),
+            &amount.to_le_bytes(),
+        ])
+        .to_bytes(),
+    ])
+    .to_bytes()
+}
+
+/// Hashes a [LEAF_VERSION_V1] `(index, claimant, amount)` leaf.
+pub fn hash_bound_indexed_leaf(
+    domain: &LeafDomain,
+    index: u64,
+    claimant: &Pubkey,
+    amount: u64,
+) -> [u8; 32] {
+    hashv(&[
+        LEAF_PREFIX,
+        &hashv(&[
+            &[LEAF_VERSION_V1],
+            &domain.reward_collection_account.to_bytes(),
+            &domain.creation_epoch.to_le_bytes(),
+            &index.to_le_bytes(),
+            &claimant.to_bytes(),
+            &amount.to_le_bytes(),
+        ])
+        .to_bytes(),
+    ])
+    .to_bytes()
+}
+
+/// Hashes a lifetime total leaf, claimed through the `claim_cumulative` instruction. The
+/// preimage length differs from every other leaf format, so the proofs of a
+/// `RewardCollectionAccount` tree never verify against a `CumulativeDistributor` and vice versa.
+pub fn hash_cumulative_leaf(
+    distributor: &Pubkey,
+    claimant: &Pubkey,
+    total_amount: u64,
+) -> [u8; 32] {
+    hashv(&[
+        LEAF_PREFIX,
+        &hashv(&[
+            &distributor.to_bytes(),
+            &claimant.to_bytes(),
+            &total_amount.to_le_bytes(),
+        ])
+        .to_bytes(),
+    ])
+    .to_bytes()
+}
+
+/// Hashes two sibling nodes. The pair is sorted first, so the order of the arguments does not matter.
+pub fn hash_intermediate(a: &[u8; 32], b: &[u8; 32]) -> [u8; 32] {
+    if a <= b {
+        hashv(&[INTERMEDIATE_PREFIX, a, b]).to_bytes()
+    } else {
+        hashv(&[INTERMEDIATE_PREFIX, b, a]).to_bytes()
+    }
+}
+
+/// Checks that `proof` leads from `leaf` up to `root`, for `reward_distribution::merkle_proof::verify`.
+pub fn verify(proof: &[[u8; 32]], root: [u8; 32], leaf: [u8; 32]) -> bool {
+    let computed_hash = proof.iter().fold(leaf, |computed_hash, proof_element| {
+        hash_intermediate(&computed_hash, proof_element)
+    });
+    computed_hash == root
+}
+
+/// Builds every level of the tree, starting with the leaves and ending with the root.
+fn build_levels(leaves: Vec<[u8; 32]>) -> Vec<Vec<[u8; 32]>> {
+    let mut levels = vec![leaves];
+    while levels[levels.len() - 1].len() > 1 {
+        let next_level = levels[levels.len() - 1]
+            .chunks(2)
+            .map(|pair| hash_intermediate(&pair[0], pair.get(1).unwrap_or(&pair[0])))
+            .collect();
+        levels.push(next_level);
+    }
+    levels
+}
+
+/// Collects the sibling of the node at `index` on every level below the root.
+fn build_proof(levels: &[Vec<[u8; 32]>], mut index: usize) -> Vec<[u8; 32]> {
+    let mut proof = Vec::with_capacity(levels.len() - 1);
+    for level in &levels[..levels.len() - 1] {
+        let sibling = level.get(index ^ 1).unwrap_or(&level[index]);
+        proof.push(*sibling);
+        index /= 2;
+    }
+    proof
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entries(num_entries: u8) -> Vec<(Pubkey, u64)> {
+        (1..=num_entries)
+            .map(|i| (Pubkey::new_from_array([i; 32]), i as u64 * 1_000))
+            .collect()
+    }
+
+    fn domain() -> LeafDomain {
+        LeafDomain {
+            reward_collection_account: Pubkey::new_from_array([42; 32]),
+            creation_epoch: 7,
+        }
+    }
+
+    fn distributor() -> Pubkey {
+        Pubkey::new_from_array([43; 32])
+    }
+
+    /// Every tree format, built from `entries`.
+    fn trees(entries: &[(Pubkey, u64)]) -> Vec<MerkleTree> {
+        vec![
+            MerkleTree::new(entries).unwrap(),
+            MerkleTree::new_indexed(entries).unwrap(),
+            MerkleTree::new_bound(entries, domain()).unwrap(),
+            MerkleTree::new_indexed_bound(entries, domain()).unwrap(),
+            MerkleTree::new_cumulative(entries, distributor()).unwrap(),
+        ]
+    }
+
+    /// `hashv([0u8, hashv(preimage)])`, spelled out independently of the functions under test.
+    fn reference_leaf(preimage: &[&[u8]]) -> [u8; 32] {
+        hashv(&[&[0u8], &hashv(preimage).to_bytes()]).to_bytes()
+    }
+
+    /// `hashv([1u8, min(a, b), max(a, b)])`.
+    fn reference_node(a: [u8; 32], b: [u8; 32]) -> [u8; 32] {
+        let (low, high) = if a <= b { (a, b) } else { (b, a) };
+        hashv(&[&[1u8], &low, &high]).to_bytes()
+    }
+
+    /// The leaf the on-chain instructions expect for `node` of `tree`, per the module docs.
+    fn reference_node_leaf(tree: &MerkleTree, node: &TreeNode) -> [u8; 32] {
+        let index = node.index.to_le_bytes();
+        let claimant = node.claimant.to_bytes();
+        let amount = node.amount.to_le_bytes();
+        if let Some(distributor) = tree.distributor {
+            return reference_leaf(&[&distributor.to_bytes(), &claimant, &amount]);
+        }
+        match (tree.indexed, tree.domain) {
+            (false, None) => reference_leaf(&[&claimant, &amount]),
+            (true, None) => reference_leaf(&[&index, &claimant, &amount]),
+            (false, Some(domain)) => reference_leaf(&[
+                &[LEAF_VERSION_V1],
+                &domain.reward_collection_account.to_bytes(),
+                &domain.creation_epoch.to_le_bytes(),
+                &claimant,
+                &amount,
+            ]),
+            (true, Some(domain)) => reference_leaf(&[
+                &[LEAF_VERSION_V1],
+                &domain.reward_collection_account.to_bytes(),
+                &domain.creation_epoch.to_le_bytes(),
+                &index,
+                &claimant,
+                &amount,
+            ]),
+        }
+    }
+
+    /// `merkle_proof::verify` of the program, spelled out with the reference hashing.
+    fn reference_verify(proof: &[[u8; 32]], root: [u8; 32], leaf: [u8; 32]) -> bool {
+        proof
+            .iter()
+            .fold(leaf, |hash, sibling| reference_node(hash, *sibling))
+            == root
+    }
+
+    #[test]
+    fn single_leaf_tree() {
+        for tree in trees(&entries(1)) {
+            let node = &tree.tree_nodes[0];
+            assert_eq!(tree.root, reference_node_leaf(&tree, node));
+            assert!(node.proof.is_empty());
+            assert_eq!(tree.max_total_claim, 1_000);
+            assert_eq!(tree.max_num_nodes, 1);
+            assert!(tree.verify_proofs());
+        }
+    }
+
+    #[test]
+    fn two_leaf_tree() {
+        for tree in trees(&entries(2)) {
+            let leaves: Vec<_> = tree
+                .tree_nodes
+                .iter()
+                .map(|node| reference_node_leaf(&tree, node))
+                .collect();
+            assert_eq!(tree.root, reference_node(leaves[0], leaves[1]));
+            assert_eq!(tree.tree_nodes[0].proof, vec![leaves[1]]);
+            assert_eq!(tree.tree_nodes[1].proof, vec![leaves[0]]);
+        }
+    }
+
+    #[test]
+    fn odd_leaf_count_pairs_last_node_with_itself() {
+        for tree in trees(&entries(3)) {
+            let leaves: Vec<_> = tree
+                .tree_nodes
+                .iter()
+                .map(|node| reference_node_leaf(&tree, node))
+                .collect();
+            let left = reference_node(leaves[0], leaves[1]);
+            let right = reference_node(leaves[2], leaves[2]);
+            assert_eq!(tree.root, reference_node(left, right));
+            assert_eq!(tree.tree_nodes[0].proof, vec![leaves[1], right]);
+            assert_eq!(tree.tree_nodes[2].proof, vec![leaves[2], left]);
+            assert_eq!(tree.max_total_claim, 6_000);
+            assert_eq!(tree.max_num_nodes, 3);
+        }
+    }
+
+    #[test]
+    fn every_proof_verifies_with_reference_hashing() {
+        for num_entries in 1..=17 {
+            let entries = entries(num_entries);
+            for tree in trees(&entries) {
+                assert_eq!(tree.tree_nodes.len(), entries.len());
+                for (index, (node, (claimant, amount))) in
+                    tree.tree_nodes.iter().zip(&entries).enumerate()
+                {
+                    assert_eq!(node.index, index as u64);
+                    assert_eq!((node.claimant, node.amount), (*claimant, *amount));
+                    let leaf = reference_node_leaf(&tree, node);
+                    assert_eq!(tree.hash_node(node), leaf);
+                    assert!(reference_verify(&node.proof, tree.root, leaf));
+                    assert!(verify(&node.proof, tree.root, leaf));
+                }
+                assert!(tree.verify_proofs());
+            }
+        }
+    }
+
+    #[test]
+    fn proofs_only_verify_in_their_own_format() {
+        let entries = entries(5);
+        let trees = trees(&entries);
+        for (i, tree) in trees.iter().enumerate() {
+            for (j, other) in trees.iter().enumerate() {
+                if i == j {
+                    continue;
+                }
+                for node in &tree.tree_nodes {
+                    assert!(!verify(&node.proof, tree.root, other.hash_node(node)));
+                }
+            }
+        }
+
+        let tree = MerkleTree::new_bound(&entries, domain()).unwrap();
+        let other_domain = LeafDomain {
+            creation_epoch: 8,
+            ..domain()
+        };
+        for node in &tree.tree_nodes {
+            let leaf = hash_bound_leaf(&other_domain, &node.claimant, node.amount);
+            assert!(!verify(&node.proof, tree.root, leaf));
+            let leaf = hash_bound_leaf(&domain(), &node.claimant, node.amount + 1);
+            assert!(!verify(&node.proof, tree.root, leaf));
+        }
+    }
+
+    #[test]
+    fn versions() {
+        let entries = entries(2);
+        assert_eq!(
+            MerkleTree::new(&entries).unwrap().version(),
+            LEAF_VERSION_V0
+        );
+        assert_eq!(
+            MerkleTree::new_indexed(&entries).unwrap().version(),
+            LEAF_VERSION_V0
+        );
+        assert_eq!(
+            MerkleTree::new_bound(&entries, domain()).unwrap().version(),
+            LEAF_VERSION_V1
+        );
+        assert_eq!(
+            MerkleTree::new_indexed_bound(&entries, domain())
+                .unwrap()
+                .version(),
+            LEAF_VERSION_V1
+        );
+    }
+
+    #[test]
+    fn build_errors() {
+        assert_eq!(MerkleTree::new(&[]), Err(MerkleTreeError::EmptyTree));
+
+        let claimant = Pubkey::new_from_array([1; 32]);
+        assert_eq!(
+            MerkleTree::new_indexed(&[(claimant, 1), (claimant, 2)]),
+            Err(MerkleTreeError::DuplicateClaimant(claimant))
+        );
+        assert_eq!(
+            MerkleTree::new_cumulative(
+                &[(claimant, u64::MAX), (Pubkey::new_from_array([2; 32]), 1)],
+                distributor()
+            ),
+            Err(MerkleTreeError::ArithmeticError)
+        );
+    }
+}
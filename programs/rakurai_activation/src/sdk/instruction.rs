@@ -61,7 +61,7 @@ pub fn initialize_ix(
 
 /// Arguments to update the global config account.
 pub struct UpdateConfigArgs {
-    new_config: RakuraiActivationConfigAccount,
+    pub new_config: RakuraiActivationConfigAccount,
 }
 
 /// Accounts required to perform the config update.
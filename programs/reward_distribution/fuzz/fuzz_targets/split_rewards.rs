@@ -0,0 +1,41 @@
+//! Fuzzes [split_rewards] with arbitrary totals and commissions, including invalid ones.
+//!
+//! Run with `cargo +nightly fuzz run split_rewards` from `programs/reward_distribution`.
+
+#![no_main]
+
+use {
+    libfuzzer_sys::fuzz_target,
+    reward_distribution::commission::{split_rewards, MAX_BPS},
+};
+
+fuzz_target!(|input: (u64, u16, u16)| {
+    let (total_rewards, rakurai_commission_bps, validator_commission_bps) = input;
+    let valid = rakurai_commission_bps <= MAX_BPS && validator_commission_bps <= MAX_BPS;
+
+    let Ok(split) = split_rewards(
+        total_rewards,
+        rakurai_commission_bps,
+        validator_commission_bps,
+    ) else {
+        assert!(!valid, "valid commissions must always split");
+        return;
+    };
+    assert!(valid, "commissions above MAX_BPS must be rejected");
+
+    assert_eq!(
+        split.block_builder_fee as u128
+            + split.validator_fee as u128
+            + split.staker_rewards as u128,
+        total_rewards as u128
+    );
+    assert_eq!(
+        split.block_builder_fee as u128,
+        total_rewards as u128 * rakurai_commission_bps as u128 / MAX_BPS as u128
+    );
+    let remaining = total_rewards - split.block_builder_fee;
+    assert_eq!(
+        split.validator_fee as u128,
+        remaining as u128 * validator_commission_bps as u128 / MAX_BPS as u128
+    );
+});
@@ -0,0 +1,410 @@
+//! Commission math of [crate::reward_distribution::transfer_staker_rewards].
+
+use crate::ErrorCode::ArithmeticError;
+use anchor_lang::prelude::*;
+
+/// Basis points making up the whole of the rewards.
+pub const MAX_BPS: u16 = 10_000;
+
+/// A leader turn's block rewards split between Rakurai, the validator and its stakers.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RewardSplit {
+    /// Rakurai's commission, transferred to the `rakurai_commission_account`.
+    pub block_builder_fee: u64,
+    /// Validator's commission, kept in the validator's identity account.
+    pub validator_fee: u64,
+    /// Stakers' share, transferred to the [crate::state::RewardCollectionAccount].
+    pub staker_rewards: u64,
+}
+
+/// Splits `total_rewards`: Rakurai takes `rakurai_commission_bps` of the total, the validator
+/// takes `validator_commission_bps` of what is left, and the stakers get the rest. Both fees round
+/// down, so rounding dust always goes to the stakers.
+///
+/// The products are computed in `u128`, so the split succeeds for every `total_rewards` and only
+/// fails when a commission exceeds [MAX_BPS].
+pub fn split_rewards(
+    total_rewards: u64,
+    rakurai_commission_bps: u16,
+    validator_commission_bps: u16,
+) -> Result<RewardSplit> {
+    let block_builder_fee = bps_of(total_rewards, rakurai_commission_bps)?;
+    let remaining = total_rewards
+        .checked_sub(block_builder_fee)
+        .ok_or(ArithmeticError)?;
+
+    let validator_fee = bps_of(remaining, validator_commission_bps)?;
+    let staker_rewards = remaining
+        .checked_sub(validator_fee)
+        .ok_or(ArithmeticError)?;
+
+    Ok(RewardSplit {
+        block_builder_fee,
+        validator_fee,
+        staker_rewards,
+    })
+}
+
+/// A leader turn's rewards broken down by where they came from, in lamports before commissions.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CategorizedRewards {
+    /// Base transaction fees.
+    pub block_fees: u64,
+    /// Priority fees.
+    pub priority_fees: u64,
+    /// Tips paid outside of the transaction fees, e.g. bundle tips.
+    pub tips: u64,
+}
+
+/// Validator commissions of the categories of [CategorizedRewards], stored on the
+/// [crate::state::RewardCollectionAccount]. Each replaces the account's
+/// `validator_commission_bps` for its category when set.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CategoryCommissions {
+    /// Validator commission of the base transaction fees.
+    pub block_fees: Option<u16>,
+    /// Validator commission of the priority fees.
+    pub priority_fees: Option<u16>,
+    /// Validator commission of the tips.
+    pub tips: Option<u16>,
+}
+
+/// [CategorizedRewards] split by [split_categorized_rewards], one [RewardSplit] per category.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CategorizedSplit {
+    /// Split of the base transaction fees.
+    pub block_fees: RewardSplit,
+    /// Split of the priority fees.
+    pub priority_fees: RewardSplit,
+    /// Split of the tips.
+    pub tips: RewardSplit,
+}
+
+impl CategorizedRewards {
+    /// Every category, in the order of the fields.
+    pub fn categories(&self) -> [u64; 3] {
+        [self.block_fees, self.priority_fees, self.tips]
+    }
+
+    /// Sum of the categories' amounts.
+    pub fn total(&self) -> Result<u64> {
+        Ok(self
+            .categories()
+            .iter()
+            .try_fold(0u64, |total, amount| total.checked_add(*amount))
+            .ok_or(ArithmeticError)?)
+    }
+}
+
+impl CategoryCommissions {
+    /// Every category's commission, in the order of the fields.
+    pub fn categories(&self) -> [Option<u16>; 3] {
+        [self.block_fees, self.priority_fees, self.tips]
+    }
+}
+
+impl CategorizedSplit {
+    /// Sum of the categories' splits.
+    pub fn total(&self) -> Result<RewardSplit> {
+        Ok([self.block_fees, self.priority_fees, self.tips]
+            .iter()
+            .try_fold(RewardSplit::default(), |total, split| {
+                Some(RewardSplit {
+                    block_builder_fee: total
+                        .block_builder_fee
+                        .checked_add(split.block_builder_fee)?,
+                    validator_fee: total.validator_fee.checked_add(split.validator_fee)?,
+                    staker_rewards: total.staker_rewards.checked_add(split.staker_rewards)?,
+                })
+            })
+            .ok_or(ArithmeticError)?)
+    }
+}
+
+/// Splits each category of `rewards` with [split_rewards], using the category's commission of
+/// `category_commissions` where one is set and `validator_commission_bps` otherwise. Rakurai's
+/// commission is the same for every category.
+pub fn split_categorized_rewards(
+    rewards: &CategorizedRewards,
+    category_commissions: &CategoryCommissions,
+    rakurai_commission_bps: u16,
+    validator_commission_bps: u16,
+) -> Result<CategorizedSplit> {
+    let split = |amount: u64, category_commission_bps: Option<u16>| {
+        split_rewards(
+            amount,
+            rakurai_commission_bps,
+            category_commission_bps.unwrap_or(validator_commission_bps),
+        )
+    };
+
+    Ok(CategorizedSplit {
+        block_fees: split(rewards.block_fees, category_commissions.block_fees)?,
+        priority_fees: split(rewards.priority_fees, category_commissions.priority_fees)?,
+        tips: split(rewards.tips, category_commissions.tips)?,
+    })
+}
+
+/// `amount * bps / MAX_BPS`, rounded down.
+fn bps_of(amount: u64, bps: u16) -> Result<u64> {
+    if bps > MAX_BPS {
+        return Err(ArithmeticError.into());
+    }
+    // Can not overflow: the quotient is at most `amount`.
+    Ok((amount as u128 * bps as u128 / MAX_BPS as u128) as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, proptest::prelude::*};
+
+    /// Reference implementation of a fee: `floor(amount * bps / 10_000)`.
+    fn expected_fee(amount: u64, bps: u16) -> u64 {
+        (amount as u128 * bps as u128 / 10_000) as u64
+    }
+
+    /// Checks every property of a split of `total_rewards` with valid commissions.
+    fn check_split(total_rewards: u64, rakurai_commission_bps: u16, validator_commission_bps: u16) {
+        let split = split_rewards(
+            total_rewards,
+            rakurai_commission_bps,
+            validator_commission_bps,
+        )
+        .unwrap();
+
+        assert_eq!(
+            split.block_builder_fee as u128
+                + split.validator_fee as u128
+                + split.staker_rewards as u128,
+            total_rewards as u128
+        );
+        assert_eq!(
+            split.block_builder_fee,
+            expected_fee(total_rewards, rakurai_commission_bps)
+        );
+        assert_eq!(
+            split.validator_fee,
+            expected_fee(
+                total_rewards - split.block_builder_fee,
+                validator_commission_bps
+            )
+        );
+    }
+
+    #[test]
+    fn split_examples() {
+        assert_eq!(
+            split_rewards(10_000_000_000, 1_000, 500).unwrap(),
+            RewardSplit {
+                block_builder_fee: 1_000_000_000,
+                validator_fee: 450_000_000,
+                staker_rewards: 8_550_000_000,
+            }
+        );
+        // Rounding dust goes to the stakers.
+        assert_eq!(
+            split_rewards(10_001, 1_000, 500).unwrap(),
+            RewardSplit {
+                block_builder_fee: 1_000,
+                validator_fee: 450,
+                staker_rewards: 8_551,
+            }
+        );
+        assert_eq!(
+            split_rewards(u64::MAX, MAX_BPS, MAX_BPS).unwrap(),
+            RewardSplit {
+                block_builder_fee: u64::MAX,
+                validator_fee: 0,
+                staker_rewards: 0,
+            }
+        );
+        assert_eq!(
+            split_rewards(u64::MAX, 0, 0).unwrap(),
+            RewardSplit {
+                block_builder_fee: 0,
+                validator_fee: 0,
+                staker_rewards: u64::MAX,
+            }
+        );
+    }
+
+    #[test]
+    fn split_rejects_commission_above_max() {
+        assert!(split_rewards(1, MAX_BPS + 1, 0).is_err());
+        assert!(split_rewards(1, 0, MAX_BPS + 1).is_err());
+        assert!(split_rewards(0, u16::MAX, u16::MAX).is_err());
+    }
+
+    #[test]
+    fn split_every_bps() {
+        for total_rewards in [
+            0,
+            1,
+            9_999,
+            10_000,
+            10_001,
+            1_000_000_007,
+            u64::MAX - 1,
+            u64::MAX,
+        ] {
+            for bps in 0..=MAX_BPS {
+                check_split(total_rewards, bps, bps);
+                check_split(total_rewards, bps, MAX_BPS - bps);
+            }
+        }
+    }
+
+    #[test]
+    fn fee_is_monotonic_in_bps() {
+        for total_rewards in [1, 10_001, 123_456_789, u64::MAX] {
+            let mut previous = 0;
+            for bps in 0..=MAX_BPS {
+                let fee = bps_of(total_rewards, bps).unwrap();
+                assert!(fee >= previous);
+                previous = fee;
+            }
+            assert_eq!(bps_of(total_rewards, 0).unwrap(), 0);
+            assert_eq!(bps_of(total_rewards, MAX_BPS).unwrap(), total_rewards);
+        }
+    }
+
+    #[test]
+    fn categorized_split_examples() {
+        let rewards = CategorizedRewards {
+            block_fees: 10_000,
+            priority_fees: 10_000,
+            tips: 10_001,
+        };
+        assert_eq!(rewards.total().unwrap(), 30_001);
+        let category_commissions = CategoryCommissions {
+            block_fees: None,
+            priority_fees: Some(MAX_BPS),
+            tips: Some(0),
+        };
+
+        let split = split_categorized_rewards(&rewards, &category_commissions, 1_000, 500).unwrap();
+        assert_eq!(split.block_fees, split_rewards(10_000, 1_000, 500).unwrap());
+        assert_eq!(
+            split.priority_fees,
+            RewardSplit {
+                block_builder_fee: 1_000,
+                validator_fee: 9_000,
+                staker_rewards: 0,
+            }
+        );
+        assert_eq!(
+            split.tips,
+            RewardSplit {
+                block_builder_fee: 1_000,
+                validator_fee: 0,
+                staker_rewards: 9_001,
+            }
+        );
+        assert_eq!(
+            split.total().unwrap(),
+            RewardSplit {
+                block_builder_fee: 3_000,
+                validator_fee: 9_450,
+                staker_rewards: 17_551,
+            }
+        );
+
+        // Without category commissions, every category takes the account's commission.
+        let split =
+            split_categorized_rewards(&rewards, &CategoryCommissions::default(), 1_000, 500)
+                .unwrap();
+        assert_eq!(
+            split.priority_fees,
+            split_rewards(10_000, 1_000, 500).unwrap()
+        );
+        assert_eq!(split.tips, split_rewards(10_001, 1_000, 500).unwrap());
+    }
+
+    #[test]
+    fn categorized_split_errors() {
+        let overflowing = CategorizedRewards {
+            block_fees: u64::MAX,
+            priority_fees: 1,
+            tips: 0,
+        };
+        assert!(overflowing.total().is_err());
+        let split =
+            split_categorized_rewards(&overflowing, &CategoryCommissions::default(), 0, 0).unwrap();
+        assert!(split.total().is_err());
+
+        let rewards = CategorizedRewards {
+            tips: 1,
+            ..Default::default()
+        };
+        let invalid_commission = CategoryCommissions {
+            tips: Some(MAX_BPS + 1),
+            ..Default::default()
+        };
+        assert!(split_categorized_rewards(&rewards, &invalid_commission, 0, 0).is_err());
+    }
+
+    proptest! {
+        #[test]
+        fn split_sums_to_total(
+            total_rewards in any::<u64>(),
+            rakurai_commission_bps in 0..=MAX_BPS,
+            validator_commission_bps in 0..=MAX_BPS,
+        ) {
+            check_split(total_rewards, rakurai_commission_bps, validator_commission_bps);
+        }
+
+        #[test]
+        fn split_fails_only_above_max_bps(
+            total_rewards in any::<u64>(),
+            rakurai_commission_bps in any::<u16>(),
+            validator_commission_bps in any::<u16>(),
+        ) {
+            let result = split_rewards(total_rewards, rakurai_commission_bps, validator_commission_bps);
+            prop_assert_eq!(
+                result.is_ok(),
+                rakurai_commission_bps <= MAX_BPS && validator_commission_bps <= MAX_BPS
+            );
+        }
+
+        #[test]
+        fn fee_rounds_down(amount in any::<u64>(), bps in 0..=MAX_BPS) {
+            let fee = bps_of(amount, bps).unwrap() as u128;
+            let exact = amount as u128 * bps as u128;
+            // fee <= amount * bps / 10_000 < fee + 1
+            prop_assert!(fee * 10_000 <= exact);
+            prop_assert!(exact < (fee + 1) * 10_000);
+        }
+
+        #[test]
+        fn categorized_split_sums_to_total(
+            amounts in proptest::array::uniform3(0..=u64::MAX / 3),
+            commissions in proptest::array::uniform3(proptest::option::of(0..=MAX_BPS)),
+            rakurai_commission_bps in 0..=MAX_BPS,
+            validator_commission_bps in 0..=MAX_BPS,
+        ) {
+            let rewards = CategorizedRewards {
+                block_fees: amounts[0],
+                priority_fees: amounts[1],
+                tips: amounts[2],
+            };
+            let category_commissions = CategoryCommissions {
+                block_fees: commissions[0],
+                priority_fees: commissions[1],
+                tips: commissions[2],
+            };
+            let split = split_categorized_rewards(
+                &rewards,
+                &category_commissions,
+                rakurai_commission_bps,
+                validator_commission_bps,
+            )
+            .unwrap()
+            .total()
+            .unwrap();
+            prop_assert_eq!(
+                split.block_builder_fee + split.validator_fee + split.staker_rewards,
+                rewards.total().unwrap()
+            );
+        }
+    }
+}
@@ -2,9 +2,20 @@ use anchor_lang::prelude::*;
 #[cfg(not(feature = "no-entrypoint"))]
 use solana_security_txt::security_txt;
 
+use rakurai_activation::state::{RakuraiActivationAccount, RakuraiActivationConfigAccount};
+
 use crate::{
-    state::{ClaimStatus, MerkleRoot, RewardCollectionAccount, RewardDistributionConfigAccount},
-    ErrorCode::{InvalidRakuraiCommissionAccount, Unauthorized},
+    commission::{split_categorized_rewards, CategorizedRewards, CategoryCommissions, RewardSplit},
+    state::{
+        AttestedRoot, ClaimBitmap, ClaimScale, ClaimStatus, CumulativeClaimStatus,
+        CumulativeDistributor, DepositSource, LegacyRewardCollectionAccountV0, MerkleRoot,
+        ProofBuffer, RewardCollectionAccount, RewardDistributionConfigAccount, RootAttestation,
+    },
+    ErrorCode::{
+        ExpiredRewardCollectionAccount, FundsAlreadyClaimed, InvalidClaimStatusAccount,
+        InvalidRakuraiCommissionAccount, MerkleRootAlreadyUploaded, PrematureMerkleRootUpload,
+        RootNotUploaded, Unauthorized,
+    },
 };
 
 #[cfg(not(feature = "no-entrypoint"))]
@@ -15,16 +26,29 @@ security_txt! {
     contacts: "https://rakurai.io/company",
     policy: "https://rakurai.io/faq"
 }
+pub mod commission;
 pub mod merkle_proof;
 pub mod sdk;
 pub mod state;
 
 declare_id!("A37zgM34Q43gKAxBWQ9zSbQRRhjPqGK8jM49H7aWqNVB");
 
+/// The stake config account, still taken by the stake program's delegate instruction.
+#[allow(deprecated)]
+const STAKE_CONFIG_ID: Pubkey = solana_program::stake::config::ID;
+
 #[program]
 pub mod reward_distribution {
     use rakurai_vote_state::VoteState;
-    use solana_program::{program::invoke, system_instruction};
+    use solana_program::{
+        program::{invoke, invoke_signed},
+        program_pack::Pack,
+        stake::{
+            self,
+            state::{Authorized, Lockup, StakeStateV2},
+        },
+        system_instruction,
+    };
 
     use super::*;
     use crate::ErrorCode::*;
@@ -48,23 +72,32 @@ pub mod reward_distribution {
     }
 
     /// Initialize a new [RewardCollectionAccount] associated with the given validator vote key
-    /// and current epoch.
+    /// and current epoch. The commissions are copied from the validator's [RakuraiActivationAccount]
+    /// and the [RakuraiActivationConfigAccount], which must have Rakurai enabled.
+    /// With `rollover_unclaimed`, what is left unclaimed on expiry goes to the stakers of a later
+    /// epoch through [rollover_unclaimed_rewards] instead of back to the validator identity.
     pub fn initialize_reward_collection_account(
         ctx: Context<InitializeRewardCollectionAccount>,
         merkle_root_upload_authority: Pubkey,
-        validator_commission_bps: u16,
-        rakurai_commission_account: Pubkey,
-        rakurai_commission_bps: u16,
         bump: u8,
+        rollover_unclaimed: bool,
     ) -> Result<()> {
-        if validator_commission_bps > ctx.accounts.config.max_commission_bps
-            || rakurai_commission_bps > ctx.accounts.config.max_commission_bps
-            || (validator_commission_bps + rakurai_commission_bps)
-                > ctx.accounts.config.max_commission_bps
-        {
-            return Err(MaxCommissionFeeBpsExceeded.into());
+        let activation_account = &ctx.accounts.activation_account;
+        if !activation_account.is_enabled {
+            return Err(RakuraiActivationDisabled.into());
         }
 
+        let validator_commission_bps = activation_account.validator_commission_bps;
+        let rakurai_commission_bps = activation_account.block_builder_commission_bps;
+        let rakurai_commission_account = ctx
+            .accounts
+            .activation_config
+            .block_builder_commission_account;
+
+        ctx.accounts
+            .config
+            .check_commissions(validator_commission_bps, rakurai_commission_bps)?;
+
         if ctx.accounts.validator_vote_account.owner != &solana_program::vote::program::id() {
             return Err(Unauthorized.into());
         }
@@ -90,6 +123,8 @@ pub mod reward_distribution {
             .ok_or(ArithmeticError)?;
         reward_collection_acc.initializer = ctx.accounts.signer.key();
         reward_collection_acc.bump = bump;
+        reward_collection_acc.account_version = RewardCollectionAccount::CURRENT_VERSION;
+        reward_collection_acc.rollover_unclaimed = rollover_unclaimed;
         reward_collection_acc.validate()?;
 
         emit!(RewardCollectionAccountInitializedEvent {
@@ -120,85 +155,267 @@ pub mod reward_distribution {
     }
 
     /// Uploads a merkle root to the [RewardCollectionAccount]. Only the `merkle_root_upload_authority` can invole this instruction.
+    /// `version` is the leaf format of the tree; [MerkleRoot::LEAF_VERSION_V1] leaves are bound to
+    /// this account and its creation epoch.
+    /// With `pro_rata`, an account holding less than `max_total_claim` scales every claim down by
+    /// `available / max_total_claim`, fixed here, so that every staker takes the same haircut.
     pub fn upload_merkle_root(
         ctx: Context<UploadMerkleRoot>,
         root: [u8; 32],
         max_total_claim: u64,
         max_num_nodes: u64,
+        version: u8,
+        pro_rata: bool,
     ) -> Result<()> {
         UploadMerkleRoot::auth(&ctx)?;
+        MerkleRoot::validate_version(version)?;
 
-        let current_epoch = Clock::get()?.epoch;
         let reward_collection_acc = &mut ctx.accounts.reward_collection_account;
+        check_merkle_root_upload(reward_collection_acc)?;
+        store_merkle_root(
+            reward_collection_acc,
+            AttestedRoot {
+                root,
+                max_total_claim,
+                max_num_nodes,
+                version,
+                pro_rata,
+            },
+        )?;
 
-        if let Some(merkle_root) = &reward_collection_acc.merkle_root {
-            if merkle_root.num_nodes_claimed > 0 {
-                return Err(Unauthorized.into());
-            }
-        }
-        if current_epoch <= reward_collection_acc.creation_epoch {
-            return Err(PrematureMerkleRootUpload.into());
+        emit!(MerkleRootUploadedEvent {
+            merkle_root_upload_authority: ctx.accounts.merkle_root_upload_authority.key(),
+            reward_collection_account: reward_collection_acc.key(),
+            claim_scale: reward_collection_acc.claim_scale,
+        });
+
+        Ok(())
+    }
+
+    /// Changes the `merkle_root_upload_authority` of a [RewardCollectionAccount], e.g. to move
+    /// between Rakurai-managed and self-managed distribution. Only the validator identity that
+    /// initialized the account can invoke this, and only before a merkle root is uploaded. An
+    /// upload handed to its [RootAttestation] stays there, so the attesters' quorum can not be
+    /// bypassed.
+    pub fn update_merkle_root_upload_authority(
+        ctx: Context<UpdateMerkleRootUploadAuthority>,
+        new_merkle_root_upload_authority: Pubkey,
+    ) -> Result<()> {
+        UpdateMerkleRootUploadAuthority::auth(&ctx)?;
+
+        let reward_collection_acc = &mut ctx.accounts.reward_collection_account;
+        let old_authority = reward_collection_acc.merkle_root_upload_authority;
+        reward_collection_acc.merkle_root_upload_authority = new_merkle_root_upload_authority;
+        reward_collection_acc.validate()?;
+
+        emit!(MerkleRootUploadAuthorityUpdatedEvent {
+            old_authority,
+            new_authority: new_merkle_root_upload_authority,
+        });
+
+        Ok(())
+    }
+
+    /// Changes the `validator_commission_bps` and `category_commissions` of a
+    /// [RewardCollectionAccount], each within the config's `max_commission_bps`. Only the
+    /// validator identity that initialized the account can invoke this, and only before a merkle
+    /// root is uploaded.
+    pub fn update_validator_commission(
+        ctx: Context<UpdateValidatorCommission>,
+        new_validator_commission_bps: u16,
+        new_category_commissions: CategoryCommissions,
+    ) -> Result<()> {
+        UpdateValidatorCommission::auth(&ctx)?;
+
+        let reward_collection_acc = &mut ctx.accounts.reward_collection_account;
+        reward_collection_acc.check_version()?;
+        for validator_commission_bps in new_category_commissions
+            .categories()
+            .into_iter()
+            .flatten()
+            .chain([new_validator_commission_bps])
+        {
+            ctx.accounts.config.check_commissions(
+                validator_commission_bps,
+                reward_collection_acc.rakurai_commission_bps,
+            )?;
         }
 
-        if current_epoch > reward_collection_acc.expires_at {
-            return Err(ExpiredRewardCollectionAccount.into());
+        let old_commission_bps = reward_collection_acc.validator_commission_bps;
+        let old_category_commissions = reward_collection_acc.category_commissions;
+        reward_collection_acc.validator_commission_bps = new_validator_commission_bps;
+        reward_collection_acc.category_commissions = new_category_commissions;
+
+        emit!(ValidatorCommissionBpsUpdatedEvent {
+            reward_collection_account: reward_collection_acc.key(),
+            old_commission_bps,
+            new_commission_bps: new_validator_commission_bps,
+            old_category_commissions,
+            new_category_commissions,
+        });
+
+        Ok(())
+    }
+
+    /// Hands the merkle root upload of a [RewardCollectionAccount] to several attesters: the new
+    /// [RootAttestation] becomes its `merkle_root_upload_authority`, and the root is only uploaded
+    /// once `threshold` of `attesters` submitted the same one with `attest_merkle_root`, so
+    /// claims open only then. Only the validator identity that initialized the account can
+    /// invoke this, and only before a merkle root is uploaded. The upload can not be handed back
+    /// to a single authority afterwards.
+    pub fn initialize_root_attestation(
+        ctx: Context<InitializeRootAttestation>,
+        attesters: Vec<Pubkey>,
+        threshold: u8,
+    ) -> Result<()> {
+        InitializeRootAttestation::auth(&ctx)?;
+        RootAttestation::check_attesters(&attesters, threshold)?;
+
+        let reward_collection_acc = &mut ctx.accounts.reward_collection_account;
+        let root_attestation = &mut ctx.accounts.root_attestation;
+        root_attestation.reward_collection_account = reward_collection_acc.key();
+        root_attestation.payer = ctx.accounts.signer.key();
+        root_attestation.expires_at = reward_collection_acc.expires_at;
+        root_attestation.threshold = threshold;
+        root_attestation.bump = ctx.bumps.root_attestation;
+        root_attestation.submissions = vec![None; attesters.len()];
+        root_attestation.attesters = attesters;
+
+        let old_authority = reward_collection_acc.merkle_root_upload_authority;
+        reward_collection_acc.merkle_root_upload_authority = root_attestation.key();
+        reward_collection_acc.validate()?;
+
+        emit!(MerkleRootUploadAuthorityUpdatedEvent {
+            old_authority,
+            new_authority: root_attestation.key(),
+        });
+        emit!(RootAttestationInitializedEvent {
+            reward_collection_account: reward_collection_acc.key(),
+            root_attestation: root_attestation.key(),
+            attesters: root_attestation.attesters.clone(),
+            threshold,
+        });
+
+        Ok(())
+    }
+
+    /// Submits an attester's merkle root for a [RewardCollectionAccount] whose upload is handed to
+    /// its [RootAttestation], replacing the attester's previous submission. The submission that
+    /// reaches the `threshold` is uploaded, as `upload_merkle_root` would, and can not be changed
+    /// afterwards. Submissions differing from other attesters' are recorded in
+    /// `MerkleRootAttestationConflictEvent`.
+    pub fn attest_merkle_root(
+        ctx: Context<AttestMerkleRoot>,
+        root: [u8; 32],
+        max_total_claim: u64,
+        max_num_nodes: u64,
+        version: u8,
+        pro_rata: bool,
+    ) -> Result<()> {
+        AttestMerkleRoot::auth(&ctx)?;
+        MerkleRoot::validate_version(version)?;
+
+        let reward_collection_acc = &mut ctx.accounts.reward_collection_account;
+        if reward_collection_acc.merkle_root.is_some() {
+            return Err(MerkleRootAlreadyUploaded.into());
         }
+        check_merkle_root_upload(reward_collection_acc)?;
 
-        reward_collection_acc.merkle_root = Some(MerkleRoot {
+        let submission = AttestedRoot {
             root,
             max_total_claim,
             max_num_nodes,
-            total_funds_claimed: 0,
-            num_nodes_claimed: 0,
+            version,
+            pro_rata,
+        };
+        let attester = ctx.accounts.attester.key();
+        let root_attestation = &mut ctx.accounts.root_attestation;
+        let (num_agreeing, conflicting_attesters) =
+            root_attestation.submit(&attester, submission)?;
+
+        emit!(MerkleRootAttestedEvent {
+            reward_collection_account: reward_collection_acc.key(),
+            attester,
+            submission,
+            num_agreeing,
         });
-        reward_collection_acc.validate()?;
+        if !conflicting_attesters.is_empty() {
+            emit!(MerkleRootAttestationConflictEvent {
+                reward_collection_account: reward_collection_acc.key(),
+                attester,
+                submission,
+                conflicting_attesters,
+            });
+        }
 
-        emit!(MerkleRootUploadedEvent {
-            merkle_root_upload_authority: ctx.accounts.merkle_root_upload_authority.key(),
-            reward_collection_account: reward_collection_acc.key(),
+        if num_agreeing >= root_attestation.threshold {
+            store_merkle_root(reward_collection_acc, submission)?;
+
+            emit!(MerkleRootUploadedEvent {
+                merkle_root_upload_authority: root_attestation.key(),
+                reward_collection_account: reward_collection_acc.key(),
+                claim_scale: reward_collection_acc.claim_scale,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Permissionless; closes a [RootAttestation] once it has expired, returning rent to its
+    /// payer.
+    pub fn close_root_attestation(ctx: Context<CloseRootAttestation>) -> Result<()> {
+        let root_attestation = &ctx.accounts.root_attestation;
+
+        if Clock::get()?.epoch <= root_attestation.expires_at {
+            return Err(PrematureCloseRootAttestation.into());
+        }
+
+        emit!(RootAttestationClosedEvent {
+            payer: ctx.accounts.payer.key(),
+            root_attestation: root_attestation.key(),
         });
 
         Ok(())
     }
 
     /// Transfer staker rewards according to the commission to the [RewardCollectionAccount]. This is invoked every leader turn.
+    /// `rewards` breaks the turn's rewards down by category; each category takes the validator
+    /// commission stored for it in `category_commissions`, or the account's
+    /// `validator_commission_bps`. The running totals of the account and of each category are
+    /// updated, so the account must be at the current version.
+    /// `start_slot..=end_slot` are the slots of the leader turn being settled; a turn is only
+    /// accepted if it starts after the last one settled, so retries can not transfer it twice.
     pub fn transfer_staker_rewards(
         ctx: Context<TransferStakerRewards>,
-        total_rewards: u64,
+        rewards: CategorizedRewards,
+        start_slot: u64,
+        end_slot: u64,
     ) -> Result<()> {
         TransferStakerRewards::auth(&ctx)?;
 
-        if total_rewards <= 0 {
+        if rewards.total()? == 0 {
             return Err(RewardsTooLow.into());
         }
 
-        let reward_collection_acc = &ctx.accounts.reward_collection_account;
-        let block_builder_fee = total_rewards
-            .checked_mul(reward_collection_acc.rakurai_commission_bps as u64)
-            .ok_or(ArithmeticError)?
-            .checked_div(10_000)
-            .ok_or(ArithmeticError)?;
-
-        let remaining = total_rewards
-            .checked_sub(block_builder_fee)
-            .ok_or(ArithmeticError)?;
-
-        let validator_fee = remaining
-            .checked_mul(reward_collection_acc.validator_commission_bps as u64)
-            .ok_or(ArithmeticError)?
-            .checked_div(10_000)
-            .ok_or(ArithmeticError)?;
-        let staker_rewards = remaining
-            .checked_sub(validator_fee)
-            .ok_or(ArithmeticError)?;
-        if block_builder_fee + validator_fee + staker_rewards != total_rewards {
-            return Err(ArithmeticError.into());
-        }
+        let reward_collection_acc = &mut ctx.accounts.reward_collection_account;
+        reward_collection_acc.check_version()?;
+        reward_collection_acc.settle_slot_range(start_slot, end_slot)?;
+
+        let split = split_categorized_rewards(
+            &rewards,
+            &reward_collection_acc.category_commissions,
+            reward_collection_acc.rakurai_commission_bps,
+            reward_collection_acc.validator_commission_bps,
+        )?;
+        let RewardSplit {
+            block_builder_fee,
+            validator_fee,
+            staker_rewards,
+        } = reward_collection_acc.record_transfer(&split)?;
         invoke(
             &system_instruction::transfer(
                 &ctx.accounts.signer.key(),
-                &&ctx
-                    .accounts
+                &ctx.accounts
                     .reward_collection_account
                     .rakurai_commission_account
                     .key(),
@@ -226,7 +443,118 @@ pub mod reward_distribution {
             ],
         )?;
 
-        emit!(StakerRewardsTransferredEvent { staker_rewards });
+        emit!(StakerRewardsTransferredEvent {
+            reward_collection_account: ctx.accounts.reward_collection_account.key(),
+            rewards,
+            block_builder_fee,
+            validator_fee,
+            staker_rewards,
+            start_slot,
+            end_slot,
+        });
+
+        Ok(())
+    }
+
+    /// Permissionless; adds `amount` lamports from `depositor` to the staker rewards of a
+    /// [RewardCollectionAccount], without any commission taken. Used for rewards earned outside
+    /// of block production, e.g. MEV tips, partner incentives or validator top-ups, as told by
+    /// `source`. Only before the merkle root is uploaded, so the deposit is part of the
+    /// distribution.
+    pub fn deposit_rewards(
+        ctx: Context<DepositRewards>,
+        amount: u64,
+        source: DepositSource,
+    ) -> Result<()> {
+        if amount == 0 {
+            return Err(RewardsTooLow.into());
+        }
+
+        let reward_collection_account = &mut ctx.accounts.reward_collection_account;
+        if Clock::get()?.epoch > reward_collection_account.expires_at {
+            return Err(ExpiredRewardCollectionAccount.into());
+        }
+        if reward_collection_account.merkle_root.is_some() {
+            return Err(MerkleRootAlreadyUploaded.into());
+        }
+
+        invoke(
+            &system_instruction::transfer(
+                &ctx.accounts.depositor.key(),
+                &reward_collection_account.key(),
+                amount,
+            ),
+            &[
+                ctx.accounts.depositor.to_account_info(),
+                reward_collection_account.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+            ],
+        )?;
+        reward_collection_account.record_deposit(amount)?;
+
+        emit!(RewardsDepositedEvent {
+            reward_collection_account: reward_collection_account.key(),
+            depositor: ctx.accounts.depositor.key(),
+            amount,
+            source,
+        });
+
+        Ok(())
+    }
+
+    /// Permissionless; brings a [RewardCollectionAccount] of
+    /// [RewardCollectionAccount::ACCOUNT_VERSION_V0], in the layout of
+    /// [LegacyRewardCollectionAccountV0], to [RewardCollectionAccount::CURRENT_VERSION]. The
+    /// account is grown to [RewardCollectionAccount::SIZE], with `payer` funding the extra rent.
+    /// The running totals of a migrated account only count the leader turns transferred after the
+    /// migration.
+    pub fn migrate_reward_collection_account(
+        ctx: Context<MigrateRewardCollectionAccount>,
+    ) -> Result<()> {
+        let account_info = ctx.accounts.reward_collection_account.to_account_info();
+        if !account_info
+            .try_borrow_data()?
+            .starts_with(RewardCollectionAccount::DISCRIMINATOR)
+        {
+            return Err(AccountValidationFailure.into());
+        }
+
+        if account_info.data_len() != LegacyRewardCollectionAccountV0::SIZE {
+            return Err(AccountAlreadyMigrated.into());
+        }
+        let mut reward_collection_account: RewardCollectionAccount =
+            LegacyRewardCollectionAccountV0::try_deserialize(&account_info.try_borrow_data()?)?
+                .into();
+
+        let top_up = Rent::get()?
+            .minimum_balance(RewardCollectionAccount::SIZE)
+            .saturating_sub(account_info.lamports());
+        if top_up > 0 {
+            invoke(
+                &system_instruction::transfer(
+                    &ctx.accounts.payer.key(),
+                    &account_info.key(),
+                    top_up,
+                ),
+                &[
+                    ctx.accounts.payer.to_account_info(),
+                    account_info.clone(),
+                    ctx.accounts.system_program.to_account_info(),
+                ],
+            )?;
+        }
+        account_info.realloc(RewardCollectionAccount::SIZE, true)?;
+
+        let old_version = reward_collection_account.account_version;
+        reward_collection_account.account_version = RewardCollectionAccount::CURRENT_VERSION;
+        reward_collection_account
+            .try_serialize(&mut &mut account_info.try_borrow_mut_data()?[..])?;
+
+        emit!(RewardCollectionAccountMigratedEvent {
+            reward_collection_account: account_info.key(),
+            old_version,
+            new_version: RewardCollectionAccount::CURRENT_VERSION,
+        });
 
         Ok(())
     }
@@ -248,7 +576,9 @@ pub mod reward_distribution {
     }
 
     /// Sends unclaimed funds to the `initializer` and closes the [`RewardCollectionAccount`],
-    /// returning rent to the validator.
+    /// returning rent to the validator. Possible once the account has expired, or as soon as
+    /// every node of the uploaded merkle root has claimed. The unclaimed funds of an expired
+    /// account created with `rollover_unclaimed` are left to [rollover_unclaimed_rewards].
     pub fn close_reward_collection_account(
         ctx: Context<CloseRewardCollectionAccount>,
         _epoch: u64,
@@ -257,9 +587,16 @@ pub mod reward_distribution {
 
         let reward_collection_account = &mut ctx.accounts.reward_collection_account;
 
-        if Clock::get()?.epoch <= reward_collection_account.expires_at {
+        let fully_claimed = reward_collection_account
+            .merkle_root
+            .as_ref()
+            .is_some_and(MerkleRoot::is_fully_claimed);
+        if Clock::get()?.epoch <= reward_collection_account.expires_at && !fully_claimed {
             return Err(PrematureCloseRewardCollectionAccount.into());
         }
+        if reward_collection_account.rollover_unclaimed && !fully_claimed {
+            return Err(UnclaimedRewardsMustRollOver.into());
+        }
 
         let expired_amount = RewardCollectionAccount::claim_expired(
             reward_collection_account.to_account_info(),
@@ -276,135 +613,832 @@ pub mod reward_distribution {
         Ok(())
     }
 
-    /// Claims rewards for a staker from the [RewardCollectionAccount] according to their merkle proof.
-    pub fn claim(ctx: Context<Claim>, bump: u8, amount: u64, proof: Vec<[u8; 32]>) -> Result<()> {
-        let claim_status = &mut ctx.accounts.claim_status;
-        claim_status.bump = bump;
-
-        let claimant_account = &mut ctx.accounts.claimant;
+    /// Permissionless; closes an expired [RewardCollectionAccount] created with
+    /// `rollover_unclaimed` and moves its unclaimed funds into the validator's
+    /// [RewardCollectionAccount] of the current epoch, to be distributed to that epoch's stakers,
+    /// and added to its `deposited_rewards`. Like [deposit_rewards], only into a current account
+    /// at [RewardCollectionAccount::CURRENT_VERSION] whose merkle root is not uploaded yet; until
+    /// there is one, the expired account stays open with its funds. The rent goes back to the
+    /// validator.
+    pub fn rollover_unclaimed_rewards(
+        ctx: Context<RolloverUnclaimedRewards>,
+        _epoch: u64,
+    ) -> Result<()> {
         let reward_collection_account = &mut ctx.accounts.reward_collection_account;
-
-        let clock = Clock::get()?;
-        if clock.epoch > reward_collection_account.expires_at {
-            return Err(ExpiredRewardCollectionAccount.into());
+        if !reward_collection_account.rollover_unclaimed {
+            return Err(RolloverNotEnabled.into());
         }
-
-        if claim_status.is_claimed {
-            return Err(FundsAlreadyClaimed.into());
+        if Clock::get()?.epoch <= reward_collection_account.expires_at {
+            return Err(PrematureCloseRewardCollectionAccount.into());
         }
 
-        let reward_distribution_info = reward_collection_account.to_account_info();
-        let reward_distribution_epoch_expires_at = reward_collection_account.expires_at;
-        let merkle_root = reward_collection_account
-            .merkle_root
-            .as_mut()
-            .ok_or(RootNotUploaded)?;
+        let current_account_info = ctx
+            .accounts
+            .current_reward_collection_account
+            .to_account_info();
+        let current_reward_collection_account = if current_account_info.owner == &crate::ID {
+            RewardCollectionAccount::try_deserialize(
+                &mut &current_account_info.try_borrow_data()?[..],
+            )
+            .ok()
+            .filter(|current| {
+                current.account_version == RewardCollectionAccount::CURRENT_VERSION
+                    && current.merkle_root.is_none()
+            })
+        } else {
+            None
+        };
 
-        let node = &solana_program::hash::hashv(&[
-            &[0u8],
-            &solana_program::hash::hashv(&[
-                &claimant_account.key().to_bytes(),
-                &amount.to_le_bytes(),
-            ])
-            .to_bytes(),
-        ]);
+        let Some(mut current_reward_collection_account) = current_reward_collection_account else {
+            return Err(NoRolloverTarget.into());
+        };
 
-        if !merkle_proof::verify(proof, merkle_root.root, node.to_bytes()) {
-            return Err(InvalidProof.into());
-        }
+        let amount = RewardCollectionAccount::claim_expired(
+            reward_collection_account.to_account_info(),
+            current_account_info.clone(),
+        )?;
+        current_reward_collection_account.record_deposit(amount)?;
+        current_reward_collection_account
+            .try_serialize(&mut &mut current_account_info.try_borrow_mut_data()?[..])?;
+        reward_collection_account.validate()?;
 
-        RewardCollectionAccount::claim(
-            reward_distribution_info,
-            claimant_account.to_account_info(),
+        emit!(UnclaimedRewardsRolledOverEvent {
+            reward_collection_account: reward_collection_account.key(),
+            current_reward_collection_account: current_account_info.key(),
             amount,
-        )?;
+        });
 
-        claim_status.amount = amount;
-        claim_status.is_claimed = true;
-        claim_status.slot_claimed_at = clock.slot;
-        claim_status.claimant = claimant_account.key();
-        claim_status.claim_status_payer = ctx.accounts.payer.key();
-        claim_status.expires_at = reward_distribution_epoch_expires_at;
+        Ok(())
+    }
 
-        merkle_root.total_funds_claimed = merkle_root
-            .total_funds_claimed
-            .checked_add(amount)
-            .ok_or(ArithmeticError)?;
-        if merkle_root.total_funds_claimed > merkle_root.max_total_claim {
-            return Err(ExceedsMaxClaim.into());
+    /// Sends the lamports of a [RewardCollectionAccount] that no claim of its merkle root can take,
+    /// `balance - rent` minus what the outstanding `max_total_claim - total_funds_claimed` pays out
+    /// after the `claim_scale` haircut, back to the `initializer`.
+    /// Only the validator identity that initialized the account can invoke this, once a merkle
+    /// root is uploaded.
+    pub fn withdraw_surplus(ctx: Context<WithdrawSurplus>) -> Result<()> {
+        WithdrawSurplus::auth(&ctx)?;
+
+        let reward_collection_account = &ctx.accounts.reward_collection_account;
+        let amount =
+            reward_collection_account.surplus(&reward_collection_account.to_account_info())?;
+        if amount == 0 {
+            return Err(NoSurplus.into());
         }
 
-        merkle_root.num_nodes_claimed = merkle_root
-            .num_nodes_claimed
-            .checked_add(1)
-            .ok_or(ArithmeticError)?;
-        if merkle_root.num_nodes_claimed > merkle_root.max_num_nodes {
-            return Err(ExceedsMaxNumNodes.into());
-        }
+        RewardCollectionAccount::transfer_lamports(
+            reward_collection_account.to_account_info(),
+            ctx.accounts.initializer.to_account_info(),
+            amount,
+        )?;
 
-        emit!(ClaimedEvent {
+        emit!(SurplusWithdrawnEvent {
             reward_collection_account: reward_collection_account.key(),
-            payer: ctx.accounts.payer.key(),
-            claimant: claimant_account.key(),
-            amount
+            initializer: ctx.accounts.initializer.key(),
+            amount,
         });
 
-        reward_collection_account.validate()?;
+        Ok(())
+    }
 
+    /// Claims rewards for a staker from the [RewardCollectionAccount] according to their merkle proof.
+    pub fn claim(ctx: Context<Claim>, bump: u8, amount: u64, proof: Vec<[u8; 32]>) -> Result<()> {
+        ctx.accounts.claim_status.bump = bump;
+        settle_claim(
+            &mut ctx.accounts.reward_collection_account,
+            &mut ctx.accounts.claim_status,
+            &ctx.accounts.claimant,
+            &ctx.accounts.claimant,
+            ctx.accounts.payer.key(),
+            amount,
+            proof,
+        )?;
         Ok(())
     }
-}
 
-/// Custom errors for Rakurai activation instructions.
-#[error_code]
-pub enum ErrorCode {
-    #[msg("Account failed validation.")]
-    AccountValidationFailure,
+    /// Same as [claim], with the reward staked in `stake_account`, a new stake account delegated
+    /// to the validator the reward was earned with, so it compounds. The claimant signs and
+    /// becomes the stake and withdraw authority. The account's rent-exempt reserve is taken out of
+    /// the reward and stays the claimant's, so the payout must cover it plus the stake program's
+    /// minimum delegation. Topping up an existing stake account is not supported: the stake
+    /// program only adds lamports to an active delegation by merging two stake accounts, so a
+    /// claimant who wants a single account merges the new one into theirs afterwards.
+    pub fn claim_to_stake_account(
+        ctx: Context<ClaimToStakeAccount>,
+        amount: u64,
+        proof: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        ctx.accounts.claim_status.bump = ctx.bumps.claim_status;
+        let claimant_info = ctx.accounts.claimant.to_account_info();
+        let stake_info = ctx.accounts.stake_account.to_account_info();
+        let payout = settle_claim(
+            &mut ctx.accounts.reward_collection_account,
+            &mut ctx.accounts.claim_status,
+            &claimant_info,
+            &stake_info,
+            ctx.accounts.payer.key(),
+            amount,
+            proof,
+        )?;
+        let minimum_stake = ctx
+            .accounts
+            .rent
+            .minimum_balance(StakeStateV2::size_of())
+            .checked_add(stake::tools::get_minimum_delegation()?)
+            .ok_or(ArithmeticError)?;
+        if payout < minimum_stake {
+            return Err(ClaimBelowStakeReserve.into());
+        }
 
-    #[msg("Encountered an arithmetic under/overflow error.")]
-    ArithmeticError,
+        // The stake account is created from the lamports it was paid, the way the stake
+        // program's `create_account` would from a transfer.
+        let system_program = ctx.accounts.system_program.to_account_info();
+        invoke(
+            &system_instruction::allocate(stake_info.key, StakeStateV2::size_of() as u64),
+            &[stake_info.clone(), system_program.clone()],
+        )?;
+        invoke(
+            &system_instruction::assign(stake_info.key, &stake::program::ID),
+            &[stake_info.clone(), system_program],
+        )?;
+        invoke(
+            &stake::instruction::initialize(
+                stake_info.key,
+                &Authorized::auto(claimant_info.key),
+                &Lockup::default(),
+            ),
+            &[
+                stake_info.clone(),
+                ctx.accounts.rent.to_account_info(),
+                ctx.accounts.stake_program.to_account_info(),
+            ],
+        )?;
+        invoke(
+            &stake::instruction::delegate_stake(
+                stake_info.key,
+                claimant_info.key,
+                ctx.accounts.validator_vote_account.key,
+            ),
+            &[
+                stake_info.clone(),
+                ctx.accounts.validator_vote_account.to_account_info(),
+                ctx.accounts.clock.to_account_info(),
+                ctx.accounts.stake_history.to_account_info(),
+                ctx.accounts.stake_config.to_account_info(),
+                claimant_info.clone(),
+                ctx.accounts.stake_program.to_account_info(),
+            ],
+        )?;
 
-    #[msg("The maximum number of funds to be claimed has been exceeded.")]
-    ExceedsMaxClaim,
+        emit!(ClaimStakedEvent {
+            reward_collection_account: ctx.accounts.reward_collection_account.key(),
+            claimant: claimant_info.key(),
+            stake_account: stake_info.key(),
+            amount: payout,
+        });
 
-    #[msg("The maximum number of claims has been exceeded.")]
-    ExceedsMaxNumNodes,
+        Ok(())
+    }
 
-    #[msg("The given RewardCollectionAccount has expired.")]
-    ExpiredRewardCollectionAccount,
+    /// Same as [claim], with the reward deposited into an SPL stake pool through its `deposit_sol`
+    /// instruction, and the pool tokens minted to the claimant's `pool_tokens_to` account. The
+    /// claimant signs. Pools restricting SOL deposits to a deposit authority are not supported.
+    pub fn claim_to_stake_pool(
+        ctx: Context<ClaimToStakePool>,
+        amount: u64,
+        proof: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        let pool_tokens_to =
+            spl_token::state::Account::unpack(&ctx.accounts.pool_tokens_to.try_borrow_data()?)?;
+        if pool_tokens_to.owner != ctx.accounts.claimant.key()
+            || pool_tokens_to.mint != ctx.accounts.pool_mint.key()
+        {
+            return Err(InvalidPoolTokenAccount.into());
+        }
 
-    #[msg("The funds for the given index and RewardCollectionAccount have already been claimed.")]
-    FundsAlreadyClaimed,
+        ctx.accounts.claim_status.bump = ctx.bumps.claim_status;
+        let claimant_info = ctx.accounts.claimant.to_account_info();
+        let deposit_info = ctx.accounts.deposit_account.to_account_info();
+        let payout = settle_claim(
+            &mut ctx.accounts.reward_collection_account,
+            &mut ctx.accounts.claim_status,
+            &claimant_info,
+            &deposit_info,
+            ctx.accounts.payer.key(),
+            amount,
+            proof,
+        )?;
 
-    #[msg("The given proof is invalid.")]
-    InvalidProof,
+        // `deposit_sol` pulls the lamports with a system transfer, so they pass through a system
+        // account of this program that is emptied again right away.
+        let reward_collection_key = ctx.accounts.reward_collection_account.key();
+        let signer_seeds: &[&[u8]] = &[
+            RewardCollectionAccount::STAKE_POOL_DEPOSIT_SEED,
+            reward_collection_key.as_ref(),
+            &[ctx.bumps.deposit_account],
+        ];
+        invoke_signed(
+            &spl_stake_pool::instruction::deposit_sol(
+                &spl_stake_pool::ID,
+                ctx.accounts.stake_pool.key,
+                ctx.accounts.stake_pool_withdraw_authority.key,
+                ctx.accounts.reserve_stake.key,
+                deposit_info.key,
+                ctx.accounts.pool_tokens_to.key,
+                ctx.accounts.manager_fee_account.key,
+                ctx.accounts.pool_tokens_to.key,
+                ctx.accounts.pool_mint.key,
+                ctx.accounts.token_program.key,
+                payout,
+            ),
+            &[
+                ctx.accounts.stake_pool.to_account_info(),
+                ctx.accounts.stake_pool_withdraw_authority.to_account_info(),
+                ctx.accounts.reserve_stake.to_account_info(),
+                deposit_info,
+                ctx.accounts.pool_tokens_to.to_account_info(),
+                ctx.accounts.manager_fee_account.to_account_info(),
+                ctx.accounts.pool_mint.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+                ctx.accounts.token_program.to_account_info(),
+                ctx.accounts.stake_pool_program.to_account_info(),
+            ],
+            &[signer_seeds],
+        )?;
 
-    #[msg("Validator's commission basis points must be less than or equal to the RewardDistributionConfigAccount account's max_commission_bps.")]
-    MaxCommissionFeeBpsExceeded,
+        emit!(ClaimDepositedToStakePoolEvent {
+            reward_collection_account: reward_collection_key,
+            claimant: claimant_info.key(),
+            stake_pool: ctx.accounts.stake_pool.key(),
+            amount: payout,
+        });
 
-    #[msg("The given RewardCollectionAccount is not ready to be closed.")]
-    PrematureCloseRewardCollectionAccount,
+        Ok(())
+    }
 
-    #[msg("The given ClaimStatus account is not ready to be closed.")]
-    PrematureCloseClaimStatus,
+    /// Creates the [ProofBuffer] of `payer` for `claimant`'s leaf of the tree uploaded to the
+    /// reward collection account, for proofs of `proof_len` hashes that are too long to be
+    /// passed to [claim] inline.
+    pub fn initialize_proof_buffer(
+        ctx: Context<InitializeProofBuffer>,
+        claimant: Pubkey,
+        proof_len: u8,
+    ) -> Result<()> {
+        if proof_len == 0 || proof_len > ProofBuffer::MAX_PROOF_LEN {
+            return Err(InvalidProofLength.into());
+        }
 
-    #[msg("Must wait till at least one epoch after the reward distribution account was created to upload the merkle root.")]
-    PrematureMerkleRootUpload,
+        let proof_buffer = &mut ctx.accounts.proof_buffer;
+        proof_buffer.reward_collection_account = ctx.accounts.reward_collection_account.key();
+        proof_buffer.claimant = claimant;
+        proof_buffer.payer = ctx.accounts.payer.key();
+        proof_buffer.proof_len = proof_len;
+        proof_buffer.bump = ctx.bumps.proof_buffer;
 
-    #[msg("No merkle root has been uploaded to the given RewardCollectionAccount.")]
-    RootNotUploaded,
+        Ok(())
+    }
 
-    #[msg("Unauthorized signer.")]
-    Unauthorized,
+    /// Appends `hashes` to a [ProofBuffer]. `offset` is the number of hashes written before.
+    pub fn write_proof_buffer(
+        ctx: Context<WriteProofBuffer>,
+        offset: u8,
+        hashes: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        ctx.accounts.proof_buffer.write(offset, &hashes)
+    }
 
-    #[msg("Total rewards must be greater than 0.")]
-    RewardsTooLow,
+    /// Same as [claim], with the proof read from a complete [ProofBuffer]. The buffer is closed
+    /// and its rent returned to `payer`.
+    pub fn claim_with_proof_buffer(ctx: Context<ClaimWithProofBuffer>, amount: u64) -> Result<()> {
+        let proof = ctx.accounts.proof_buffer.proof()?;
+        ctx.accounts.claim_status.bump = ctx.bumps.claim_status;
+        settle_claim(
+            &mut ctx.accounts.reward_collection_account,
+            &mut ctx.accounts.claim_status,
+            &ctx.accounts.claimant,
+            &ctx.accounts.claimant,
+            ctx.accounts.payer.key(),
+            amount,
+            proof,
+        )?;
+        Ok(())
+    }
 
-    #[msg("Rakurai's commission account must be equal to the RewardCollectionAccount account's rakurai_commission_account.")]
-    InvalidRakuraiCommissionAccount,
-}
+    /// Closes a [ProofBuffer] that is no longer needed and returns its rent to `payer`.
+    pub fn close_proof_buffer(_ctx: Context<CloseProofBuffer>) -> Result<()> {
+        Ok(())
+    }
 
-/// Closes a `ClaimStatus` account and refunds lamports to the payer.
+    /// Claims rewards for several stakers in one transaction. For every entry of `claims`, the
+    /// remaining accounts hold the claimant followed by its [ClaimStatus] PDA, both writable.
+    /// The [ClaimStatus] accounts are created here and paid for by `payer`. The root's claim
+    /// totals are updated once for the whole batch.
+    pub fn claim_batch<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ClaimBatch<'info>>,
+        claims: Vec<BatchClaim>,
+    ) -> Result<()> {
+        if claims.is_empty() || ctx.remaining_accounts.len() != claims.len() * 2 {
+            return Err(InvalidRemainingAccounts.into());
+        }
+
+        let slot = Clock::get()?.slot;
+        let reward_collection_account = &mut ctx.accounts.reward_collection_account;
+        let reward_collection_key = reward_collection_account.key();
+        let claim_status_rent = Rent::get()?.minimum_balance(ClaimStatus::SIZE);
+        let num_claims = claims.len() as u64;
+        let mut total_amount: u64 = 0;
+
+        for (claim, accounts) in claims.into_iter().zip(ctx.remaining_accounts.chunks(2)) {
+            let claimant_account = &accounts[0];
+            let claim_status_account = &accounts[1];
+
+            total_amount = total_amount
+                .checked_add(claim.amount)
+                .ok_or(ArithmeticError)?;
+            let payout = pay_unrecorded_claim(
+                reward_collection_account,
+                claimant_account.key,
+                claimant_account,
+                ctx.accounts.payer.key(),
+                claim.amount,
+                claim.proof,
+            )?;
+
+            create_claim_status(
+                claim_status_account,
+                &reward_collection_key,
+                &ctx.accounts.payer,
+                &ctx.accounts.system_program,
+                claim_status_rent,
+                ClaimStatus {
+                    is_claimed: true,
+                    claimant: claimant_account.key(),
+                    claim_status_payer: ctx.accounts.payer.key(),
+                    slot_claimed_at: slot,
+                    amount: payout,
+                    expires_at: reward_collection_account.expires_at,
+                    bump: 0,
+                },
+            )?;
+        }
+
+        record_claims(reward_collection_account, total_amount, num_claims)
+    }
+
+    /// Claims the rewards of one staker from several [RewardCollectionAccount]s, e.g. of every
+    /// Rakurai validator the staker delegates to, in one transaction. For every entry of `claims`,
+    /// the remaining accounts hold the [RewardCollectionAccount] followed by the claimant's
+    /// [ClaimStatus] PDA on it, both writable. Either every claim settles or none does.
+    pub fn claim_aggregated<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ClaimAggregated<'info>>,
+        claims: Vec<AggregatedClaim>,
+    ) -> Result<()> {
+        if claims.is_empty() || ctx.remaining_accounts.len() != claims.len() * 2 {
+            return Err(InvalidRemainingAccounts.into());
+        }
+
+        let slot = Clock::get()?.slot;
+        let claimant_info = ctx.accounts.claimant.to_account_info();
+        let claim_status_rent = Rent::get()?.minimum_balance(ClaimStatus::SIZE);
+
+        for (claim, accounts) in claims.into_iter().zip(ctx.remaining_accounts.chunks(2)) {
+            let reward_collection_info = &accounts[0];
+            let claim_status_account = &accounts[1];
+
+            let mut reward_collection_account =
+                Account::<RewardCollectionAccount>::try_from(reward_collection_info)?;
+            let payout = pay_claim(
+                &mut reward_collection_account,
+                claimant_info.key,
+                &claimant_info,
+                ctx.accounts.payer.key(),
+                claim.amount,
+                claim.proof,
+            )?;
+            reward_collection_account.exit(&crate::ID)?;
+
+            create_claim_status(
+                claim_status_account,
+                &reward_collection_account.key(),
+                &ctx.accounts.payer,
+                &ctx.accounts.system_program,
+                claim_status_rent,
+                ClaimStatus {
+                    is_claimed: true,
+                    claimant: claimant_info.key(),
+                    claim_status_payer: ctx.accounts.payer.key(),
+                    slot_claimed_at: slot,
+                    amount: payout,
+                    expires_at: reward_collection_account.expires_at,
+                    bump: 0,
+                },
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Creates the [ClaimBitmap] of a [RewardCollectionAccount] whose merkle tree uses indexed
+    /// leaves. Permissionless; the bitmap is sized for the uploaded root's `max_num_nodes`, and
+    /// grows on the first claim if a larger root is uploaded afterwards.
+    pub fn initialize_claim_bitmap(ctx: Context<InitializeClaimBitmap>) -> Result<()> {
+        let reward_collection_account = &ctx.accounts.reward_collection_account;
+        let merkle_root = reward_collection_account
+            .merkle_root
+            .as_ref()
+            .ok_or(RootNotUploaded)?;
+        if merkle_root.max_num_nodes > ClaimBitmap::MAX_LEAVES {
+            return Err(ClaimBitmapTooLarge.into());
+        }
+
+        let claim_bitmap = &mut ctx.accounts.claim_bitmap;
+        claim_bitmap.reward_collection_account = reward_collection_account.key();
+        claim_bitmap.payer = ctx.accounts.payer.key();
+        claim_bitmap.expires_at = reward_collection_account.expires_at;
+        claim_bitmap.bump = ctx.bumps.claim_bitmap;
+        claim_bitmap.bits = vec![0; merkle_root.max_num_nodes.div_ceil(8) as usize];
+
+        Ok(())
+    }
+
+    /// Claims rewards for the staker at leaf `index` of an indexed merkle tree, recording the
+    /// claim in the [ClaimBitmap] instead of a [ClaimStatus] account.
+    pub fn claim_indexed(
+        ctx: Context<ClaimIndexed>,
+        index: u64,
+        amount: u64,
+        proof: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        let claimant_account = &ctx.accounts.claimant;
+        let reward_collection_account = &mut ctx.accounts.reward_collection_account;
+
+        if Clock::get()?.epoch > reward_collection_account.expires_at {
+            return Err(ExpiredRewardCollectionAccount.into());
+        }
+
+        let reward_distribution_info = reward_collection_account.to_account_info();
+        let domain = reward_collection_account.leaf_domain(reward_collection_account.key());
+        let leaf_version = reward_collection_account.leaf_version;
+        let payout = reward_collection_account.payout(amount)?;
+        let merkle_root = reward_collection_account
+            .merkle_root
+            .as_mut()
+            .ok_or(RootNotUploaded)?;
+        if index >= merkle_root.max_num_nodes {
+            return Err(InvalidLeafIndex.into());
+        }
+
+        merkle_root.verify_indexed_claim(
+            leaf_version,
+            &domain,
+            index,
+            &claimant_account.key(),
+            amount,
+            proof,
+        )?;
+        ClaimBitmap::grow(
+            &mut ctx.accounts.claim_bitmap,
+            merkle_root.max_num_nodes,
+            &ctx.accounts.payer,
+            &ctx.accounts.system_program,
+        )?;
+        ctx.accounts.claim_bitmap.set_claimed(index)?;
+
+        RewardCollectionAccount::claim(
+            reward_distribution_info,
+            claimant_account.to_account_info(),
+            payout,
+        )?;
+
+        merkle_root.record_claims(amount, 1)?;
+
+        emit!(ClaimedEvent {
+            reward_collection_account: reward_collection_account.key(),
+            payer: ctx.accounts.payer.key(),
+            claimant: claimant_account.key(),
+            amount: payout
+        });
+
+        reward_collection_account.validate()?;
+
+        Ok(())
+    }
+
+    /// Permissionless; closes a [ClaimBitmap] once it has expired, returning rent to its payer.
+    pub fn close_claim_bitmap(ctx: Context<CloseClaimBitmap>) -> Result<()> {
+        let claim_bitmap = &ctx.accounts.claim_bitmap;
+
+        if Clock::get()?.epoch <= claim_bitmap.expires_at {
+            return Err(PrematureCloseClaimBitmap.into());
+        }
+
+        emit!(ClaimBitmapClosedEvent {
+            payer: ctx.accounts.payer.key(),
+            claim_bitmap: claim_bitmap.key(),
+        });
+
+        Ok(())
+    }
+
+    /// Initializes the [CumulativeDistributor] of a validator vote account. Only the validator
+    /// identity can invoke this instruction.
+    pub fn initialize_cumulative_distributor(
+        ctx: Context<InitializeCumulativeDistributor>,
+        merkle_root_upload_authority: Pubkey,
+    ) -> Result<()> {
+        if ctx.accounts.validator_vote_account.owner != &solana_program::vote::program::id() {
+            return Err(Unauthorized.into());
+        }
+
+        let validator_vote_state =
+            VoteState::deserialize(&ctx.accounts.validator_vote_account).unwrap();
+        if &validator_vote_state.node_pubkey != ctx.accounts.signer.key {
+            return Err(Unauthorized.into());
+        }
+
+        let distributor = &mut ctx.accounts.distributor;
+        distributor.validator_vote_account = ctx.accounts.validator_vote_account.key();
+        distributor.initializer = ctx.accounts.signer.key();
+        distributor.merkle_root_upload_authority = merkle_root_upload_authority;
+        distributor.bump = ctx.bumps.distributor;
+        distributor.validate()?;
+
+        emit!(CumulativeDistributorInitializedEvent {
+            distributor: distributor.key(),
+        });
+
+        Ok(())
+    }
+
+    /// Moves the staker rewards of a past epoch's [RewardCollectionAccount] into the validator's
+    /// [CumulativeDistributor]. Only the initializer of the [RewardCollectionAccount] can invoke
+    /// this, and only while no per-epoch merkle root has been uploaded to it.
+    pub fn sweep_to_cumulative_distributor(
+        ctx: Context<SweepToCumulativeDistributor>,
+        _epoch: u64,
+    ) -> Result<()> {
+        SweepToCumulativeDistributor::auth(&ctx)?;
+
+        let reward_collection_account = &ctx.accounts.reward_collection_account;
+        if Clock::get()?.epoch <= reward_collection_account.creation_epoch {
+            return Err(PrematureSweep.into());
+        }
+
+        let amount = RewardCollectionAccount::claim_expired(
+            reward_collection_account.to_account_info(),
+            ctx.accounts.distributor.to_account_info(),
+        )?;
+        ctx.accounts.distributor.record_deposit(amount)?;
+
+        emit!(SweptToCumulativeDistributorEvent {
+            reward_collection_account: reward_collection_account.key(),
+            distributor: ctx.accounts.distributor.key(),
+            amount,
+        });
+
+        Ok(())
+    }
+
+    /// Replaces the root of a [CumulativeDistributor]. Its leaves hold every staker's lifetime
+    /// total, so `max_total_claim` can never decrease and must be covered by the funds swept in.
+    /// Only the distributor's `merkle_root_upload_authority` can invoke this instruction.
+    pub fn upload_cumulative_merkle_root(
+        ctx: Context<UploadCumulativeMerkleRoot>,
+        root: [u8; 32],
+        max_total_claim: u64,
+    ) -> Result<()> {
+        UploadCumulativeMerkleRoot::auth(&ctx)?;
+
+        let distributor = &mut ctx.accounts.distributor;
+        if max_total_claim < distributor.max_total_claim {
+            return Err(CumulativeTotalDecreased.into());
+        }
+        if max_total_claim > distributor.total_funds_deposited {
+            return Err(InsufficientDistributorFunds.into());
+        }
+
+        distributor.root = root;
+        distributor.max_total_claim = max_total_claim;
+        distributor.root_epoch = Clock::get()?.epoch;
+
+        emit!(CumulativeMerkleRootUploadedEvent {
+            merkle_root_upload_authority: ctx.accounts.merkle_root_upload_authority.key(),
+            distributor: distributor.key(),
+            max_total_claim,
+        });
+
+        Ok(())
+    }
+
+    /// Claims a staker's rewards from the [CumulativeDistributor]. The proof is for the staker's
+    /// lifetime `total_amount`; only the part not claimed before is paid out. The
+    /// [CumulativeClaimStatus] is created on the first claim and reused afterwards.
+    pub fn claim_cumulative(
+        ctx: Context<ClaimCumulative>,
+        total_amount: u64,
+        proof: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        let claimant_account = &ctx.accounts.claimant;
+        let distributor = &mut ctx.accounts.distributor;
+        let claim_status = &mut ctx.accounts.claim_status;
+
+        distributor.verify_claim(
+            &distributor.key(),
+            &claimant_account.key(),
+            total_amount,
+            proof,
+        )?;
+
+        let amount = total_amount
+            .checked_sub(claim_status.claimed_amount)
+            .ok_or(FundsAlreadyClaimed)?;
+        if amount == 0 {
+            return Err(FundsAlreadyClaimed.into());
+        }
+
+        CumulativeDistributor::claim(
+            distributor.to_account_info(),
+            claimant_account.to_account_info(),
+            amount,
+        )?;
+        distributor.record_claim(amount)?;
+
+        claim_status.distributor = distributor.key();
+        claim_status.claimant = claimant_account.key();
+        claim_status.claimed_amount = total_amount;
+        claim_status.slot_claimed_at = Clock::get()?.slot;
+        claim_status.bump = ctx.bumps.claim_status;
+
+        emit!(CumulativeClaimedEvent {
+            distributor: distributor.key(),
+            payer: ctx.accounts.payer.key(),
+            claimant: claimant_account.key(),
+            amount,
+            total_claimed: total_amount,
+        });
+
+        Ok(())
+    }
+}
+
+/// A single `(amount, proof)` entry of [reward_distribution::claim_batch]. The claimant is passed
+/// in the remaining accounts.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct BatchClaim {
+    /// Lamports the claimant is entitled to.
+    pub amount: u64,
+    /// Merkle proof of the `(claimant, amount)` leaf.
+    pub proof: Vec<[u8; 32]>,
+}
+
+/// A single `(amount, proof)` entry of [reward_distribution::claim_aggregated]. The
+/// [RewardCollectionAccount] and [ClaimStatus] are passed in the remaining accounts.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct AggregatedClaim {
+    /// Lamports the claimant is entitled to from the [RewardCollectionAccount].
+    pub amount: u64,
+    /// Merkle proof of the `(claimant, amount)` leaf.
+    pub proof: Vec<[u8; 32]>,
+}
+
+/// Custom errors for Rakurai activation instructions.
+#[error_code]
+pub enum ErrorCode {
+    #[msg("Account failed validation.")]
+    AccountValidationFailure,
+
+    #[msg("Encountered an arithmetic under/overflow error.")]
+    ArithmeticError,
+
+    #[msg("The maximum number of funds to be claimed has been exceeded.")]
+    ExceedsMaxClaim,
+
+    #[msg("The maximum number of claims has been exceeded.")]
+    ExceedsMaxNumNodes,
+
+    #[msg("The given RewardCollectionAccount has expired.")]
+    ExpiredRewardCollectionAccount,
+
+    #[msg("The funds for the given index and RewardCollectionAccount have already been claimed.")]
+    FundsAlreadyClaimed,
+
+    #[msg("The given proof is invalid.")]
+    InvalidProof,
+
+    #[msg("Validator's commission basis points must be less than or equal to the RewardDistributionConfigAccount account's max_commission_bps.")]
+    MaxCommissionFeeBpsExceeded,
+
+    #[msg("The given RewardCollectionAccount is not ready to be closed.")]
+    PrematureCloseRewardCollectionAccount,
+
+    #[msg("The given ClaimStatus account is not ready to be closed.")]
+    PrematureCloseClaimStatus,
+
+    #[msg("Must wait till at least one epoch after the reward distribution account was created to upload the merkle root.")]
+    PrematureMerkleRootUpload,
+
+    #[msg("No merkle root has been uploaded to the given RewardCollectionAccount.")]
+    RootNotUploaded,
+
+    #[msg("Unauthorized signer.")]
+    Unauthorized,
+
+    #[msg("Total rewards must be greater than 0.")]
+    RewardsTooLow,
+
+    #[msg("Rakurai's commission account must be equal to the RewardCollectionAccount account's rakurai_commission_account.")]
+    InvalidRakuraiCommissionAccount,
+
+    #[msg("Expected a claimant and a ClaimStatus account for every claim in the batch.")]
+    InvalidRemainingAccounts,
+
+    #[msg("The ClaimStatus account does not match the claimant and RewardCollectionAccount.")]
+    InvalidClaimStatusAccount,
+
+    #[msg("The leaf index is outside of the merkle tree.")]
+    InvalidLeafIndex,
+
+    #[msg("The merkle tree has more leaves than a ClaimBitmap can track.")]
+    ClaimBitmapTooLarge,
+
+    #[msg("The given ClaimBitmap account is not ready to be closed.")]
+    PrematureCloseClaimBitmap,
+
+    #[msg("Rakurai is not enabled in the validator's RakuraiActivationAccount.")]
+    RakuraiActivationDisabled,
+
+    #[msg("The RewardCollectionAccount can not be changed once a merkle root is uploaded.")]
+    MerkleRootAlreadyUploaded,
+
+    #[msg("The merkle root's leaf version is not supported.")]
+    UnsupportedLeafVersion,
+
+    #[msg("Must wait till at least one epoch after the reward collection account was created to sweep it.")]
+    PrematureSweep,
+
+    #[msg("The cumulative max_total_claim can not be lower than the previous one.")]
+    CumulativeTotalDecreased,
+
+    #[msg(
+        "The cumulative max_total_claim exceeds the funds swept into the CumulativeDistributor."
+    )]
+    InsufficientDistributorFunds,
+
+    #[msg("A proof buffer holds between 1 and 32 hashes.")]
+    InvalidProofLength,
+
+    #[msg("Proof buffer writes must start where the previous write ended and not exceed the proof length.")]
+    InvalidProofBufferWrite,
+
+    #[msg("The proof buffer is missing hashes.")]
+    ProofBufferIncomplete,
+
+    #[msg("The RewardCollectionAccount must be migrated to the current account version first.")]
+    OutdatedAccountVersion,
+
+    #[msg("The RewardCollectionAccount is already at the current account version.")]
+    AccountAlreadyMigrated,
+
+    #[msg("The start slot of a leader turn must not be after its end slot.")]
+    InvalidSlotRange,
+
+    #[msg("The leader turn overlaps or precedes a turn already settled in the RewardCollectionAccount.")]
+    SlotRangeAlreadySettled,
+
+    #[msg("The RewardCollectionAccount holds no funds beyond its rent and outstanding claims.")]
+    NoSurplus,
+
+    #[msg("The RewardCollectionAccount was not created to roll its unclaimed rewards over.")]
+    RolloverNotEnabled,
+
+    #[msg("The unclaimed rewards of the RewardCollectionAccount must be rolled over to the validator's current RewardCollectionAccount.")]
+    UnclaimedRewardsMustRollOver,
+
+    #[msg("The claim does not cover the rent-exempt reserve of a stake account plus the minimum delegation.")]
+    ClaimBelowStakeReserve,
+
+    #[msg(
+        "The pool token account must be the claimant's token account for the stake pool's mint."
+    )]
+    InvalidPoolTokenAccount,
+
+    #[msg("Attesters must be distinct, at most RootAttestation::MAX_ATTESTERS, and at least the threshold, which must not be zero.")]
+    InvalidAttesters,
+
+    #[msg("The RootAttestation is not the merkle root upload authority of the RewardCollectionAccount.")]
+    RootAttestationNotInUse,
+
+    #[msg("Can't close RootAttestation before it expires.")]
+    PrematureCloseRootAttestation,
+
+    #[msg("The validator's RewardCollectionAccount of the current epoch can't take the rolled over rewards.")]
+    NoRolloverTarget,
+
+    #[msg("The merkle root upload is handed to the RootAttestation and can only be attested.")]
+    RootAttestationInUse,
+}
+
+/// Closes a `ClaimStatus` account and refunds lamports to the payer.
 #[derive(Accounts)]
 pub struct CloseClaimStatus<'info> {
     /// The global configuration account for Rakurai settings.
@@ -448,11 +1482,7 @@ pub struct Initialize<'info> {
 
 /// Initializes a new reward collection account for a validator at the current epoch.
 #[derive(Accounts)]
-#[instruction(
-    _merkle_root_upload_authority: Pubkey,
-    _validator_commission_bps: u16,
-    _bump: u8
-)]
+#[instruction(_merkle_root_upload_authority: Pubkey, _bump: u8)]
 pub struct InitializeRewardCollectionAccount<'info> {
     /// The global configuration account for Rakurai settings.
     pub config: Account<'info, RewardDistributionConfigAccount>,
@@ -478,6 +1508,22 @@ pub struct InitializeRewardCollectionAccount<'info> {
     #[account(mut)]
     pub signer: Signer<'info>,
 
+    /// The validator's activation account, owned by the `rakurai_activation` program.
+    #[account(
+        seeds = [RakuraiActivationAccount::SEED, signer.key().as_ref()],
+        bump = activation_account.bump,
+        seeds::program = rakurai_activation::ID
+    )]
+    pub activation_account: Account<'info, RakuraiActivationAccount>,
+
+    /// The `rakurai_activation` config holding Rakurai's commission account.
+    #[account(
+        seeds = [RakuraiActivationConfigAccount::SEED],
+        bump = activation_config.bump,
+        seeds::program = rakurai_activation::ID
+    )]
+    pub activation_config: Account<'info, RakuraiActivationConfigAccount>,
+
     pub system_program: Program<'info, System>,
 }
 
@@ -503,40 +1549,765 @@ impl UpdateConfig<'_> {
     }
 }
 
-/// Instruction to close a reward collection account after the epoch has ended.
+/// Instruction to close a reward collection account after the epoch has ended.
+#[derive(Accounts)]
+#[instruction(epoch: u64)]
+pub struct CloseRewardCollectionAccount<'info> {
+    /// The global configuration account for Rakurai settings.
+    pub config: Account<'info, RewardDistributionConfigAccount>,
+
+    /// CHECK:
+    #[account(mut)]
+    pub initializer: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        close = validator_vote_account,
+        seeds = [
+            RewardCollectionAccount::SEED,
+            validator_vote_account.key().as_ref(),
+            epoch.to_le_bytes().as_ref(),
+        ],
+        bump = reward_collection_account.bump,
+    )]
+    pub reward_collection_account: Account<'info, RewardCollectionAccount>,
+
+    /// CHECK: safe see auth fn
+    #[account(mut)]
+    pub validator_vote_account: AccountInfo<'info>,
+
+    #[account(mut)]
+    pub signer: Signer<'info>,
+}
+
+impl CloseRewardCollectionAccount<'_> {
+    fn auth(ctx: &Context<CloseRewardCollectionAccount>) -> Result<()> {
+        if ctx.accounts.reward_collection_account.initializer != ctx.accounts.initializer.key() {
+            Err(Unauthorized.into())
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Closes an expired reward collection account, moving its unclaimed funds into the validator's
+/// reward collection account of the current epoch.
+#[derive(Accounts)]
+#[instruction(epoch: u64)]
+pub struct RolloverUnclaimedRewards<'info> {
+    #[account(
+        mut,
+        close = validator_vote_account,
+        seeds = [
+            RewardCollectionAccount::SEED,
+            validator_vote_account.key().as_ref(),
+            epoch.to_le_bytes().as_ref(),
+        ],
+        bump = reward_collection_account.bump,
+    )]
+    pub reward_collection_account: Account<'info, RewardCollectionAccount>,
+
+    /// CHECK: The validator's [RewardCollectionAccount] of the current epoch, receiving the
+    /// unclaimed funds. Must exist and still take deposits; checked in the instruction.
+    #[account(
+        mut,
+        seeds = [
+            RewardCollectionAccount::SEED,
+            validator_vote_account.key().as_ref(),
+            Clock::get().unwrap().epoch.to_le_bytes().as_ref(),
+        ],
+        bump,
+    )]
+    pub current_reward_collection_account: UncheckedAccount<'info>,
+
+    /// CHECK: Seed of both [RewardCollectionAccount]s; receives the rent of the closed one.
+    #[account(mut)]
+    pub validator_vote_account: AccountInfo<'info>,
+}
+
+/// Withdraws the surplus of a reward collection account to its initializer.
+#[derive(Accounts)]
+pub struct WithdrawSurplus<'info> {
+    #[account(mut, rent_exempt = enforce)]
+    pub reward_collection_account: Account<'info, RewardCollectionAccount>,
+
+    /// The validator identity that initialized the [RewardCollectionAccount], receiving the
+    /// surplus.
+    #[account(mut)]
+    pub initializer: Signer<'info>,
+}
+
+impl WithdrawSurplus<'_> {
+    fn auth(ctx: &Context<WithdrawSurplus>) -> Result<()> {
+        if ctx.accounts.reward_collection_account.initializer != ctx.accounts.initializer.key() {
+            Err(Unauthorized.into())
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Instruction to claim a portion of the reward collection.
+/// A new `ClaimStatus` account is created to prevent double claims.
+#[derive(Accounts)]
+#[instruction(_bump: u8, _amount: u64, _proof: Vec<[u8; 32]>)]
+pub struct Claim<'info> {
+    /// The global configuration account for Rakurai settings.
+    pub config: Account<'info, RewardDistributionConfigAccount>,
+
+    #[account(mut, rent_exempt = enforce)]
+    pub reward_collection_account: Account<'info, RewardCollectionAccount>,
+
+    /// Status of the claim. Used to prevent the same party from claiming multiple times.
+    #[account(
+        init,
+        rent_exempt = enforce,
+        seeds = [
+            ClaimStatus::SEED,
+            claimant.key().as_ref(),
+            reward_collection_account.key().as_ref()
+        ],
+        bump,
+        space = ClaimStatus::SIZE,
+        payer = payer
+    )]
+    pub claim_status: Account<'info, ClaimStatus>,
+
+    /// CHECK: This is safe.
+    /// Receiver of the funds.
+    #[account(mut)]
+    pub claimant: AccountInfo<'info>,
+
+    /// Fee payer for the claim transaction.
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Same as [Claim], with the reward staked in a new stake account of the claimant.
+#[derive(Accounts)]
+pub struct ClaimToStakeAccount<'info> {
+    /// The global configuration account for Rakurai settings.
+    #[account(seeds = [RewardDistributionConfigAccount::SEED], bump)]
+    pub config: Account<'info, RewardDistributionConfigAccount>,
+
+    #[account(mut, rent_exempt = enforce)]
+    pub reward_collection_account: Account<'info, RewardCollectionAccount>,
+
+    /// Status of the claim. Used to prevent the same party from claiming multiple times.
+    #[account(
+        init,
+        rent_exempt = enforce,
+        seeds = [
+            ClaimStatus::SEED,
+            claimant.key().as_ref(),
+            reward_collection_account.key().as_ref()
+        ],
+        bump,
+        space = ClaimStatus::SIZE,
+        payer = payer
+    )]
+    pub claim_status: Account<'info, ClaimStatus>,
+
+    /// Claimant of the leaf; becomes the stake and withdraw authority of the stake account.
+    pub claimant: Signer<'info>,
+
+    /// New stake account receiving the reward, from a fresh keypair.
+    #[account(mut)]
+    pub stake_account: Signer<'info>,
+
+    /// CHECK: The vote account the reward was earned with, which the stake is delegated to.
+    #[account(address = reward_collection_account.validator_vote_account)]
+    pub validator_vote_account: UncheckedAccount<'info>,
+
+    /// CHECK: The stake config account, passed along to the stake program's delegate instruction.
+    #[account(address = STAKE_CONFIG_ID)]
+    pub stake_config: UncheckedAccount<'info>,
+
+    /// CHECK: The stake history sysvar.
+    #[account(address = solana_program::sysvar::stake_history::ID)]
+    pub stake_history: UncheckedAccount<'info>,
+
+    pub clock: Sysvar<'info, Clock>,
+
+    pub rent: Sysvar<'info, Rent>,
+
+    /// CHECK: The stake program.
+    #[account(address = solana_program::stake::program::ID)]
+    pub stake_program: UncheckedAccount<'info>,
+
+    /// Fee payer for the claim transaction.
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Same as [Claim], with the reward deposited into an SPL stake pool for the claimant.
+#[derive(Accounts)]
+pub struct ClaimToStakePool<'info> {
+    /// The global configuration account for Rakurai settings.
+    #[account(seeds = [RewardDistributionConfigAccount::SEED], bump)]
+    pub config: Account<'info, RewardDistributionConfigAccount>,
+
+    #[account(mut, rent_exempt = enforce)]
+    pub reward_collection_account: Account<'info, RewardCollectionAccount>,
+
+    /// Status of the claim. Used to prevent the same party from claiming multiple times.
+    #[account(
+        init,
+        rent_exempt = enforce,
+        seeds = [
+            ClaimStatus::SEED,
+            claimant.key().as_ref(),
+            reward_collection_account.key().as_ref()
+        ],
+        bump,
+        space = ClaimStatus::SIZE,
+        payer = payer
+    )]
+    pub claim_status: Account<'info, ClaimStatus>,
+
+    /// Claimant of the leaf, owner of `pool_tokens_to`.
+    pub claimant: Signer<'info>,
+
+    /// System account the reward passes through, as `deposit_sol` transfers from a system account.
+    #[account(
+        mut,
+        seeds = [
+            RewardCollectionAccount::STAKE_POOL_DEPOSIT_SEED,
+            reward_collection_account.key().as_ref()
+        ],
+        bump
+    )]
+    pub deposit_account: SystemAccount<'info>,
+
+    /// CHECK: Checked by the stake pool program.
+    #[account(mut)]
+    pub stake_pool: UncheckedAccount<'info>,
+
+    /// CHECK: Checked by the stake pool program.
+    pub stake_pool_withdraw_authority: UncheckedAccount<'info>,
+
+    /// CHECK: Checked by the stake pool program.
+    #[account(mut)]
+    pub reserve_stake: UncheckedAccount<'info>,
+
+    /// CHECK: Checked in the instruction to be a token account of the claimant for `pool_mint`;
+    /// also receives the referral fee.
+    #[account(mut)]
+    pub pool_tokens_to: UncheckedAccount<'info>,
+
+    /// CHECK: Checked by the stake pool program.
+    #[account(mut)]
+    pub manager_fee_account: UncheckedAccount<'info>,
+
+    /// CHECK: Checked by the stake pool program.
+    #[account(mut)]
+    pub pool_mint: UncheckedAccount<'info>,
+
+    /// CHECK: The SPL token program.
+    #[account(address = spl_token::ID)]
+    pub token_program: UncheckedAccount<'info>,
+
+    /// CHECK: The SPL stake pool program.
+    #[account(address = spl_stake_pool::ID)]
+    pub stake_pool_program: UncheckedAccount<'info>,
+
+    /// Fee payer for the claim transaction.
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Creates a proof buffer for a claim on a reward collection account.
+#[derive(Accounts)]
+#[instruction(claimant: Pubkey, proof_len: u8)]
+pub struct InitializeProofBuffer<'info> {
+    /// The global configuration account for Rakurai settings.
+    #[account(seeds = [RewardDistributionConfigAccount::SEED], bump)]
+    pub config: Account<'info, RewardDistributionConfigAccount>,
+
+    pub reward_collection_account: Account<'info, RewardCollectionAccount>,
+
+    #[account(
+        init,
+        rent_exempt = enforce,
+        seeds = [
+            ProofBuffer::SEED,
+            reward_collection_account.key().as_ref(),
+            claimant.as_ref(),
+            payer.key().as_ref()
+        ],
+        bump,
+        space = ProofBuffer::size(proof_len),
+        payer = payer
+    )]
+    pub proof_buffer: Account<'info, ProofBuffer>,
+
+    /// Pays the rent of the buffer and is the only one allowed to use it.
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Writes part of a proof into a proof buffer.
+#[derive(Accounts)]
+pub struct WriteProofBuffer<'info> {
+    #[account(mut, has_one = payer)]
+    pub proof_buffer: Account<'info, ProofBuffer>,
+
+    pub payer: Signer<'info>,
+}
+
+/// Same as [Claim], with the proof read from the payer's proof buffer, which is closed.
+#[derive(Accounts)]
+pub struct ClaimWithProofBuffer<'info> {
+    /// The global configuration account for Rakurai settings.
+    #[account(seeds = [RewardDistributionConfigAccount::SEED], bump)]
+    pub config: Account<'info, RewardDistributionConfigAccount>,
+
+    #[account(mut, rent_exempt = enforce)]
+    pub reward_collection_account: Account<'info, RewardCollectionAccount>,
+
+    /// Status of the claim. Used to prevent the same party from claiming multiple times.
+    #[account(
+        init,
+        rent_exempt = enforce,
+        seeds = [
+            ClaimStatus::SEED,
+            claimant.key().as_ref(),
+            reward_collection_account.key().as_ref()
+        ],
+        bump,
+        space = ClaimStatus::SIZE,
+        payer = payer
+    )]
+    pub claim_status: Account<'info, ClaimStatus>,
+
+    /// CHECK: This is safe.
+    /// Receiver of the funds.
+    #[account(mut)]
+    pub claimant: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        close = payer,
+        seeds = [
+            ProofBuffer::SEED,
+            reward_collection_account.key().as_ref(),
+            claimant.key().as_ref(),
+            payer.key().as_ref()
+        ],
+        bump = proof_buffer.bump
+    )]
+    pub proof_buffer: Account<'info, ProofBuffer>,
+
+    /// Fee payer for the claim transaction, and payer of the proof buffer.
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Closes a proof buffer and refunds lamports to its payer.
+#[derive(Accounts)]
+pub struct CloseProofBuffer<'info> {
+    #[account(mut, close = payer, has_one = payer)]
+    pub proof_buffer: Account<'info, ProofBuffer>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+}
+
+/// Instruction to claim rewards for several stakers at once. Claimants and their `ClaimStatus`
+/// accounts are passed as remaining accounts, see [reward_distribution::claim_batch].
+#[derive(Accounts)]
+pub struct ClaimBatch<'info> {
+    /// The global configuration account for Rakurai settings.
+    pub config: Account<'info, RewardDistributionConfigAccount>,
+
+    #[account(mut, rent_exempt = enforce)]
+    pub reward_collection_account: Account<'info, RewardCollectionAccount>,
+
+    /// Fee payer for the claim transaction and the new `ClaimStatus` accounts.
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Instruction to claim rewards of one staker from several reward collection accounts at once.
+/// The accounts and `ClaimStatus` PDAs are passed as remaining accounts, see
+/// [reward_distribution::claim_aggregated].
+#[derive(Accounts)]
+pub struct ClaimAggregated<'info> {
+    /// The global configuration account for Rakurai settings.
+    #[account(seeds = [RewardDistributionConfigAccount::SEED], bump)]
+    pub config: Account<'info, RewardDistributionConfigAccount>,
+
+    /// CHECK: This is safe.
+    /// Receiver of the funds.
+    #[account(mut)]
+    pub claimant: AccountInfo<'info>,
+
+    /// Fee payer for the claim transaction and the new `ClaimStatus` accounts.
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Creates the claim bitmap of a reward collection account with an uploaded merkle root.
+#[derive(Accounts)]
+pub struct InitializeClaimBitmap<'info> {
+    /// The global configuration account for Rakurai settings.
+    pub config: Account<'info, RewardDistributionConfigAccount>,
+
+    pub reward_collection_account: Account<'info, RewardCollectionAccount>,
+
+    #[account(
+        init,
+        rent_exempt = enforce,
+        seeds = [
+            ClaimBitmap::SEED,
+            reward_collection_account.key().as_ref()
+        ],
+        bump,
+        space = ClaimBitmap::size_for(&reward_collection_account),
+        payer = payer
+    )]
+    pub claim_bitmap: Account<'info, ClaimBitmap>,
+
+    /// Pays the rent of the bitmap and receives it back on close.
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Instruction to claim a leaf of an indexed merkle tree.
+/// The claim is recorded in the reward collection account's `ClaimBitmap`.
+#[derive(Accounts)]
+pub struct ClaimIndexed<'info> {
+    /// The global configuration account for Rakurai settings.
+    pub config: Account<'info, RewardDistributionConfigAccount>,
+
+    #[account(mut, rent_exempt = enforce)]
+    pub reward_collection_account: Account<'info, RewardCollectionAccount>,
+
+    #[account(
+        mut,
+        seeds = [
+            ClaimBitmap::SEED,
+            reward_collection_account.key().as_ref()
+        ],
+        bump = claim_bitmap.bump
+    )]
+    pub claim_bitmap: Account<'info, ClaimBitmap>,
+
+    /// CHECK: This is safe.
+    /// Receiver of the funds.
+    #[account(mut)]
+    pub claimant: AccountInfo<'info>,
+
+    /// Fee payer for the claim transaction, and for the rent of the bitmap if it has to grow.
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Closes an expired `ClaimBitmap` account and refunds lamports to the payer.
+#[derive(Accounts)]
+pub struct CloseClaimBitmap<'info> {
+    #[account(
+        mut,
+        close = payer,
+        constraint = payer.key() == claim_bitmap.payer
+    )]
+    pub claim_bitmap: Account<'info, ClaimBitmap>,
+
+    /// CHECK: This is checked against claim_bitmap in the constraint
+    /// Account that receives the closed account's lamports.
+    #[account(mut)]
+    pub payer: UncheckedAccount<'info>,
+}
+
+/// Accounts required to upload a Merkle root for reward distribution.
+#[derive(Accounts)]
+pub struct UploadMerkleRoot<'info> {
+    /// The global configuration account for Rakurai settings.
+    pub config: Account<'info, RewardDistributionConfigAccount>,
+
+    #[account(mut, rent_exempt = enforce)]
+    pub reward_collection_account: Account<'info, RewardCollectionAccount>,
+
+    #[account(mut)]
+    pub merkle_root_upload_authority: Signer<'info>,
+}
+
+impl UploadMerkleRoot<'_> {
+    fn auth(ctx: &Context<UploadMerkleRoot>) -> Result<()> {
+        if ctx.accounts.merkle_root_upload_authority.key()
+            != ctx
+                .accounts
+                .reward_collection_account
+                .merkle_root_upload_authority
+        {
+            Err(Unauthorized.into())
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Changes the merkle root upload authority of a [RewardCollectionAccount] before its merkle
+/// root is uploaded.
+#[derive(Accounts)]
+pub struct UpdateMerkleRootUploadAuthority<'info> {
+    #[account(mut, rent_exempt = enforce)]
+    pub reward_collection_account: Account<'info, RewardCollectionAccount>,
+
+    /// The validator identity that initialized the [RewardCollectionAccount].
+    pub signer: Signer<'info>,
+}
+
+impl UpdateMerkleRootUploadAuthority<'_> {
+    fn auth(ctx: &Context<UpdateMerkleRootUploadAuthority>) -> Result<()> {
+        let reward_collection_account = &ctx.accounts.reward_collection_account;
+        check_reward_collection_account_update(reward_collection_account, &ctx.accounts.signer)?;
+
+        let (root_attestation, _) = Pubkey::find_program_address(
+            &[
+                RootAttestation::SEED,
+                reward_collection_account.key().as_ref(),
+            ],
+            &crate::ID,
+        );
+        if reward_collection_account.merkle_root_upload_authority == root_attestation {
+            Err(RootAttestationInUse.into())
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Creates the root attestation of a [RewardCollectionAccount] and makes it the account's merkle
+/// root upload authority.
+#[derive(Accounts)]
+#[instruction(attesters: Vec<Pubkey>)]
+pub struct InitializeRootAttestation<'info> {
+    #[account(mut, rent_exempt = enforce)]
+    pub reward_collection_account: Account<'info, RewardCollectionAccount>,
+
+    #[account(
+        init,
+        rent_exempt = enforce,
+        seeds = [
+            RootAttestation::SEED,
+            reward_collection_account.key().as_ref()
+        ],
+        bump,
+        space = RootAttestation::size(attesters.len()),
+        payer = signer
+    )]
+    pub root_attestation: Account<'info, RootAttestation>,
+
+    /// The validator identity that initialized the [RewardCollectionAccount]. Pays the rent of the
+    /// attestation and receives it back on close.
+    #[account(mut)]
+    pub signer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl InitializeRootAttestation<'_> {
+    fn auth(ctx: &Context<InitializeRootAttestation>) -> Result<()> {
+        check_reward_collection_account_update(
+            &ctx.accounts.reward_collection_account,
+            &ctx.accounts.signer,
+        )
+    }
+}
+
+/// Accounts required to attest a Merkle root of a reward collection account.
+#[derive(Accounts)]
+pub struct AttestMerkleRoot<'info> {
+    /// The global configuration account for Rakurai settings.
+    pub config: Account<'info, RewardDistributionConfigAccount>,
+
+    #[account(mut, rent_exempt = enforce)]
+    pub reward_collection_account: Account<'info, RewardCollectionAccount>,
+
+    #[account(
+        mut,
+        seeds = [
+            RootAttestation::SEED,
+            reward_collection_account.key().as_ref()
+        ],
+        bump = root_attestation.bump
+    )]
+    pub root_attestation: Account<'info, RootAttestation>,
+
+    /// One of the `attesters` of the [RootAttestation].
+    pub attester: Signer<'info>,
+}
+
+impl AttestMerkleRoot<'_> {
+    fn auth(ctx: &Context<AttestMerkleRoot>) -> Result<()> {
+        if ctx
+            .accounts
+            .reward_collection_account
+            .merkle_root_upload_authority
+            != ctx.accounts.root_attestation.key()
+        {
+            Err(RootAttestationNotInUse.into())
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Closes an expired `RootAttestation` account and refunds lamports to the payer.
+#[derive(Accounts)]
+pub struct CloseRootAttestation<'info> {
+    #[account(
+        mut,
+        close = payer,
+        constraint = payer.key() == root_attestation.payer
+    )]
+    pub root_attestation: Account<'info, RootAttestation>,
+
+    /// CHECK: This is checked against root_attestation in the constraint
+    /// Account that receives the closed account's lamports.
+    #[account(mut)]
+    pub payer: UncheckedAccount<'info>,
+}
+
+/// Changes the validator commission of a [RewardCollectionAccount] before its merkle root is
+/// uploaded.
+#[derive(Accounts)]
+pub struct UpdateValidatorCommission<'info> {
+    /// The global configuration account for Rakurai settings.
+    #[account(seeds = [RewardDistributionConfigAccount::SEED], bump)]
+    pub config: Account<'info, RewardDistributionConfigAccount>,
+
+    #[account(mut, rent_exempt = enforce)]
+    pub reward_collection_account: Account<'info, RewardCollectionAccount>,
+
+    /// The validator identity that initialized the [RewardCollectionAccount].
+    pub signer: Signer<'info>,
+}
+
+impl UpdateValidatorCommission<'_> {
+    fn auth(ctx: &Context<UpdateValidatorCommission>) -> Result<()> {
+        check_reward_collection_account_update(
+            &ctx.accounts.reward_collection_account,
+            &ctx.accounts.signer,
+        )
+    }
+}
+
+/// A [RewardCollectionAccount] can only be changed by its initializer, and only until a merkle
+/// root is uploaded: stakers' claims are computed against the values in place at upload.
+fn check_reward_collection_account_update(
+    reward_collection_account: &RewardCollectionAccount,
+    signer: &Signer,
+) -> Result<()> {
+    if reward_collection_account.initializer != signer.key() {
+        return Err(Unauthorized.into());
+    }
+    if reward_collection_account.merkle_root.is_some() {
+        return Err(MerkleRootAlreadyUploaded.into());
+    }
+    Ok(())
+}
+
+/// Initializes the cumulative distributor of a validator vote account.
 #[derive(Accounts)]
-#[instruction(epoch: u64)]
-pub struct CloseRewardCollectionAccount<'info> {
+pub struct InitializeCumulativeDistributor<'info> {
     /// The global configuration account for Rakurai settings.
+    #[account(seeds = [RewardDistributionConfigAccount::SEED], bump)]
     pub config: Account<'info, RewardDistributionConfigAccount>,
 
-    /// CHECK:
+    #[account(
+        init,
+        seeds = [
+            CumulativeDistributor::SEED,
+            validator_vote_account.key().as_ref(),
+        ],
+        bump,
+        payer = signer,
+        space = CumulativeDistributor::SIZE,
+        rent_exempt = enforce
+    )]
+    pub distributor: Account<'info, CumulativeDistributor>,
+
+    /// CHECK: The validator's vote account, checked against the signer in the instruction.
+    pub validator_vote_account: AccountInfo<'info>,
+
+    /// The validator's identity account.
     #[account(mut)]
-    pub initializer: AccountInfo<'info>,
+    pub signer: Signer<'info>,
 
+    pub system_program: Program<'info, System>,
+}
+
+/// Sweeps the staker rewards of a reward collection account into the cumulative distributor of
+/// the same vote account.
+#[derive(Accounts)]
+#[instruction(epoch: u64)]
+pub struct SweepToCumulativeDistributor<'info> {
     #[account(
         mut,
-        close = validator_vote_account,
+        rent_exempt = enforce,
         seeds = [
             RewardCollectionAccount::SEED,
-            validator_vote_account.key().as_ref(),
+            distributor.validator_vote_account.as_ref(),
             epoch.to_le_bytes().as_ref(),
         ],
         bump = reward_collection_account.bump,
     )]
     pub reward_collection_account: Account<'info, RewardCollectionAccount>,
 
-    /// CHECK: safe see auth fn
-    #[account(mut)]
-    pub validator_vote_account: AccountInfo<'info>,
+    #[account(mut, rent_exempt = enforce)]
+    pub distributor: Account<'info, CumulativeDistributor>,
 
-    #[account(mut)]
+    /// The validator identity that initialized the [RewardCollectionAccount].
     pub signer: Signer<'info>,
 }
 
-impl CloseRewardCollectionAccount<'_> {
-    fn auth(ctx: &Context<CloseRewardCollectionAccount>) -> Result<()> {
-        if ctx.accounts.reward_collection_account.initializer != ctx.accounts.initializer.key() {
+impl SweepToCumulativeDistributor<'_> {
+    fn auth(ctx: &Context<SweepToCumulativeDistributor>) -> Result<()> {
+        let reward_collection_account = &ctx.accounts.reward_collection_account;
+        if reward_collection_account.initializer != ctx.accounts.signer.key() {
+            return Err(Unauthorized.into());
+        }
+        if reward_collection_account.merkle_root.is_some() {
+            return Err(MerkleRootAlreadyUploaded.into());
+        }
+        Ok(())
+    }
+}
+
+/// Accounts required to upload the merkle root of a cumulative distributor.
+#[derive(Accounts)]
+pub struct UploadCumulativeMerkleRoot<'info> {
+    #[account(mut, rent_exempt = enforce)]
+    pub distributor: Account<'info, CumulativeDistributor>,
+
+    pub merkle_root_upload_authority: Signer<'info>,
+}
+
+impl UploadCumulativeMerkleRoot<'_> {
+    fn auth(ctx: &Context<UploadCumulativeMerkleRoot>) -> Result<()> {
+        if ctx.accounts.merkle_root_upload_authority.key()
+            != ctx.accounts.distributor.merkle_root_upload_authority
+        {
             Err(Unauthorized.into())
         } else {
             Ok(())
@@ -544,31 +2315,27 @@ impl CloseRewardCollectionAccount<'_> {
     }
 }
 
-/// Instruction to claim a portion of the reward collection.
-/// A new `ClaimStatus` account is created to prevent double claims.
+/// Instruction to claim from a cumulative distributor.
+/// The `CumulativeClaimStatus` is created on the first claim of the claimant.
 #[derive(Accounts)]
-#[instruction(_bump: u8, _amount: u64, _proof: Vec<[u8; 32]>)]
-pub struct Claim<'info> {
-    /// The global configuration account for Rakurai settings.
-    pub config: Account<'info, RewardDistributionConfigAccount>,
-
+pub struct ClaimCumulative<'info> {
     #[account(mut, rent_exempt = enforce)]
-    pub reward_collection_account: Account<'info, RewardCollectionAccount>,
+    pub distributor: Account<'info, CumulativeDistributor>,
 
-    /// Status of the claim. Used to prevent the same party from claiming multiple times.
+    /// Lifetime total the claimant has claimed so far.
     #[account(
-        init,
+        init_if_needed,
         rent_exempt = enforce,
         seeds = [
-            ClaimStatus::SEED,
+            CumulativeClaimStatus::SEED,
             claimant.key().as_ref(),
-            reward_collection_account.key().as_ref()
+            distributor.key().as_ref()
         ],
         bump,
-        space = ClaimStatus::SIZE,
+        space = CumulativeClaimStatus::SIZE,
         payer = payer
     )]
-    pub claim_status: Account<'info, ClaimStatus>,
+    pub claim_status: Account<'info, CumulativeClaimStatus>,
 
     /// CHECK: This is safe.
     /// Receiver of the funds.
@@ -582,32 +2349,201 @@ pub struct Claim<'info> {
     pub system_program: Program<'info, System>,
 }
 
-/// Accounts required to upload a Merkle root for reward distribution.
-#[derive(Accounts)]
-pub struct UploadMerkleRoot<'info> {
-    /// The global configuration account for Rakurai settings.
-    pub config: Account<'info, RewardDistributionConfigAccount>,
+/// Checks that a merkle root can be uploaded to `reward_collection_acc` now: from the epoch after
+/// its creation until it expires, and only until the first claim.
+fn check_merkle_root_upload(reward_collection_acc: &RewardCollectionAccount) -> Result<()> {
+    let current_epoch = Clock::get()?.epoch;
+    reward_collection_acc.check_version()?;
 
-    #[account(mut, rent_exempt = enforce)]
-    pub reward_collection_account: Account<'info, RewardCollectionAccount>,
+    if let Some(merkle_root) = &reward_collection_acc.merkle_root {
+        if merkle_root.num_nodes_claimed > 0 {
+            return Err(Unauthorized.into());
+        }
+    }
+    if current_epoch <= reward_collection_acc.creation_epoch {
+        return Err(PrematureMerkleRootUpload.into());
+    }
 
-    #[account(mut)]
-    pub merkle_root_upload_authority: Signer<'info>,
+    if current_epoch > reward_collection_acc.expires_at {
+        return Err(ExpiredRewardCollectionAccount.into());
+    }
+    Ok(())
 }
 
-impl UploadMerkleRoot<'_> {
-    fn auth(ctx: &Context<UploadMerkleRoot>) -> Result<()> {
-        if ctx.accounts.merkle_root_upload_authority.key()
-            != ctx
-                .accounts
-                .reward_collection_account
-                .merkle_root_upload_authority
-        {
-            Err(Unauthorized.into())
-        } else {
-            Ok(())
-        }
+/// Sets the merkle root of `reward_collection_acc`, with the `claim_scale` of its current funds
+/// in pro-rata mode.
+fn store_merkle_root(
+    reward_collection_acc: &mut Account<'_, RewardCollectionAccount>,
+    submission: AttestedRoot,
+) -> Result<()> {
+    reward_collection_acc.merkle_root = Some(MerkleRoot {
+        root: submission.root,
+        max_total_claim: submission.max_total_claim,
+        max_num_nodes: submission.max_num_nodes,
+        total_funds_claimed: 0,
+        num_nodes_claimed: 0,
+    });
+    reward_collection_acc.leaf_version = submission.version;
+    reward_collection_acc.claim_scale = if submission.pro_rata {
+        let available =
+            RewardCollectionAccount::available_funds(&reward_collection_acc.to_account_info())?;
+        ClaimScale::new(available, submission.max_total_claim)
+    } else {
+        None
+    };
+    reward_collection_acc.validate()
+}
+
+/// Pays out the claim of `claimant_account` for `amount` through [pay_claim] and records it in the
+/// new `claim_status`. Returns the amount paid out.
+fn settle_claim<'info>(
+    reward_collection_account: &mut Account<'info, RewardCollectionAccount>,
+    claim_status: &mut Account<'info, ClaimStatus>,
+    claimant_account: &AccountInfo<'info>,
+    recipient: &AccountInfo<'info>,
+    payer: Pubkey,
+    amount: u64,
+    proof: Vec<[u8; 32]>,
+) -> Result<u64> {
+    if claim_status.is_claimed {
+        return Err(FundsAlreadyClaimed.into());
+    }
+
+    let payout = pay_claim(
+        reward_collection_account,
+        claimant_account.key,
+        recipient,
+        payer,
+        amount,
+        proof,
+    )?;
+
+    claim_status.amount = payout;
+    claim_status.is_claimed = true;
+    claim_status.slot_claimed_at = Clock::get()?.slot;
+    claim_status.claimant = claimant_account.key();
+    claim_status.claim_status_payer = payer;
+    claim_status.expires_at = reward_collection_account.expires_at;
+
+    Ok(payout)
+}
+
+/// Verifies the leaf of `claimant` and `amount` against the root uploaded to
+/// `reward_collection_account`, pays it out to `recipient` after the `claim_scale` haircut if any
+/// and adds it to the root's claim totals. Recording the claim itself is up to the caller.
+/// Returns the amount paid out.
+fn pay_claim<'info>(
+    reward_collection_account: &mut Account<'info, RewardCollectionAccount>,
+    claimant: &Pubkey,
+    recipient: &AccountInfo<'info>,
+    payer: Pubkey,
+    amount: u64,
+    proof: Vec<[u8; 32]>,
+) -> Result<u64> {
+    let payout = pay_unrecorded_claim(
+        reward_collection_account,
+        claimant,
+        recipient,
+        payer,
+        amount,
+        proof,
+    )?;
+    record_claims(reward_collection_account, amount, 1)?;
+    Ok(payout)
+}
+
+/// [pay_claim] without adding the claim to the root's claim totals, for callers paying several
+/// claims of the same root and recording them at once with [record_claims].
+fn pay_unrecorded_claim<'info>(
+    reward_collection_account: &mut Account<'info, RewardCollectionAccount>,
+    claimant: &Pubkey,
+    recipient: &AccountInfo<'info>,
+    payer: Pubkey,
+    amount: u64,
+    proof: Vec<[u8; 32]>,
+) -> Result<u64> {
+    if Clock::get()?.epoch > reward_collection_account.expires_at {
+        return Err(ExpiredRewardCollectionAccount.into());
+    }
+
+    let reward_distribution_info = reward_collection_account.to_account_info();
+    let domain = reward_collection_account.leaf_domain(reward_collection_account.key());
+    let leaf_version = reward_collection_account.leaf_version;
+    let payout = reward_collection_account.payout(amount)?;
+    let merkle_root = reward_collection_account
+        .merkle_root
+        .as_ref()
+        .ok_or(RootNotUploaded)?;
+
+    merkle_root.verify_claim(leaf_version, &domain, claimant, amount, proof)?;
+
+    RewardCollectionAccount::claim(reward_distribution_info, recipient.clone(), payout)?;
+
+    emit!(ClaimedEvent {
+        reward_collection_account: reward_collection_account.key(),
+        payer,
+        claimant: *claimant,
+        amount: payout
+    });
+
+    Ok(payout)
+}
+
+/// Adds `num_claims` claims of leaf amounts totalling `amount` to the claim totals of the root
+/// uploaded to `reward_collection_account`.
+fn record_claims(
+    reward_collection_account: &mut Account<RewardCollectionAccount>,
+    amount: u64,
+    num_claims: u64,
+) -> Result<()> {
+    reward_collection_account
+        .merkle_root
+        .as_mut()
+        .ok_or(RootNotUploaded)?
+        .record_claims(amount, num_claims)?;
+    reward_collection_account.validate()
+}
+
+/// Creates the [ClaimStatus] PDA of `claim_status.claimant` on the [RewardCollectionAccount] at
+/// `reward_collection_key` and writes `claim_status` to it with the PDA's bump. Fails if
+/// `claim_status_account` is not that PDA or was already created by an earlier claim.
+fn create_claim_status<'info>(
+    claim_status_account: &AccountInfo<'info>,
+    reward_collection_key: &Pubkey,
+    payer: &Signer<'info>,
+    system_program: &Program<'info, System>,
+    rent_lamports: u64,
+    mut claim_status: ClaimStatus,
+) -> Result<()> {
+    let (claim_status_key, bump) = Pubkey::find_program_address(
+        &[
+            ClaimStatus::SEED,
+            claim_status.claimant.as_ref(),
+            reward_collection_key.as_ref(),
+        ],
+        &crate::ID,
+    );
+    if claim_status_account.key() != claim_status_key {
+        return Err(InvalidClaimStatusAccount.into());
+    }
+    if claim_status_account.owner == &crate::ID {
+        return Err(FundsAlreadyClaimed.into());
     }
+
+    ClaimStatus::create(
+        claim_status_account,
+        payer,
+        system_program,
+        &[
+            ClaimStatus::SEED,
+            claim_status.claimant.as_ref(),
+            reward_collection_key.as_ref(),
+            &[bump],
+        ],
+        rent_lamports,
+    )?;
+    claim_status.bump = bump;
+    claim_status.try_serialize(&mut &mut claim_status_account.try_borrow_mut_data()?[..])
 }
 
 /// Accounts required to transfer staker rewards with Rakurai commission applied.
@@ -643,6 +2579,34 @@ impl TransferStakerRewards<'_> {
     }
 }
 
+/// Accounts required to deposit commission-free staker rewards.
+#[derive(Accounts)]
+pub struct DepositRewards<'info> {
+    #[account(mut, rent_exempt = enforce)]
+    pub reward_collection_account: Account<'info, RewardCollectionAccount>,
+
+    /// Source of the deposited lamports.
+    #[account(mut)]
+    pub depositor: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts required to migrate a reward collection account to the current account version.
+#[derive(Accounts)]
+pub struct MigrateRewardCollectionAccount<'info> {
+    /// CHECK: Owned by this program; the discriminator is checked in the instruction, since an
+    /// account of an older version can not be deserialized before it is grown.
+    #[account(mut, owner = crate::ID)]
+    pub reward_collection_account: UncheckedAccount<'info>,
+
+    /// Pays the rent of the grown account.
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
 // Events
 
 // Emitted when a new RewardCollectionAccount is initialized.
@@ -658,6 +2622,8 @@ pub struct ValidatorCommissionBpsUpdatedEvent {
     pub reward_collection_account: Pubkey,
     pub old_commission_bps: u16,
     pub new_commission_bps: u16,
+    pub old_category_commissions: CategoryCommissions,
+    pub new_category_commissions: CategoryCommissions,
 }
 
 // Emitted when the Merkle root upload authority is changed.
@@ -683,10 +2649,42 @@ pub struct ClaimedEvent {
     /// User that paid for the claim, may or may not be the same as claimant.
     pub payer: Pubkey,
 
-    /// Account that received the funds.
+    /// Claimant of the leaf; received the funds, unless they were staked for it.
+    pub claimant: Pubkey,
+
+    /// Amount of funds paid out, after the `claim_scale` haircut if any.
+    pub amount: u64,
+}
+
+// Emitted when a claim is staked in a new stake account of the claimant.
+#[event]
+pub struct ClaimStakedEvent {
+    /// [RewardCollectionAccount] claimed from.
+    pub reward_collection_account: Pubkey,
+
+    /// Stake and withdraw authority of the stake account.
+    pub claimant: Pubkey,
+
+    /// Stake account created, delegated to the validator's vote account.
+    pub stake_account: Pubkey,
+
+    /// Amount paid into the stake account, including its rent-exempt reserve.
+    pub amount: u64,
+}
+
+// Emitted when a claim is deposited into a stake pool for the claimant.
+#[event]
+pub struct ClaimDepositedToStakePoolEvent {
+    /// [RewardCollectionAccount] claimed from.
+    pub reward_collection_account: Pubkey,
+
+    /// Owner of the pool tokens minted.
     pub claimant: Pubkey,
 
-    /// Amount of funds to distribute.
+    /// Stake pool deposited into.
+    pub stake_pool: Pubkey,
+
+    /// Lamports deposited.
     pub amount: u64,
 }
 
@@ -698,12 +2696,121 @@ pub struct MerkleRootUploadedEvent {
 
     /// Where the root was uploaded to.
     pub reward_collection_account: Pubkey,
+
+    /// Haircut of every claim, if the root was uploaded in pro-rata mode to an underfunded account.
+    pub claim_scale: Option<ClaimScale>,
+}
+
+// Emitted when the merkle root upload of a reward account is handed to attesters.
+#[event]
+pub struct RootAttestationInitializedEvent {
+    /// [RewardCollectionAccount] whose root is attested.
+    pub reward_collection_account: Pubkey,
+
+    /// [RootAttestation] created, the new merkle root upload authority.
+    pub root_attestation: Pubkey,
+
+    /// Keys allowed to submit the root.
+    pub attesters: Vec<Pubkey>,
+
+    /// Number of matching submissions that uploads the root.
+    pub threshold: u8,
+}
+
+// Emitted when an attester submits a merkle root.
+#[event]
+pub struct MerkleRootAttestedEvent {
+    /// [RewardCollectionAccount] the root is submitted for.
+    pub reward_collection_account: Pubkey,
+
+    /// Who submitted the root.
+    pub attester: Pubkey,
+
+    /// Root submitted.
+    pub submission: AttestedRoot,
+
+    /// Number of attesters whose latest submission matches, including this one.
+    pub num_agreeing: u8,
+}
+
+// Emitted when an attester submits a merkle root that differs from other attesters' submissions.
+#[event]
+pub struct MerkleRootAttestationConflictEvent {
+    /// [RewardCollectionAccount] the root is submitted for.
+    pub reward_collection_account: Pubkey,
+
+    /// Who submitted the root.
+    pub attester: Pubkey,
+
+    /// Root submitted.
+    pub submission: AttestedRoot,
+
+    /// Attesters whose latest submission differs from `submission`.
+    pub conflicting_attesters: Vec<Pubkey>,
+}
+
+// Emitted when a root attestation account is closed.
+#[event]
+pub struct RootAttestationClosedEvent {
+    /// Account that received the rent.
+    pub payer: Pubkey,
+
+    /// [RootAttestation] closed.
+    pub root_attestation: Pubkey,
 }
 
 // Emitted when a portion of funds is transferred to the staker.
 #[event]
 pub struct StakerRewardsTransferredEvent {
+    /// [RewardCollectionAccount] the staker rewards were transferred to.
+    pub reward_collection_account: Pubkey,
+
+    /// The leader turn's rewards by category, as passed to the instruction.
+    pub rewards: CategorizedRewards,
+
+    /// Rakurai's commission, transferred to the `rakurai_commission_account`.
+    pub block_builder_fee: u64,
+
+    /// Validator's commission, kept in the validator's identity.
+    pub validator_fee: u64,
+
+    /// Stakers' share, transferred to the [RewardCollectionAccount].
     pub staker_rewards: u64,
+
+    /// First slot of the leader turn settled.
+    pub start_slot: u64,
+
+    /// Last slot of the leader turn settled.
+    pub end_slot: u64,
+}
+
+// Emitted when rewards are deposited to a reward collection account without commissions.
+#[event]
+pub struct RewardsDepositedEvent {
+    /// [RewardCollectionAccount] the rewards were deposited to.
+    pub reward_collection_account: Pubkey,
+
+    /// Account the lamports came from.
+    pub depositor: Pubkey,
+
+    /// Lamports deposited, all of them for the stakers.
+    pub amount: u64,
+
+    /// Where the lamports came from.
+    pub source: DepositSource,
+}
+
+// Emitted when a reward collection account is migrated to the current account version.
+#[event]
+pub struct RewardCollectionAccountMigratedEvent {
+    /// [RewardCollectionAccount] migrated.
+    pub reward_collection_account: Pubkey,
+
+    /// Account version before the migration.
+    pub old_version: u8,
+
+    /// Account version after the migration.
+    pub new_version: u8,
 }
 
 // Emitted when a reward collection account is closed and unclaimed funds are returned.
@@ -719,6 +2826,32 @@ pub struct RewardCollectionAccountClosedEvent {
     pub expired_amount: u64,
 }
 
+// Emitted when the unclaimed funds of an expired reward collection account are rolled over.
+#[event]
+pub struct UnclaimedRewardsRolledOverEvent {
+    /// Expired [RewardCollectionAccount], closed.
+    pub reward_collection_account: Pubkey,
+
+    /// The validator's [RewardCollectionAccount] of the current epoch, receiving the funds.
+    pub current_reward_collection_account: Pubkey,
+
+    /// Unclaimed amount rolled over.
+    pub amount: u64,
+}
+
+// Emitted when the surplus of a reward collection account is returned to its initializer.
+#[event]
+pub struct SurplusWithdrawnEvent {
+    /// [RewardCollectionAccount] the surplus was withdrawn from.
+    pub reward_collection_account: Pubkey,
+
+    /// Account the surplus was transferred to.
+    pub initializer: Pubkey,
+
+    /// Lamports withdrawn.
+    pub amount: u64,
+}
+
 // Emitted when a user's ClaimStatus account is closed and remaining funds are returned.
 #[event]
 pub struct ClaimStatusClosedEvent {
@@ -728,3 +2861,65 @@ pub struct ClaimStatusClosedEvent {
     /// [ClaimStatus] account that was closed.
     pub claim_status_account: Pubkey,
 }
+
+// Emitted when a ClaimBitmap account is closed and its rent is returned.
+#[event]
+pub struct ClaimBitmapClosedEvent {
+    /// Account where funds were transferred to.
+    pub payer: Pubkey,
+
+    /// [ClaimBitmap] account that was closed.
+    pub claim_bitmap: Pubkey,
+}
+
+// Emitted when a new CumulativeDistributor is initialized.
+#[event]
+pub struct CumulativeDistributorInitializedEvent {
+    /// The newly initialized cumulative distributor.
+    pub distributor: Pubkey,
+}
+
+// Emitted when a reward collection account is swept into a cumulative distributor.
+#[event]
+pub struct SweptToCumulativeDistributorEvent {
+    /// [RewardCollectionAccount] swept.
+    pub reward_collection_account: Pubkey,
+
+    /// [CumulativeDistributor] receiving the funds.
+    pub distributor: Pubkey,
+
+    /// Amount swept.
+    pub amount: u64,
+}
+
+// Emitted when a Merkle root is uploaded to a cumulative distributor.
+#[event]
+pub struct CumulativeMerkleRootUploadedEvent {
+    /// Who uploaded the root.
+    pub merkle_root_upload_authority: Pubkey,
+
+    /// Where the root was uploaded to.
+    pub distributor: Pubkey,
+
+    /// Sum of the lifetime totals in the root.
+    pub max_total_claim: u64,
+}
+
+// Emitted when a user claims from a cumulative distributor.
+#[event]
+pub struct CumulativeClaimedEvent {
+    /// [CumulativeDistributor] claimed from.
+    pub distributor: Pubkey,
+
+    /// User that paid for the claim, may or may not be the same as claimant.
+    pub payer: Pubkey,
+
+    /// Account that received the funds.
+    pub claimant: Pubkey,
+
+    /// Amount paid out by this claim.
+    pub amount: u64,
+
+    /// Lifetime total claimed by the claimant after this claim.
+    pub total_claimed: u64,
+}
@@ -1,27 +1,15 @@
 //! copy-pasta from [here](https://github.com/saber-hq/merkle-distributor/blob/ac937d1901033ecb7fa3b0db22f7b39569c8e052/programs/merkle-distributor/src/merkle_proof.rs)
 //! modified to include INTERMEDIATE_HASH prefix and sha256 hashing
 
-use solana_program;
-
 /// This function deals with verification of Merkle trees (hash trees).
 /// Direct port of https://github.com/OpenZeppelin/openzeppelin-contracts/blob/v3.4.0/contracts/cryptography/MerkleProof.sol
 /// Returns true if a `leaf` can be proved to be a part of a Merkle tree
 /// defined by `root`. For this, a `proof` must be provided, containing
 /// sibling hashes on the branch from the leaf to the root of the tree. Each
 /// pair of leaves and each pair of pre-images are assumed to be sorted.
+///
+/// The intermediate hashing is the off-chain tree builder's, so proofs built by
+/// `rakurai_merkle_tree` always verify here.
 pub fn verify(proof: Vec<[u8; 32]>, root: [u8; 32], leaf: [u8; 32]) -> bool {
-    let mut computed_hash = leaf;
-    for proof_element in proof.into_iter() {
-        if computed_hash <= proof_element {
-            // Hash(current computed hash + current element of the proof)
-            computed_hash =
-                solana_program::hash::hashv(&[&[1u8], &computed_hash, &proof_element]).to_bytes();
-        } else {
-            // Hash(current element of the proof + current computed hash)
-            computed_hash =
-                solana_program::hash::hashv(&[&[1u8], &proof_element, &computed_hash]).to_bytes();
-        }
-    }
-    // Check if the computed hash (root) is equal to the provided root
-    computed_hash == root
+    rakurai_merkle_tree::verify(&proof, root, leaf)
 }
@@ -1,9 +1,15 @@
 //! This module contains functions that build instructions to interact with the block-reward-distribution program.
 use anchor_lang::{
-    prelude::Pubkey, solana_program::instruction::Instruction, InstructionData, ToAccountMetas,
+    prelude::Pubkey,
+    solana_program::instruction::{AccountMeta, Instruction},
+    InstructionData, ToAccountMetas,
 };
 
-use crate::RewardDistributionConfigAccount;
+use crate::{
+    commission::{CategorizedRewards, CategoryCommissions},
+    state::DepositSource,
+    AggregatedClaim, BatchClaim, RewardDistributionConfigAccount,
+};
 
 /// Arguments for initializing the reward distribution config account.
 pub struct InitializeArgs {
@@ -60,10 +66,10 @@ pub fn initialize_ix(
 /// Arguments for initializing the reward collection account.
 pub struct InitializeRewardCollectionAccountArgs {
     pub merkle_root_upload_authority: Pubkey,
-    pub validator_commission_bps: u16,
-    pub rakurai_commission_account: Pubkey,
-    pub rakurai_commission_bps: u16,
     pub bump: u8,
+    /// Roll unclaimed funds over to a later epoch's stakers on expiry, see
+    /// [crate::reward_distribution::rollover_unclaimed_rewards].
+    pub rollover_unclaimed: bool,
 }
 
 /// Accounts needed to initialize the reward collection account.
@@ -73,6 +79,8 @@ pub struct InitializeRewardCollectionAccountAccounts {
     pub system_program: Pubkey,
     pub reward_collection_account: Pubkey,
     pub validator_vote_account: Pubkey,
+    pub activation_account: Pubkey,
+    pub activation_config: Pubkey,
 }
 
 /// Builds the instruction to initialize the reward collection account.
@@ -83,10 +91,8 @@ pub fn initialize_reward_collection_account_ix(
 ) -> Instruction {
     let InitializeRewardCollectionAccountArgs {
         merkle_root_upload_authority,
-        validator_commission_bps,
-        rakurai_commission_account,
-        rakurai_commission_bps,
         bump,
+        rollover_unclaimed,
     } = args;
 
     let InitializeRewardCollectionAccountAccounts {
@@ -95,16 +101,16 @@ pub fn initialize_reward_collection_account_ix(
         system_program,
         validator_vote_account,
         signer,
+        activation_account,
+        activation_config,
     } = accounts;
 
     Instruction {
         program_id,
         data: crate::instruction::InitializeRewardCollectionAccount {
             merkle_root_upload_authority,
-            validator_commission_bps,
-            rakurai_commission_account,
-            rakurai_commission_bps,
             bump,
+            rollover_unclaimed,
         }
         .data(),
         accounts: crate::accounts::InitializeRewardCollectionAccount {
@@ -113,6 +119,8 @@ pub fn initialize_reward_collection_account_ix(
             system_program,
             reward_collection_account,
             validator_vote_account,
+            activation_account,
+            activation_config,
         }
         .to_account_metas(None),
     }
@@ -154,7 +162,7 @@ pub fn close_claim_status_ix(
 
 /// Arguments to update the reward config account.
 pub struct UpdateConfigArgs {
-    new_config: RewardDistributionConfigAccount,
+    pub new_config: RewardDistributionConfigAccount,
 }
 
 /// Accounts needed to update the config.
@@ -185,6 +193,11 @@ pub struct UploadMerkleRootArgs {
     pub root: [u8; 32],
     pub max_total_claim: u64,
     pub max_num_nodes: u64,
+    /// Leaf format of the tree, see [crate::state::MerkleRoot::LEAF_VERSION_V1].
+    pub version: u8,
+    /// Scale every claim down if the account holds less than `max_total_claim`, see
+    /// [crate::state::ClaimScale].
+    pub pro_rata: bool,
 }
 
 /// Accounts for uploading a Merkle root to the reward collection account.
@@ -204,6 +217,8 @@ pub fn upload_merkle_root_ix(
         root,
         max_total_claim,
         max_num_nodes,
+        version,
+        pro_rata,
     } = args;
 
     let UploadMerkleRootAccounts {
@@ -218,6 +233,8 @@ pub fn upload_merkle_root_ix(
             max_total_claim,
             max_num_nodes,
             root,
+            version,
+            pro_rata,
         }
         .data(),
         accounts: crate::accounts::UploadMerkleRoot {
@@ -229,9 +246,236 @@ pub fn upload_merkle_root_ix(
     }
 }
 
-/// Total amount to be transferred to stakers.
+/// Attesters and quorum for the root of a reward collection account.
+pub struct InitializeRootAttestationArgs {
+    pub attesters: Vec<Pubkey>,
+    pub threshold: u8,
+}
+
+/// Accounts needed to hand the root upload of a reward collection account to attesters.
+pub struct InitializeRootAttestationAccounts {
+    pub reward_collection_account: Pubkey,
+    pub root_attestation: Pubkey,
+    /// Validator identity that initialized the reward collection account.
+    pub signer: Pubkey,
+    pub system_program: Pubkey,
+}
+
+/// Builds the instruction to create the root attestation of a reward collection account.
+pub fn initialize_root_attestation_ix(
+    program_id: Pubkey,
+    args: InitializeRootAttestationArgs,
+    accounts: InitializeRootAttestationAccounts,
+) -> Instruction {
+    let InitializeRootAttestationArgs {
+        attesters,
+        threshold,
+    } = args;
+
+    let InitializeRootAttestationAccounts {
+        reward_collection_account,
+        root_attestation,
+        signer,
+        system_program,
+    } = accounts;
+
+    Instruction {
+        program_id,
+        data: crate::instruction::InitializeRootAttestation {
+            attesters,
+            threshold,
+        }
+        .data(),
+        accounts: crate::accounts::InitializeRootAttestation {
+            reward_collection_account,
+            root_attestation,
+            signer,
+            system_program,
+        }
+        .to_account_metas(None),
+    }
+}
+
+/// Root submitted by an attester, see [UploadMerkleRootArgs].
+pub struct AttestMerkleRootArgs {
+    pub root: [u8; 32],
+    pub max_total_claim: u64,
+    pub max_num_nodes: u64,
+    pub version: u8,
+    pub pro_rata: bool,
+}
+
+/// Accounts for attesting a Merkle root of a reward collection account.
+pub struct AttestMerkleRootAccounts {
+    pub config: Pubkey,
+    pub reward_collection_account: Pubkey,
+    pub root_attestation: Pubkey,
+    pub attester: Pubkey,
+}
+
+/// Builds the instruction to attest a Merkle root.
+pub fn attest_merkle_root_ix(
+    program_id: Pubkey,
+    args: AttestMerkleRootArgs,
+    accounts: AttestMerkleRootAccounts,
+) -> Instruction {
+    let AttestMerkleRootArgs {
+        root,
+        max_total_claim,
+        max_num_nodes,
+        version,
+        pro_rata,
+    } = args;
+
+    let AttestMerkleRootAccounts {
+        config,
+        reward_collection_account,
+        root_attestation,
+        attester,
+    } = accounts;
+
+    Instruction {
+        program_id,
+        data: crate::instruction::AttestMerkleRoot {
+            root,
+            max_total_claim,
+            max_num_nodes,
+            version,
+            pro_rata,
+        }
+        .data(),
+        accounts: crate::accounts::AttestMerkleRoot {
+            config,
+            reward_collection_account,
+            root_attestation,
+            attester,
+        }
+        .to_account_metas(None),
+    }
+}
+
+/// Args for closing a root attestation (empty).
+pub struct CloseRootAttestationArgs;
+
+/// Accounts needed to close an expired root attestation.
+pub struct CloseRootAttestationAccounts {
+    pub root_attestation: Pubkey,
+    pub payer: Pubkey,
+}
+
+/// Builds the instruction to close an expired root attestation.
+pub fn close_root_attestation_ix(
+    program_id: Pubkey,
+    _args: CloseRootAttestationArgs,
+    accounts: CloseRootAttestationAccounts,
+) -> Instruction {
+    let CloseRootAttestationAccounts {
+        root_attestation,
+        payer,
+    } = accounts;
+
+    Instruction {
+        program_id,
+        data: crate::instruction::CloseRootAttestation {}.data(),
+        accounts: crate::accounts::CloseRootAttestation {
+            root_attestation,
+            payer,
+        }
+        .to_account_metas(None),
+    }
+}
+
+/// New merkle root upload authority of the reward collection account.
+pub struct UpdateMerkleRootUploadAuthorityArgs {
+    pub new_merkle_root_upload_authority: Pubkey,
+}
+
+/// Accounts for changing the merkle root upload authority of the reward collection account.
+pub struct UpdateMerkleRootUploadAuthorityAccounts {
+    pub reward_collection_account: Pubkey,
+    pub signer: Pubkey,
+}
+
+/// Builds the instruction to change the merkle root upload authority.
+pub fn update_merkle_root_upload_authority_ix(
+    program_id: Pubkey,
+    args: UpdateMerkleRootUploadAuthorityArgs,
+    accounts: UpdateMerkleRootUploadAuthorityAccounts,
+) -> Instruction {
+    let UpdateMerkleRootUploadAuthorityArgs {
+        new_merkle_root_upload_authority,
+    } = args;
+
+    let UpdateMerkleRootUploadAuthorityAccounts {
+        reward_collection_account,
+        signer,
+    } = accounts;
+
+    Instruction {
+        program_id,
+        data: crate::instruction::UpdateMerkleRootUploadAuthority {
+            new_merkle_root_upload_authority,
+        }
+        .data(),
+        accounts: crate::accounts::UpdateMerkleRootUploadAuthority {
+            reward_collection_account,
+            signer,
+        }
+        .to_account_metas(None),
+    }
+}
+
+/// New validator commissions of the reward collection account, overall and per reward category.
+pub struct UpdateValidatorCommissionArgs {
+    pub new_validator_commission_bps: u16,
+    pub new_category_commissions: CategoryCommissions,
+}
+
+/// Accounts for changing the validator commission of the reward collection account.
+pub struct UpdateValidatorCommissionAccounts {
+    pub config: Pubkey,
+    pub reward_collection_account: Pubkey,
+    pub signer: Pubkey,
+}
+
+/// Builds the instruction to change the validator commission.
+pub fn update_validator_commission_ix(
+    program_id: Pubkey,
+    args: UpdateValidatorCommissionArgs,
+    accounts: UpdateValidatorCommissionAccounts,
+) -> Instruction {
+    let UpdateValidatorCommissionArgs {
+        new_validator_commission_bps,
+        new_category_commissions,
+    } = args;
+
+    let UpdateValidatorCommissionAccounts {
+        config,
+        reward_collection_account,
+        signer,
+    } = accounts;
+
+    Instruction {
+        program_id,
+        data: crate::instruction::UpdateValidatorCommission {
+            new_validator_commission_bps,
+            new_category_commissions,
+        }
+        .data(),
+        accounts: crate::accounts::UpdateValidatorCommission {
+            config,
+            reward_collection_account,
+            signer,
+        }
+        .to_account_metas(None),
+    }
+}
+
+/// A leader turn's rewards by category, before commissions, and the turn's slots.
 pub struct TransferStakerRewardsArgs {
-    pub total_rewards: u64,
+    pub rewards: CategorizedRewards,
+    pub start_slot: u64,
+    pub end_slot: u64,
 }
 
 /// Accounts required to transfer rewards to stakers.
@@ -248,7 +492,11 @@ pub fn transfer_staker_rewards_ix(
     args: TransferStakerRewardsArgs,
     accounts: TransferStakerRewardsAccounts,
 ) -> Instruction {
-    let TransferStakerRewardsArgs { total_rewards } = args;
+    let TransferStakerRewardsArgs {
+        rewards,
+        start_slot,
+        end_slot,
+    } = args;
 
     let TransferStakerRewardsAccounts {
         rakurai_commission_account,
@@ -259,7 +507,12 @@ pub fn transfer_staker_rewards_ix(
 
     Instruction {
         program_id,
-        data: crate::instruction::TransferStakerRewards { total_rewards }.data(),
+        data: crate::instruction::TransferStakerRewards {
+            rewards,
+            start_slot,
+            end_slot,
+        }
+        .data(),
         accounts: crate::accounts::TransferStakerRewards {
             rakurai_commission_account,
             reward_collection_account,
@@ -270,6 +523,110 @@ pub fn transfer_staker_rewards_ix(
     }
 }
 
+/// Lamports to deposit for the stakers, and where they came from.
+pub struct DepositRewardsArgs {
+    pub amount: u64,
+    pub source: DepositSource,
+}
+
+/// Accounts required to deposit rewards for the stakers.
+pub struct DepositRewardsAccounts {
+    pub reward_collection_account: Pubkey,
+    pub depositor: Pubkey,
+    pub system_program: Pubkey,
+}
+
+/// Builds the instruction to deposit commission-free staker rewards.
+pub fn deposit_rewards_ix(
+    program_id: Pubkey,
+    args: DepositRewardsArgs,
+    accounts: DepositRewardsAccounts,
+) -> Instruction {
+    let DepositRewardsArgs { amount, source } = args;
+
+    let DepositRewardsAccounts {
+        reward_collection_account,
+        depositor,
+        system_program,
+    } = accounts;
+
+    Instruction {
+        program_id,
+        data: crate::instruction::DepositRewards { amount, source }.data(),
+        accounts: crate::accounts::DepositRewards {
+            reward_collection_account,
+            depositor,
+            system_program,
+        }
+        .to_account_metas(None),
+    }
+}
+
+/// Args for migrating a reward collection account (empty).
+pub struct MigrateRewardCollectionAccountArgs;
+
+/// Accounts needed to migrate a reward collection account.
+pub struct MigrateRewardCollectionAccountAccounts {
+    pub reward_collection_account: Pubkey,
+    pub payer: Pubkey,
+    pub system_program: Pubkey,
+}
+
+/// Builds the instruction to migrate a reward collection account to the current account version.
+pub fn migrate_reward_collection_account_ix(
+    program_id: Pubkey,
+    _args: MigrateRewardCollectionAccountArgs,
+    accounts: MigrateRewardCollectionAccountAccounts,
+) -> Instruction {
+    let MigrateRewardCollectionAccountAccounts {
+        reward_collection_account,
+        payer,
+        system_program,
+    } = accounts;
+
+    Instruction {
+        program_id,
+        data: crate::instruction::MigrateRewardCollectionAccount {}.data(),
+        accounts: crate::accounts::MigrateRewardCollectionAccount {
+            reward_collection_account,
+            payer,
+            system_program,
+        }
+        .to_account_metas(None),
+    }
+}
+
+/// Args for withdrawing the surplus of a reward collection account (empty).
+pub struct WithdrawSurplusArgs;
+
+/// Accounts needed to withdraw the surplus of a reward collection account.
+pub struct WithdrawSurplusAccounts {
+    pub reward_collection_account: Pubkey,
+    pub initializer: Pubkey,
+}
+
+/// Builds the instruction to withdraw the surplus of a reward collection account.
+pub fn withdraw_surplus_ix(
+    program_id: Pubkey,
+    _args: WithdrawSurplusArgs,
+    accounts: WithdrawSurplusAccounts,
+) -> Instruction {
+    let WithdrawSurplusAccounts {
+        reward_collection_account,
+        initializer,
+    } = accounts;
+
+    Instruction {
+        program_id,
+        data: crate::instruction::WithdrawSurplus {}.data(),
+        accounts: crate::accounts::WithdrawSurplus {
+            reward_collection_account,
+            initializer,
+        }
+        .to_account_metas(None),
+    }
+}
+
 /// Epoch argument (for context) when closing the reward collection account.
 pub struct CloseRewardCollectionAccountArgs {
     pub _epoch: u64,
@@ -314,6 +671,45 @@ pub fn close_reward_collection_account_ix(
     }
 }
 
+/// Epoch of the expired reward collection account to roll over.
+pub struct RolloverUnclaimedRewardsArgs {
+    pub epoch: u64,
+}
+
+/// Accounts needed to roll the unclaimed funds of a reward collection account over.
+pub struct RolloverUnclaimedRewardsAccounts {
+    pub reward_collection_account: Pubkey,
+    pub current_reward_collection_account: Pubkey,
+    pub validator_vote_account: Pubkey,
+}
+
+/// Builds the instruction to roll the unclaimed funds of an expired reward collection account
+/// over to the validator's reward collection account of the current epoch.
+pub fn rollover_unclaimed_rewards_ix(
+    program_id: Pubkey,
+    args: RolloverUnclaimedRewardsArgs,
+    accounts: RolloverUnclaimedRewardsAccounts,
+) -> Instruction {
+    let RolloverUnclaimedRewardsArgs { epoch } = args;
+
+    let RolloverUnclaimedRewardsAccounts {
+        reward_collection_account,
+        current_reward_collection_account,
+        validator_vote_account,
+    } = accounts;
+
+    Instruction {
+        program_id,
+        data: crate::instruction::RolloverUnclaimedRewards { _epoch: epoch }.data(),
+        accounts: crate::accounts::RolloverUnclaimedRewards {
+            reward_collection_account,
+            current_reward_collection_account,
+            validator_vote_account,
+        }
+        .to_account_metas(None),
+    }
+}
+
 /// Proof and metadata for a Merkle claim.
 pub struct ClaimArgs {
     pub proof: Vec<[u8; 32]>,
@@ -367,3 +763,734 @@ pub fn claim_ix(program_id: Pubkey, args: ClaimArgs, accounts: ClaimAccounts) ->
         .to_account_metas(None),
     }
 }
+
+/// Proof and amount of a claim staked in a new stake account.
+pub struct ClaimToStakeAccountArgs {
+    pub proof: Vec<[u8; 32]>,
+    pub amount: u64,
+}
+
+/// Accounts needed to stake a Merkle reward claim.
+pub struct ClaimToStakeAccountAccounts {
+    pub config: Pubkey,
+    pub reward_collection_account: Pubkey,
+    pub claim_status: Pubkey,
+    pub claimant: Pubkey,
+    /// New keypair, signs the transaction.
+    pub stake_account: Pubkey,
+    pub validator_vote_account: Pubkey,
+    pub stake_config: Pubkey,
+    pub stake_history: Pubkey,
+    pub clock: Pubkey,
+    pub rent: Pubkey,
+    pub stake_program: Pubkey,
+    pub payer: Pubkey,
+    pub system_program: Pubkey,
+}
+
+/// Builds the instruction for claiming Merkle rewards into a new stake account.
+pub fn claim_to_stake_account_ix(
+    program_id: Pubkey,
+    args: ClaimToStakeAccountArgs,
+    accounts: ClaimToStakeAccountAccounts,
+) -> Instruction {
+    let ClaimToStakeAccountArgs { proof, amount } = args;
+
+    let ClaimToStakeAccountAccounts {
+        config,
+        reward_collection_account,
+        claim_status,
+        claimant,
+        stake_account,
+        validator_vote_account,
+        stake_config,
+        stake_history,
+        clock,
+        rent,
+        stake_program,
+        payer,
+        system_program,
+    } = accounts;
+
+    Instruction {
+        program_id,
+        data: crate::instruction::ClaimToStakeAccount { amount, proof }.data(),
+        accounts: crate::accounts::ClaimToStakeAccount {
+            config,
+            reward_collection_account,
+            claim_status,
+            claimant,
+            stake_account,
+            validator_vote_account,
+            stake_config,
+            stake_history,
+            clock,
+            rent,
+            stake_program,
+            payer,
+            system_program,
+        }
+        .to_account_metas(None),
+    }
+}
+
+/// Proof and amount of a claim deposited into a stake pool.
+pub struct ClaimToStakePoolArgs {
+    pub proof: Vec<[u8; 32]>,
+    pub amount: u64,
+}
+
+/// Accounts needed to deposit a Merkle reward claim into a stake pool.
+pub struct ClaimToStakePoolAccounts {
+    pub config: Pubkey,
+    pub reward_collection_account: Pubkey,
+    pub claim_status: Pubkey,
+    pub claimant: Pubkey,
+    /// See [crate::sdk::derive_stake_pool_deposit_address].
+    pub deposit_account: Pubkey,
+    pub stake_pool: Pubkey,
+    pub stake_pool_withdraw_authority: Pubkey,
+    pub reserve_stake: Pubkey,
+    /// Claimant's token account for the pool mint.
+    pub pool_tokens_to: Pubkey,
+    pub manager_fee_account: Pubkey,
+    pub pool_mint: Pubkey,
+    pub token_program: Pubkey,
+    pub stake_pool_program: Pubkey,
+    pub payer: Pubkey,
+    pub system_program: Pubkey,
+}
+
+/// Builds the instruction for claiming Merkle rewards into a stake pool.
+pub fn claim_to_stake_pool_ix(
+    program_id: Pubkey,
+    args: ClaimToStakePoolArgs,
+    accounts: ClaimToStakePoolAccounts,
+) -> Instruction {
+    let ClaimToStakePoolArgs { proof, amount } = args;
+
+    let ClaimToStakePoolAccounts {
+        config,
+        reward_collection_account,
+        claim_status,
+        claimant,
+        deposit_account,
+        stake_pool,
+        stake_pool_withdraw_authority,
+        reserve_stake,
+        pool_tokens_to,
+        manager_fee_account,
+        pool_mint,
+        token_program,
+        stake_pool_program,
+        payer,
+        system_program,
+    } = accounts;
+
+    Instruction {
+        program_id,
+        data: crate::instruction::ClaimToStakePool { amount, proof }.data(),
+        accounts: crate::accounts::ClaimToStakePool {
+            config,
+            reward_collection_account,
+            claim_status,
+            claimant,
+            deposit_account,
+            stake_pool,
+            stake_pool_withdraw_authority,
+            reserve_stake,
+            pool_tokens_to,
+            manager_fee_account,
+            pool_mint,
+            token_program,
+            stake_pool_program,
+            payer,
+            system_program,
+        }
+        .to_account_metas(None),
+    }
+}
+
+/// Args for creating a proof buffer.
+pub struct InitializeProofBufferArgs {
+    pub claimant: Pubkey,
+    /// Number of hashes of the proof.
+    pub proof_len: u8,
+}
+
+/// Accounts needed to create a proof buffer.
+pub struct InitializeProofBufferAccounts {
+    pub config: Pubkey,
+    pub reward_collection_account: Pubkey,
+    pub proof_buffer: Pubkey,
+    pub payer: Pubkey,
+    pub system_program: Pubkey,
+}
+
+/// Builds the instruction to create a proof buffer.
+pub fn initialize_proof_buffer_ix(
+    program_id: Pubkey,
+    args: InitializeProofBufferArgs,
+    accounts: InitializeProofBufferAccounts,
+) -> Instruction {
+    let InitializeProofBufferArgs {
+        claimant,
+        proof_len,
+    } = args;
+
+    let InitializeProofBufferAccounts {
+        config,
+        reward_collection_account,
+        proof_buffer,
+        payer,
+        system_program,
+    } = accounts;
+
+    Instruction {
+        program_id,
+        data: crate::instruction::InitializeProofBuffer {
+            claimant,
+            proof_len,
+        }
+        .data(),
+        accounts: crate::accounts::InitializeProofBuffer {
+            config,
+            reward_collection_account,
+            proof_buffer,
+            payer,
+            system_program,
+        }
+        .to_account_metas(None),
+    }
+}
+
+/// Args for writing part of a proof into a proof buffer.
+pub struct WriteProofBufferArgs {
+    /// Number of hashes written before.
+    pub offset: u8,
+    pub hashes: Vec<[u8; 32]>,
+}
+
+/// Accounts needed to write into a proof buffer.
+pub struct WriteProofBufferAccounts {
+    pub proof_buffer: Pubkey,
+    pub payer: Pubkey,
+}
+
+/// Builds the instruction to write part of a proof into a proof buffer.
+pub fn write_proof_buffer_ix(
+    program_id: Pubkey,
+    args: WriteProofBufferArgs,
+    accounts: WriteProofBufferAccounts,
+) -> Instruction {
+    let WriteProofBufferArgs { offset, hashes } = args;
+
+    let WriteProofBufferAccounts {
+        proof_buffer,
+        payer,
+    } = accounts;
+
+    Instruction {
+        program_id,
+        data: crate::instruction::WriteProofBuffer { offset, hashes }.data(),
+        accounts: crate::accounts::WriteProofBuffer {
+            proof_buffer,
+            payer,
+        }
+        .to_account_metas(None),
+    }
+}
+
+/// Args for claiming with the proof held by a proof buffer.
+pub struct ClaimWithProofBufferArgs {
+    pub amount: u64,
+}
+
+/// Accounts needed to claim with the proof held by a proof buffer.
+pub struct ClaimWithProofBufferAccounts {
+    pub config: Pubkey,
+    pub reward_collection_account: Pubkey,
+    pub claim_status: Pubkey,
+    pub claimant: Pubkey,
+    pub proof_buffer: Pubkey,
+    pub payer: Pubkey,
+    pub system_program: Pubkey,
+}
+
+/// Builds the instruction for claiming Merkle rewards with the proof held by a proof buffer.
+pub fn claim_with_proof_buffer_ix(
+    program_id: Pubkey,
+    args: ClaimWithProofBufferArgs,
+    accounts: ClaimWithProofBufferAccounts,
+) -> Instruction {
+    let ClaimWithProofBufferArgs { amount } = args;
+
+    let ClaimWithProofBufferAccounts {
+        config,
+        reward_collection_account,
+        claim_status,
+        claimant,
+        proof_buffer,
+        payer,
+        system_program,
+    } = accounts;
+
+    Instruction {
+        program_id,
+        data: crate::instruction::ClaimWithProofBuffer { amount }.data(),
+        accounts: crate::accounts::ClaimWithProofBuffer {
+            config,
+            reward_collection_account,
+            claim_status,
+            claimant,
+            proof_buffer,
+            payer,
+            system_program,
+        }
+        .to_account_metas(None),
+    }
+}
+
+/// Args for closing a proof buffer (empty).
+pub struct CloseProofBufferArgs;
+
+/// Accounts needed to close a proof buffer.
+pub struct CloseProofBufferAccounts {
+    pub proof_buffer: Pubkey,
+    pub payer: Pubkey,
+}
+
+/// Builds the instruction to close a proof buffer.
+pub fn close_proof_buffer_ix(
+    program_id: Pubkey,
+    _args: CloseProofBufferArgs,
+    accounts: CloseProofBufferAccounts,
+) -> Instruction {
+    let CloseProofBufferAccounts {
+        proof_buffer,
+        payer,
+    } = accounts;
+
+    Instruction {
+        program_id,
+        data: crate::instruction::CloseProofBuffer {}.data(),
+        accounts: crate::accounts::CloseProofBuffer {
+            proof_buffer,
+            payer,
+        }
+        .to_account_metas(None),
+    }
+}
+
+/// Claims to execute in a single batch.
+pub struct ClaimBatchArgs {
+    pub claims: Vec<BatchClaim>,
+}
+
+/// Accounts needed to execute a batch of Merkle reward claims.
+pub struct ClaimBatchAccounts {
+    pub config: Pubkey,
+    pub reward_collection_account: Pubkey,
+    pub payer: Pubkey,
+    pub system_program: Pubkey,
+    /// `(claimant, claim_status)` for every entry of [ClaimBatchArgs::claims], in the same order.
+    pub claimants: Vec<(Pubkey, Pubkey)>,
+}
+
+/// Builds the instruction for claiming Merkle rewards for several stakers at once.
+pub fn claim_batch_ix(
+    program_id: Pubkey,
+    args: ClaimBatchArgs,
+    accounts: ClaimBatchAccounts,
+) -> Instruction {
+    let ClaimBatchArgs { claims } = args;
+
+    let ClaimBatchAccounts {
+        config,
+        reward_collection_account,
+        payer,
+        system_program,
+        claimants,
+    } = accounts;
+
+    let mut account_metas = crate::accounts::ClaimBatch {
+        config,
+        reward_collection_account,
+        payer,
+        system_program,
+    }
+    .to_account_metas(None);
+    for (claimant, claim_status) in claimants {
+        account_metas.push(AccountMeta::new(claimant, false));
+        account_metas.push(AccountMeta::new(claim_status, false));
+    }
+
+    Instruction {
+        program_id,
+        data: crate::instruction::ClaimBatch { claims }.data(),
+        accounts: account_metas,
+    }
+}
+
+/// Claims of one claimant to settle in a single aggregated claim.
+pub struct ClaimAggregatedArgs {
+    pub claims: Vec<AggregatedClaim>,
+}
+
+/// Accounts needed to claim from several reward collection accounts at once.
+pub struct ClaimAggregatedAccounts {
+    pub config: Pubkey,
+    pub claimant: Pubkey,
+    pub payer: Pubkey,
+    pub system_program: Pubkey,
+    /// `(reward_collection_account, claim_status)` for every entry of
+    /// [ClaimAggregatedArgs::claims], in the same order.
+    pub reward_collection_accounts: Vec<(Pubkey, Pubkey)>,
+}
+
+/// Builds the instruction for claiming Merkle rewards of one claimant from several reward
+/// collection accounts at once.
+pub fn claim_aggregated_ix(
+    program_id: Pubkey,
+    args: ClaimAggregatedArgs,
+    accounts: ClaimAggregatedAccounts,
+) -> Instruction {
+    let ClaimAggregatedArgs { claims } = args;
+
+    let ClaimAggregatedAccounts {
+        config,
+        claimant,
+        payer,
+        system_program,
+        reward_collection_accounts,
+    } = accounts;
+
+    let mut account_metas = crate::accounts::ClaimAggregated {
+        config,
+        claimant,
+        payer,
+        system_program,
+    }
+    .to_account_metas(None);
+    for (reward_collection_account, claim_status) in reward_collection_accounts {
+        account_metas.push(AccountMeta::new(reward_collection_account, false));
+        account_metas.push(AccountMeta::new(claim_status, false));
+    }
+
+    Instruction {
+        program_id,
+        data: crate::instruction::ClaimAggregated { claims }.data(),
+        accounts: account_metas,
+    }
+}
+
+/// Args for creating a claim bitmap (empty).
+pub struct InitializeClaimBitmapArgs;
+
+/// Accounts needed to create the claim bitmap of a reward collection account.
+pub struct InitializeClaimBitmapAccounts {
+    pub config: Pubkey,
+    pub reward_collection_account: Pubkey,
+    pub claim_bitmap: Pubkey,
+    pub payer: Pubkey,
+    pub system_program: Pubkey,
+}
+
+/// Builds the instruction to create a claim bitmap.
+pub fn initialize_claim_bitmap_ix(
+    program_id: Pubkey,
+    _args: InitializeClaimBitmapArgs,
+    accounts: InitializeClaimBitmapAccounts,
+) -> Instruction {
+    let InitializeClaimBitmapAccounts {
+        config,
+        reward_collection_account,
+        claim_bitmap,
+        payer,
+        system_program,
+    } = accounts;
+
+    Instruction {
+        program_id,
+        data: crate::instruction::InitializeClaimBitmap {}.data(),
+        accounts: crate::accounts::InitializeClaimBitmap {
+            config,
+            reward_collection_account,
+            claim_bitmap,
+            payer,
+            system_program,
+        }
+        .to_account_metas(None),
+    }
+}
+
+/// Leaf index, proof and amount of an indexed Merkle claim.
+pub struct ClaimIndexedArgs {
+    pub index: u64,
+    pub proof: Vec<[u8; 32]>,
+    pub amount: u64,
+}
+
+/// Accounts needed to execute an indexed Merkle reward claim.
+pub struct ClaimIndexedAccounts {
+    pub config: Pubkey,
+    pub reward_collection_account: Pubkey,
+    pub claim_bitmap: Pubkey,
+    pub claimant: Pubkey,
+    pub payer: Pubkey,
+    pub system_program: Pubkey,
+}
+
+/// Builds the instruction for claiming Merkle rewards through a claim bitmap.
+pub fn claim_indexed_ix(
+    program_id: Pubkey,
+    args: ClaimIndexedArgs,
+    accounts: ClaimIndexedAccounts,
+) -> Instruction {
+    let ClaimIndexedArgs {
+        index,
+        proof,
+        amount,
+    } = args;
+
+    let ClaimIndexedAccounts {
+        config,
+        reward_collection_account,
+        claim_bitmap,
+        claimant,
+        payer,
+        system_program,
+    } = accounts;
+
+    Instruction {
+        program_id,
+        data: crate::instruction::ClaimIndexed {
+            index,
+            amount,
+            proof,
+        }
+        .data(),
+        accounts: crate::accounts::ClaimIndexed {
+            config,
+            reward_collection_account,
+            claim_bitmap,
+            claimant,
+            payer,
+            system_program,
+        }
+        .to_account_metas(None),
+    }
+}
+
+/// Args for closing a claim bitmap (empty).
+pub struct CloseClaimBitmapArgs;
+
+/// Accounts needed to close an expired claim bitmap.
+pub struct CloseClaimBitmapAccounts {
+    pub claim_bitmap: Pubkey,
+    pub payer: Pubkey,
+}
+
+/// Builds the instruction to close an expired claim bitmap.
+pub fn close_claim_bitmap_ix(
+    program_id: Pubkey,
+    _args: CloseClaimBitmapArgs,
+    accounts: CloseClaimBitmapAccounts,
+) -> Instruction {
+    let CloseClaimBitmapAccounts {
+        claim_bitmap,
+        payer,
+    } = accounts;
+
+    Instruction {
+        program_id,
+        data: crate::instruction::CloseClaimBitmap {}.data(),
+        accounts: crate::accounts::CloseClaimBitmap {
+            claim_bitmap,
+            payer,
+        }
+        .to_account_metas(None),
+    }
+}
+
+/// Arguments for initializing a cumulative distributor.
+pub struct InitializeCumulativeDistributorArgs {
+    pub merkle_root_upload_authority: Pubkey,
+}
+
+/// Accounts needed to initialize a cumulative distributor.
+pub struct InitializeCumulativeDistributorAccounts {
+    pub config: Pubkey,
+    pub distributor: Pubkey,
+    pub validator_vote_account: Pubkey,
+    pub signer: Pubkey,
+    pub system_program: Pubkey,
+}
+
+/// Builds the instruction to initialize a validator's cumulative distributor.
+pub fn initialize_cumulative_distributor_ix(
+    program_id: Pubkey,
+    args: InitializeCumulativeDistributorArgs,
+    accounts: InitializeCumulativeDistributorAccounts,
+) -> Instruction {
+    let InitializeCumulativeDistributorArgs {
+        merkle_root_upload_authority,
+    } = args;
+
+    let InitializeCumulativeDistributorAccounts {
+        config,
+        distributor,
+        validator_vote_account,
+        signer,
+        system_program,
+    } = accounts;
+
+    Instruction {
+        program_id,
+        data: crate::instruction::InitializeCumulativeDistributor {
+            merkle_root_upload_authority,
+        }
+        .data(),
+        accounts: crate::accounts::InitializeCumulativeDistributor {
+            config,
+            distributor,
+            validator_vote_account,
+            signer,
+            system_program,
+        }
+        .to_account_metas(None),
+    }
+}
+
+/// Epoch of the reward collection account to sweep.
+pub struct SweepToCumulativeDistributorArgs {
+    pub epoch: u64,
+}
+
+/// Accounts needed to sweep a reward collection account into a cumulative distributor.
+pub struct SweepToCumulativeDistributorAccounts {
+    pub reward_collection_account: Pubkey,
+    pub distributor: Pubkey,
+    pub signer: Pubkey,
+}
+
+/// Builds the instruction to sweep a past epoch's staker rewards into a cumulative distributor.
+pub fn sweep_to_cumulative_distributor_ix(
+    program_id: Pubkey,
+    args: SweepToCumulativeDistributorArgs,
+    accounts: SweepToCumulativeDistributorAccounts,
+) -> Instruction {
+    let SweepToCumulativeDistributorArgs { epoch } = args;
+
+    let SweepToCumulativeDistributorAccounts {
+        reward_collection_account,
+        distributor,
+        signer,
+    } = accounts;
+
+    Instruction {
+        program_id,
+        data: crate::instruction::SweepToCumulativeDistributor { _epoch: epoch }.data(),
+        accounts: crate::accounts::SweepToCumulativeDistributor {
+            reward_collection_account,
+            distributor,
+            signer,
+        }
+        .to_account_metas(None),
+    }
+}
+
+/// Root and lifetime total of a cumulative merkle tree.
+pub struct UploadCumulativeMerkleRootArgs {
+    pub root: [u8; 32],
+    pub max_total_claim: u64,
+}
+
+/// Accounts needed to upload a cumulative merkle root.
+pub struct UploadCumulativeMerkleRootAccounts {
+    pub distributor: Pubkey,
+    pub merkle_root_upload_authority: Pubkey,
+}
+
+/// Builds the instruction to upload the merkle root of a cumulative distributor.
+pub fn upload_cumulative_merkle_root_ix(
+    program_id: Pubkey,
+    args: UploadCumulativeMerkleRootArgs,
+    accounts: UploadCumulativeMerkleRootAccounts,
+) -> Instruction {
+    let UploadCumulativeMerkleRootArgs {
+        root,
+        max_total_claim,
+    } = args;
+
+    let UploadCumulativeMerkleRootAccounts {
+        distributor,
+        merkle_root_upload_authority,
+    } = accounts;
+
+    Instruction {
+        program_id,
+        data: crate::instruction::UploadCumulativeMerkleRoot {
+            root,
+            max_total_claim,
+        }
+        .data(),
+        accounts: crate::accounts::UploadCumulativeMerkleRoot {
+            distributor,
+            merkle_root_upload_authority,
+        }
+        .to_account_metas(None),
+    }
+}
+
+/// Lifetime total and proof of a cumulative Merkle claim.
+pub struct ClaimCumulativeArgs {
+    pub total_amount: u64,
+    pub proof: Vec<[u8; 32]>,
+}
+
+/// Accounts needed to claim from a cumulative distributor.
+pub struct ClaimCumulativeAccounts {
+    pub distributor: Pubkey,
+    pub claim_status: Pubkey,
+    pub claimant: Pubkey,
+    pub payer: Pubkey,
+    pub system_program: Pubkey,
+}
+
+/// Builds the instruction for claiming from a cumulative distributor.
+pub fn claim_cumulative_ix(
+    program_id: Pubkey,
+    args: ClaimCumulativeArgs,
+    accounts: ClaimCumulativeAccounts,
+) -> Instruction {
+    let ClaimCumulativeArgs {
+        total_amount,
+        proof,
+    } = args;
+
+    let ClaimCumulativeAccounts {
+        distributor,
+        claim_status,
+        claimant,
+        payer,
+        system_program,
+    } = accounts;
+
+    Instruction {
+        program_id,
+        data: crate::instruction::ClaimCumulative {
+            total_amount,
+            proof,
+        }
+        .data(),
+        accounts: crate::accounts::ClaimCumulative {
+            distributor,
+            claim_status,
+            claimant,
+            payer,
+            system_program,
+        }
+        .to_account_metas(None),
+    }
+}
@@ -1,8 +1,12 @@
 pub mod instruction;
+pub mod packing;
 
 use anchor_lang::{prelude::Pubkey, solana_program::clock::Epoch};
 
-use crate::{RewardCollectionAccount, RewardDistributionConfigAccount};
+use crate::{
+    ClaimBitmap, ClaimStatus, CumulativeClaimStatus, CumulativeDistributor, ProofBuffer,
+    RewardCollectionAccount, RewardDistributionConfigAccount, RootAttestation,
+};
 
 /// Derives the PDA for a reward collection account using vote pubkey and epoch.
 /// Returns the PDA and the bump.
@@ -21,6 +25,36 @@ pub fn derive_reward_collection_account_address(
     )
 }
 
+/// Derives the PDA for the root attestation of the given reward collection account.
+/// Returns the PDA and the bump.
+pub fn derive_root_attestation_address(
+    reward_distribution_program_id: &Pubkey,
+    reward_collection_account: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            RootAttestation::SEED,
+            reward_collection_account.to_bytes().as_ref(),
+        ],
+        reward_distribution_program_id,
+    )
+}
+
+/// Derives the PDA claims into a stake pool pass through for the given reward collection account.
+/// Returns the PDA and the bump.
+pub fn derive_stake_pool_deposit_address(
+    reward_distribution_program_id: &Pubkey,
+    reward_collection_account: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            RewardCollectionAccount::STAKE_POOL_DEPOSIT_SEED,
+            reward_collection_account.to_bytes().as_ref(),
+        ],
+        reward_distribution_program_id,
+    )
+}
+
 /// Derives the PDA for the reward distribution config account.
 /// Returns the PDA and the bump.
 pub fn derive_config_account_address(reward_distribution_program_id: &Pubkey) -> (Pubkey, u8) {
@@ -29,3 +63,83 @@ pub fn derive_config_account_address(reward_distribution_program_id: &Pubkey) ->
         reward_distribution_program_id,
     )
 }
+
+/// Derives the PDA for a claimant's claim status account on the given reward collection account.
+/// Returns the PDA and the bump.
+pub fn derive_claim_status_account_address(
+    reward_distribution_program_id: &Pubkey,
+    claimant: &Pubkey,
+    reward_collection_account: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            ClaimStatus::SEED,
+            claimant.to_bytes().as_ref(),
+            reward_collection_account.to_bytes().as_ref(),
+        ],
+        reward_distribution_program_id,
+    )
+}
+
+/// Derives the PDA for the claim bitmap of the given reward collection account.
+/// Returns the PDA and the bump.
+pub fn derive_claim_bitmap_account_address(
+    reward_distribution_program_id: &Pubkey,
+    reward_collection_account: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            ClaimBitmap::SEED,
+            reward_collection_account.to_bytes().as_ref(),
+        ],
+        reward_distribution_program_id,
+    )
+}
+
+/// Derives the PDA for the proof buffer of `payer` for a claimant's claim on a reward collection
+/// account. Returns the PDA and the bump.
+pub fn derive_proof_buffer_address(
+    reward_distribution_program_id: &Pubkey,
+    reward_collection_account: &Pubkey,
+    claimant: &Pubkey,
+    payer: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            ProofBuffer::SEED,
+            reward_collection_account.to_bytes().as_ref(),
+            claimant.to_bytes().as_ref(),
+            payer.to_bytes().as_ref(),
+        ],
+        reward_distribution_program_id,
+    )
+}
+
+/// Derives the PDA for the cumulative distributor of the given vote account.
+/// Returns the PDA and the bump.
+pub fn derive_cumulative_distributor_address(
+    reward_distribution_program_id: &Pubkey,
+    vote_pubkey: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[CumulativeDistributor::SEED, vote_pubkey.to_bytes().as_ref()],
+        reward_distribution_program_id,
+    )
+}
+
+/// Derives the PDA for a claimant's claim status account on the given cumulative distributor.
+/// Returns the PDA and the bump.
+pub fn derive_cumulative_claim_status_address(
+    reward_distribution_program_id: &Pubkey,
+    claimant: &Pubkey,
+    distributor: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            CumulativeClaimStatus::SEED,
+            claimant.to_bytes().as_ref(),
+            distributor.to_bytes().as_ref(),
+        ],
+        reward_distribution_program_id,
+    )
+}
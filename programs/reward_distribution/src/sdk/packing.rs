@@ -0,0 +1,297 @@
+//! Splits claims into transactions that fit the packet size limit: aggregated claims of one
+//! claimant across reward collection accounts, and single claims whose proof is too long to be
+//! passed inline.
+use anchor_lang::{
+    prelude::Pubkey,
+    solana_program::{
+        address_lookup_table::AddressLookupTableAccount,
+        hash::Hash,
+        instruction::Instruction,
+        message::{v0, Message, VersionedMessage},
+    },
+};
+
+use crate::{
+    sdk::{
+        derive_claim_status_account_address, derive_proof_buffer_address,
+        instruction::{
+            claim_aggregated_ix, claim_ix, claim_with_proof_buffer_ix, initialize_proof_buffer_ix,
+            write_proof_buffer_ix, ClaimAccounts, ClaimAggregatedAccounts, ClaimAggregatedArgs,
+            ClaimArgs, ClaimWithProofBufferAccounts, ClaimWithProofBufferArgs,
+            InitializeProofBufferAccounts, InitializeProofBufferArgs, WriteProofBufferAccounts,
+            WriteProofBufferArgs,
+        },
+    },
+    AggregatedClaim,
+};
+
+/// Maximum size of a serialized transaction.
+pub const PACKET_DATA_SIZE: usize = 1232;
+
+/// A claim on one reward collection account, as passed to [pack_aggregated_claims].
+#[derive(Clone)]
+pub struct AggregatedClaimEntry {
+    pub reward_collection_account: Pubkey,
+    /// The claimant's `ClaimStatus` PDA on `reward_collection_account`.
+    pub claim_status: Pubkey,
+    pub amount: u64,
+    pub proof: Vec<[u8; 32]>,
+}
+
+/// Accounts shared by every instruction built by [pack_aggregated_claims].
+#[derive(Clone, Copy)]
+pub struct PackAccounts {
+    pub config: Pubkey,
+    pub claimant: Pubkey,
+    pub payer: Pubkey,
+    pub system_program: Pubkey,
+}
+
+/// Returns the size of a transaction signed by `payer` with `instructions`. The message is
+/// compiled as a v0 message against `lookup_tables` when any are given, as a legacy message
+/// otherwise. Returns `usize::MAX` if the v0 message can not be compiled.
+pub fn transaction_size(
+    payer: &Pubkey,
+    instructions: &[Instruction],
+    lookup_tables: &[AddressLookupTableAccount],
+) -> usize {
+    let message = if lookup_tables.is_empty() {
+        VersionedMessage::Legacy(Message::new(instructions, Some(payer)))
+    } else {
+        match v0::Message::try_compile(payer, instructions, lookup_tables, Hash::default()) {
+            Ok(message) => VersionedMessage::V0(message),
+            Err(_) => return usize::MAX,
+        }
+    };
+    let num_signatures = message.header().num_required_signatures as usize;
+
+    // The signature count is a compact-u16, which takes a single byte below 128 signatures.
+    1 + num_signatures * 64 + message.serialize().len()
+}
+
+/// Packs `entries` into as few `claim_aggregated` instructions as possible, each of which fits
+/// into a single transaction next to `extra_instructions` (e.g. compute budget instructions).
+/// Entries keep their order. An entry whose proof is too long to fit even on its own is returned
+/// in an instruction of its own, which will be rejected when sent.
+///
+/// Passing a lookup table holding the [lookup_table_addresses] lets considerably more claims
+/// fit into one transaction.
+pub fn pack_aggregated_claims(
+    program_id: Pubkey,
+    accounts: PackAccounts,
+    entries: Vec<AggregatedClaimEntry>,
+    extra_instructions: &[Instruction],
+    lookup_tables: &[AddressLookupTableAccount],
+) -> Vec<Instruction> {
+    let build = |batch: &[AggregatedClaimEntry]| {
+        claim_aggregated_ix(
+            program_id,
+            ClaimAggregatedArgs {
+                claims: batch
+                    .iter()
+                    .map(|entry| AggregatedClaim {
+                        amount: entry.amount,
+                        proof: entry.proof.clone(),
+                    })
+                    .collect(),
+            },
+            ClaimAggregatedAccounts {
+                config: accounts.config,
+                claimant: accounts.claimant,
+                payer: accounts.payer,
+                system_program: accounts.system_program,
+                reward_collection_accounts: batch
+                    .iter()
+                    .map(|entry| (entry.reward_collection_account, entry.claim_status))
+                    .collect(),
+            },
+        )
+    };
+    let fits = |instruction: Instruction| {
+        let mut instructions = extra_instructions.to_vec();
+        instructions.push(instruction);
+        transaction_size(&accounts.payer, &instructions, lookup_tables) <= PACKET_DATA_SIZE
+    };
+
+    let mut packed = Vec::new();
+    let mut batch: Vec<AggregatedClaimEntry> = Vec::new();
+    for entry in entries {
+        batch.push(entry);
+        if batch.len() > 1 && !fits(build(&batch)) {
+            let entry = batch.pop().unwrap();
+            packed.push(build(&batch));
+            batch = vec![entry];
+        }
+    }
+    if !batch.is_empty() {
+        packed.push(build(&batch));
+    }
+
+    packed
+}
+
+/// Returns the addresses to store in an address lookup table for [pack_aggregated_claims]. The
+/// payer signs, so it has to stay in the static account keys.
+pub fn lookup_table_addresses(
+    accounts: &PackAccounts,
+    entries: &[AggregatedClaimEntry],
+) -> Vec<Pubkey> {
+    let mut addresses = vec![accounts.config, accounts.claimant, accounts.system_program];
+    for entry in entries {
+        addresses.push(entry.reward_collection_account);
+        addresses.push(entry.claim_status);
+    }
+    addresses
+}
+
+/// Accounts of a single claim, as passed to [plan_claim].
+#[derive(Clone, Copy)]
+pub struct ClaimPlanAccounts {
+    pub config: Pubkey,
+    pub reward_collection_account: Pubkey,
+    pub claimant: Pubkey,
+    pub payer: Pubkey,
+    pub system_program: Pubkey,
+}
+
+/// Returns the transactions settling the claim of `amount` with `proof`, each starting with
+/// `extra_instructions`. A proof that fits is passed to `claim` inline in a single transaction.
+/// A longer one is written to the payer's proof buffer first, and the transactions have to be
+/// sent in order, each after the previous one is confirmed.
+pub fn plan_claim(
+    program_id: Pubkey,
+    accounts: ClaimPlanAccounts,
+    amount: u64,
+    proof: Vec<[u8; 32]>,
+    extra_instructions: &[Instruction],
+) -> Vec<Vec<Instruction>> {
+    plan_claim_with_max_size(
+        program_id,
+        accounts,
+        amount,
+        proof,
+        extra_instructions,
+        PACKET_DATA_SIZE,
+    )
+}
+
+/// Same as [plan_claim], with transactions of up to `max_transaction_size` bytes instead of a
+/// packet.
+pub fn plan_claim_with_max_size(
+    program_id: Pubkey,
+    accounts: ClaimPlanAccounts,
+    amount: u64,
+    proof: Vec<[u8; 32]>,
+    extra_instructions: &[Instruction],
+    max_transaction_size: usize,
+) -> Vec<Vec<Instruction>> {
+    let ClaimPlanAccounts {
+        config,
+        reward_collection_account,
+        claimant,
+        payer,
+        system_program,
+    } = accounts;
+    let (claim_status, bump) =
+        derive_claim_status_account_address(&program_id, &claimant, &reward_collection_account);
+    let with_extra = |instructions: &[Instruction]| {
+        let mut transaction = extra_instructions.to_vec();
+        transaction.extend_from_slice(instructions);
+        transaction
+    };
+    let fits = |transaction: &[Instruction]| {
+        transaction_size(&payer, transaction, &[]) <= max_transaction_size
+    };
+
+    let inline = with_extra(&[claim_ix(
+        program_id,
+        ClaimArgs {
+            proof: proof.clone(),
+            amount,
+            bump,
+        },
+        ClaimAccounts {
+            config,
+            reward_collection_account,
+            claim_status,
+            claimant,
+            payer,
+            system_program,
+        },
+    )]);
+    if fits(&inline) {
+        return vec![inline];
+    }
+
+    let (proof_buffer, _) =
+        derive_proof_buffer_address(&program_id, &reward_collection_account, &claimant, &payer);
+    let write = |offset: usize, hashes: &[[u8; 32]]| {
+        write_proof_buffer_ix(
+            program_id,
+            WriteProofBufferArgs {
+                offset: offset as u8,
+                hashes: hashes.to_vec(),
+            },
+            WriteProofBufferAccounts {
+                proof_buffer,
+                payer,
+            },
+        )
+    };
+
+    let mut transactions = Vec::new();
+    let mut offset = 0;
+    while offset < proof.len() {
+        let mut prefix = Vec::new();
+        if offset == 0 {
+            prefix.push(initialize_proof_buffer_ix(
+                program_id,
+                InitializeProofBufferArgs {
+                    claimant,
+                    proof_len: proof.len() as u8,
+                },
+                InitializeProofBufferAccounts {
+                    config,
+                    reward_collection_account,
+                    proof_buffer,
+                    payer,
+                    system_program,
+                },
+            ));
+        }
+        let mut end = offset + 1;
+        while end < proof.len() {
+            let mut transaction = prefix.clone();
+            transaction.push(write(offset, &proof[offset..end + 1]));
+            if !fits(&with_extra(&transaction)) {
+                break;
+            }
+            end += 1;
+        }
+        prefix.push(write(offset, &proof[offset..end]));
+        transactions.push(with_extra(&prefix));
+        offset = end;
+    }
+
+    let claim = claim_with_proof_buffer_ix(
+        program_id,
+        ClaimWithProofBufferArgs { amount },
+        ClaimWithProofBufferAccounts {
+            config,
+            reward_collection_account,
+            claim_status,
+            claimant,
+            proof_buffer,
+            payer,
+            system_program,
+        },
+    );
+    let last = transactions.last_mut().unwrap();
+    last.push(claim.clone());
+    if !fits(last) {
+        last.pop();
+        transactions.push(with_extra(&[claim]));
+    }
+
+    transactions
+}
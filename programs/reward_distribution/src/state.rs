@@ -1,5 +1,15 @@
-use crate::ErrorCode::{AccountValidationFailure, ArithmeticError};
-use anchor_lang::prelude::*;
+use crate::{
+    commission::{CategorizedSplit, CategoryCommissions, RewardSplit},
+    merkle_proof,
+    ErrorCode::{
+        AccountValidationFailure, ArithmeticError, ClaimBitmapTooLarge, ExceedsMaxClaim,
+        ExceedsMaxNumNodes, FundsAlreadyClaimed, InvalidAttesters, InvalidLeafIndex, InvalidProof,
+        InvalidProofBufferWrite, InvalidSlotRange, MaxCommissionFeeBpsExceeded,
+        OutdatedAccountVersion, ProofBufferIncomplete, RootNotUploaded, SlotRangeAlreadySettled,
+        Unauthorized, UnsupportedLeafVersion,
+    },
+};
+use anchor_lang::{prelude::*, system_program};
 use std::mem::size_of;
 
 /// Stores configuration for the reward distribution program.
@@ -40,6 +50,150 @@ pub struct RewardCollectionAccount {
     pub initializer: Pubkey,
     /// PDA bump.
     pub bump: u8,
+    /// Leaf format of the tree of `merkle_root`, [MerkleRoot::LEAF_VERSION_V0] or
+    /// [MerkleRoot::LEAF_VERSION_V1]. Kept out of [MerkleRoot] so that accounts created before
+    /// leaves were versioned keep their layout up to here.
+    pub leaf_version: u8,
+    /// Running totals of the transferred rewards by category.
+    pub reward_buckets: RewardBuckets,
+    /// Layout of the account, one of the `RewardCollectionAccount::ACCOUNT_VERSION_*`.
+    pub account_version: u8,
+    /// Rewards transferred by leader turns, before commissions.
+    pub total_rewards: u64,
+    /// Rakurai's commission of the transferred rewards.
+    pub block_builder_fees: u64,
+    /// Validator's commission of the transferred rewards, kept in the validator's identity.
+    pub validator_fees: u64,
+    /// Stakers' share of the transferred rewards.
+    pub staker_rewards: u64,
+    /// Number of leader turns transferred.
+    pub num_transfers: u64,
+    /// Last slot of the latest leader turn transferred; a turn can only be transferred once.
+    pub last_settled_slot: Option<u64>,
+    /// Scale of every claim of `merkle_root`, set when it was uploaded in pro-rata mode to an
+    /// underfunded account. Claims are paid in full when `None`.
+    pub claim_scale: Option<ClaimScale>,
+    /// Whether unclaimed funds move into the validator's [RewardCollectionAccount] of the current
+    /// epoch on expiry, instead of going back to the `initializer`.
+    pub rollover_unclaimed: bool,
+    /// Staker rewards added without commissions, by `deposit_rewards` and by rollovers of
+    /// earlier epochs' unclaimed funds. Not part of `total_rewards`.
+    pub deposited_rewards: u64,
+    /// Validator commissions of the reward categories that differ from
+    /// `validator_commission_bps`, set with `update_validator_commission`.
+    pub category_commissions: CategoryCommissions,
+}
+
+/// Layout of the [RewardCollectionAccount]s created before `account_version` existed, i.e. of
+/// [RewardCollectionAccount::ACCOUNT_VERSION_V0] accounts, whose fields are those of the same name
+/// of [RewardCollectionAccount]. Only read by `migrate_reward_collection_account`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default)]
+pub struct LegacyRewardCollectionAccountV0 {
+    pub validator_vote_account: Pubkey,
+    pub merkle_root_upload_authority: Pubkey,
+    pub merkle_root: Option<MerkleRoot>,
+    pub creation_epoch: u64,
+    pub validator_commission_bps: u16,
+    pub rakurai_commission_bps: u16,
+    pub rakurai_commission_account: Pubkey,
+    pub expires_at: u64,
+    pub initializer: Pubkey,
+    pub bump: u8,
+}
+
+/// Haircut taken by every claim of a root uploaded in pro-rata mode to a
+/// [RewardCollectionAccount] holding less than its `max_total_claim`: each claim pays
+/// `amount * available / outstanding`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ClaimScale {
+    /// Funds of the account beyond its rent when the root was uploaded.
+    pub available: u64,
+    /// `max_total_claim` of the root.
+    pub outstanding: u64,
+}
+
+/// Where the lamports of a `deposit_rewards` came from, the way
+/// [crate::commission::CategorizedRewards] breaks down the rewards of a leader turn.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DepositSource {
+    /// Tips paid outside of the transaction fees, e.g. MEV tips.
+    Tips,
+    /// Incentives paid by a partner.
+    PartnerIncentives,
+    /// Lamports added by the validator itself.
+    ValidatorTopUp,
+    /// Any other source.
+    Other,
+}
+
+/// Running totals of one reward category of a [RewardCollectionAccount].
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RewardBucket {
+    /// Rewards transferred, before commissions.
+    pub total_rewards: u64,
+    /// Share of the stakers, transferred to the [RewardCollectionAccount].
+    pub staker_rewards: u64,
+}
+
+/// Running totals of a [RewardCollectionAccount], one [RewardBucket] per category of
+/// [crate::commission::CategorizedRewards].
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RewardBuckets {
+    /// Base transaction fees.
+    pub block_fees: RewardBucket,
+    /// Priority fees.
+    pub priority_fees: RewardBucket,
+    /// Tips paid outside of the transaction fees.
+    pub tips: RewardBucket,
+}
+
+impl ClaimScale {
+    /// The scale of a root of `outstanding` claims backed by `available` funds, or `None` if the
+    /// funds cover every claim.
+    pub fn new(available: u64, outstanding: u64) -> Option<Self> {
+        (available < outstanding).then_some(Self {
+            available,
+            outstanding,
+        })
+    }
+
+    /// `amount` scaled down, rounded down so that the scaled claims never add up to more than
+    /// `available`.
+    pub fn apply(&self, amount: u64) -> Result<u64> {
+        let scaled = (amount as u128)
+            .checked_mul(self.available as u128)
+            .and_then(|product| product.checked_div(self.outstanding as u128))
+            .ok_or(ArithmeticError)?;
+        Ok(u64::try_from(scaled).map_err(|_| ArithmeticError)?)
+    }
+}
+
+impl RewardBucket {
+    fn record(&mut self, split: &RewardSplit) -> Result<()> {
+        let total_rewards = split
+            .block_builder_fee
+            .checked_add(split.validator_fee)
+            .and_then(|fees| fees.checked_add(split.staker_rewards))
+            .ok_or(ArithmeticError)?;
+        self.total_rewards = self
+            .total_rewards
+            .checked_add(total_rewards)
+            .ok_or(ArithmeticError)?;
+        self.staker_rewards = self
+            .staker_rewards
+            .checked_add(split.staker_rewards)
+            .ok_or(ArithmeticError)?;
+        Ok(())
+    }
+}
+
+impl RewardBuckets {
+    /// Adds a leader turn's split rewards to the bucket of each category.
+    pub fn record(&mut self, split: &CategorizedSplit) -> Result<()> {
+        self.block_fees.record(&split.block_fees)?;
+        self.priority_fees.record(&split.priority_fees)?;
+        self.tips.record(&split.tips)
+    }
 }
 
 /// Metadata about the Merkle root used for claims.
@@ -57,8 +211,106 @@ pub struct MerkleRoot {
     pub num_nodes_claimed: u64,
 }
 
+/// What a [MerkleRoot::LEAF_VERSION_V1] leaf commits to besides the claim itself, so that its
+/// proof only verifies against the tree of one [RewardCollectionAccount]. Shared with the
+/// off-chain tree builder, along with the leaf hashing.
+pub use rakurai_merkle_tree::LeafDomain;
+
 const HEADER_SIZE: usize = 8;
 
+impl MerkleRoot {
+    /// Leaves commit to the claim only: `(claimant, amount)`, or `(index, claimant, amount)`
+    /// for indexed trees.
+    pub const LEAF_VERSION_V0: u8 = rakurai_merkle_tree::LEAF_VERSION_V0;
+    /// Leaves also commit to the [LeafDomain], so a root and its proofs can not be replayed
+    /// against another validator's or epoch's [RewardCollectionAccount].
+    pub const LEAF_VERSION_V1: u8 = rakurai_merkle_tree::LEAF_VERSION_V1;
+
+    /// Checks that `version` is a leaf format this program can verify.
+    pub fn validate_version(version: u8) -> Result<()> {
+        if version > Self::LEAF_VERSION_V1 {
+            return Err(UnsupportedLeafVersion.into());
+        }
+        Ok(())
+    }
+
+    /// Checks that `(index, claimant, amount)` is a leaf of the tree, in the `leaf_version` format.
+    pub fn verify_indexed_claim(
+        &self,
+        leaf_version: u8,
+        domain: &LeafDomain,
+        index: u64,
+        claimant: &Pubkey,
+        amount: u64,
+        proof: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        let leaf = match leaf_version {
+            Self::LEAF_VERSION_V0 => {
+                rakurai_merkle_tree::hash_indexed_leaf(index, claimant, amount)
+            }
+            _ => rakurai_merkle_tree::hash_bound_indexed_leaf(domain, index, claimant, amount),
+        };
+        if !merkle_proof::verify(proof, self.root, leaf) {
+            return Err(InvalidProof.into());
+        }
+        Ok(())
+    }
+
+    /// Checks that `(claimant, amount)` is a leaf of the tree, in the `leaf_version` format.
+    pub fn verify_claim(
+        &self,
+        leaf_version: u8,
+        domain: &LeafDomain,
+        claimant: &Pubkey,
+        amount: u64,
+        proof: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        let leaf = match leaf_version {
+            Self::LEAF_VERSION_V0 => rakurai_merkle_tree::hash_leaf(claimant, amount),
+            _ => rakurai_merkle_tree::hash_bound_leaf(domain, claimant, amount),
+        };
+        if !merkle_proof::verify(proof, self.root, leaf) {
+            return Err(InvalidProof.into());
+        }
+        Ok(())
+    }
+
+    /// Whether every node of the tree has claimed, after which nothing more can be claimed.
+    pub fn is_fully_claimed(&self) -> bool {
+        self.num_nodes_claimed >= self.max_num_nodes
+    }
+
+    /// Funds of the tree not claimed yet.
+    pub fn outstanding_claims(&self) -> Result<u64> {
+        Ok(self
+            .max_total_claim
+            .checked_sub(self.total_funds_claimed)
+            .ok_or(ArithmeticError)?)
+    }
+
+    /// Adds `num_claims` claims totalling `amount` to the running totals, enforcing
+    /// `max_total_claim` and `max_num_nodes`.
+    pub fn record_claims(&mut self, amount: u64, num_claims: u64) -> Result<()> {
+        self.total_funds_claimed = self
+            .total_funds_claimed
+            .checked_add(amount)
+            .ok_or(ArithmeticError)?;
+        if self.total_funds_claimed > self.max_total_claim {
+            return Err(ExceedsMaxClaim.into());
+        }
+
+        self.num_nodes_claimed = self
+            .num_nodes_claimed
+            .checked_add(num_claims)
+            .ok_or(ArithmeticError)?;
+        if self.num_nodes_claimed > self.max_num_nodes {
+            return Err(ExceedsMaxNumNodes.into());
+        }
+
+        Ok(())
+    }
+}
+
 impl RewardDistributionConfigAccount {
     /// PDA seed for the config account.
     pub const SEED: &'static [u8] = b"RD_CONFIG_ACCOUNT";
@@ -80,15 +332,145 @@ impl RewardDistributionConfigAccount {
 
         Ok(())
     }
+
+    /// Checks that each commission, and both combined, stay within `max_commission_bps`.
+    pub fn check_commissions(
+        &self,
+        validator_commission_bps: u16,
+        rakurai_commission_bps: u16,
+    ) -> Result<()> {
+        if validator_commission_bps > self.max_commission_bps
+            || rakurai_commission_bps > self.max_commission_bps
+            || (validator_commission_bps + rakurai_commission_bps) > self.max_commission_bps
+        {
+            return Err(MaxCommissionFeeBpsExceeded.into());
+        }
+
+        Ok(())
+    }
+}
+
+impl LegacyRewardCollectionAccountV0 {
+    /// Size the accounts of this layout were created with.
+    pub const SIZE: usize = HEADER_SIZE + size_of::<Self>();
+
+    /// Decodes the data of a version 0 account, discriminator included.
+    pub fn try_deserialize(data: &[u8]) -> Result<Self> {
+        let mut body = data
+            .strip_prefix(RewardCollectionAccount::DISCRIMINATOR)
+            .ok_or(AccountValidationFailure)?;
+        Self::deserialize(&mut body)
+            .map_err(|_| anchor_lang::error::ErrorCode::AccountDidNotDeserialize.into())
+    }
+}
+
+impl From<LegacyRewardCollectionAccountV0> for RewardCollectionAccount {
+    /// Carries every field of a version 0 account over; the fields added since start empty.
+    /// Its root, if any, was built with [MerkleRoot::LEAF_VERSION_V0] leaves.
+    fn from(legacy: LegacyRewardCollectionAccountV0) -> Self {
+        Self {
+            validator_vote_account: legacy.validator_vote_account,
+            merkle_root_upload_authority: legacy.merkle_root_upload_authority,
+            merkle_root: legacy.merkle_root,
+            creation_epoch: legacy.creation_epoch,
+            validator_commission_bps: legacy.validator_commission_bps,
+            rakurai_commission_bps: legacy.rakurai_commission_bps,
+            rakurai_commission_account: legacy.rakurai_commission_account,
+            expires_at: legacy.expires_at,
+            initializer: legacy.initializer,
+            bump: legacy.bump,
+            leaf_version: MerkleRoot::LEAF_VERSION_V0,
+            account_version: Self::ACCOUNT_VERSION_V0,
+            ..Self::default()
+        }
+    }
 }
 
 impl RewardCollectionAccount {
     /// PDA seed for collection accounts.
     pub const SEED: &'static [u8] = b"REWARD_COLLECTION_ACCOUNT";
+    /// PDA seed of the system account a claim passes through on its way into a stake pool, see
+    /// `claim_to_stake_pool`.
+    pub const STAKE_POOL_DEPOSIT_SEED: &'static [u8] = b"STAKE_POOL_DEPOSIT";
 
     /// Account size for rent-exemption.
     pub const SIZE: usize = HEADER_SIZE + size_of::<Self>();
 
+    /// Accounts created before `account_version` existed, in the layout of
+    /// [LegacyRewardCollectionAccountV0]. They are smaller than [RewardCollectionAccount::SIZE],
+    /// and are grown and brought to the current version by `migrate_reward_collection_account`.
+    pub const ACCOUNT_VERSION_V0: u8 = 0;
+    /// Accounts holding every field of [RewardCollectionAccount].
+    pub const ACCOUNT_VERSION_V1: u8 = 1;
+    /// Version of the accounts created by this program.
+    pub const CURRENT_VERSION: u8 = Self::ACCOUNT_VERSION_V1;
+
+    /// Checks that the account was created at, or migrated to, [RewardCollectionAccount::CURRENT_VERSION].
+    pub fn check_version(&self) -> Result<()> {
+        if self.account_version != Self::CURRENT_VERSION {
+            return Err(OutdatedAccountVersion.into());
+        }
+        Ok(())
+    }
+
+    /// Records the leader turn of slots `start_slot..=end_slot` as settled. Fails if the range is
+    /// empty, or overlaps or precedes a turn settled before, so a retried transfer of the same
+    /// turn is rejected.
+    pub fn settle_slot_range(&mut self, start_slot: u64, end_slot: u64) -> Result<()> {
+        if start_slot > end_slot {
+            return Err(InvalidSlotRange.into());
+        }
+        if let Some(last_settled_slot) = self.last_settled_slot {
+            if start_slot <= last_settled_slot {
+                return Err(SlotRangeAlreadySettled.into());
+            }
+        }
+        self.last_settled_slot = Some(end_slot);
+        Ok(())
+    }
+
+    /// Adds a leader turn's split rewards to `reward_buckets` and the running totals, and returns
+    /// the turn's total split.
+    pub fn record_transfer(&mut self, split: &CategorizedSplit) -> Result<RewardSplit> {
+        self.reward_buckets.record(split)?;
+
+        let total = split.total()?;
+        let total_rewards = total
+            .block_builder_fee
+            .checked_add(total.validator_fee)
+            .and_then(|fees| fees.checked_add(total.staker_rewards))
+            .ok_or(ArithmeticError)?;
+        self.total_rewards = self
+            .total_rewards
+            .checked_add(total_rewards)
+            .ok_or(ArithmeticError)?;
+        self.block_builder_fees = self
+            .block_builder_fees
+            .checked_add(total.block_builder_fee)
+            .ok_or(ArithmeticError)?;
+        self.validator_fees = self
+            .validator_fees
+            .checked_add(total.validator_fee)
+            .ok_or(ArithmeticError)?;
+        self.staker_rewards = self
+            .staker_rewards
+            .checked_add(total.staker_rewards)
+            .ok_or(ArithmeticError)?;
+        self.num_transfers = self.num_transfers.checked_add(1).ok_or(ArithmeticError)?;
+
+        Ok(total)
+    }
+
+    /// Adds `amount` lamports deposited, or rolled over, without commissions to
+    /// `deposited_rewards`.
+    pub fn record_deposit(&mut self, amount: u64) -> Result<()> {
+        self.deposited_rewards = self
+            .deposited_rewards
+            .checked_add(amount)
+            .ok_or(ArithmeticError)?;
+        Ok(())
+    }
+
     /// Validates that required fields are not default.
     pub fn validate(&self) -> Result<()> {
         let default_pubkey = Pubkey::default();
@@ -120,13 +502,43 @@ impl RewardCollectionAccount {
         Ok(amount)
     }
 
+    /// Lamports of the account, held at `account_info`, beyond its rent and what the claims of
+    /// `merkle_root` still outstanding pay out, which no claim can take.
+    pub fn surplus(&self, account_info: &AccountInfo) -> Result<u64> {
+        let merkle_root = self.merkle_root.as_ref().ok_or(RootNotUploaded)?;
+        let outstanding_payout = self.payout(merkle_root.outstanding_claims()?)?;
+        Ok(Self::available_funds(account_info)?.saturating_sub(outstanding_payout))
+    }
+
+    /// Lamports of the account at `account_info` beyond its rent.
+    pub fn available_funds(account_info: &AccountInfo) -> Result<u64> {
+        let min_rent_lamports = Rent::get()?.minimum_balance(account_info.data_len());
+        Ok(account_info.lamports().saturating_sub(min_rent_lamports))
+    }
+
+    /// What a leaf of `amount` pays out, after the `claim_scale` haircut if any.
+    pub fn payout(&self, amount: u64) -> Result<u64> {
+        match &self.claim_scale {
+            Some(claim_scale) => claim_scale.apply(amount),
+            None => Ok(amount),
+        }
+    }
+
+    /// The [LeafDomain] of the account at `address`.
+    pub fn leaf_domain(&self, address: Pubkey) -> LeafDomain {
+        LeafDomain {
+            reward_collection_account: address,
+            creation_epoch: self.creation_epoch,
+        }
+    }
+
     /// Claims a specified amount from the account.
     pub fn claim(from: AccountInfo, to: AccountInfo, amount: u64) -> Result<()> {
         Self::transfer_lamports(from, to, amount)
     }
 
     /// Internal helper to safely transfer lamports.
-    fn transfer_lamports(from: AccountInfo, to: AccountInfo, amount: u64) -> Result<()> {
+    pub(crate) fn transfer_lamports(from: AccountInfo, to: AccountInfo, amount: u64) -> Result<()> {
         // debit lamports
         **from.try_borrow_mut_lamports()? =
             from.lamports().checked_sub(amount).ok_or(ArithmeticError)?;
@@ -163,4 +575,431 @@ impl ClaimStatus {
     pub const SEED: &'static [u8] = b"CLAIM_STATUS";
     /// Account size for rent-exemption.
     pub const SIZE: usize = HEADER_SIZE + size_of::<Self>();
+
+    /// Creates a claim status PDA owned by this program, the same way Anchor's `init` constraint
+    /// does: an address that was already funded is topped up, allocated and assigned instead.
+    pub fn create<'info>(
+        account: &AccountInfo<'info>,
+        payer: &Signer<'info>,
+        system_program: &Program<'info, System>,
+        seeds: &[&[u8]],
+        rent_lamports: u64,
+    ) -> Result<()> {
+        let signer_seeds: &[&[&[u8]]] = &[seeds];
+        let current_lamports = account.lamports();
+        if current_lamports == 0 {
+            return system_program::create_account(
+                CpiContext::new_with_signer(
+                    system_program.to_account_info(),
+                    system_program::CreateAccount {
+                        from: payer.to_account_info(),
+                        to: account.clone(),
+                    },
+                    signer_seeds,
+                ),
+                rent_lamports,
+                Self::SIZE as u64,
+                &crate::ID,
+            );
+        }
+
+        let top_up = rent_lamports.saturating_sub(current_lamports);
+        if top_up > 0 {
+            system_program::transfer(
+                CpiContext::new(
+                    system_program.to_account_info(),
+                    system_program::Transfer {
+                        from: payer.to_account_info(),
+                        to: account.clone(),
+                    },
+                ),
+                top_up,
+            )?;
+        }
+        system_program::allocate(
+            CpiContext::new_with_signer(
+                system_program.to_account_info(),
+                system_program::Allocate {
+                    account_to_allocate: account.clone(),
+                },
+                signer_seeds,
+            ),
+            Self::SIZE as u64,
+        )?;
+        system_program::assign(
+            CpiContext::new_with_signer(
+                system_program.to_account_info(),
+                system_program::Assign {
+                    account_to_assign: account.clone(),
+                },
+                signer_seeds,
+            ),
+            &crate::ID,
+        )
+    }
+}
+
+/// Records which leaves of an indexed merkle tree were claimed. Replaces one [ClaimStatus]
+/// account per claimant with a single account per [RewardCollectionAccount].
+#[account]
+#[derive(Default)]
+pub struct ClaimBitmap {
+    /// [RewardCollectionAccount] the bitmap belongs to.
+    pub reward_collection_account: Pubkey,
+    /// Payer of the bitmap account; receives the rent back on close.
+    pub payer: Pubkey,
+    /// Expiry of the bitmap, copied from the [RewardCollectionAccount].
+    pub expires_at: u64,
+    /// PDA bump.
+    pub bump: u8,
+    /// Bit `i % 8` of byte `i / 8` is set once leaf `i` has been claimed.
+    pub bits: Vec<u8>,
+}
+
+impl ClaimBitmap {
+    /// PDA seed for claim bitmap accounts.
+    pub const SEED: &'static [u8] = b"CLAIM_BITMAP";
+    /// Size of the fixed fields, including the length prefix of `bits`.
+    const FIXED_SIZE: usize = HEADER_SIZE + 32 + 32 + 8 + 1 + 4;
+    /// Largest account that can be created through a CPI.
+    pub const MAX_SIZE: usize = solana_program::entrypoint::MAX_PERMITTED_DATA_INCREASE;
+    /// Most leaves a single bitmap can track.
+    pub const MAX_LEAVES: u64 = ((Self::MAX_SIZE - Self::FIXED_SIZE) * 8) as u64;
+
+    /// Account size needed to track `num_leaves` leaves.
+    pub fn size(num_leaves: u64) -> usize {
+        Self::FIXED_SIZE + Self::num_bytes(num_leaves.min(Self::MAX_LEAVES))
+    }
+
+    /// Account size needed for the tree uploaded to `reward_collection_account`, if any.
+    pub fn size_for(reward_collection_account: &RewardCollectionAccount) -> usize {
+        Self::size(
+            reward_collection_account
+                .merkle_root
+                .as_ref()
+                .map_or(0, |merkle_root| merkle_root.max_num_nodes),
+        )
+    }
+
+    fn num_bytes(num_leaves: u64) -> usize {
+        num_leaves.div_ceil(8) as usize
+    }
+
+    /// Grows `claim_bitmap` to track `num_leaves` leaves, with `payer` topping up the rent. A
+    /// bitmap is sized for the root uploaded when it was created, and that root can still be
+    /// replaced by a larger one until the first claim.
+    pub fn grow<'info>(
+        claim_bitmap: &mut Account<'info, ClaimBitmap>,
+        num_leaves: u64,
+        payer: &Signer<'info>,
+        system_program: &Program<'info, System>,
+    ) -> Result<()> {
+        if num_leaves > Self::MAX_LEAVES {
+            return Err(ClaimBitmapTooLarge.into());
+        }
+        let num_bytes = Self::num_bytes(num_leaves);
+        if claim_bitmap.bits.len() >= num_bytes {
+            return Ok(());
+        }
+
+        let account = claim_bitmap.to_account_info();
+        let size = Self::size(num_leaves);
+        let top_up = Rent::get()?
+            .minimum_balance(size)
+            .saturating_sub(account.lamports());
+        if top_up > 0 {
+            system_program::transfer(
+                CpiContext::new(
+                    system_program.to_account_info(),
+                    system_program::Transfer {
+                        from: payer.to_account_info(),
+                        to: account.clone(),
+                    },
+                ),
+                top_up,
+            )?;
+        }
+        account.realloc(size, false)?;
+        claim_bitmap.bits.resize(num_bytes, 0);
+        Ok(())
+    }
+
+    /// Whether leaf `index` has been claimed.
+    pub fn is_claimed(&self, index: u64) -> Result<bool> {
+        let byte = self
+            .bits
+            .get((index / 8) as usize)
+            .ok_or(InvalidLeafIndex)?;
+        Ok(byte & (1 << (index % 8)) != 0)
+    }
+
+    /// Marks leaf `index` as claimed, failing if it already was.
+    pub fn set_claimed(&mut self, index: u64) -> Result<()> {
+        let byte = self
+            .bits
+            .get_mut((index / 8) as usize)
+            .ok_or(InvalidLeafIndex)?;
+        let mask = 1 << (index % 8);
+        if *byte & mask != 0 {
+            return Err(FundsAlreadyClaimed.into());
+        }
+        *byte |= mask;
+        Ok(())
+    }
+}
+
+/// Attesters of the merkle root of a [RewardCollectionAccount] whose
+/// `merkle_root_upload_authority` is this account. The root is only uploaded once `threshold`
+/// attesters submitted the same [AttestedRoot].
+#[account]
+#[derive(Default)]
+pub struct RootAttestation {
+    /// [RewardCollectionAccount] the root is attested for.
+    pub reward_collection_account: Pubkey,
+    /// Payer of the account; receives the rent back on close.
+    pub payer: Pubkey,
+    /// Expiry of the attestation, copied from the [RewardCollectionAccount].
+    pub expires_at: u64,
+    /// Number of matching submissions that uploads the root.
+    pub threshold: u8,
+    /// PDA bump.
+    pub bump: u8,
+    /// Keys allowed to submit.
+    pub attesters: Vec<Pubkey>,
+    /// Latest submission of each attester, by position in `attesters`.
+    pub submissions: Vec<Option<AttestedRoot>>,
+}
+
+/// Merkle root upload submitted by an attester of a [RootAttestation].
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct AttestedRoot {
+    pub root: [u8; 32],
+    pub max_total_claim: u64,
+    pub max_num_nodes: u64,
+    /// Leaf format of the tree, one of the `MerkleRoot::LEAF_VERSION_*`.
+    pub version: u8,
+    /// Whether the root is uploaded in pro-rata mode.
+    pub pro_rata: bool,
+}
+
+impl RootAttestation {
+    /// PDA seed for root attestation accounts.
+    pub const SEED: &'static [u8] = b"ROOT_ATTESTATION";
+    /// Size of the fixed fields, including the length prefixes of `attesters` and `submissions`.
+    const FIXED_SIZE: usize = HEADER_SIZE + 32 + 32 + 8 + 1 + 1 + 4 + 4;
+    /// Most attesters an account can name.
+    pub const MAX_ATTESTERS: usize = 16;
+
+    /// Account size needed for `num_attesters` attesters.
+    pub fn size(num_attesters: usize) -> usize {
+        Self::FIXED_SIZE + num_attesters * (32 + 1 + size_of::<AttestedRoot>())
+    }
+
+    /// Checks that `attesters` are distinct and that a quorum of `threshold` of them can be
+    /// reached.
+    pub fn check_attesters(attesters: &[Pubkey], threshold: u8) -> Result<()> {
+        if attesters.len() > Self::MAX_ATTESTERS
+            || threshold == 0
+            || threshold as usize > attesters.len()
+        {
+            return Err(InvalidAttesters.into());
+        }
+        for (i, attester) in attesters.iter().enumerate() {
+            if attesters[..i].contains(attester) {
+                return Err(InvalidAttesters.into());
+            }
+        }
+        Ok(())
+    }
+
+    /// Records `submission` as the latest of `attester`, replacing its previous one. Returns the
+    /// number of attesters whose latest submission matches it, and the attesters whose latest
+    /// submission differs.
+    pub fn submit(
+        &mut self,
+        attester: &Pubkey,
+        submission: AttestedRoot,
+    ) -> Result<(u8, Vec<Pubkey>)> {
+        let index = self
+            .attesters
+            .iter()
+            .position(|key| key == attester)
+            .ok_or(Unauthorized)?;
+        self.submissions[index] = Some(submission);
+
+        let mut num_agreeing = 0;
+        let mut conflicting_attesters = vec![];
+        for (key, other) in self.attesters.iter().zip(&self.submissions) {
+            match other {
+                Some(other) if *other == submission => num_agreeing += 1,
+                Some(_) => conflicting_attesters.push(*key),
+                None => {}
+            }
+        }
+        Ok((num_agreeing, conflicting_attesters))
+    }
+}
+
+/// Holds a merkle proof that is too long to be passed inline to `claim`. The proof is written
+/// across several transactions and consumed, and the account closed, by the claim.
+#[account]
+#[derive(Default)]
+pub struct ProofBuffer {
+    /// [RewardCollectionAccount] the proof is for.
+    pub reward_collection_account: Pubkey,
+    /// Claimant whose leaf the proof is for.
+    pub claimant: Pubkey,
+    /// Payer of the buffer; the only signer allowed to write, claim with or close it, and
+    /// receives the rent back on close.
+    pub payer: Pubkey,
+    /// Number of hashes of the complete proof.
+    pub proof_len: u8,
+    /// PDA bump.
+    pub bump: u8,
+    /// Hashes written so far.
+    pub proof: Vec<[u8; 32]>,
+}
+
+impl ProofBuffer {
+    /// PDA seed for proof buffer accounts.
+    pub const SEED: &'static [u8] = b"PROOF_BUFFER";
+    /// Size of the fixed fields, including the length prefix of `proof`.
+    const FIXED_SIZE: usize = HEADER_SIZE + 32 + 32 + 32 + 1 + 1 + 4;
+    /// Longest proof a buffer can hold, enough for trees of 2^32 leaves.
+    pub const MAX_PROOF_LEN: u8 = 32;
+
+    /// Account size needed to hold a proof of `proof_len` hashes.
+    pub fn size(proof_len: u8) -> usize {
+        Self::FIXED_SIZE + 32 * proof_len as usize
+    }
+
+    /// Appends `hashes` to the proof. `offset` must be the number of hashes written so far, so
+    /// a retried write can never append the same hashes twice.
+    pub fn write(&mut self, offset: u8, hashes: &[[u8; 32]]) -> Result<()> {
+        if offset as usize != self.proof.len()
+            || self.proof.len() + hashes.len() > self.proof_len as usize
+        {
+            return Err(InvalidProofBufferWrite.into());
+        }
+        self.proof.extend_from_slice(hashes);
+        Ok(())
+    }
+
+    /// The complete proof, failing if hashes are still missing.
+    pub fn proof(&self) -> Result<Vec<[u8; 32]>> {
+        if self.proof.len() != self.proof_len as usize {
+            return Err(ProofBufferIncomplete.into());
+        }
+        Ok(self.proof.clone())
+    }
+}
+
+/// Vault of a validator's cumulative distribution. Staker rewards of several epochs are swept in
+/// from [RewardCollectionAccount]s, and every uploaded root covers each staker's lifetime
+/// earnings, so a staker can claim all epochs at once with a single proof.
+#[account]
+#[derive(Default)]
+pub struct CumulativeDistributor {
+    /// Validator's vote account.
+    pub validator_vote_account: Pubkey,
+    /// Who initialized the account (validator identity).
+    pub initializer: Pubkey,
+    /// Authorized uploader of the cumulative Merkle root.
+    pub merkle_root_upload_authority: Pubkey,
+    /// Merkle root of the lifetime totals, see `rakurai_merkle_tree::hash_cumulative_leaf`.
+    pub root: [u8; 32],
+    /// Sum of the lifetime totals in `root`.
+    pub max_total_claim: u64,
+    /// Funds swept in from [RewardCollectionAccount]s.
+    pub total_funds_deposited: u64,
+    /// Funds claimed over the lifetime of the account.
+    pub total_funds_claimed: u64,
+    /// Epoch the current root was uploaded in.
+    pub root_epoch: u64,
+    /// PDA bump.
+    pub bump: u8,
+}
+
+impl CumulativeDistributor {
+    /// PDA seed for cumulative distributor accounts.
+    pub const SEED: &'static [u8] = b"CUMULATIVE_DISTRIBUTOR";
+    /// Account size for rent-exemption.
+    pub const SIZE: usize = HEADER_SIZE + size_of::<Self>();
+
+    /// Validates that required fields are not default.
+    pub fn validate(&self) -> Result<()> {
+        let default_pubkey = Pubkey::default();
+        if self.validator_vote_account == default_pubkey
+            || self.merkle_root_upload_authority == default_pubkey
+            || self.initializer == default_pubkey
+        {
+            return Err(AccountValidationFailure.into());
+        }
+
+        Ok(())
+    }
+
+    /// Checks that `(claimant, total_amount)` is a leaf of the current root.
+    pub fn verify_claim(
+        &self,
+        distributor: &Pubkey,
+        claimant: &Pubkey,
+        total_amount: u64,
+        proof: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        let leaf = rakurai_merkle_tree::hash_cumulative_leaf(distributor, claimant, total_amount);
+        if !merkle_proof::verify(proof, self.root, leaf) {
+            return Err(InvalidProof.into());
+        }
+        Ok(())
+    }
+
+    /// Adds swept funds to `total_funds_deposited`.
+    pub fn record_deposit(&mut self, amount: u64) -> Result<()> {
+        self.total_funds_deposited = self
+            .total_funds_deposited
+            .checked_add(amount)
+            .ok_or(ArithmeticError)?;
+        Ok(())
+    }
+
+    /// Adds a claim to `total_funds_claimed`, enforcing `max_total_claim`.
+    pub fn record_claim(&mut self, amount: u64) -> Result<()> {
+        self.total_funds_claimed = self
+            .total_funds_claimed
+            .checked_add(amount)
+            .ok_or(ArithmeticError)?;
+        if self.total_funds_claimed > self.max_total_claim {
+            return Err(ExceedsMaxClaim.into());
+        }
+        Ok(())
+    }
+
+    /// Moves `amount` lamports out of the distributor vault.
+    pub fn claim(from: AccountInfo, to: AccountInfo, amount: u64) -> Result<()> {
+        RewardCollectionAccount::transfer_lamports(from, to, amount)
+    }
+}
+
+/// How much of their lifetime total a staker has claimed from a [CumulativeDistributor].
+#[account]
+#[derive(Default)]
+pub struct CumulativeClaimStatus {
+    /// [CumulativeDistributor] claimed from.
+    pub distributor: Pubkey,
+    /// Who made the claims.
+    pub claimant: Pubkey,
+    /// Lifetime total claimed so far; the next claim pays out the difference to the new total.
+    pub claimed_amount: u64,
+    /// Slot when the last claim was made.
+    pub slot_claimed_at: u64,
+    /// PDA bump.
+    pub bump: u8,
+}
+
+impl CumulativeClaimStatus {
+    /// PDA seed for cumulative claim status accounts.
+    pub const SEED: &'static [u8] = b"CUMULATIVE_CLAIM_STATUS";
+    /// Account size for rent-exemption.
+    pub const SIZE: usize = HEADER_SIZE + size_of::<Self>();
 }